@@ -0,0 +1,94 @@
+use chrono::Local;
+use leptos::{
+    component,
+    create_memo,
+    view,
+    with,
+    For,
+    IntoView,
+    Signal,
+    SignalUpdate,
+};
+use leptos_router::A;
+
+use super::{
+    expect_context,
+    refresh_summary,
+    BootstrapIcon,
+    Context,
+};
+use crate::state::{
+    use_conversation,
+    StorageSignals,
+};
+
+#[component]
+pub fn Archive() -> impl IntoView {
+    let Context {
+        summaries, trash, ..
+    } = expect_context();
+
+    let archived = create_memo(move |_| {
+        with!(|summaries, trash| {
+            let mut items = summaries
+                .values()
+                .filter(|summary| summary.archived && !trash.contains_key(&summary.id))
+                .map(|summary| (summary.timestamp_last_interaction, summary.id))
+                .collect::<Vec<_>>();
+            items.sort();
+            items.reverse();
+            items.into_iter().map(|(_, id)| id).collect::<Vec<_>>()
+        })
+    });
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll h-100 w-100 p-4">
+            <h4>"Archived conversations"</h4>
+            {move || with!(|archived| archived.is_empty()).then(|| view!{
+                <p class="text-body-secondary">"No archived conversations."</p>
+            })}
+            <ul class="list-group w-75">
+                <For
+                    each=archived
+                    key=|id| *id
+                    children=move |id| {
+                        let StorageSignals { write: update_conversation, .. } = use_conversation(id);
+                        let title = Signal::derive(move || with!(|summaries| {
+                            summaries.get(&id)
+                                .and_then(|summary| summary.title.clone())
+                                .unwrap_or_else(|| "Untitled".to_owned())
+                        }));
+                        let last_interaction = Signal::derive(move || with!(|summaries| {
+                            summaries.get(&id)
+                                .map(|summary| summary.timestamp_last_interaction.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                        }));
+
+                        view! {
+                            <li class="list-group-item d-flex flex-row align-items-center">
+                                <A href=format!("/conversation/{id}") class="text-truncate me-auto">
+                                    {title}
+                                </A>
+                                <small class="text-body-secondary text-nowrap mx-3">{last_interaction}</small>
+                                <button
+                                    type="button"
+                                    class="btn btn-sm btn-outline-secondary"
+                                    title="Unarchive"
+                                    on:click=move |_| {
+                                        update_conversation.update(|conversation| {
+                                            if let Some(conversation) = conversation {
+                                                conversation.archived = false;
+                                            }
+                                        });
+                                        refresh_summary(id);
+                                    }
+                                >
+                                    <BootstrapIcon icon="box-arrow-up" />
+                                </button>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+        </div>
+    }
+}
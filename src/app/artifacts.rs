@@ -0,0 +1,285 @@
+use chrono::{
+    Local,
+    Utc,
+};
+use leptos::{
+    component,
+    create_rw_signal,
+    event_target_value,
+    view,
+    with,
+    CollectView,
+    For,
+    IntoView,
+    Signal,
+    SignalGet,
+    SignalGetUntracked,
+    SignalSet,
+    SignalUpdate,
+    SignalWithUntracked,
+};
+use uuid::Uuid;
+
+use super::BootstrapIcon;
+use crate::{
+    render::{
+        code_blocks,
+        render_markdown,
+    },
+    state::{
+        use_artifacts,
+        Artifact,
+        ArtifactVersion,
+        ConversationId,
+        Message,
+        StorageSignals,
+    },
+    utils::{
+        copy_to_clipboard,
+        download_file,
+    },
+};
+
+pub const ARTIFACTS_OFFCANVAS_ID: &'static str = "artifacts_offcanvas";
+
+/// Messages at least this long can be promoted as a document.
+const MIN_DOCUMENT_LENGTH: usize = 1500;
+
+/// Code blocks with at least this many lines can be promoted.
+const MIN_CODE_LINES: usize = 10;
+
+/// Finds the content of a message that would become an artifact: the longest
+/// code block, or the whole message, if it's a long document.
+fn artifact_content(message: &Message) -> Option<(Option<String>, String)> {
+    let code_block = code_blocks(&message.text)
+        .into_iter()
+        .filter(|block| block.code.lines().count() >= MIN_CODE_LINES)
+        .max_by_key(|block| block.code.len());
+
+    if let Some(code_block) = code_block {
+        Some((code_block.language, code_block.code))
+    }
+    else if message.text.len() >= MIN_DOCUMENT_LENGTH {
+        Some((None, message.text.clone()))
+    }
+    else {
+        None
+    }
+}
+
+pub fn can_promote(message: &Message) -> bool {
+    artifact_content(message).is_some()
+}
+
+fn artifact_title(language: Option<&str>, content: &str) -> String {
+    match language {
+        Some(language) => format!("{language} code"),
+        None => {
+            // use the first heading or line of the document
+            content
+                .lines()
+                .map(|line| line.trim_start_matches('#').trim())
+                .find(|line| !line.is_empty())
+                .map(|line| line.chars().take(60).collect())
+                .unwrap_or_else(|| "Document".to_owned())
+        }
+    }
+}
+
+pub fn promote_to_artifact(conversation_id: ConversationId, message: &Message) {
+    let Some((language, content)) = artifact_content(message)
+    else {
+        return;
+    };
+
+    let StorageSignals {
+        write: update_artifacts,
+        ..
+    } = use_artifacts(conversation_id);
+
+    let artifact = Artifact {
+        id: Uuid::new_v4(),
+        title: artifact_title(language.as_deref(), &content),
+        language,
+        versions: vec![ArtifactVersion {
+            message_id: message.id,
+            content,
+            timestamp: Utc::now(),
+        }],
+    };
+    update_artifacts.update(|artifacts| artifacts.push(artifact));
+}
+
+/// Adds new versions to code artifacts when a later message contains a code
+/// block in the same language. Documents have to be promoted again.
+pub fn update_artifacts(conversation_id: ConversationId, message: &Message) {
+    let StorageSignals {
+        read: artifacts,
+        write: update_artifacts,
+        ..
+    } = use_artifacts(conversation_id);
+
+    if artifacts.with_untracked(|artifacts| artifacts.is_empty()) {
+        return;
+    }
+
+    let code_blocks = code_blocks(&message.text);
+
+    update_artifacts.update(|artifacts| {
+        for artifact in artifacts {
+            let Some(language) = &artifact.language
+            else {
+                continue;
+            };
+            let Some(code_block) = code_blocks
+                .iter()
+                .find(|block| block.language.as_ref() == Some(language))
+            else {
+                continue;
+            };
+
+            let is_new = artifact
+                .versions
+                .last()
+                .map_or(true, |version| version.content != code_block.code);
+            if is_new {
+                log::debug!("new version of artifact {}", artifact.id);
+                artifact.versions.push(ArtifactVersion {
+                    message_id: message.id,
+                    content: code_block.code.clone(),
+                    timestamp: Utc::now(),
+                });
+            }
+        }
+    });
+}
+
+fn file_name(artifact: &Artifact) -> String {
+    let extension = match artifact.language.as_deref() {
+        None => "md",
+        Some("rust") => "rs",
+        Some("python") => "py",
+        Some("javascript") => "js",
+        Some("typescript") => "ts",
+        Some("shell" | "bash" | "sh") => "sh",
+        Some(language) => language,
+    };
+    format!("artifact-{}.{extension}", artifact.id)
+}
+
+/// An offcanvas panel listing the artifacts of a conversation.
+#[component]
+pub fn ArtifactsPanel(#[prop(into)] conversation_id: Signal<ConversationId>) -> impl IntoView {
+    let artifacts = Signal::derive(move || {
+        let StorageSignals {
+            read: artifacts, ..
+        } = use_artifacts(conversation_id.get());
+        artifacts.get()
+    });
+
+    view! {
+        <div class="offcanvas offcanvas-end artifacts" tabindex="-1" id=ARTIFACTS_OFFCANVAS_ID>
+            <div class="offcanvas-header">
+                <h5 class="offcanvas-title">"Artifacts"</h5>
+                <button type="button" class="btn-close" data-bs-dismiss="offcanvas" aria-label="Close"></button>
+            </div>
+            <div class="offcanvas-body">
+                {move || with!(|artifacts| artifacts.is_empty()).then(|| view!{
+                    <p class="text-body-secondary">"Long documents and code in responses can be promoted to artifacts."</p>
+                })}
+                <For
+                    each=artifacts
+                    // re-render when a new version is added
+                    key=|artifact| (artifact.id, artifact.versions.len())
+                    children=move |artifact| {
+                        view!{ <ArtifactView conversation_id artifact /> }
+                    }
+                />
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn ArtifactView(conversation_id: Signal<ConversationId>, artifact: Artifact) -> impl IntoView {
+    let artifact_id = artifact.id;
+    let file_name = file_name(&artifact);
+    let num_versions = artifact.versions.len();
+    let selected_version = create_rw_signal(num_versions.saturating_sub(1));
+
+    let version = move || artifact.versions.get(selected_version.get()).cloned();
+    let content = {
+        let version = version.clone();
+        move || version().map(|version| version.content).unwrap_or_default()
+    };
+    let html = {
+        let content = content.clone();
+        let language = artifact.language.clone();
+        move || {
+            match &language {
+                Some(language) => render_markdown(&format!("```{language}\n{}\n```", content())),
+                None => render_markdown(&content()),
+            }
+        }
+    };
+    let timestamp = move || {
+        version().map(|version| {
+            version
+                .timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+    };
+
+    let copy = {
+        let content = content.clone();
+        move |_| copy_to_clipboard(&content())
+    };
+    let download = {
+        let content = content.clone();
+        move |_| download_file(&file_name, "text/plain", &content())
+    };
+    let delete = move |_| {
+        let StorageSignals {
+            write: update_artifacts,
+            ..
+        } = use_artifacts(conversation_id.get_untracked());
+        update_artifacts
+            .update(|artifacts| artifacts.retain(|artifact| artifact.id != artifact_id));
+    };
+
+    view! {
+        <div class="card mb-3">
+            <div class="card-header d-flex flex-row align-items-center">
+                <span class="text-truncate me-auto">{artifact.title.clone()}</span>
+                <select
+                    class="form-select form-select-sm w-auto mx-2"
+                    aria-label="Version"
+                    on:change=move |event| {
+                        if let Ok(index) = event_target_value(&event).parse() {
+                            selected_version.set(index);
+                        }
+                    }
+                >
+                    {(0..num_versions).map(|index| view!{
+                        <option value=index.to_string() selected=move || selected_version.get() == index>
+                            {format!("Version {}", index + 1)}
+                        </option>
+                    }).collect_view()}
+                </select>
+                <button type="button" class="btn btn-sm btn-link link-secondary p-0 me-2" title="Copy" on:click=copy>
+                    <BootstrapIcon icon="clipboard" />
+                </button>
+                <button type="button" class="btn btn-sm btn-link link-secondary p-0 me-2" title="Download" on:click=download>
+                    <BootstrapIcon icon="download" />
+                </button>
+                <button type="button" class="btn btn-sm btn-link link-danger p-0" title="Remove artifact" on:click=delete>
+                    <BootstrapIcon icon="x-lg" />
+                </button>
+            </div>
+            <div class="card-body markdown" inner_html=html></div>
+            <div class="card-footer small text-body-secondary">{timestamp}</div>
+        </div>
+    }
+}
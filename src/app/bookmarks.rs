@@ -0,0 +1,148 @@
+//! Lists the bookmarked messages from all conversations.
+
+use chrono::Local;
+use leptos::{
+    component,
+    create_memo,
+    view,
+    with,
+    For,
+    IntoView,
+    Signal,
+    SignalGet,
+    SignalUpdate,
+};
+use leptos_router::A;
+
+use super::{
+    conversation::message_anchor,
+    expect_context,
+    BootstrapIcon,
+    Context,
+};
+use crate::state::{
+    use_message,
+    Role,
+    StorageSignals,
+};
+
+/// How much of a message is shown in the list.
+const SNIPPET_LENGTH: usize = 200;
+
+#[component]
+pub fn BookmarkList() -> impl IntoView {
+    let Context {
+        bookmarks,
+        update_bookmarks,
+        summaries,
+        trash,
+        ..
+    } = expect_context();
+
+    // the most recently bookmarked first.
+    let items = create_memo(move |_| {
+        with!(|bookmarks| {
+            let mut items = bookmarks
+                .iter()
+                .map(|(message_id, bookmark)| {
+                    (bookmark.timestamp, *message_id, bookmark.conversation_id)
+                })
+                .collect::<Vec<_>>();
+            items.sort();
+            items.reverse();
+            items
+                .into_iter()
+                .map(|(_, message_id, conversation_id)| (message_id, conversation_id))
+                .collect::<Vec<_>>()
+        })
+    });
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll h-100 w-100 p-4">
+            <h4>"Bookmarks"</h4>
+            {move || with!(|items| items.is_empty()).then(|| view!{
+                <p class="text-body-secondary">
+                    "No bookmarks yet. Use the "
+                    <BootstrapIcon icon="star" />
+                    " below a message to bookmark it."
+                </p>
+            })}
+            <ul class="list-group w-75">
+                <For
+                    each=items
+                    key=|(message_id, _)| *message_id
+                    children=move |(message_id, conversation_id)| {
+                        let StorageSignals { read: message, .. } = use_message(message_id);
+                        // the conversation may have been deleted since.
+                        let available = Signal::derive(move || {
+                            with!(|summaries, trash, message| {
+                                message.is_some() && summaries.contains_key(&conversation_id) && !trash.contains_key(&conversation_id)
+                            })
+                        });
+                        let title = Signal::derive(move || with!(|summaries| {
+                            summaries.get(&conversation_id)
+                                .and_then(|summary| summary.title.clone())
+                                .unwrap_or_else(|| "Untitled".to_owned())
+                        }));
+                        let role = Signal::derive(move || with!(|message| {
+                            match message.as_ref().map(|message| message.role) {
+                                Some(Role::User) => "You",
+                                Some(Role::Assitant) => "Assistant",
+                                None => "",
+                            }
+                        }));
+                        let snippet = Signal::derive(move || with!(|message| {
+                            message.as_ref().map(|message| {
+                                let mut snippet = message.text.chars().take(SNIPPET_LENGTH).collect::<String>();
+                                if message.text.chars().count() > SNIPPET_LENGTH {
+                                    snippet.push('…');
+                                }
+                                snippet
+                            })
+                        }));
+                        let bookmarked = Signal::derive(move || with!(|bookmarks| {
+                            bookmarks.get(&message_id)
+                                .map(|bookmark| bookmark.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                        }));
+                        let remove = move |_| {
+                            update_bookmarks.update(|bookmarks| {
+                                bookmarks.remove(&message_id);
+                            });
+                        };
+
+                        view! {
+                            <li class="list-group-item d-flex flex-row align-items-start">
+                                <div class="d-flex flex-column me-auto overflow-hidden">
+                                    {move || if available.get() {
+                                        view!{
+                                            <A href=format!("/conversation/{conversation_id}#{}", message_anchor(message_id)) class="text-truncate">
+                                                {title}
+                                            </A>
+                                            <small class="text-body-secondary">{role}</small>
+                                            <div class="bookmark-snippet">{snippet}</div>
+                                        }.into_view()
+                                    }
+                                    else {
+                                        view!{
+                                            <span class="text-body-secondary">"This message was deleted."</span>
+                                        }.into_view()
+                                    }}
+                                </div>
+                                <small class="text-body-secondary text-nowrap mx-3">{bookmarked}</small>
+                                <button
+                                    type="button"
+                                    class="btn btn-sm btn-outline-secondary"
+                                    title="Remove bookmark"
+                                    aria-label="Remove bookmark"
+                                    on:click=remove
+                                >
+                                    <BootstrapIcon icon="star-fill" />
+                                </button>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+        </div>
+    }
+}
@@ -0,0 +1,129 @@
+//! Slash commands in the message input, e.g. `/model zephyr` or `/retry`.
+
+use crate::state::{
+    ModelId,
+    Settings,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CommandError {
+    #[error("Unknown command: /{0}")]
+    Unknown(String),
+    #[error("/{0} needs an argument")]
+    MissingArgument(&'static str),
+    #[error("No model is named {0:?}")]
+    UnknownModel(String),
+    #[error("There's no response to retry")]
+    NothingToRetry,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Sets the system prompt of the conversation, or removes it if the text
+    /// is empty.
+    System(Option<String>),
+    Model(ModelId),
+    /// Starts a new conversation.
+    Clear,
+    /// Generates the last response again.
+    Retry,
+    Title(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommandInfo {
+    pub name: &'static str,
+    pub argument: Option<&'static str>,
+    pub description: &'static str,
+}
+
+/// The commands that are listed when typing `/`.
+pub const COMMANDS: &[CommandInfo] = &[
+    CommandInfo {
+        name: "system",
+        argument: Some("text"),
+        description: "Set the system prompt",
+    },
+    CommandInfo {
+        name: "model",
+        argument: Some("name"),
+        description: "Switch to another model",
+    },
+    CommandInfo {
+        name: "clear",
+        argument: None,
+        description: "Start a new conversation",
+    },
+    CommandInfo {
+        name: "retry",
+        argument: None,
+        description: "Generate the last response again",
+    },
+    CommandInfo {
+        name: "title",
+        argument: Some("text"),
+        description: "Rename the conversation",
+    },
+];
+
+/// The commands whose name starts with what was typed after the `/`, as long
+/// as no argument is typed yet.
+pub fn completions(input: &str) -> Vec<&'static CommandInfo> {
+    let Some(prefix) = input.strip_prefix('/')
+    else {
+        return vec![];
+    };
+    if prefix.contains(char::is_whitespace) {
+        return vec![];
+    }
+    COMMANDS
+        .iter()
+        .filter(|command| command.name.starts_with(prefix))
+        .collect()
+}
+
+/// Parses a message that starts with `/`. Returns `None` for any other
+/// message, which is sent as usual.
+pub fn parse_command(input: &str, settings: &Settings) -> Option<Result<Command, CommandError>> {
+    let input = input.trim().strip_prefix('/')?;
+    let (name, argument) = input
+        .split_once(char::is_whitespace)
+        .map(|(name, argument)| (name, argument.trim()))
+        .unwrap_or((input, ""));
+
+    let required = |name: &'static str| {
+        (!argument.is_empty())
+            .then(|| argument.to_owned())
+            .ok_or(CommandError::MissingArgument(name))
+    };
+
+    let command = match name {
+        "system" => {
+            Ok(Command::System(
+                (!argument.is_empty()).then(|| argument.to_owned()),
+            ))
+        }
+        "model" => {
+            required("model").and_then(|name| find_model(&name, settings).map(Command::Model))
+        }
+        "clear" => Ok(Command::Clear),
+        "retry" => Ok(Command::Retry),
+        "title" => required("title").map(Command::Title),
+        _ => Err(CommandError::Unknown(name.to_owned())),
+    };
+    Some(command)
+}
+
+/// Finds a model by its ID or its display name, ignoring case.
+pub fn find_model(name: &str, settings: &Settings) -> Result<ModelId, CommandError> {
+    let name_lowercase = name.to_lowercase();
+    settings
+        .models
+        .values()
+        .find(|model| {
+            model.model_id.0.to_lowercase() == name_lowercase
+                || model.display_name().to_lowercase() == name_lowercase
+        })
+        .map(|model| model.model_id.clone())
+        .ok_or_else(|| CommandError::UnknownModel(name.to_owned()))
+}
@@ -1,23 +1,35 @@
 use std::{
     fmt::Display,
     str::FromStr,
+    time::Duration,
 };
 
+use chrono::Utc;
 use leptos::{
     component,
     create_effect,
+    create_memo,
     create_node_ref,
     create_rw_signal,
+    document,
+    event_target_checked,
     event_target_value,
     html::{
         Div,
         Input,
+        Textarea,
     },
+    on_cleanup,
+    request_animation_frame,
+    set_interval_with_handle,
+    set_timeout,
+    spawn_local,
     store_value,
-    update,
     view,
+    window,
     with,
     Callback,
+    CollectView,
     For,
     IntoView,
     MaybeSignal,
@@ -33,50 +45,196 @@ use leptos::{
     WriteSignal,
 };
 use leptos_router::{
+    use_location,
     use_navigate,
     NavigateOptions,
+    A,
+};
+use strum::{
+    EnumMessage,
+    VariantArray,
+};
+use wasm_bindgen::{
+    JsCast,
+    JsValue,
 };
 use web_sys::{
+    DataTransfer,
+    DragEvent,
     Event,
+    FileList,
+    KeyboardEvent,
     ScrollLogicalPosition,
     SubmitEvent,
 };
 
 use crate::{
     app::{
+        artifacts::{
+            can_promote,
+            promote_to_artifact,
+            ArtifactsPanel,
+            ARTIFACTS_OFFCANVAS_ID,
+        },
+        commands::{
+            completions,
+            parse_command,
+            Command,
+            CommandError,
+            CommandInfo,
+        },
+        continue_response,
         expect_context,
+        is_private,
+        model_card::{
+            ModelCard,
+            MODEL_CARD_OFFCANVAS_ID,
+        },
         push_user_message,
+        refresh_summary,
+        request_conversation_title,
+        retry_response,
+        sidebar::update_conversation,
+        speech::VoiceInput,
+        stop_generation,
+        summarize_conversation,
+        trash_conversation,
         BootstrapIcon,
         Context,
+        Error,
+        SendOverrides,
     },
+    parameters::ResponseLength,
+    render::{
+        diagrams::{
+            render_diagrams,
+            toggle_diagram,
+            wrap_diagrams,
+        },
+        escape_html,
+        export::conversation_document,
+        render_markdown,
+    },
+    share::SharedConversation,
     state::{
-        delete_storage,
+        use_artifacts,
+        use_audit_log,
         use_conversation,
         use_message,
+        Bookmark,
         ConversationId,
         ConversationParameters,
+        GenerationMetadata,
         MessageId,
+        MessageStatus,
+        ModelId,
+        Rating,
         Role,
-        StorageKey,
+        Settings,
         StorageSignals,
     },
-    utils::non_empty,
+    tokenizer::count_tokens,
+    tools::search::{
+        link_citations,
+        search_sources,
+    },
+    utils::{
+        auto_grow,
+        copy_to_clipboard,
+        download_file,
+        non_empty,
+        read_data_url,
+        submit_on_enter,
+    },
 };
 
+/// How close to the bottom (in pixels) counts as being at the bottom.
+const SCROLL_BOTTOM_THRESHOLD: i32 = 40;
+
+const STREAMING_STATUS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long the share button shows that the link was copied.
+const SHARE_COPIED_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Images are stored inline with the message, so they can't be too large.
+const MAX_IMAGE_SIZE: f64 = 1024.0 * 1024.0;
+
+/// How many of the previous user messages Up and Down go through.
+const INPUT_HISTORY_SIZE: usize = 50;
+
+/// How long a message that is linked to stays highlighted.
+const MESSAGE_HIGHLIGHT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long "Draft saved" is shown after typing stops.
+const DRAFT_SAVED_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The files of a paste event. `ClipboardEvent` is still unstable in `web-sys`,
+/// so the clipboard data is read through `js_sys`.
+fn pasted_files(event: &Event) -> Option<FileList> {
+    js_sys::Reflect::get(event, &JsValue::from_str("clipboardData"))
+        .ok()?
+        .dyn_into::<DataTransfer>()
+        .ok()?
+        .files()
+}
+
+/// Reads the images from pasted or dropped files and adds them to `images`.
+/// Returns whether there were any images.
+fn attach_images(files: Option<FileList>, images: RwSignal<Vec<String>>) -> bool {
+    let files = files
+        .map(|files| {
+            (0..files.length())
+                .filter_map(|i| files.get(i))
+                .filter(|file| file.type_().starts_with("image/"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if files.is_empty() {
+        return false;
+    }
+
+    let Context { errors, .. } = expect_context();
+    for file in files {
+        if file.size() > MAX_IMAGE_SIZE {
+            errors.push(Error::ImageTooLarge {
+                size: file.size(),
+                max_size: MAX_IMAGE_SIZE,
+            });
+            continue;
+        }
+        spawn_local(async move {
+            match read_data_url(&file).await {
+                Ok(data_url) => images.update(|images| images.push(data_url)),
+                Err(error) => {
+                    log::error!("failed to read image: {error:?}");
+                    errors.push(Error::ReadImage);
+                }
+            }
+        });
+    }
+    true
+}
+
 #[component]
 pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoView {
     let Context {
-        is_loading,
+        loading,
+        errors,
         settings,
-        update_conversations,
         scroll_trigger,
+        tabs,
+        warming_up,
+        summarizing,
+        local_load_progress,
+        online,
         ..
     } = expect_context();
 
     // auto-scrolling
     // this is done by having an empty div at the bottom of the page (right before
     // the spacer) that we scroll into view whenever a change to the conversation
-    // happens.
+    // happens. but only while the user is at the bottom: scrolling up stops it, and
+    // scrolling back down (or jumping to the latest message) resumes it.
 
     pub fn scroll_to(target: NodeRef<Div>, smooth: bool) {
         let Some(scroll_target) = target.get_untracked()
@@ -101,11 +259,59 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
             let StorageSignals { read: conversation, write: update_conversation, .. } = use_conversation(id.get());
 
             // create effect to auto-scroll
+            let scroll_container = create_node_ref::<Div>();
             let scroll_target = create_node_ref::<Div>();
             let is_initial_scroll = store_value(true);
+            let stick_to_bottom = create_rw_signal(true);
+            let last_scroll_top = store_value(0);
+
+            // only scrolling up counts as the user leaving the bottom, so that content
+            // growing below (or our own smooth scrolling) doesn't stop auto-scrolling.
+            let on_scroll = move |_| {
+                let Some(container) = scroll_container.get_untracked() else { return; };
+                let scroll_top = container.scroll_top();
+                let at_bottom = container.scroll_height() - scroll_top - container.client_height() <= SCROLL_BOTTOM_THRESHOLD;
+                let scrolled_up = scroll_top < last_scroll_top.get_value();
+                last_scroll_top.set_value(scroll_top);
+
+                if at_bottom {
+                    stick_to_bottom.set(true);
+                }
+                else if scrolled_up {
+                    stick_to_bottom.set(false);
+                }
+            };
+
+            let jump_to_latest = move |_| {
+                stick_to_bottom.set(true);
+                scroll_to(scroll_target, true);
+            };
+
+            // a link to a message (`#msg-...`) scrolls to it and highlights it for a moment.
+            let location = use_location();
+            create_effect(move |_| {
+                let Some(anchor) = location.hash.with(|hash| hash.strip_prefix('#').filter(|anchor| anchor.starts_with("msg-")).map(ToOwned::to_owned)) else { return; };
+                // wait until the messages are in the page.
+                request_animation_frame(move || {
+                    let Some(element) = document().get_element_by_id(&anchor) else { return; };
+                    stick_to_bottom.set(false);
+
+                    let mut scroll_options = web_sys::ScrollIntoViewOptions::new();
+                    scroll_options.block(ScrollLogicalPosition::Center);
+                    element.scroll_into_view_with_scroll_into_view_options(&scroll_options);
+
+                    let _ = element.class_list().add_1("message-highlight");
+                    set_timeout(move || {
+                        let _ = element.class_list().remove_1("message-highlight");
+                    }, MESSAGE_HIGHLIGHT_TIMEOUT);
+                });
+            });
 
             create_effect(move |_| {
                 scroll_trigger.track();
+                if !stick_to_bottom.get_untracked() {
+                    return;
+                }
 
                 let initial = is_initial_scroll.try_update_value(|value| {
                     let current = *value;
@@ -116,14 +322,155 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                 scroll_to(scroll_target, !initial);
             });
 
-            let user_message_input = create_node_ref::<Input>();
+            let user_message_input = create_node_ref::<Textarea>();
+
+            // this is rendered again for every conversation that is opened, so
+            // focus moves to the new conversation's input.
+            create_effect(move |_| {
+                if let Some(user_message_input) = user_message_input.get() {
+                    let _ = user_message_input.focus();
+                }
+            });
+
+            // drafts
+            // the draft is saved on every input, and other tabs get it through the
+            // `storage` event. it's taken over, unless it's being edited here, in
+            // which case the user can choose to take it.
+
+            let draft_saved = create_rw_signal(false);
+            let draft_saves = store_value(0u32);
+            let remote_draft = create_rw_signal(None::<String>);
+
+            create_effect(move |_| {
+                let Some(draft) = with!(|conversation| conversation.as_ref().map(|conversation| conversation.user_message.clone())) else { return; };
+                let Some(user_message_input) = user_message_input.get_untracked() else { return; };
+                if draft == user_message_input.value() {
+                    return;
+                }
+
+                let editing = document().active_element().map_or(false, |element| element == ***user_message_input);
+                if editing && !user_message_input.value().is_empty() {
+                    remote_draft.set(Some(draft));
+                }
+                else {
+                    user_message_input.set_value(&draft);
+                    auto_grow(&user_message_input);
+                    remote_draft.set(None);
+                }
+            });
+
+            let show_draft_saved = move || {
+                if is_private(id.get_untracked()) {
+                    return;
+                }
+                draft_saves.update_value(|saves| *saves += 1);
+                let saves = draft_saves.get_value();
+                draft_saved.set(true);
+                set_timeout(move || {
+                    if draft_saves.try_get_value() == Some(saves) {
+                        draft_saved.set(false);
+                    }
+                }, DRAFT_SAVED_TIMEOUT);
+            };
+
+            let take_remote_draft = move |_| {
+                let Some(draft) = remote_draft.get_untracked() else { return; };
+                if let Some(user_message_input) = user_message_input.get_untracked() {
+                    user_message_input.set_value(&draft);
+                    auto_grow(&user_message_input);
+                }
+                remote_draft.set(None);
+            };
 
             // send message
 
+            let send_with_model = create_rw_signal(None::<ModelId>);
+            let send_with_temperature = create_rw_signal(None::<f32>);
+            let has_overrides = Signal::derive(move || with!(|send_with_model, send_with_temperature| send_with_model.is_some() || send_with_temperature.is_some()));
+
+            // images pasted or dropped into the message input.
+            let pending_images = create_rw_signal(Vec::<String>::new());
+
+            // going through the previous user messages with Up and Down, like in a
+            // shell. this counts back from the most recent one.
+            let history_position = create_rw_signal(None::<usize>);
+
+            let command_completions = create_memo(move |_| {
+                with!(|conversation| conversation.as_ref().map(|conversation| completions(&conversation.user_message)).unwrap_or_default())
+            });
+            let complete_command = move |command: &CommandInfo| {
+                let Some(user_message_input) = user_message_input.get_untracked() else { return; };
+                let user_message = if command.argument.is_some() { format!("/{} ", command.name) } else { format!("/{}", command.name) };
+                user_message_input.set_value(&user_message);
+                auto_grow(&user_message_input);
+                let _ = user_message_input.focus();
+                update_conversation.update(|conversation| {
+                    let Some(conversation) = conversation else { return; };
+                    conversation.user_message = user_message;
+                });
+            };
+
+            let on_keydown = move |event: KeyboardEvent| {
+                if event.key() == "Tab" && !event.shift_key() {
+                    if let Some(command) = command_completions.with_untracked(|completions| completions.first().copied()) {
+                        event.prevent_default();
+                        complete_command(command);
+                        return;
+                    }
+                }
+
+                let up = event.key() == "ArrowUp";
+                let down = event.key() == "ArrowDown";
+                let modifier = event.shift_key() || event.ctrl_key() || event.alt_key() || event.meta_key();
+                if !(up || down) || modifier || event.is_composing() {
+                    submit_on_enter(event);
+                    return;
+                }
+
+                let Some(user_message_input) = user_message_input.get_untracked() else { return; };
+                let position = history_position.get_untracked();
+                // only an empty input, or one that shows a previous message, is replaced.
+                if position.is_none() && !user_message_input.value().is_empty() {
+                    return;
+                }
+
+                let history = conversation.with_untracked(|conversation| {
+                    let Some(conversation) = conversation else { return vec![]; };
+                    conversation.messages
+                        .iter()
+                        .rev()
+                        .filter_map(|message_id| use_message(*message_id).read.get_untracked())
+                        .filter(|message| matches!(message.role, Role::User) && message.tool.is_none())
+                        .map(|message| message.text)
+                        .take(INPUT_HISTORY_SIZE)
+                        .collect::<Vec<_>>()
+                });
+                let next = match (position, up) {
+                    (None, true) if !history.is_empty() => Some(0),
+                    (Some(position), true) => Some((position + 1).min(history.len().saturating_sub(1))),
+                    (Some(position), false) => position.checked_sub(1),
+                    _ => return,
+                };
+
+                event.prevent_default();
+                history_position.set(next);
+                let user_message = next.and_then(|next| history.get(next).cloned()).unwrap_or_default();
+                user_message_input.set_value(&user_message);
+                auto_grow(&user_message_input);
+                update_conversation.update(|conversation| {
+                    let Some(conversation) = conversation else { return; };
+                    conversation.user_message = user_message;
+                });
+            };
+
             let on_submit = move |event: SubmitEvent| {
                 event.prevent_default();
+                history_position.set(None);
 
                 let id = id.get_untracked();
+                if loading.with_untracked(|loading| loading.contains(&id)) {
+                    return;
+                }
 
                 let Some(user_message_input) = user_message_input.get_untracked() else {
                     log::error!("user_message_input missing");
@@ -131,12 +478,24 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                 };
 
                 let user_message = user_message_input.value();
-                if user_message.is_empty() {
+                if user_message.is_empty() && pending_images.with_untracked(|images| images.is_empty()) {
                     return;
                 }
 
+                // slash commands are run instead of being sent. if one is invalid, it's
+                // left in the input to be fixed.
+                let command = match settings.with_untracked(|settings| parse_command(&user_message, settings)) {
+                    Some(Ok(command)) => Some(command),
+                    Some(Err(error)) => {
+                        errors.push(Error::from(error));
+                        return;
+                    }
+                    None => None,
+                };
+
                 // clear message field
                 user_message_input.set_value("");
+                auto_grow(&user_message_input);
 
                 // clear message in local storage
                 update_conversation.try_update(|conversation| {
@@ -147,24 +506,67 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                     conversation.user_message = "".to_owned();
                 });
 
-                push_user_message(id, user_message);
+                match command {
+                    Some(Command::Clear) => {
+                        use_navigate()("/", Default::default());
+                        return;
+                    }
+                    Some(command) => {
+                        run_command(id, command);
+                        return;
+                    }
+                    None => {}
+                }
+
+                // overrides only apply to this message
+                let images = pending_images.get_untracked();
+                let overrides = SendOverrides {
+                    model_id: send_with_model.get_untracked(),
+                    temperature: send_with_temperature.get_untracked(),
+                    images: images.clone(),
+                };
+                send_with_model.set(None);
+                send_with_temperature.set(None);
+                pending_images.set(vec![]);
+
+                // the user wants to see the response to their message.
+                stick_to_bottom.set(true);
+                if !push_user_message(id, user_message.clone(), overrides) {
+                    // it wasn't sent, so it's put back for the user to change.
+                    user_message_input.set_value(&user_message);
+                    auto_grow(&user_message_input);
+                    pending_images.set(images);
+                }
             };
 
-            let title = Signal::derive(move || {
+            // the conversation changes with every keystroke in the message input, since the
+            // input is stored with it. so everything else reads the conversation through
+            // memos, which only notify when their part of the conversation changes.
+            let title = create_memo(move |_| {
                 with!(|conversation| conversation.as_ref().and_then(|conversation| conversation.title.clone()))
             });
 
-            let model_id = Signal::derive(move || {
+            let model_id = create_memo(move |_| {
                 with!(|conversation| conversation.as_ref().and_then(|conversation| conversation.model_id.clone()))
             });
 
-            let model_name = Signal::derive(move || {
-                with!(|conversation, settings| {
-                    conversation.as_ref().and_then(move |conversation| {
-                        conversation.model_id.as_ref().and_then(move |model_id| {
-                            settings.models.get(model_id)
-                                .map(|model| model.display_name().to_owned())
-                        })
+            let message_ids = create_memo(move |_| {
+                with!(|conversation| conversation.as_ref().map(|conversation| conversation.messages.clone()).unwrap_or_default())
+            });
+
+            let conversation_parameters = create_memo(move |_| {
+                with!(|conversation| conversation.as_ref().map(|conversation| conversation.conversation_parameters.clone()).unwrap_or_default())
+            });
+
+            let user_message_empty = create_memo(move |_| {
+                with!(|conversation| conversation.as_ref().map_or(true, |conversation| conversation.user_message.is_empty()))
+            });
+
+            let model_name = create_memo(move |_| {
+                with!(|model_id, settings| {
+                    model_id.as_ref().and_then(move |model_id| {
+                        settings.models.get(model_id)
+                            .map(|model| model.display_name().to_owned())
                     })
                 })
             });
@@ -181,12 +583,122 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                 })
             });
 
+            let context_length = Signal::derive(move || {
+                with!(|settings, model_id| {
+                    model_id.as_ref()
+                        .and_then(|model_id| settings.models.get(model_id))
+                        .and_then(|model| model.context_length)
+                })
+            });
+
+            // estimated number of tokens of the prompt without the user message.
+            let history_tokens = create_memo(move |_| {
+                with!(|model_id, message_ids, conversation_parameters, settings| {
+                    let Some(chat_template) = model_id.as_ref()
+                        .and_then(|model_id| settings.models.get(model_id))
+                        .map(|model| model.chat_template)
+                    else {
+                        return 0;
+                    };
+
+                    let messages = message_ids
+                        .iter()
+                        .filter_map(|message_id| {
+                            let StorageSignals { read: message, .. } = use_message(*message_id);
+                            message.get()
+                        })
+                        .collect::<Vec<_>>();
+
+                    let prompt = chat_template.generate_prompt(
+                        conversation_parameters.system_prompt.as_deref(),
+                        &messages,
+                        conversation_parameters.start_response_with.as_deref(),
+                    );
+
+                    count_tokens(&prompt)
+                })
+            });
+
+            let prompt_tokens = Signal::derive(move || {
+                history_tokens.get() + with!(|conversation| {
+                    conversation.as_ref()
+                        .map(|conversation| count_tokens(&conversation.user_message))
+                        .unwrap_or_default()
+                })
+            });
+
+            let is_loading = Signal::derive(move || with!(|loading| loading.contains(&id.get())));
+            let generating_elsewhere = Signal::derive(move || tabs.is_locked_elsewhere(id.get()));
+            let is_warming_up = Signal::derive(move || with!(|warming_up| warming_up.contains(&id.get())));
+
+            let supports_images = Signal::derive(move || {
+                with!(|settings, model_id| {
+                    model_id.as_ref()
+                        .and_then(|model_id| settings.models.get(model_id))
+                        .map_or(false, |model| model.vision)
+                })
+            });
+
+            // the transcript is added to what was already typed.
+            let append_transcript = Callback::new(move |transcript: String| {
+                let Some(user_message_input) = user_message_input.get_untracked() else { return; };
+                let mut user_message = user_message_input.value();
+                if !user_message.is_empty() && !user_message.ends_with(char::is_whitespace) {
+                    user_message.push(' ');
+                }
+                user_message.push_str(&transcript);
+                user_message_input.set_value(&user_message);
+                auto_grow(&user_message_input);
+                update_conversation.update(|conversation| {
+                    let Some(conversation) = conversation else { return; };
+                    conversation.user_message = user_message;
+                });
+            });
+
             let disable_send = Signal::derive(move || {
-                is_loading.get() || with!(|conversation| conversation.as_ref().map(|conversation| conversation.user_message.is_empty()).unwrap_or(true))
+                is_loading.get() || generating_elsewhere.get() || (user_message_empty.get() && pending_images.with(|images| images.is_empty()))
             });
 
             let edit_title = create_rw_signal(false);
 
+            let auto_title = create_memo(move |_| {
+                with!(|conversation| conversation.as_ref().map_or(false, |conversation| conversation.auto_title))
+            });
+
+            // generate a new title from the first user message.
+            let regenerate_title = move || {
+                let id = id.get_untracked();
+                let first_user_message = conversation.with_untracked(|conversation| {
+                    conversation.as_ref()?.messages.iter().find_map(|message_id| {
+                        let StorageSignals { read: message, .. } = use_message(*message_id);
+                        message
+                            .get_untracked()
+                            .filter(|message| matches!(message.role, Role::User))
+                            .map(|message| message.text)
+                    })
+                });
+
+                if let Some(first_user_message) = first_user_message {
+                    request_conversation_title(id, &first_user_message, true);
+                }
+                else {
+                    log::warn!("no user message to generate a title from: {id}");
+                }
+            };
+            let model_card_open = create_rw_signal(false);
+
+            let archived = create_memo(move |_| {
+                with!(|conversation| conversation.as_ref().map_or(false, |conversation| conversation.archived))
+            });
+            let private = is_private(id.get_untracked());
+            let toggle_archived = move |_| {
+                update_conversation.update(|conversation| {
+                    let Some(conversation) = conversation else { return; };
+                    conversation.archived = !conversation.archived;
+                });
+                refresh_summary(id.get_untracked());
+            };
+
             log::debug!("render conversation: {}", id.get_untracked());
 
             // this takes a closure which updates the conversation parameters and returns a closure that only takes the new value.
@@ -203,10 +715,57 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                 }
             }
 
-            let delete_conversation = move |_| {
+            let export_audit_log = move |_| {
                 let id = id.get_untracked();
+                let StorageSignals { read: audit_log, .. } = use_audit_log(id);
+                match audit_log.with_untracked(|audit_log| serde_json::to_string_pretty(audit_log)) {
+                    Ok(json) => download_file(&format!("prompts-{id}.json"), "application/json", &json),
+                    Err(error) => errors.push(error),
+                }
+            };
+
+            let export_html = move |_| export_conversation_html(id.get_untracked());
+
+            let summary = create_memo(move |_| {
+                with!(|conversation| conversation.as_ref().and_then(|conversation| conversation.summary.clone()))
+            });
+            let is_summarizing = Signal::derive(move || with!(|summarizing| summarizing.contains(&id.get())));
+            // whether messages were added after the summary was made.
+            let summary_outdated = Signal::derive(move || {
+                with!(|conversation, summary| {
+                    summary.as_ref().zip(conversation.as_ref()).map_or(false, |(summary, conversation)| {
+                        conversation.messages.last() != Some(&summary.up_to)
+                    })
+                })
+            });
+            let summarize = move |_| summarize_conversation(id.get_untracked());
+            let set_summary_as_context = move |use_as_context: bool| {
+                update_conversation.update(|conversation| {
+                    let Some(summary) = conversation.as_mut().and_then(|conversation| conversation.summary.as_mut()) else { return; };
+                    summary.use_as_context = use_as_context;
+                });
+            };
+            let remove_summary = move |_| {
+                update_conversation.update(|conversation| {
+                    let Some(conversation) = conversation else { return; };
+                    conversation.summary = None;
+                });
+            };
 
-                log::warn!("deleting conversation: {}", id);
+            let share_copied = create_rw_signal(false);
+            let share = move |_| {
+                let Some(conversation) = conversation.get_untracked() else { return; };
+                let messages = conversation.messages
+                    .iter()
+                    .filter_map(|message_id| use_message(*message_id).read.get_untracked())
+                    .collect::<Vec<_>>();
+                copy_to_clipboard(&SharedConversation::new(&conversation, &messages).url());
+                share_copied.set(true);
+                set_timeout(move || share_copied.set(false), SHARE_COPIED_TIMEOUT);
+            };
+
+            let delete_conversation = move |()| {
+                let id = id.get_untracked();
 
                 // browse to home, but don't remember this page in the history.
                 use_navigate()("/", NavigateOptions {
@@ -214,59 +773,22 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                     ..Default::default()
                 });
 
-                // remove from conversations list
-                update!(|update_conversations| {
-                    update_conversations.remove(&id);
-                });
-
-                // remove the conversation
-                let conversation = use_conversation(id);
-                let message_ids = conversation
-                    .read
-                    .with(|conversation| {
-                        let Some(conversation) = conversation else {
-                            log::warn!("conversation gone: {id}");
-                            return vec![];
-                        };
-                        conversation.messages.clone()
-                    });
-                conversation.delete();
-
-                // remove all messages
-                for message_id in message_ids {
-                    delete_storage(StorageKey::Message(message_id));
-                }
+                trash_conversation(id);
             };
 
+            let num_artifacts = Signal::derive(move || {
+                let StorageSignals { read: artifacts, .. } = use_artifacts(id.get());
+                with!(|artifacts| artifacts.len())
+            });
+
             view! {
-                // delete modal
-                <div class="modal fade" id="conversation_delete_modal_modal" tabindex="-1">
-                    <div class="modal-dialog">
-                        <div class="modal-content">
-                            <div class="modal-header">
-                                <h5 class="modal-title">"Delete conversation"</h5>
-                                <button type="button" class="btn-close" data-bs-dismiss="modal" aria-label="Close"></button>
-                            </div>
-                            <div class="modal-body">
-                                <p>"Confirm to delete this conversation."</p>
-                            </div>
-                            <div class="modal-footer">
-                                <button type="button" class="btn btn-secondary" data-bs-dismiss="modal">"Cancel"</button>
-                                <button
-                                    type="button"
-                                    class="btn btn-danger"
-                                    data-bs-dismiss="modal"
-                                    on:click=delete_conversation
-                                >
-                                    "Delete"
-                                </button>
-                            </div>
-                        </div>
-                    </div>
-                </div>
+                <ModelCard model_id=model_id open=model_card_open />
+                <ArtifactsPanel conversation_id=Signal::derive(move || id.get()) />
+
+                <DeleteConversationModal id="conversation_delete_modal_modal" on_delete=delete_conversation />
 
                 // header
-                <div class="d-flex flex-row px-4 pt-3 shadow-sm w-100">
+                <div class="d-flex flex-row flex-wrap px-2 px-md-4 pt-3 shadow-sm w-100">
                     <div class="d-flex flex-row">
                         {move || if edit_title.get() {
                             let edit_title_input = create_node_ref::<Input>();
@@ -284,8 +806,11 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                                         log::warn!("conversation gone");
                                         return;
                                     };
-                                    conversation.title = Some(new_title)
+                                    conversation.title = Some(new_title);
+                                    // the user chose a title, so don't override it with a generated one.
+                                    conversation.auto_title = false;
                                 });
+                                refresh_summary(id.get_untracked());
                                 edit_title.set(false);
                             };
 
@@ -322,6 +847,29 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                                 >
                                     <BootstrapIcon icon="pencil-square" />
                                 </span>
+                                <span
+                                    class="ms-1 mt-1 link-secondary"
+                                    style="cursor: pointer;"
+                                    title="Regenerate title"
+                                    on:click=move |_| regenerate_title()
+                                >
+                                    <BootstrapIcon icon="arrow-clockwise" />
+                                </span>
+                                <span
+                                    class="ms-1 mt-1"
+                                    class:link-secondary=move || !auto_title.get()
+                                    class:link-primary=auto_title
+                                    style="cursor: pointer;"
+                                    title=move || if auto_title.get() { "Automatic title enabled" } else { "Automatic title disabled" }
+                                    on:click=move |_| {
+                                        update_conversation.update(|conversation| {
+                                            let Some(conversation) = conversation else { return; };
+                                            conversation.auto_title = !conversation.auto_title;
+                                        });
+                                    }
+                                >
+                                    <BootstrapIcon icon="magic" />
+                                </span>
                             }.into_view()
                         }}
                     </div>
@@ -330,22 +878,126 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                             model_id.as_ref().map(move |model_id| {
                                 view!{
                                     <h6 class="mt-auto ms-4">
-                                        <span class="badge bg-secondary">
-                                            <a href={model_id.url()} target="_blank" class="text-white text-decoration-none">{model_name.as_ref().cloned()}</a>
+                                        <span
+                                            class="badge bg-secondary"
+                                            style="cursor: pointer;"
+                                            title=model_id.to_string()
+                                            data-bs-toggle="offcanvas"
+                                            data-bs-target=format!("#{MODEL_CARD_OFFCANVAS_ID}")
+                                            on:click=move |_| model_card_open.set(true)
+                                        >
+                                            {model_name.as_ref().cloned()}
                                             <span class="ms-1">
-                                                <BootstrapIcon icon="link-45deg" />
+                                                <BootstrapIcon icon="info-circle" />
                                             </span>
                                         </span>
+                                        {move || context_length.get().map(|context_length| view!{
+                                            <span class="badge text-bg-light border ms-1" title="Context length of the model">
+                                                {format!("{context_length} tokens")}
+                                            </span>
+                                        })}
                                     </h6>
                                 }
                             })
                         })
                     }}
+                    {private.then(|| view!{
+                        <h6 class="mt-auto ms-3">
+                            <span class="badge text-bg-dark" title="This conversation isn't saved, and is gone when you reload the page">
+                                <span class="me-1"><BootstrapIcon icon="incognito" /></span>
+                                "Private"
+                            </span>
+                        </h6>
+                    })}
+                    <ConversationTags
+                        conversation_id=Signal::derive(move || id.get())
+                        tags=create_memo(move |_| with!(|conversation| conversation.as_ref().map(|conversation| conversation.tags.clone()).unwrap_or_default()))
+                        update_conversation
+                    />
                     <div class="d-flex flex-row ms-auto pb-2">
+                        <ConversationStats conversation_id=Signal::derive(move || id.get()) />
+                        {move || (num_artifacts.get() > 0).then(|| view!{
+                            <button
+                                type="button"
+                                class="btn btn-sm btn-outline-secondary me-2"
+                                style="height: 100%;"
+                                title="Artifacts"
+                                data-bs-toggle="offcanvas"
+                                data-bs-target=format!("#{ARTIFACTS_OFFCANVAS_ID}")
+                            >
+                                <BootstrapIcon icon="file-earmark-code" />
+                                <span class="ms-1">{num_artifacts}</span>
+                            </button>
+                        })}
+                        {move || with!(|settings| settings.debug_mode).then(|| view!{
+                            <button
+                                type="button"
+                                class="btn btn-sm btn-outline-secondary me-2"
+                                style="height: 100%;"
+                                title="Export prompts sent to the API"
+                                on:click=export_audit_log
+                            >
+                                <BootstrapIcon icon="journal-code" />
+                            </button>
+                        })}
+                        <button
+                            type="button"
+                            class="btn btn-sm btn-outline-secondary me-2"
+                            style="height: 100%;"
+                            title="Summarize conversation"
+                            aria-label="Summarize conversation"
+                            disabled=move || is_summarizing.get() || message_ids.with(|message_ids| message_ids.is_empty())
+                            on:click=summarize
+                        >
+                            {move || if is_summarizing.get() {
+                                view!{ <div class="spinner-border spinner-border-sm" role="status"></div> }.into_view()
+                            }
+                            else {
+                                view!{ <BootstrapIcon icon="card-text" /> }.into_view()
+                            }}
+                        </button>
+                        <div class="btn-group btn-group-sm me-2" style="height: 100%;">
+                            <button
+                                type="button"
+                                class="btn btn-outline-secondary"
+                                title="Export as HTML"
+                                on:click=export_html
+                            >
+                                <BootstrapIcon icon="filetype-html" />
+                            </button>
+                            <A
+                                class="btn btn-outline-secondary"
+                                href=move || format!("/conversation/{}/print", id.get())
+                            >
+                                <span title="Print"><BootstrapIcon icon="printer" /></span>
+                            </A>
+                        </div>
+                        <button
+                            type="button"
+                            class="btn btn-sm btn-outline-secondary me-2"
+                            style="height: 100%;"
+                            title=move || if share_copied.get() { "Link copied" } else { "Copy a read-only link to this conversation" }
+                            on:click=share
+                        >
+                            {move || view!{ <BootstrapIcon icon=if share_copied.get() { "check-lg" } else { "share" } /> }}
+                        </button>
+                        {(!private).then(|| view!{
+                            <button
+                                type="button"
+                                class="btn btn-sm btn-outline-secondary me-2"
+                                style="height: 100%;"
+                                title=move || if archived.get() { "Unarchive" } else { "Archive" }
+                                on:click=toggle_archived
+                            >
+                                {move || view!{ <BootstrapIcon icon=if archived.get() { "archive-fill" } else { "archive" } /> }}
+                            </button>
+                        })}
                         <button
                             type="button"
                             class="btn btn-sm btn-outline-danger"
                             style="height: 100%;"
+                            title="Delete conversation"
+                            aria-label="Delete conversation"
                             data-bs-toggle="modal"
                             data-bs-target="#conversation_delete_modal_modal"
                         >
@@ -355,16 +1007,66 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                 </div>
 
                 // messages
-                <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100">
+                <div class="d-flex flex-column overflow-y-scroll mb-auto p-2 p-md-4 mw-100" node_ref=scroll_container on:scroll=on_scroll>
+                    {move || summary.get().map(|summary| view!{
+                        <details class="conversation-summary card mb-3">
+                            <summary class="card-header">
+                                <span class="me-1"><BootstrapIcon icon="card-text" /></span>
+                                "Summary"
+                                {summary.use_as_context.then(|| view!{
+                                    <span class="badge text-bg-secondary ms-2">"Used as context"</span>
+                                })}
+                            </summary>
+                            <div class="card-body">
+                                <p class="conversation-summary-text">{summary.text}</p>
+                                {move || summary_outdated.get().then(|| view!{
+                                    <p class="small text-body-secondary">"Newer messages aren't included in this summary."</p>
+                                })}
+                                <div class="d-flex flex-row align-items-center">
+                                    <div class="form-check form-switch me-auto">
+                                        <input
+                                            class="form-check-input"
+                                            type="checkbox"
+                                            role="switch"
+                                            id="conversation_summary_as_context"
+                                            prop:checked=summary.use_as_context
+                                            on:change=move |event| set_summary_as_context(event_target_checked(&event))
+                                        />
+                                        <label class="form-check-label" for="conversation_summary_as_context">
+                                            "Use as context instead of the earlier messages"
+                                        </label>
+                                    </div>
+                                    <button
+                                        type="button"
+                                        class="btn btn-sm btn-outline-secondary me-2"
+                                        disabled=is_summarizing
+                                        on:click=summarize
+                                    >
+                                        "Update"
+                                    </button>
+                                    <button
+                                        type="button"
+                                        class="btn btn-sm btn-outline-danger"
+                                        on:click=remove_summary
+                                    >
+                                        "Remove"
+                                    </button>
+                                </div>
+                            </div>
+                        </details>
+                    })}
                     <For
-                        each=move || with!(|conversation| conversation.as_ref().map(|conversation| conversation.messages.clone()).unwrap_or_default())
+                        each=message_ids
                         key=|message_id| *message_id
                         children=move |message_id| {
                             view! {
-                                <Message id=message_id />
+                                <Message id=message_id conversation_id=id />
                             }
                         }
                     />
+                    <StreamingStatus conversation_id=id />
+                    <ResponseAnnouncer conversation_id=id />
+                    <QueuedMessages conversation_id=id />
                     // some padding at the bottom
                     <div style="min-height: 5em;"></div>
                     // we scroll this div into view whenever messages are added or updates
@@ -373,6 +1075,75 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
 
                 // message form
                 <div class="d-flex flex-column px-3 pt-3 shadow-lg">
+                    {move || (!stick_to_bottom.get()).then(|| view!{
+                        <div class="position-relative">
+                            <button
+                                type="button"
+                                class="btn btn-sm btn-secondary rounded-pill shadow position-absolute start-50 translate-middle-x jump-to-latest"
+                                on:click=jump_to_latest
+                            >
+                                <span class="me-1"><BootstrapIcon icon="arrow-down" /></span>
+                                "Jump to latest"
+                            </button>
+                        </div>
+                    })}
+                    {move || is_warming_up.get().then(|| {
+                        match local_load_progress.get() {
+                            Some(progress) => view!{
+                                <div class="mb-2 small text-body-secondary">
+                                    <div class="mb-1">"Loading model into the browser… " {progress.text}</div>
+                                    <div class="progress" role="progressbar" aria-label="Model download" aria-valuenow=format!("{:.0}", progress.progress * 100.0) aria-valuemin="0" aria-valuemax="100" style="height: 4px">
+                                        <div class="progress-bar" style=format!("width: {:.1}%", progress.progress * 100.0)></div>
+                                    </div>
+                                </div>
+                            }.into_view(),
+                            None => view!{
+                                <small class="mb-2 text-body-secondary">
+                                    <div class="spinner-border spinner-border-sm me-1" role="status"></div>
+                                    "Warming up model… Your message will be sent once it's loaded."
+                                </small>
+                            }.into_view(),
+                        }
+                    })}
+                    {move || (!online.get()).then(|| view!{
+                        <small class="mb-2 text-warning-emphasis">
+                            <span class="me-1"><BootstrapIcon icon="wifi-off" /></span>
+                            "You're offline. New messages are queued and will be sent once you're back online."
+                        </small>
+                    })}
+                    {move || remote_draft.with(|draft| draft.is_some()).then(|| view!{
+                        <small class="mb-2 text-body-secondary">
+                            <span class="me-1"><BootstrapIcon icon="window-stack" /></span>
+                            "The draft was changed in another tab."
+                            <button
+                                type="button"
+                                class="btn btn-link btn-sm p-0 ms-1 align-baseline"
+                                on:click=take_remote_draft
+                            >
+                                "Use it"
+                            </button>
+                            <button
+                                type="button"
+                                class="btn btn-link btn-sm p-0 ms-2 align-baseline"
+                                on:click=move |_| remote_draft.set(None)
+                            >
+                                "Keep mine"
+                            </button>
+                        </small>
+                    })}
+                    {move || generating_elsewhere.get().then(|| view!{
+                        <small class="mb-2 text-warning-emphasis">
+                            <span class="me-1"><BootstrapIcon icon="window-stack" /></span>
+                            "Another tab is generating a response in this conversation."
+                            <button
+                                type="button"
+                                class="btn btn-link btn-sm p-0 ms-1 align-baseline"
+                                on:click=move |_| tabs.take_over(id.get_untracked())
+                            >
+                                "Take over"
+                            </button>
+                        </small>
+                    })}
                     <div class="collapse pb-2" id="sendMessageAdvancedContainer">
                         <ConversationParametersInputGroup
                             value=Signal::derive(move || conversation.with_untracked(|conversation| {
@@ -385,17 +1156,58 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                             on_top_k_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.top_k = value)
                             on_top_p_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.top_p = value)
                             on_repetition_penalty_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.repetition_penalty = value)
+                            on_min_p_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.min_p = value)
+                            on_typical_p_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.typical_p = value)
+                            on_no_repeat_ngram_size_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.no_repeat_ngram_size = value)
+                            on_n_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.n = value)
+                            on_do_sample_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.do_sample = value)
                             on_token_limit_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.token_limit = value)
+                            on_ramp_after_turns_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.ramp_after_turns = value)
+                            on_ramp_temperature_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.ramp_temperature = value)
+                            on_json_schema_input=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.json_schema = value)
                             hide_system_prompt=hide_system_prompt_input
                         />
                     </div>
                     <form on:submit=on_submit>
+                        {move || pending_images.with(|images| (!images.is_empty()).then(|| view!{
+                            <div class="d-flex flex-row flex-wrap mb-2">
+                                {images.iter().cloned().enumerate().map(|(i, image)| view!{
+                                    <div class="position-relative me-2 mb-2">
+                                        <img class="img-thumbnail message-image" src=image />
+                                        <button
+                                            type="button"
+                                            class="btn-close position-absolute top-0 end-0 m-1"
+                                            title="Remove image"
+                                            on:click=move |_| pending_images.update(|images| { images.remove(i); })
+                                        ></button>
+                                    </div>
+                                }).collect_view()}
+                            </div>
+                        }))}
+                        <div class="position-relative">
+                            <small class="position-absolute bottom-0 end-0 me-2 mb-1 text-body-secondary draft-saved" class:show=draft_saved aria-hidden="true">
+                                "Draft saved"
+                            </small>
+                            {move || with!(|command_completions| (!command_completions.is_empty()).then(|| view!{
+                                <ul class="dropdown-menu show position-absolute bottom-0 start-0 mb-1 command-completions" role="listbox" aria-label="Commands">
+                                    {command_completions.iter().copied().map(|command| view!{
+                                        <li>
+                                            <button type="button" class="dropdown-item" on:click=move |_| complete_command(command)>
+                                                <code>"/"{command.name}</code>
+                                                {command.argument.map(|argument| view!{ <span class="text-body-secondary ms-1">{format!("<{argument}>")}</span> })}
+                                                <small class="text-body-secondary ms-3">{command.description}</small>
+                                            </button>
+                                        </li>
+                                    }).collect_view()}
+                                </ul>
+                            }))}
+                        </div>
                         <div class="input-group input-group-lg mb-3">
-                            <input
-                                type="text"
-                                class="form-control"
+                            <textarea
+                                class="form-control message-input"
+                                rows="1"
                                 placeholder="Ask anything"
-                                value=move || {
+                                prop:value=move || {
                                     conversation.with_untracked(|conversation| {
                                         conversation.as_ref()
                                             .map(|conversation| conversation.user_message.clone())
@@ -403,75 +1215,1399 @@ pub fn Conversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoV
                                     })
                                 }
                                 node_ref=user_message_input
+                                on:keydown=on_keydown
+                                on:paste=move |event: Event| {
+                                    if supports_images.get_untracked() && attach_images(pasted_files(&event), pending_images) {
+                                        event.prevent_default();
+                                    }
+                                }
+                                on:dragover=move |event: DragEvent| {
+                                    if supports_images.get_untracked() {
+                                        event.prevent_default();
+                                    }
+                                }
+                                on:drop=move |event: DragEvent| {
+                                    if supports_images.get_untracked() && attach_images(event.data_transfer().and_then(|data| data.files()), pending_images) {
+                                        event.prevent_default();
+                                    }
+                                }
                                 on:input=move |event| {
+                                    // a previous message that is edited is a new one.
+                                    history_position.set(None);
+                                    if let Some(user_message_input) = user_message_input.get_untracked() {
+                                        auto_grow(&user_message_input);
+                                    }
                                     let user_message = event_target_value(&event);
                                     update_conversation.update(|conversation| {
                                         let Some(conversation) = conversation else { return; };
                                         conversation.user_message = user_message
                                     });
+                                    show_draft_saved();
                                 }
-                            />
-                            <button class="btn btn-outline-secondary" type="submit" disabled=disable_send>
-                                {move || {
-                                    if is_loading.get() {
-                                        view! {
-                                            <div class="spinner-border spinner-border-sm" role="status">
-                                                <span class="visually-hidden">"Generating..."</span>
-                                            </div>
-                                        }.into_view()
-                                    }
-                                    else {
-                                        view!{ <BootstrapIcon icon="send" /> }.into_view()
-                                    }
-                                }}
+                            ></textarea>
+                            <VoiceInput on_transcript=append_transcript disabled=is_loading />
+                            {move || if is_loading.get() {
+                                view! {
+                                    <button
+                                        class="btn btn-outline-danger message-form-button"
+                                        type="button"
+                                        title="Stop generating"
+                                        aria-label="Stop generating"
+                                        on:click=move |_| stop_generation(id.get_untracked())
+                                    >
+                                        <BootstrapIcon icon="stop-fill" />
+                                        <span class="visually-hidden">"Stop generating"</span>
+                                    </button>
+                                }
+                            }
+                            else {
+                                view! {
+                                    <button class="btn btn-outline-secondary message-form-button" type="submit" title="Send" aria-label="Send" disabled=disable_send>
+                                        <BootstrapIcon icon="send" />
+                                    </button>
+                                }
+                            }}
+                            <button
+                                class="btn btn-outline-secondary dropdown-toggle dropdown-toggle-split"
+                                class:text-primary=has_overrides
+                                type="button"
+                                title="Send with…"
+                                data-bs-toggle="dropdown"
+                                data-bs-auto-close="outside"
+                                aria-expanded="false"
+                            >
+                                <span class="visually-hidden">"Send with…"</span>
                             </button>
+                            <div class="dropdown-menu dropdown-menu-end p-3 send-with">
+                                <h6 class="dropdown-header px-0">"Send this message with…"</h6>
+                                <div class="form-floating mb-2">
+                                    <select
+                                        class="form-select"
+                                        id="send_with_model"
+                                        on:change=move |event| send_with_model.set(non_empty(event_target_value(&event)).map(ModelId))
+                                    >
+                                        <option value="" selected=move || with!(|send_with_model| send_with_model.is_none())>"Conversation model"</option>
+                                        <For
+                                            each=move || with!(|settings| {
+                                                let mut items = settings.models
+                                                    .iter()
+                                                    .map(|(id, model)| (id.clone(), model.display_name().to_owned()))
+                                                    .collect::<Vec<_>>();
+                                                items.sort_by_cached_key(|(_, name)| name.to_lowercase());
+                                                items
+                                            })
+                                            key=|(model_id, _)| model_id.clone()
+                                            children=move |(model_id, display_name)| {
+                                                let value = model_id.to_string();
+                                                view!{
+                                                    <option
+                                                        value=value
+                                                        selected=move || with!(|send_with_model| send_with_model.as_ref() == Some(&model_id))
+                                                    >
+                                                        {display_name}
+                                                    </option>
+                                                }
+                                            }
+                                        />
+                                    </select>
+                                    <label for="send_with_model">"Model"</label>
+                                </div>
+                                <div class="form-floating mb-2">
+                                    <input
+                                        type="number"
+                                        class="form-control"
+                                        id="send_with_temperature"
+                                        min="0"
+                                        step="0.1"
+                                        placeholder="Conversation temperature"
+                                        prop:value=move || with!(|send_with_temperature| send_with_temperature.map(|value| value.to_string()).unwrap_or_default())
+                                        on:input=move |event| send_with_temperature.set(non_empty(event_target_value(&event)).and_then(|value| value.parse().ok()))
+                                    />
+                                    <label for="send_with_temperature">"Temperature"</label>
+                                </div>
+                                <button class="btn btn-primary btn-sm w-100" type="submit" disabled=disable_send>
+                                    "Send"
+                                </button>
+                            </div>
                             <button class="btn btn-outline-secondary" type="button" data-bs-toggle="collapse" data-bs-target="#sendMessageAdvancedContainer"><BootstrapIcon icon="three-dots" /></button>
                         </div>
                     </form>
+                    <div class="d-flex flex-row justify-content-between align-items-start">
+                        <ResponseLengthButtons
+                            value=Signal::derive(move || with!(|conversation_parameters| conversation_parameters.response_length))
+                            on_change=update_conversation_parameters(update_conversation, |params: &mut ConversationParameters, value| params.response_length = value)
+                        />
+                        <TokenCount tokens=prompt_tokens context_length=context_length />
+                    </div>
                 </div>
             }
         }}
     }
 }
 
-#[component]
-fn Message(#[prop(into)] id: MaybeSignal<MessageId>) -> impl IntoView {
-    let message = Signal::derive(move || {
-        let StorageSignals { read: message, .. } = use_message(id.get());
-        message.get()
-    });
-
-    view! {
-        {move || {
-            // when the assistant replies, there is a moment where the message id is logged, but the message hasn't been created yet.
-            // not sure if this is a good way to do this, but we can just ignore the message in this case.
-            message.get().map(|message| {
-                let is_assistant = matches!(message.role, Role::Assitant);
-                let html = markdown::to_html(&message.text);
+/// Runs a slash command, except for `/clear`, which needs the router.
+fn run_command(conversation_id: ConversationId, command: Command) {
+    let Context { errors, .. } = expect_context();
 
-                view!{
-                    <div
-                        class="rounded rounded-3 w-75 mw-75 my-2 p-2 shadow-sm message markdown"
-                        class:ms-auto=is_assistant
-                        inner_html=html
-                    >
-                    </div>
-                }
+    match command {
+        Command::System(system_prompt) => {
+            update_conversation(conversation_id, |conversation| {
+                conversation.conversation_parameters.system_prompt = system_prompt;
             })
-        }}
-    }
-}
-
-#[component]
-pub fn ConversationParametersInputGroup(
-    #[prop(into, optional)] value: MaybeSignal<ConversationParameters>,
-    #[prop(into, optional)] on_system_prompt_input: Option<Callback<Option<String>>>,
-    #[prop(into, optional)] on_token_limit_input: Option<Callback<Option<usize>>>,
+        }
+        Command::Model(model_id) => {
+            update_conversation(conversation_id, |conversation| {
+                conversation.model_id = Some(model_id);
+            })
+        }
+        Command::Title(title) => {
+            update_conversation(conversation_id, |conversation| {
+                conversation.title = Some(title);
+                // the user chose a title, so don't override it with a generated one.
+                conversation.auto_title = false;
+            })
+        }
+        Command::Retry => {
+            let last_response = use_conversation(conversation_id)
+                .read
+                .with_untracked(|conversation| conversation.as_ref()?.messages.last().copied())
+                .filter(|message_id| {
+                    use_message(*message_id).read.with_untracked(|message| {
+                        message
+                            .as_ref()
+                            .map_or(false, |message| matches!(message.role, Role::Assitant))
+                    })
+                });
+            match last_response {
+                Some(message_id) => retry_response(conversation_id, message_id),
+                None => errors.push(Error::from(CommandError::NothingToRetry)),
+            }
+        }
+        Command::Clear => {}
+    }
+}
+
+/// Asks to confirm before a conversation is moved to the trash. It's opened
+/// with `data-bs-toggle="modal"` and `data-bs-target="#{id}"`.
+#[component]
+pub fn DeleteConversationModal(
+    id: &'static str,
+    #[prop(into)] on_delete: Callback<()>,
+    /// How many conversations are deleted at once.
+    #[prop(into, default = 1.into())]
+    count: MaybeSignal<usize>,
+) -> impl IntoView {
+    view! {
+        <div class="modal fade" id=id tabindex="-1">
+            <div class="modal-dialog">
+                <div class="modal-content">
+                    <div class="modal-header">
+                        <h5 class="modal-title">{move || if count.get() == 1 { "Delete conversation" } else { "Delete conversations" }}</h5>
+                        <button type="button" class="btn-close" data-bs-dismiss="modal" aria-label="Close"></button>
+                    </div>
+                    <div class="modal-body">
+                        <p>
+                            {move || match count.get() {
+                                1 => "Confirm to move this conversation to the trash.".to_owned(),
+                                count => format!("Confirm to move {count} conversations to the trash."),
+                            }}
+                        </p>
+                    </div>
+                    <div class="modal-footer">
+                        <button type="button" class="btn btn-secondary" data-bs-dismiss="modal">"Cancel"</button>
+                        <button
+                            type="button"
+                            class="btn btn-danger"
+                            data-bs-dismiss="modal"
+                            on:click=move |_| on_delete(())
+                        >
+                            "Delete"
+                        </button>
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+/// Downloads a conversation as a standalone HTML page.
+pub fn export_conversation_html(id: ConversationId) {
+    let Context { settings, .. } = expect_context();
+    let StorageSignals {
+        read: conversation, ..
+    } = use_conversation(id);
+
+    let Some(conversation) = conversation.get_untracked()
+    else {
+        return;
+    };
+    let model_name = settings.with_untracked(|settings| {
+        let model_id = conversation.model_id.as_ref()?;
+        settings
+            .models
+            .get(model_id)
+            .map(|model| model.display_name().to_owned())
+    });
+    let messages = conversation
+        .messages
+        .iter()
+        .filter_map(|message_id| use_message(*message_id).read.get_untracked())
+        .collect::<Vec<_>>();
+    let document = conversation_document(&conversation, model_name.as_deref(), &messages);
+    let file_name = format!(
+        "{}.html",
+        conversation.title.as_deref().unwrap_or("conversation")
+    );
+    download_file(&file_name, "text/html", &document);
+}
+
+/// The id of a message's element in the page, so that it can be linked to.
+pub fn message_anchor(id: MessageId) -> String {
+    format!("msg-{id}")
+}
+
+#[component]
+fn Message(
+    #[prop(into)] id: MaybeSignal<MessageId>,
+    #[prop(into)] conversation_id: MaybeSignal<ConversationId>,
+) -> impl IntoView {
+    let Context {
+        loading,
+        online,
+        settings,
+        toasts,
+        bookmarks,
+        update_bookmarks,
+        ..
+    } = expect_context();
+
+    let is_loading =
+        Signal::derive(move || with!(|loading| loading.contains(&conversation_id.get())));
+
+    let conversation_model_id = create_memo(move |_| {
+        let StorageSignals {
+            read: conversation, ..
+        } = use_conversation(conversation_id.get());
+        with!(|conversation| {
+            conversation
+                .as_ref()
+                .and_then(|conversation| conversation.model_id.clone())
+        })
+    });
+
+    let message = Signal::derive(move || {
+        let StorageSignals { read: message, .. } = use_message(id.get());
+        message.get()
+    });
+
+    // a response can cite the web search results right before it.
+    let sources = create_memo(move |_| {
+        let StorageSignals {
+            read: conversation, ..
+        } = use_conversation(conversation_id.get());
+        let previous = with!(|conversation| {
+            let messages = &conversation.as_ref()?.messages;
+            let index = messages
+                .iter()
+                .position(|message_id| *message_id == id.get())?;
+            index.checked_sub(1).map(|index| messages[index])
+        });
+        let Some(previous) = previous
+        else {
+            return vec![];
+        };
+        let StorageSignals { read: previous, .. } = use_message(previous);
+        with!(|previous| {
+            previous
+                .as_ref()
+                .filter(|message| message.tool.is_some())
+                .map(|message| search_sources(&message.text))
+                .unwrap_or_default()
+        })
+    });
+
+    // the text changes with every streamed token, so only the parts that depend on
+    // it are updated, instead of rebuilding the whole message.
+    let role = create_memo(move |_| with!(|message| message.as_ref().map(|message| message.role)));
+    let html = create_memo(move |_| {
+        with!(|message, settings, conversation_model_id, sources| {
+            message
+                .as_ref()
+                .map(|message| {
+                    if let Some(tool) = &message.tool {
+                        return tool_result_html(tool, &message.text);
+                    }
+                    if !matches!(message.role, Role::Assitant) {
+                        return render_markdown(&message.text);
+                    }
+
+                    let (reasoning, answer) =
+                        split_reasoning(settings, message, conversation_model_id.as_ref());
+
+                    let mut html = String::new();
+                    if let Some(reasoning) = reasoning {
+                        html.push_str("<details class=\"reasoning\"><summary>");
+                        html.push_str(if answer.is_empty() {
+                            "Thinking..."
+                        }
+                        else {
+                            "Reasoning"
+                        });
+                        html.push_str("</summary>");
+                        html.push_str(&render_markdown(reasoning));
+                        html.push_str("</details>");
+                    }
+                    match parse_json_reply(message, answer) {
+                        Some(Ok(value)) => {
+                            let pretty = serde_json::to_string_pretty(&value).unwrap_or_default();
+                            html.push_str(&render_markdown(&format!("```json\n{pretty}\n```")));
+                        }
+                        _ => {
+                            let answer_html = wrap_diagrams(&render_markdown(answer));
+                            if sources.is_empty() {
+                                html.push_str(&answer_html);
+                            }
+                            else {
+                                html.push_str(&link_citations(&answer_html, sources));
+                            }
+                        }
+                    }
+                    html
+                })
+                .unwrap_or_default()
+        })
+    });
+    let is_tool_result = create_memo(move |_| {
+        with!(|message| {
+            message
+                .as_ref()
+                .map_or(false, |message| message.tool.is_some())
+        })
+    });
+    let invalid_json = create_memo(move |_| {
+        with!(|message, settings, conversation_model_id| {
+            message.as_ref().map_or(false, |message| {
+                let (_, answer) =
+                    split_reasoning(settings, message, conversation_model_id.as_ref());
+                matches!(parse_json_reply(message, answer), Some(Err(_)))
+            })
+        })
+    });
+
+    // diagrams are drawn once the response is done, since they can't be parsed
+    // while they're still being generated.
+    let content = create_node_ref::<Div>();
+    create_effect(move |_| {
+        html.with(|_| ());
+        if is_loading.get() {
+            return;
+        }
+        if let Some(content) = content.get() {
+            // wait until the new html is in the page.
+            let content = web_sys::Element::clone(&content);
+            request_animation_frame(move || {
+                spawn_local(async move { render_diagrams(&content).await });
+            });
+        }
+    });
+    let show_promote = create_memo(move |_| {
+        with!(|message| {
+            message.as_ref().map_or(false, |message| {
+                matches!(message.role, Role::Assitant) && can_promote(message)
+            })
+        })
+    });
+    let generation = create_memo(move |_| {
+        with!(|message| {
+            message
+                .as_ref()
+                .and_then(|message| message.generation.clone())
+        })
+    });
+    let images = create_memo(move |_| {
+        with!(|message| {
+            message
+                .as_ref()
+                .map(|message| message.images.clone())
+                .unwrap_or_default()
+        })
+    });
+    let error = create_memo(move |_| {
+        with!(|message| {
+            message
+                .as_ref()
+                .and_then(|message| message.status.failure_reason().map(ToOwned::to_owned))
+        })
+    });
+
+    // only the last response can be continued, if it was cut off.
+    let is_last = create_memo(move |_| {
+        let StorageSignals {
+            read: conversation, ..
+        } = use_conversation(conversation_id.get());
+        with!(|conversation| {
+            conversation
+                .as_ref()
+                .and_then(|conversation| conversation.messages.last().copied())
+                == Some(id.get())
+        })
+    });
+    let show_continue = create_memo(move |_| {
+        is_last.get()
+            && generation.with(|generation| {
+                generation
+                    .as_ref()
+                    .map_or(false, |generation| generation.truncated)
+            })
+    });
+
+    let continue_generating = move |_| {
+        continue_response(conversation_id.get_untracked(), id.get_untracked());
+    };
+
+    let retry = move |_| {
+        retry_response(conversation_id.get_untracked(), id.get_untracked());
+    };
+
+    let promote = move |_| {
+        if let Some(message) = message.get_untracked() {
+            promote_to_artifact(conversation_id.get_untracked(), &message);
+        }
+    };
+
+    let rating =
+        create_memo(move |_| with!(|message| message.as_ref().and_then(|message| message.rating)));
+    let show_rating = create_memo(move |_| {
+        with!(|message| {
+            message.as_ref().map_or(false, |message| {
+                matches!(message.role, Role::Assitant) && message.status == MessageStatus::Complete
+            })
+        })
+    });
+    // rating a response the same way again takes the rating back.
+    let rate = move |new_rating: Rating| {
+        use_message(id.get_untracked())
+            .write
+            .update(move |message| {
+                if let Some(message) = message {
+                    message.rating = (message.rating != Some(new_rating)).then_some(new_rating);
+                }
+            });
+    };
+
+    // the responses to choose from, and which one is chosen.
+    let candidates = create_memo(move |_| {
+        with!(|message| {
+            message.as_ref().map_or((0, None), |message| {
+                (message.candidates.len(), message.selected_candidate())
+            })
+        })
+    });
+    let select_candidate = move |index: usize| {
+        use_message(id.get_untracked())
+            .write
+            .update(move |message| {
+                if let Some(message) = message {
+                    message.select_candidate(index);
+                }
+            });
+    };
+
+    // notes are only shown here, and aren't part of the prompt.
+    let note = create_memo(move |_| {
+        with!(|message| message.as_ref().and_then(|message| message.note.clone()))
+    });
+    let editing_note = create_rw_signal(false);
+    let note_input = create_node_ref::<Textarea>();
+    let save_note = move || {
+        let Some(note_input) = note_input.get_untracked()
+        else {
+            return;
+        };
+        let note = non_empty(note_input.value().trim().to_owned());
+        use_message(id.get_untracked())
+            .write
+            .update(move |message| {
+                if let Some(message) = message {
+                    message.note = note;
+                }
+            });
+        editing_note.set(false);
+    };
+    create_effect(move |_| {
+        if let Some(note_input) = note_input.get() {
+            auto_grow(&note_input);
+            let _ = note_input.focus();
+        }
+    });
+
+    // bookmarks are kept in storage, so private conversations can't have any.
+    let can_bookmark = Signal::derive(move || !is_private(conversation_id.get()));
+    let is_bookmarked =
+        Signal::derive(move || with!(|bookmarks| bookmarks.contains_key(&id.get())));
+    let toggle_bookmark = move |_| {
+        let id = id.get_untracked();
+        let conversation_id = conversation_id.get_untracked();
+        update_bookmarks.update(|bookmarks| {
+            if bookmarks.remove(&id).is_none() {
+                bookmarks.insert(
+                    id,
+                    Bookmark {
+                        conversation_id,
+                        timestamp: Utc::now(),
+                    },
+                );
+            }
+        });
+    };
+
+    let copy_link = move |_| {
+        let location = window().location();
+        let origin = location.origin().unwrap_or_default();
+        let path = location.pathname().unwrap_or_default();
+        copy_to_clipboard(&format!(
+            "{origin}{path}#{}",
+            message_anchor(id.get_untracked())
+        ));
+        toasts.success("Link to the message copied");
+    };
+
+    // resending appends the message again as a new turn at the end of the
+    // conversation.
+    let resend = move |_| {
+        if let Some(message) = message.get_untracked() {
+            push_user_message(
+                conversation_id.get_untracked(),
+                message.text,
+                SendOverrides {
+                    images: message.images,
+                    ..Default::default()
+                },
+            );
+        }
+    };
+
+    view! {
+        {move || {
+            // when the assistant replies, there is a moment where the message id is logged, but the message hasn't been created yet.
+            // not sure if this is a good way to do this, but we can just ignore the message in this case.
+            role.get().map(move |role| {
+                let is_assistant = matches!(role, Role::Assitant);
+
+                view!{
+                    <div
+                        class="d-flex flex-column w-75 mw-75 my-2 message-container"
+                        class:ms-auto=move || is_assistant || is_tool_result.get()
+                        id=move || message_anchor(id.get())
+                    >
+                        {move || with!(|images| (!images.is_empty()).then(|| view!{
+                            <div class="d-flex flex-row flex-wrap mb-1">
+                                {images.iter().map(|image| view!{
+                                    <img class="img-thumbnail message-image me-2 mb-1" src=image.clone() />
+                                }).collect_view()}
+                            </div>
+                        }))}
+                        {move || with!(|candidates| {
+                            let (count, selected) = *candidates;
+                            (count > 1).then(|| view!{
+                                <ul class="nav nav-pills nav-sm mb-1 message-candidates" role="tablist" aria-label="Responses">
+                                    {(0..count).map(|index| view!{
+                                        <li class="nav-item" role="presentation">
+                                            <button
+                                                type="button"
+                                                class="nav-link py-0 px-2"
+                                                class:active=selected == Some(index)
+                                                role="tab"
+                                                aria-selected=(selected == Some(index)).to_string()
+                                                title=format!("Response {} of {count}", index + 1)
+                                                disabled=is_loading
+                                                on:click=move |_| select_candidate(index)
+                                            >
+                                                {index + 1}
+                                            </button>
+                                        </li>
+                                    }).collect_view()}
+                                </ul>
+                            })
+                        })}
+                        <div
+                            class="rounded rounded-3 p-2 shadow-sm message markdown"
+                            class:message-user=!is_assistant
+                            class:message-assistant=is_assistant
+                            class:message-failed=move || with!(|error| error.is_some())
+                            inner_html=html
+                            node_ref=content
+                            on:click=move |event| toggle_diagram(&event)
+                        >
+                        </div>
+                        {move || error.get().map(|error| view!{
+                            <div class="d-flex flex-row align-items-center px-1 text-danger">
+                                <small class="text-truncate me-2" title=error.clone()>
+                                    <span class="me-1"><BootstrapIcon icon="exclamation-circle" /></span>
+                                    "Failed: "
+                                    {error}
+                                </small>
+                                {move || is_last.get().then(|| view!{
+                                    <button
+                                        type="button"
+                                        class="btn btn-sm btn-link link-danger p-0"
+                                        disabled=move || is_loading.get() || !online.get()
+                                        on:click=retry
+                                    >
+                                        "Retry"
+                                    </button>
+                                })}
+                            </div>
+                        })}
+                        {move || if editing_note.get() {
+                            view!{
+                                <form
+                                    class="d-flex flex-column px-1 mt-1 message-note"
+                                    on:submit=move |event: SubmitEvent| {
+                                        event.prevent_default();
+                                        save_note();
+                                    }
+                                >
+                                    <textarea
+                                        class="form-control form-control-sm"
+                                        rows="1"
+                                        placeholder="Add a note. It's only shown to you."
+                                        aria-label="Note"
+                                        prop:value=move || note.get_untracked().unwrap_or_default()
+                                        node_ref=note_input
+                                        on:input=move |_| {
+                                            if let Some(note_input) = note_input.get_untracked() {
+                                                auto_grow(&note_input);
+                                            }
+                                        }
+                                        on:keydown=move |event: KeyboardEvent| {
+                                            if event.key() == "Escape" {
+                                                editing_note.set(false);
+                                            }
+                                            else {
+                                                submit_on_enter(event);
+                                            }
+                                        }
+                                    ></textarea>
+                                    <div class="d-flex flex-row mt-1">
+                                        <button type="submit" class="btn btn-sm btn-primary me-2">"Save"</button>
+                                        <button type="button" class="btn btn-sm btn-outline-secondary" on:click=move |_| editing_note.set(false)>"Cancel"</button>
+                                    </div>
+                                </form>
+                            }.into_view()
+                        }
+                        else {
+                            note.get().map(|note| view!{
+                                <div
+                                    class="px-1 mt-1 small text-body-secondary message-note"
+                                    title="Edit note"
+                                    on:click=move |_| editing_note.set(true)
+                                >
+                                    <span class="me-1"><BootstrapIcon icon="sticky" /></span>
+                                    {note}
+                                </div>
+                            }).into_view()
+                        }}
+                        <div class="d-flex flex-row px-1 message-actions">
+                            {(!is_assistant).then(move || view!{
+                                <button
+                                    type="button"
+                                    class="btn btn-sm btn-link link-secondary p-0 me-2"
+                                    title="Resend as new message"
+                                    class:d-none=is_tool_result
+                                    disabled=is_loading
+                                    on:click=resend
+                                >
+                                    <BootstrapIcon icon="arrow-repeat" />
+                                </button>
+                            })}
+                            {move || show_continue.get().then(move || view!{
+                                <button
+                                    type="button"
+                                    class="btn btn-sm btn-link link-secondary p-0 me-2"
+                                    title="The response was cut off by the token limit. Continue generating."
+                                    disabled=move || is_loading.get() || !online.get()
+                                    on:click=continue_generating
+                                >
+                                    <BootstrapIcon icon="fast-forward" />
+                                </button>
+                            })}
+                            {move || invalid_json.get().then(|| view!{
+                                <small class="text-warning me-2" title="A JSON schema was set, but the response isn't valid JSON.">
+                                    <span class="me-1"><BootstrapIcon icon="exclamation-triangle" /></span>
+                                    "Invalid JSON"
+                                </small>
+                            })}
+                            {move || show_rating.get().then(|| view!{
+                                <button
+                                    type="button"
+                                    class="btn btn-sm btn-link link-secondary p-0 me-2"
+                                    class:message-rated=move || rating.get() == Some(Rating::Good)
+                                    title="Good response"
+                                    aria-label="Good response"
+                                    aria-pressed=move || (rating.get() == Some(Rating::Good)).to_string()
+                                    on:click=move |_| rate(Rating::Good)
+                                >
+                                    {move || if rating.get() == Some(Rating::Good) {
+                                        view!{ <BootstrapIcon icon="hand-thumbs-up-fill" /> }
+                                    }
+                                    else {
+                                        view!{ <BootstrapIcon icon="hand-thumbs-up" /> }
+                                    }}
+                                </button>
+                                <button
+                                    type="button"
+                                    class="btn btn-sm btn-link link-secondary p-0 me-2"
+                                    class:message-rated=move || rating.get() == Some(Rating::Bad)
+                                    title="Bad response"
+                                    aria-label="Bad response"
+                                    aria-pressed=move || (rating.get() == Some(Rating::Bad)).to_string()
+                                    on:click=move |_| rate(Rating::Bad)
+                                >
+                                    {move || if rating.get() == Some(Rating::Bad) {
+                                        view!{ <BootstrapIcon icon="hand-thumbs-down-fill" /> }
+                                    }
+                                    else {
+                                        view!{ <BootstrapIcon icon="hand-thumbs-down" /> }
+                                    }}
+                                </button>
+                            })}
+                            <button
+                                type="button"
+                                class="btn btn-sm btn-link link-secondary p-0 me-2"
+                                title=move || if with!(|note| note.is_some()) { "Edit note" } else { "Add note" }
+                                aria-label=move || if with!(|note| note.is_some()) { "Edit note" } else { "Add note" }
+                                on:click=move |_| editing_note.set(true)
+                            >
+                                <BootstrapIcon icon="sticky" />
+                            </button>
+                            {move || can_bookmark.get().then(|| view!{
+                                <button
+                                    type="button"
+                                    class="btn btn-sm btn-link link-secondary p-0 me-2"
+                                    class:message-bookmarked=is_bookmarked
+                                    title=move || if is_bookmarked.get() { "Remove bookmark" } else { "Bookmark" }
+                                    aria-label=move || if is_bookmarked.get() { "Remove bookmark" } else { "Bookmark" }
+                                    aria-pressed=move || is_bookmarked.get().to_string()
+                                    on:click=toggle_bookmark
+                                >
+                                    {move || if is_bookmarked.get() {
+                                        view!{ <BootstrapIcon icon="star-fill" /> }
+                                    }
+                                    else {
+                                        view!{ <BootstrapIcon icon="star" /> }
+                                    }}
+                                </button>
+                            })}
+                            <button
+                                type="button"
+                                class="btn btn-sm btn-link link-secondary p-0 me-2"
+                                title="Copy link to message"
+                                aria-label="Copy link to message"
+                                on:click=copy_link
+                            >
+                                <BootstrapIcon icon="link-45deg" />
+                            </button>
+                            {move || show_promote.get().then(move || view!{
+                                <button
+                                    type="button"
+                                    class="btn btn-sm btn-link link-secondary p-0 me-2"
+                                    title="Promote to artifact"
+                                    data-bs-toggle="offcanvas"
+                                    data-bs-target=format!("#{ARTIFACTS_OFFCANVAS_ID}")
+                                    on:click=promote
+                                >
+                                    <BootstrapIcon icon="file-earmark-code" />
+                                </button>
+                            })}
+                        </div>
+                        {move || generation.get().map(|generation| view!{ <GenerationDetails generation /> })}
+                    </div>
+                }
+            })
+        }}
+    }
+}
+
+/// Tool results are folded, since they can be long.
+fn tool_result_html(tool: &str, text: &str) -> String {
+    let mut html = "<details class=\"tool-result\"><summary>Result of <code>".to_owned();
+    escape_html(tool, &mut html);
+    html.push_str("</code></summary><pre>");
+    escape_html(text, &mut html);
+    html.push_str("</pre></details>");
+    html
+}
+
+/// Splits a response into its reasoning, if the model has reasoning
+/// delimiters, and the answer.
+fn split_reasoning<'a>(
+    settings: &Settings,
+    message: &'a crate::state::Message,
+    model_id: Option<&ModelId>,
+) -> (Option<&'a str>, &'a str) {
+    settings
+        .reasoning_delimiters(message, model_id)
+        .map_or((None, message.text.as_str()), |delimiters| {
+            delimiters.split(&message.text)
+        })
+}
+
+/// Parses the answer of a response that was requested as JSON. `None` if no
+/// JSON schema was set, or the response isn't done yet.
+fn parse_json_reply(
+    message: &crate::state::Message,
+    answer: &str,
+) -> Option<Result<serde_json::Value, serde_json::Error>> {
+    message
+        .generation
+        .as_ref()?
+        .conversation_parameters
+        .json_schema
+        .as_ref()?;
+    Some(serde_json::from_str(answer.trim()))
+}
+
+/// Shows the progress of the response while it's being generated.
+#[component]
+fn StreamingStatus(#[prop(into)] conversation_id: MaybeSignal<ConversationId>) -> impl IntoView {
+    let Context { stream_status, .. } = expect_context();
+
+    let status = create_memo(move |_| {
+        with!(|stream_status| stream_status.get(&conversation_id.get()).cloned())
+    });
+
+    // update the elapsed time even when no tokens arrive
+    let now = create_rw_signal(Utc::now());
+    if let Ok(handle) =
+        set_interval_with_handle(move || now.set(Utc::now()), STREAMING_STATUS_INTERVAL)
+    {
+        on_cleanup(move || handle.clear());
+    }
+
+    move || {
+        let status = status.get()?;
+        let now = now.get().max(status.started);
+        let elapsed = (now - status.started).num_milliseconds() as f64 / 1000.0;
+        let tokens_per_second = status
+            .tokens_per_second(now)
+            .map(|tokens_per_second| format!("{tokens_per_second:.1} tokens/s"))
+            .unwrap_or_else(|| "waiting for first token".to_owned());
+
+        Some(view! {
+            // this changes too often to be read out, see `ResponseAnnouncer`.
+            <div class="d-flex flex-row small text-body-secondary font-monospace w-75 ms-auto px-1" aria-hidden="true">
+                <span class="me-3">{format!("{} tokens", status.tokens)}</span>
+                <span class="me-3">{tokens_per_second}</span>
+                <span>{format!("{elapsed:.1}s")}</span>
+            </div>
+        })
+    }
+}
+
+/// Tells screen readers about responses. The streamed text changes with every
+/// token, so instead of making the messages a live region, this announces when
+/// a response starts, and reads it once it's done.
+#[component]
+fn ResponseAnnouncer(#[prop(into)] conversation_id: MaybeSignal<ConversationId>) -> impl IntoView {
+    let Context { loading, .. } = expect_context();
+
+    let announcement = create_rw_signal(String::new());
+    let state = create_memo(move |_| {
+        let conversation_id = conversation_id.get();
+        let is_loading = with!(|loading| loading.contains(&conversation_id));
+        (conversation_id, is_loading)
+    });
+
+    create_effect(move |previous: Option<(ConversationId, bool)>| {
+        let (conversation_id, is_loading) = state.get();
+        // nothing is announced when another conversation is opened.
+        let Some((_, was_loading)) =
+            previous.filter(|(previous_id, _)| *previous_id == conversation_id)
+        else {
+            return (conversation_id, is_loading);
+        };
+
+        if is_loading && !was_loading {
+            announcement.set("Generating a response".to_owned());
+        }
+        else if was_loading && !is_loading {
+            let response = use_conversation(conversation_id)
+                .read
+                .with_untracked(|conversation| conversation.as_ref()?.messages.last().copied())
+                .and_then(|message_id| use_message(message_id).read.get_untracked())
+                .filter(|message| matches!(message.role, Role::Assitant));
+            if let Some(response) = response {
+                announcement.set(match response.status.failure_reason() {
+                    Some(reason) => format!("The response failed: {reason}"),
+                    None => response.text,
+                });
+            }
+        }
+
+        (conversation_id, is_loading)
+    });
+
+    view! {
+        <div class="visually-hidden" role="status" aria-live="polite" aria-atomic="true">
+            {announcement}
+        </div>
+    }
+}
+
+/// Messages that were sent while offline, or while another tab was generating,
+/// and are waiting in the outbox.
+#[component]
+fn QueuedMessages(#[prop(into)] conversation_id: MaybeSignal<ConversationId>) -> impl IntoView {
+    let Context {
+        outbox,
+        update_outbox,
+        ..
+    } = expect_context();
+
+    let queued = create_memo(move |_| {
+        with!(|outbox| {
+            outbox
+                .iter()
+                .filter(|entry| entry.conversation_id == conversation_id.get())
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    });
+
+    view! {
+        <For
+            each=queued
+            key=|entry| entry.id
+            children=move |entry| {
+                let id = entry.id;
+                let cancel = move |_| {
+                    update_outbox.update(|outbox| outbox.retain(|entry| entry.id != id));
+                };
+                view!{
+                    <div class="d-flex flex-column w-75 mw-75 my-2 message-container">
+                        <div
+                            class="rounded rounded-3 p-2 shadow-sm message message-user markdown opacity-75"
+                            inner_html=render_markdown(&entry.text)
+                        >
+                        </div>
+                        <div class="d-flex flex-row align-items-center px-1 message-actions">
+                            <small
+                                class="text-body-secondary me-2"
+                                title=if entry.tab_id.is_some() { "This message will be sent once the other tab is done." } else { "This message will be sent once you're back online." }
+                            >
+                                <span class="me-1"><BootstrapIcon icon="clock" /></span>
+                                "Queued"
+                            </small>
+                            <button
+                                type="button"
+                                class="btn btn-sm btn-link link-secondary p-0"
+                                title="Don't send"
+                                on:click=cancel
+                            >
+                                <BootstrapIcon icon="x-circle" />
+                            </button>
+                        </div>
+                    </div>
+                }
+            }
+        />
+    }
+}
+
+#[component]
+fn ConversationTags(
+    conversation_id: Signal<ConversationId>,
+    #[prop(into)] tags: Signal<Vec<String>>,
+    update_conversation: WriteSignal<Option<crate::state::Conversation>>,
+) -> impl IntoView {
+    let Context { tags: all_tags, .. } = expect_context();
+
+    // tags that aren't assigned yet
+    let available_tags = Signal::derive(move || {
+        with!(|tags, all_tags| {
+            all_tags
+                .iter()
+                .filter(|tag| !tags.contains(tag))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    });
+
+    view! {
+        <div class="d-flex flex-row flex-wrap align-items-center mt-auto ms-3 pb-2">
+            <For
+                each=tags
+                key=|tag| tag.clone()
+                children=move |tag| {
+                    let remove = {
+                        let tag = tag.clone();
+                        move |_| {
+                            update_conversation.update(|conversation| {
+                                if let Some(conversation) = conversation {
+                                    conversation.tags.retain(|other| other != &tag);
+                                }
+                            });
+                            refresh_summary(conversation_id.get_untracked());
+                        }
+                    };
+                    view!{
+                        <span class="badge rounded-pill text-bg-light border me-1">
+                            {tag}
+                            <span class="ms-1" style="cursor: pointer;" title="Remove tag" on:click=remove>
+                                <BootstrapIcon icon="x" />
+                            </span>
+                        </span>
+                    }
+                }
+            />
+            <div class="dropdown">
+                <span
+                    class="link-secondary"
+                    style="cursor: pointer;"
+                    title="Add tag"
+                    data-bs-toggle="dropdown"
+                >
+                    <BootstrapIcon icon="tag" />
+                </span>
+                <ul class="dropdown-menu">
+                    <For
+                        each=available_tags
+                        key=|tag| tag.clone()
+                        children=move |tag| {
+                            let add = {
+                                let tag = tag.clone();
+                                move |_| {
+                                    update_conversation.update(|conversation| {
+                                        if let Some(conversation) = conversation {
+                                            conversation.tags.push(tag.clone());
+                                        }
+                                    });
+                                    refresh_summary(conversation_id.get_untracked());
+                                }
+                            };
+                            view!{
+                                <li>
+                                    <button type="button" class="dropdown-item" on:click=add>{tag}</button>
+                                </li>
+                            }
+                        }
+                    />
+                    {move || with!(|available_tags| available_tags.is_empty()).then(|| view!{
+                        <li><span class="dropdown-item-text text-body-secondary">"No more tags"</span></li>
+                    })}
+                    <li><hr class="dropdown-divider" /></li>
+                    <li><A class="dropdown-item" href="/settings/tags">"Manage tags"</A></li>
+                </ul>
+            </div>
+        </div>
+    }
+}
+
+/// Statistics about a conversation, computed from its messages.
+#[derive(Clone, Debug, Default)]
+struct Stats {
+    user_messages: usize,
+    assistant_messages: usize,
+    /// Estimated from the texts of all messages.
+    tokens: usize,
+    /// The display names of the models that generated responses.
+    models: Vec<String>,
+    duration: chrono::Duration,
+    average_response_ms: Option<u64>,
+}
+
+impl Stats {
+    fn compute(conversation_id: ConversationId, settings: &Settings) -> Self {
+        let messages = use_conversation(conversation_id)
+            .read
+            .get_untracked()
+            .map(|conversation| conversation.messages)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|message_id| use_message(message_id).read.get_untracked())
+            .collect::<Vec<_>>();
+
+        let mut stats = Stats::default();
+        let mut response_ms = vec![];
+        for message in &messages {
+            match message.role {
+                Role::User => stats.user_messages += 1,
+                Role::Assitant => stats.assistant_messages += 1,
+            }
+            stats.tokens += count_tokens(&message.text);
+
+            if let Some(generation) = &message.generation {
+                response_ms.push(generation.duration_ms);
+                let name = settings.models.get(&generation.model_id).map_or_else(
+                    || generation.model_id.to_string(),
+                    |model| model.display_name().to_owned(),
+                );
+                if !stats.models.contains(&name) {
+                    stats.models.push(name);
+                }
+            }
+        }
+
+        if let (Some(first), Some(last)) = (messages.first(), messages.last()) {
+            stats.duration = last.timestamp - first.timestamp;
+        }
+        if !response_ms.is_empty() {
+            stats.average_response_ms =
+                Some(response_ms.iter().sum::<u64>() / response_ms.len() as u64);
+        }
+
+        stats
+    }
+}
+
+fn format_duration(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes();
+    if minutes < 1 {
+        "less than a minute".to_owned()
+    }
+    else if minutes < 60 {
+        format!("{minutes}m")
+    }
+    else if minutes < 24 * 60 {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    }
+    else {
+        format!("{}d {}h", minutes / (24 * 60), minutes % (24 * 60) / 60)
+    }
+}
+
+/// A dropdown with statistics about the conversation. They are only computed
+/// when it's opened.
+#[component]
+fn ConversationStats(#[prop(into)] conversation_id: Signal<ConversationId>) -> impl IntoView {
+    let Context { settings, .. } = expect_context();
+
+    let stats = create_rw_signal(None::<Stats>);
+    let open = move |_| {
+        let computed = settings
+            .with_untracked(|settings| Stats::compute(conversation_id.get_untracked(), settings));
+        stats.set(Some(computed));
+    };
+
+    view! {
+        <div class="dropdown me-2">
+            <button
+                type="button"
+                class="btn btn-sm btn-outline-secondary"
+                style="height: 100%;"
+                title="Statistics"
+                data-bs-toggle="dropdown"
+                data-bs-auto-close="outside"
+                on:click=open
+            >
+                <BootstrapIcon icon="bar-chart" />
+            </button>
+            <div class="dropdown-menu dropdown-menu-end p-3 conversation-stats">
+                {move || stats.get().map(|stats| {
+                    let rows = [
+                        ("Messages", format!("{} ({} from you, {} responses)", stats.user_messages + stats.assistant_messages, stats.user_messages, stats.assistant_messages)),
+                        ("Tokens", format!("≈ {}", stats.tokens)),
+                        ("Models", if stats.models.is_empty() { "-".to_owned() } else { stats.models.join(", ") }),
+                        ("Duration", format_duration(stats.duration)),
+                        ("Average response time", stats.average_response_ms.map_or_else(|| "-".to_owned(), |ms| format!("{:.2}s", ms as f32 / 1000.0))),
+                    ];
+                    view!{
+                        <table class="table table-sm mb-0">
+                            <tbody>
+                                {rows.into_iter().map(|(label, value)| view!{
+                                    <tr>
+                                        <th scope="row" class="text-nowrap">{label}</th>
+                                        <td>{value}</td>
+                                    </tr>
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                    }
+                })}
+            </div>
+        </div>
+    }
+}
+
+/// Buttons to pick a response length preset. Clicking the selected preset
+/// again goes back to the token limit from the parameters.
+#[component]
+pub fn ResponseLengthButtons(
+    #[prop(into)] value: Signal<Option<ResponseLength>>,
+    #[prop(into)] on_change: Callback<Option<ResponseLength>>,
+) -> impl IntoView {
+    view! {
+        <div class="btn-group btn-group-sm mb-2" role="group" aria-label="Response length">
+            {ResponseLength::VARIANTS.iter().map(|response_length| {
+                let response_length = *response_length;
+                let is_selected = move || value.get() == Some(response_length);
+                view!{
+                    <button
+                        type="button"
+                        class="btn btn-outline-secondary"
+                        class:active=is_selected
+                        title=move || format!("Up to {} tokens", response_length.token_limit())
+                        on:click=move |_| on_change((!is_selected()).then_some(response_length))
+                    >
+                        {response_length.get_message()}
+                    </button>
+                }
+            }).collect_view()}
+        </div>
+    }
+}
+
+#[component]
+fn GenerationDetails(generation: GenerationMetadata) -> impl IntoView {
+    let parameters = &generation.conversation_parameters;
+    let format_parameter = |value: Option<String>| value.unwrap_or_else(|| "default".to_owned());
+
+    let rows = [
+        ("Model", generation.model_id.to_string()),
+        (
+            "Backend",
+            generation
+                .backend
+                .and_then(|backend| backend.get_message())
+                .unwrap_or("-")
+                .to_owned(),
+        ),
+        ("Tokens", format!("≈ {}", generation.tokens)),
+        (
+            "Duration",
+            format!("{:.2}s", generation.duration_ms as f32 / 1000.0),
+        ),
+        (
+            "Time to first token",
+            generation.time_to_first_token_ms.map_or_else(
+                || "-".to_owned(),
+                |ms| format!("{:.2}s", ms as f32 / 1000.0),
+            ),
+        ),
+        (
+            "Speed",
+            generation
+                .tokens_per_second()
+                .map_or_else(|| "-".to_owned(), |speed| format!("{speed:.1} tokens/s")),
+        ),
+        (
+            "Temperature",
+            format_parameter(parameters.temperature.map(|value| value.to_string())),
+        ),
+        (
+            "Top K",
+            format_parameter(parameters.top_k.map(|value| value.to_string())),
+        ),
+        (
+            "Top P",
+            format_parameter(parameters.top_p.map(|value| value.to_string())),
+        ),
+        (
+            "Repetition penalty",
+            format_parameter(parameters.repetition_penalty.map(|value| value.to_string())),
+        ),
+        (
+            "Min P",
+            format_parameter(parameters.min_p.map(|value| value.to_string())),
+        ),
+        (
+            "Typical P",
+            format_parameter(parameters.typical_p.map(|value| value.to_string())),
+        ),
+        (
+            "No repeat n-gram size",
+            format_parameter(
+                parameters
+                    .no_repeat_ngram_size
+                    .map(|value| value.to_string()),
+            ),
+        ),
+        (
+            "Sampling",
+            format_parameter(
+                parameters
+                    .do_sample
+                    .map(|do_sample| if do_sample { "on" } else { "off" }.to_owned()),
+            ),
+        ),
+        (
+            "Token limit",
+            format_parameter(parameters.token_limit.map(|value| value.to_string())),
+        ),
+    ];
+
+    view! {
+        <details class="px-1 message-details">
+            <summary class="small text-body-secondary">"Details"</summary>
+            <table class="table table-sm small mb-0">
+                <tbody>
+                    {rows.into_iter().map(|(name, value)| view!{
+                        <tr>
+                            <th scope="row" class="fw-normal text-body-secondary">{name}</th>
+                            <td>{value}</td>
+                        </tr>
+                    }).collect_view()}
+                </tbody>
+            </table>
+        </details>
+    }
+}
+
+#[component]
+pub fn TokenCount(
+    #[prop(into)] tokens: Signal<usize>,
+    #[prop(into)] context_length: Signal<Option<usize>>,
+) -> impl IntoView {
+    // warn when the prompt uses up most of the context.
+    let warn = move || {
+        context_length.get().map_or(false, |context_length| {
+            tokens.get() * 10 > context_length * 9
+        })
+    };
+    let exceeded = move || {
+        context_length
+            .get()
+            .map_or(false, |context_length| tokens.get() >= context_length)
+    };
+
+    view! {
+        <small
+            class="mb-2 text-end text-body-secondary"
+            class:text-warning-emphasis=move || warn() && !exceeded()
+            class:text-danger-emphasis=exceeded
+        >
+            {move || {
+                if let Some(context_length) = context_length.get() {
+                    format!("≈ {} / {context_length} tokens", tokens.get())
+                }
+                else {
+                    format!("≈ {} tokens", tokens.get())
+                }
+            }}
+            {move || exceeded().then(|| " - the prompt doesn't fit into the model's context")}
+        </small>
+    }
+}
+
+#[component]
+pub fn ConversationParametersInputGroup(
+    #[prop(into, optional)] value: MaybeSignal<ConversationParameters>,
+    #[prop(into, optional)] on_system_prompt_input: Option<Callback<Option<String>>>,
+    #[prop(into, optional)] on_token_limit_input: Option<Callback<Option<usize>>>,
     #[prop(into, optional)] on_temperature_input: Option<Callback<Option<f32>>>,
     #[prop(into, optional)] on_top_k_input: Option<Callback<Option<usize>>>,
     #[prop(into, optional)] on_top_p_input: Option<Callback<Option<f32>>>,
     #[prop(into, optional)] on_repetition_penalty_input: Option<Callback<Option<f32>>>,
+    #[prop(into, optional)] on_min_p_input: Option<Callback<Option<f32>>>,
+    #[prop(into, optional)] on_typical_p_input: Option<Callback<Option<f32>>>,
+    #[prop(into, optional)] on_no_repeat_ngram_size_input: Option<Callback<Option<usize>>>,
+    #[prop(into, optional)] on_do_sample_input: Option<Callback<Option<bool>>>,
     #[prop(into, optional)] on_start_response_with_input: Option<Callback<Option<String>>>,
+    #[prop(into, optional)] on_ramp_after_turns_input: Option<Callback<Option<usize>>>,
+    #[prop(into, optional)] on_ramp_temperature_input: Option<Callback<Option<f32>>>,
+    #[prop(into, optional)] on_json_schema_input: Option<Callback<Option<String>>>,
+    #[prop(into, optional)] on_n_input: Option<Callback<Option<usize>>>,
     #[prop(into, optional)] hide_system_prompt: Signal<bool>,
 ) -> impl IntoView {
     struct Error(String);
@@ -508,6 +2644,13 @@ pub fn ConversationParametersInputGroup(
     let invalid_top_k = create_rw_signal(false);
     let invalid_top_p = create_rw_signal(false);
     let invalid_repetition_penalty = create_rw_signal(false);
+    let invalid_min_p = create_rw_signal(false);
+    let invalid_typical_p = create_rw_signal(false);
+    let invalid_no_repeat_ngram_size = create_rw_signal(false);
+    let invalid_ramp_after_turns = create_rw_signal(false);
+    let invalid_ramp_temperature = create_rw_signal(false);
+    let invalid_json_schema = create_rw_signal(false);
+    let invalid_n = create_rw_signal(false);
 
     view! {
         <div class="input-group mb-3" class:visually-hidden=hide_system_prompt>
@@ -529,8 +2672,8 @@ pub fn ConversationParametersInputGroup(
                 value=with!(|value| value.start_response_with.clone())
                 on:input=move |event| on_input(on_start_response_with_input, &event, None) />
         </div>
-        <div class="d-flex flex-row mb-3">
-            <div class="input-group me-3">
+        <div class="d-flex flex-row flex-wrap gap-2 mb-3 parameter-row">
+            <div class="input-group">
                 <span class="input-group-text">"Temperature"</span>
                 <input
                     type="text"
@@ -540,7 +2683,7 @@ pub fn ConversationParametersInputGroup(
                     on:input=move |event| on_input(on_temperature_input, &event, Some(invalid_temperature))
                 />
             </div>
-            <div class="input-group me-3">
+            <div class="input-group">
                 <span class="input-group-text">"Top K"</span>
                 <input
                     type="text"
@@ -550,7 +2693,7 @@ pub fn ConversationParametersInputGroup(
                     on:input=move |event| on_input(on_top_k_input, &event, Some(invalid_top_k))
                 />
             </div>
-            <div class="input-group me-3">
+            <div class="input-group">
                 <span class="input-group-text">"Top P"</span>
                 <input
                     type="text"
@@ -559,7 +2702,7 @@ pub fn ConversationParametersInputGroup(
                     value=with!(|value| value.top_p)
                     on:input=move |event| on_input(on_top_p_input, &event, Some(invalid_top_p)) />
             </div>
-            <div class="input-group me-3">
+            <div class="input-group">
                 <span class="input-group-text">"Repetition penalty"</span>
                 <input
                     type="text"
@@ -579,5 +2722,110 @@ pub fn ConversationParametersInputGroup(
                 />
             </div>
         </div>
+        <div class="d-flex flex-row flex-wrap gap-2 mb-3 parameter-row">
+            <div class="input-group">
+                <span class="input-group-text">"Min P"</span>
+                <input
+                    type="text"
+                    class="form-control"
+                    class:is-invalid=invalid_min_p
+                    value=with!(|value| value.min_p)
+                    on:input=move |event| on_input(on_min_p_input, &event, Some(invalid_min_p)) />
+            </div>
+            <div class="input-group">
+                <span class="input-group-text">"Typical P"</span>
+                <input
+                    type="text"
+                    class="form-control"
+                    class:is-invalid=invalid_typical_p
+                    value=with!(|value| value.typical_p)
+                    on:input=move |event| on_input(on_typical_p_input, &event, Some(invalid_typical_p)) />
+            </div>
+            <div class="input-group">
+                <span class="input-group-text">"No repeat n-gram size"</span>
+                <input
+                    type="text"
+                    class="form-control"
+                    class:is-invalid=invalid_no_repeat_ngram_size
+                    value=with!(|value| value.no_repeat_ngram_size)
+                    on:input=move |event| on_input(on_no_repeat_ngram_size_input, &event, Some(invalid_no_repeat_ngram_size)) />
+            </div>
+            <div class="input-group">
+                <span class="input-group-text">"Responses"</span>
+                <input
+                    type="text"
+                    class="form-control"
+                    class:is-invalid=invalid_n
+                    placeholder="1"
+                    title="How many responses to generate for each message. You can choose which one to continue with."
+                    value=with!(|value| value.n)
+                    on:input=move |event| on_input(on_n_input, &event, Some(invalid_n)) />
+            </div>
+            <div class="input-group">
+                <span class="input-group-text">"Sampling"</span>
+                <select
+                    class="form-select"
+                    on:change=move |event| {
+                        let do_sample = match event_target_value(&event).as_str() {
+                            "on" => Some(true),
+                            "off" => Some(false),
+                            _ => None,
+                        };
+                        if let Some(callback) = on_do_sample_input {
+                            callback(do_sample);
+                        }
+                    }
+                >
+                    <option value="" selected=with!(|value| value.do_sample.is_none())>"Default"</option>
+                    <option value="on" selected=with!(|value| value.do_sample == Some(true))>"On"</option>
+                    <option value="off" selected=with!(|value| value.do_sample == Some(false))>"Off (greedy)"</option>
+                </select>
+            </div>
+        </div>
+        <div class="d-flex flex-row flex-wrap gap-2 mb-3 parameter-row">
+            <div class="input-group">
+                <span class="input-group-text">"After turn"</span>
+                <input
+                    type="text"
+                    class="form-control"
+                    class:is-invalid=invalid_ramp_after_turns
+                    placeholder="Number of turns"
+                    value=with!(|value| value.ramp_after_turns)
+                    on:input=move |event| on_input(on_ramp_after_turns_input, &event, Some(invalid_ramp_after_turns))
+                />
+            </div>
+            <div class="input-group">
+                <span class="input-group-text">"switch temperature to"</span>
+                <input
+                    type="text"
+                    class="form-control"
+                    class:is-invalid=invalid_ramp_temperature
+                    value=with!(|value| value.ramp_temperature)
+                    on:input=move |event| on_input(on_ramp_temperature_input, &event, Some(invalid_ramp_temperature))
+                />
+            </div>
+        </div>
+        <div class="input-group mb-3">
+            <span class="input-group-text">"Structured output"</span>
+            <textarea
+                class="form-control font-monospace"
+                class:is-invalid=invalid_json_schema
+                rows="3"
+                placeholder="JSON schema, e.g. {\"type\": \"object\", \"properties\": {\"name\": {\"type\": \"string\"}}}"
+                on:input=move |event| {
+                    let json_schema = non_empty(event_target_value(&event));
+                    // only valid schemas are passed on.
+                    let valid = json_schema.as_deref().map_or(true, |json_schema| {
+                        serde_json::from_str::<serde_json::Value>(json_schema).is_ok()
+                    });
+                    invalid_json_schema.set(!valid);
+                    if let Some(callback) = on_json_schema_input {
+                        callback(json_schema.filter(|_| valid));
+                    }
+                }
+            >
+                {with!(|value| value.json_schema.clone())}
+            </textarea>
+        </div>
     }
 }
@@ -0,0 +1,208 @@
+//! The log of rated responses. It can be exported, to compare which models
+//! and parameters work best.
+
+use std::collections::BTreeMap;
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+use leptos::{
+    component,
+    create_memo,
+    view,
+    with,
+    CollectView,
+    IntoView,
+    SignalGetUntracked,
+    SignalWith,
+    SignalWithUntracked,
+};
+use serde::Serialize;
+
+use super::{
+    expect_context,
+    BootstrapIcon,
+    Context,
+};
+use crate::{
+    state::{
+        use_conversation,
+        use_message,
+        ConversationId,
+        ConversationParameters,
+        MessageId,
+        ModelId,
+        Rating,
+        Role,
+    },
+    utils::download_file,
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FeedbackEntry {
+    pub conversation_id: ConversationId,
+    pub message_id: MessageId,
+    pub timestamp: DateTime<Utc>,
+    pub model_id: Option<ModelId>,
+    pub parameters: Option<ConversationParameters>,
+    /// The user message that the response answers.
+    pub prompt: String,
+    pub response: String,
+    pub rating: Rating,
+}
+
+/// All rated responses, oldest first.
+pub fn feedback_log() -> Vec<FeedbackEntry> {
+    let Context { conversations, .. } = expect_context();
+
+    let mut entries = vec![];
+    for conversation_id in conversations.get_untracked() {
+        let Some(conversation) = use_conversation(conversation_id).read.get_untracked()
+        else {
+            continue;
+        };
+
+        let mut prompt = String::new();
+        for message_id in &conversation.messages {
+            let Some(message) = use_message(*message_id).read.get_untracked()
+            else {
+                continue;
+            };
+            if matches!(message.role, Role::User) {
+                if message.tool.is_none() {
+                    prompt = message.text;
+                }
+                continue;
+            }
+            let Some(rating) = message.rating
+            else {
+                continue;
+            };
+
+            let generation = message.generation;
+            entries.push(FeedbackEntry {
+                conversation_id,
+                message_id: message.id,
+                timestamp: message.timestamp,
+                model_id: generation
+                    .as_ref()
+                    .map(|generation| generation.model_id.clone())
+                    .or_else(|| conversation.model_id.clone()),
+                parameters: generation.map(|generation| generation.conversation_parameters),
+                prompt: prompt.clone(),
+                response: message.text,
+                rating,
+            });
+        }
+    }
+
+    entries.sort_by_key(|entry| entry.timestamp);
+    entries
+}
+
+/// How many responses of a model were rated good and bad.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct RatingCounts {
+    good: usize,
+    bad: usize,
+}
+
+#[component]
+pub fn FeedbackTab() -> impl IntoView {
+    let Context {
+        settings,
+        conversations,
+        ..
+    } = expect_context();
+
+    let entries = create_memo(move |_| {
+        conversations.track();
+        feedback_log()
+    });
+
+    let counts = create_memo(move |_| {
+        with!(|entries, settings| {
+            let mut counts = BTreeMap::<String, RatingCounts>::new();
+            for entry in entries {
+                let name = entry
+                    .model_id
+                    .as_ref()
+                    .map(|model_id| {
+                        settings.models.get(model_id).map_or_else(
+                            || model_id.0.clone(),
+                            |model| model.display_name().to_owned(),
+                        )
+                    })
+                    .unwrap_or_else(|| "Unknown model".to_owned());
+                let counts = counts.entry(name).or_default();
+                match entry.rating {
+                    Rating::Good => counts.good += 1,
+                    Rating::Bad => counts.bad += 1,
+                }
+            }
+            counts
+        })
+    });
+
+    let export = move |_| {
+        match entries.with_untracked(|entries| serde_json::to_string_pretty(entries)) {
+            Ok(json) => download_file("feedback.json", "application/json", &json),
+            Err(error) => log::error!("failed to serialize feedback log: {error}"),
+        }
+    };
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
+            <h5>"Feedback"</h5>
+            <p>
+                "Rate responses with "
+                <BootstrapIcon icon="hand-thumbs-up" />
+                " and "
+                <BootstrapIcon icon="hand-thumbs-down" />
+                " below them. The export has the prompt, parameters and response of every rated response."
+            </p>
+            {move || with!(|counts| if counts.is_empty() {
+                view!{ <p class="text-body-secondary">"No responses were rated yet."</p> }.into_view()
+            }
+            else {
+                view!{
+                    <table class="table">
+                        <thead>
+                            <tr>
+                                <th scope="col">"Model"</th>
+                                <th scope="col" class="text-end">"Good"</th>
+                                <th scope="col" class="text-end">"Bad"</th>
+                                <th scope="col" class="text-end">"Rated good"</th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {counts.iter().map(|(name, counts)| {
+                                let share = counts.good * 100 / (counts.good + counts.bad);
+                                view!{
+                                    <tr>
+                                        <td>{name.clone()}</td>
+                                        <td class="text-end">{counts.good}</td>
+                                        <td class="text-end">{counts.bad}</td>
+                                        <td class="text-end">{format!("{share}%")}</td>
+                                    </tr>
+                                }
+                            }).collect_view()}
+                        </tbody>
+                    </table>
+                }.into_view()
+            })}
+            <div>
+                <button
+                    type="button"
+                    class="btn btn-outline-primary"
+                    disabled=move || with!(|entries| entries.is_empty())
+                    on:click=export
+                >
+                    <span class="me-1"><BootstrapIcon icon="download" /></span>
+                    "Export feedback"
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,269 @@
+//! Shows whether the models are available on their backends, and lets them be
+//! warmed up before they're used.
+
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    time::Duration,
+};
+
+use chrono::{
+    DateTime,
+    Local,
+    Utc,
+};
+use leptos::{
+    component,
+    create_rw_signal,
+    on_cleanup,
+    set_interval_with_handle,
+    spawn_local,
+    view,
+    with,
+    CollectView,
+    IntoView,
+    RwSignal,
+    Signal,
+    SignalUpdate,
+    SignalWith,
+    SignalWithUntracked,
+};
+use strum::EnumMessage;
+
+use super::{
+    expect_context,
+    warm_up,
+    BootstrapIcon,
+    Context,
+};
+use crate::{
+    backend::huggingface::ModelLoadState,
+    parameters::GenerationParameters,
+    state::{
+        ModelBackend,
+        ModelId,
+        Settings,
+    },
+};
+
+/// How often the status of all models is checked while the page is open.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug)]
+enum Health {
+    Checking,
+    Loaded,
+    /// The API loads the model when it's used, which takes a while.
+    Loadable,
+    TooBig,
+    /// The backend doesn't report a status.
+    Unknown,
+    Error(String),
+}
+
+impl Health {
+    fn badge(&self) -> (&'static str, String) {
+        match self {
+            Self::Checking => ("text-bg-secondary", "Checking".to_owned()),
+            Self::Loaded => ("text-bg-success", "Loaded".to_owned()),
+            Self::Loadable => ("text-bg-info", "Loadable".to_owned()),
+            Self::TooBig => ("text-bg-warning", "Too big for the API".to_owned()),
+            Self::Unknown => ("text-bg-light", "No status".to_owned()),
+            Self::Error(error) => ("text-bg-danger", error.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct HealthCheck {
+    health: Health,
+    latency_ms: Option<i64>,
+    checked: DateTime<Utc>,
+}
+
+fn check_model(
+    settings: Signal<Settings>,
+    checks: RwSignal<HashMap<ModelId, HealthCheck>>,
+    model_id: ModelId,
+) {
+    let model = settings.with_untracked(|settings| {
+        settings.text_generation(
+            &model_id,
+            GenerationParameters::resolve(&Default::default(), 0, false),
+        )
+    });
+    let started = Utc::now();
+    checks.update(|checks| {
+        let check = checks.entry(model_id.clone()).or_insert(HealthCheck {
+            health: Health::Checking,
+            latency_ms: None,
+            checked: started,
+        });
+        check.health = Health::Checking;
+    });
+
+    spawn_local(async move {
+        let status = model.status().await;
+        let now = Utc::now();
+        let (health, latency_ms) = match status {
+            Some(Ok(status)) => {
+                let health = match (status.loaded, status.state) {
+                    (true, _) | (_, ModelLoadState::Loaded) => Health::Loaded,
+                    (false, ModelLoadState::Loadable) => Health::Loadable,
+                    (false, ModelLoadState::TooBig) => Health::TooBig,
+                    (false, ModelLoadState::Unknown) => Health::Unknown,
+                };
+                (health, Some((now - started).num_milliseconds()))
+            }
+            Some(Err(error)) => {
+                log::warn!("model status failed: {error}");
+                (Health::Error(error.to_string()), None)
+            }
+            None => (Health::Unknown, None),
+        };
+        checks.update(|checks| {
+            checks.insert(
+                model_id,
+                HealthCheck {
+                    health,
+                    latency_ms,
+                    checked: now,
+                },
+            );
+        });
+    });
+}
+
+#[component]
+pub fn BackendStatus() -> impl IntoView {
+    let Context {
+        settings, errors, ..
+    } = expect_context();
+
+    let checks = create_rw_signal(HashMap::<ModelId, HealthCheck>::new());
+    let warming_up = create_rw_signal(HashSet::<ModelId>::new());
+
+    let check_all = move || {
+        let model_ids = settings.with_untracked(|settings| {
+            settings.models.keys().cloned().collect::<Vec<_>>()
+        });
+        for model_id in model_ids {
+            check_model(settings, checks, model_id);
+        }
+    };
+    check_all();
+    if let Ok(handle) = set_interval_with_handle(check_all, POLL_INTERVAL) {
+        on_cleanup(move || handle.clear());
+    }
+
+    let warm_up_model = move |model_id: ModelId| {
+        let model = settings.with_untracked(|settings| {
+            settings.text_generation(
+                &model_id,
+                GenerationParameters::resolve(&Default::default(), 0, false),
+            )
+        });
+        warming_up.update(|warming_up| {
+            warming_up.insert(model_id.clone());
+        });
+        spawn_local(async move {
+            if let Err(error) = warm_up(&model).await {
+                errors.push(error);
+            }
+            warming_up.update(|warming_up| {
+                warming_up.remove(&model_id);
+            });
+            check_model(settings, checks, model_id);
+        });
+    };
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll h-100 w-100 p-4">
+            <div class="d-flex flex-row align-items-center mb-3">
+                <h4 class="mb-0">"Backend status"</h4>
+                <button type="button" class="btn btn-outline-secondary btn-sm ms-auto" on:click=move |_| check_all()>
+                    <span class="me-1"><BootstrapIcon icon="arrow-clockwise" /></span>
+                    "Check now"
+                </button>
+            </div>
+            <table class="table align-middle">
+                <thead>
+                    <tr>
+                        <th scope="col">"Model"</th>
+                        <th scope="col">"Backend"</th>
+                        <th scope="col">"Status"</th>
+                        <th scope="col">"Latency"</th>
+                        <th scope="col">"Checked"</th>
+                        <th scope="col"></th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {move || with!(|settings| settings.sorted_models().into_iter().map(|model| {
+                        let model_id = model.model_id.clone();
+                        let model_id_text = model_id.to_string();
+                        let backend = model.backend;
+                        let check = {
+                            let model_id = model_id.clone();
+                            move || with!(|checks| checks.get(&model_id).cloned())
+                        };
+                        let is_warming_up = {
+                            let model_id = model_id.clone();
+                            move || with!(|warming_up| warming_up.contains(&model_id))
+                        };
+                        // only these have cold models.
+                        let can_warm_up = matches!(backend, ModelBackend::HuggingFace | ModelBackend::Local);
+                        let status = {
+                            let check = check.clone();
+                            move || check().map(|check| {
+                                let (class, text) = check.health.badge();
+                                view!{ <span class=format!("badge {class}")>{text}</span> }
+                            })
+                        };
+                        let latency = {
+                            let check = check.clone();
+                            move || check()
+                                .and_then(|check| check.latency_ms)
+                                .map(|latency_ms| format!("{latency_ms} ms"))
+                        };
+                        let checked = move || check().map(|check| {
+                            check.checked.with_timezone(&Local).format("%H:%M:%S").to_string()
+                        });
+                        view!{
+                            <tr>
+                                <td>
+                                    {model.display_name().to_owned()}
+                                    <div class="small text-body-secondary font-monospace">{model_id_text}</div>
+                                </td>
+                                <td>{backend.get_message()}</td>
+                                <td>{status}</td>
+                                <td class="font-monospace">{latency}</td>
+                                <td class="small text-body-secondary">{checked}</td>
+                                <td>
+                                    {can_warm_up.then(|| view!{
+                                        <button
+                                            type="button"
+                                            class="btn btn-outline-primary btn-sm text-nowrap"
+                                            disabled=is_warming_up.clone()
+                                            on:click=move |_| warm_up_model(model_id.clone())
+                                        >
+                                            {move || if is_warming_up() {
+                                                view!{ <div class="spinner-border spinner-border-sm me-1" role="status"></div> }.into_view()
+                                            }
+                                            else {
+                                                view!{ <span class="me-1"><BootstrapIcon icon="fire" /></span> }.into_view()
+                                            }}
+                                            "Warm up"
+                                        </button>
+                                    })}
+                                </td>
+                            </tr>
+                        }
+                    }).collect_view())}
+                </tbody>
+            </table>
+            <div class="form-text">"The status is checked every minute while this page is open. Chat APIs don't report whether a model is loaded, and models that run in the browser are downloaded when they're warmed up."</div>
+        </div>
+    }
+}
@@ -1,11 +1,17 @@
-use chrono::Local;
+use chrono::Utc;
 use leptos::{
     component,
+    create_effect,
     create_node_ref,
+    create_rw_signal,
+    create_trigger,
     ev::SubmitEvent,
     event_target_value,
     expect_context,
-    html::Input,
+    html::{
+        Input,
+        Textarea,
+    },
     view,
     with,
     CollectView,
@@ -21,52 +27,143 @@ use leptos::{
 };
 use leptos_router::{
     use_navigate,
+    use_query_map,
+    NavigateOptions,
     A,
 };
+use uuid::Uuid;
 
 use super::{
-    conversation::ConversationParametersInputGroup,
+    commands::find_model,
+    conversation::{
+        ConversationParametersInputGroup,
+        ResponseLengthButtons,
+        TokenCount,
+    },
+    make_private,
+    model_card::CapabilityBadges,
     push_user_message,
+    quick_add::QuickAddModel,
+    refresh_summary,
     request_conversation_title,
     BootstrapIcon,
     Context,
 };
 use crate::{
-    config::{
-        BUILD_CONFIG,
-        GITHUB_ISSUES_PAGE,
-    },
+    config::GITHUB_ISSUES_PAGE,
     state::{
         use_conversation,
         Conversation,
         ConversationId,
+        ConversationParameters,
+        ExamplePrompt,
+        ModelId,
         StorageSignals,
+        Template,
+    },
+    tokenizer::count_tokens,
+    utils::{
+        auto_grow,
+        non_empty,
+        submit_on_enter,
     },
 };
 
+/// Links like `/?prompt=...&model=...&system=...` fill in the form. With
+/// `start`, which is used for `/new`, the conversation is started right away if
+/// there is a prompt.
 #[component]
-pub fn Home() -> impl IntoView {
+pub fn Home(#[prop(optional)] start: bool) -> impl IntoView {
     let Context {
-        is_loading,
         settings,
         home,
         update_home,
         update_conversations,
+        templates,
+        update_templates,
+        config,
+        toasts,
         ..
     } = expect_context();
 
-    let user_message_input = create_node_ref::<Input>();
+    let user_message_input = create_node_ref::<Textarea>();
+    let template_name_input = create_node_ref::<Input>();
+
+    // private conversations are only kept in memory.
+    let private = create_rw_signal(false);
+    // the saved draft is removed while the conversation is private, and saved
+    // again when it isn't anymore.
+    let toggle_private = move |_| {
+        private.update(|private| *private = !*private);
+        let user_message = if private.get_untracked() {
+            "".to_owned()
+        }
+        else {
+            user_message_input
+                .get_untracked()
+                .map(|input| input.value())
+                .unwrap_or_default()
+        };
+        update_home.update(|home| home.user_message = user_message);
+    };
+
+    // models are added inline, so that the draft is kept.
+    let show_quick_add = create_rw_signal(false);
+
+    // the parameter inputs only read their value once, so they're re-rendered when
+    // a template is applied.
+    let parameters_changed = create_trigger();
 
     let current_model = Signal::derive(move || with!(|home| home.selected_model.clone()));
 
+    // the models that a new conversation is started with.
+    let selected_models = Signal::derive(move || {
+        with!(|home, settings| {
+            let mut models = vec![home.selected_model.clone()];
+            models.extend(
+                home.broadcast_models
+                    .iter()
+                    .filter(|model_id| {
+                        **model_id != home.selected_model && settings.models.contains_key(model_id)
+                    })
+                    .cloned(),
+            );
+            models
+        })
+    });
+
     let current_model_name = Signal::derive(move || {
-        with!(|current_model, settings| {
+        let name = with!(|current_model, settings| {
             settings
                 .models
                 .get(current_model)
                 .unwrap()
                 .display_name()
                 .to_owned()
+        });
+        match with!(|selected_models| selected_models.len()) {
+            1 => name,
+            n => format!("{name} + {} more", n - 1),
+        }
+    });
+
+    let context_length = Signal::derive(move || {
+        with!(|settings, current_model| {
+            settings
+                .models
+                .get(current_model)
+                .and_then(|model| model.context_length)
+        })
+    });
+
+    let prompt_tokens = Signal::derive(move || {
+        with!(|home| {
+            count_tokens(&home.user_message)
+                + home
+                    .conversation_parameters
+                    .system_prompt
+                    .as_deref()
+                    .map_or(0, count_tokens)
         })
     });
 
@@ -80,42 +177,250 @@ pub fn Home() -> impl IntoView {
         })
     });
 
-    let start_chat = move |user_message: String, conversation_parameters| {
-        let now = Local::now();
-
-        let current_model = current_model.get_untracked();
-
-        let conversation_id = ConversationId::new();
-        let conversation = Conversation {
-            id: conversation_id,
-            model_id: Some(current_model),
-            title: None,
-            timestamp_started: now,
-            timestamp_last_interaction: now,
-            messages: vec![],
-            conversation_parameters,
-            user_message: "".to_owned(),
-        };
+    // the parameters of the first conversation can be edited on the home page.
+    // otherwise, and for `None`, conversations start with their model's defaults.
+    let start_chat = move |user_message: String, parameters: Option<ConversationParameters>| {
+        let now = Utc::now();
+
+        // with several models, one conversation is started for each of them.
+        let models = selected_models.get_untracked();
+        let group = (models.len() > 1).then(Uuid::new_v4);
+
+        let mut first_conversation_id = None;
+        for model_id in models {
+            let model_defaults = settings.with_untracked(|settings| {
+                settings
+                    .models
+                    .get(&model_id)
+                    .filter(|model| model.has_default_parameters())
+                    .map(|model| model.default_parameters.clone())
+            });
+            let conversation_parameters = match (&parameters, first_conversation_id) {
+                (Some(parameters), None) if *parameters != ConversationParameters::default() => {
+                    parameters.clone()
+                }
+                _ => {
+                    model_defaults
+                        .or_else(|| parameters.clone())
+                        .unwrap_or_default()
+                }
+            };
+
+            let conversation_id = ConversationId::new();
+            let conversation = Conversation {
+                id: conversation_id,
+                model_id: Some(model_id),
+                title: None,
+                auto_title: true,
+                timestamp_started: now,
+                timestamp_last_interaction: now,
+                timestamp_modified: now,
+                messages: vec![],
+                conversation_parameters,
+                user_message: "".to_owned(),
+                archived: false,
+                tags: vec![],
+                group,
+                pinned: false,
+                summary: None,
+            };
+
+            if private.get_untracked() {
+                make_private(conversation_id);
+            }
+            else {
+                update_conversations.update(|conversations| {
+                    conversations.insert(conversation_id);
+                });
+            }
 
-        update_conversations.update(|conversations| {
-            conversations.insert(conversation_id);
+            let StorageSignals {
+                write: update_conversation,
+                ..
+            } = use_conversation(conversation_id);
+            update_conversation.set(Some(conversation));
+            refresh_summary(conversation_id);
+
+            request_conversation_title(conversation_id, &user_message, false);
+            push_user_message(conversation_id, user_message.clone(), Default::default());
+
+            first_conversation_id.get_or_insert(conversation_id);
+        }
+
+        if let Some(conversation_id) = first_conversation_id {
+            // going back to `/new` would start the conversation again.
+            use_navigate()(
+                &format!("/conversation/{conversation_id}"),
+                NavigateOptions {
+                    replace: start,
+                    ..Default::default()
+                },
+            );
+        }
+    };
+
+    // puts the example into the input, so that it can be edited before sending.
+    let edit_example = move |example: &str| {
+        update_home.update(|home| home.user_message = example.to_owned());
+
+        if let Some(user_message_input) = user_message_input.get_untracked() {
+            user_message_input.set_value(example);
+            auto_grow(&user_message_input);
+            let _ = user_message_input.focus();
+        }
+    };
+
+    // the model's default parameters replace the current ones, so that they can
+    // be adjusted before starting the conversation.
+    let select_model = move |model_id: ModelId| {
+        let defaults = settings.with_untracked(|settings| {
+            settings
+                .models
+                .get(&model_id)
+                .filter(|model| model.has_default_parameters())
+                .map(|model| model.default_parameters.clone())
+        });
+        let has_defaults = defaults.is_some();
+        update_home.update(move |home| {
+            home.selected_model = model_id;
+            if let Some(defaults) = defaults {
+                home.conversation_parameters = defaults;
+            }
         });
+        if has_defaults {
+            parameters_changed.notify();
+        }
+    };
+
+    let apply_template = move |template: &Template| {
+        let model_exists =
+            settings.with_untracked(|settings| settings.models.contains_key(&template.model_id));
+        if !model_exists {
+            log::warn!("template model not found: {}", template.model_id);
+        }
+
+        let template = template.clone();
+        update_home.update(move |home| {
+            if model_exists {
+                home.selected_model = template.model_id;
+            }
+            home.conversation_parameters = template.conversation_parameters;
+        });
+        parameters_changed.notify();
+
+        if let Some(user_message_input) = user_message_input.get_untracked() {
+            let _ = user_message_input.focus();
+        }
+    };
+
+    // the examples from the config come first, followed by the user's own.
+    let examples = Signal::derive(move || {
+        let mut examples = with!(|config| {
+            config
+                .examples
+                .iter()
+                .map(|prompt| {
+                    ExamplePrompt {
+                        id: Uuid::nil(),
+                        prompt: prompt.clone(),
+                        model_id: None,
+                        conversation_parameters: None,
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+        examples.extend(with!(|settings| settings.examples.clone()));
+        examples
+    });
+
+    // an example's model and parameters are selected first, so that they're used
+    // for the conversation, or are there when it's edited.
+    let use_example = move |example: &ExamplePrompt, shift_key: bool| {
+        log::debug!("example: {}", example.prompt);
+
+        if let Some(model_id) = &example.model_id {
+            if settings.with_untracked(|settings| settings.models.contains_key(model_id)) {
+                select_model(model_id.clone());
+            }
+            else {
+                log::warn!("example model not found: {model_id}");
+            }
+        }
+        if let Some(conversation_parameters) = &example.conversation_parameters {
+            let conversation_parameters = conversation_parameters.clone();
+            update_home.update(move |home| home.conversation_parameters = conversation_parameters);
+            parameters_changed.notify();
+        }
+
+        let send = settings.with_untracked(|settings| settings.send_examples) != shift_key;
+        if send {
+            start_chat(example.prompt.clone(), example.conversation_parameters.clone());
+        }
+        else {
+            edit_example(&example.prompt);
+        }
+    };
 
-        let StorageSignals {
-            write: update_conversation,
-            ..
-        } = use_conversation(conversation_id);
-        update_conversation.set(Some(conversation));
+    let save_template = move |event: SubmitEvent| {
+        event.prevent_default();
 
-        request_conversation_title(conversation_id, &user_message);
-        push_user_message(conversation_id, user_message);
+        let input = template_name_input.get_untracked().unwrap();
+        let Some(name) = non_empty(input.value().trim().to_owned())
+        else {
+            return;
+        };
 
-        use_navigate()(
-            &format!("/conversation/{conversation_id}"),
-            Default::default(),
-        );
+        let template = home.with_untracked(|home| {
+            Template {
+                id: Uuid::new_v4(),
+                name,
+                model_id: home.selected_model.clone(),
+                conversation_parameters: home.conversation_parameters.clone(),
+            }
+        });
+        update_templates.update(|templates| templates.push(template));
+        input.set_value("");
     };
 
+    // prefill from the query parameters
+    let query = use_query_map().get_untracked();
+    if let Some(model) = query.get("model") {
+        match settings.with_untracked(|settings| find_model(model, settings)) {
+            Ok(model_id) => select_model(model_id),
+            Err(error) => {
+                toasts.warning(error.to_string());
+            }
+        }
+    }
+    if let Some(system_prompt) = query.get("system") {
+        let system_prompt = non_empty(system_prompt.clone());
+        update_home.update(move |home| home.conversation_parameters.system_prompt = system_prompt);
+        parameters_changed.notify();
+    }
+    let prompt = query
+        .get("prompt")
+        .and_then(|prompt| non_empty(prompt.trim().to_owned()));
+    if let Some(prompt) = &prompt {
+        let prompt = prompt.clone();
+        update_home.update(move |home| home.user_message = prompt);
+    }
+
+    if let Some(prompt) = prompt.filter(|_| start) {
+        // this runs once the page is rendered, so that it can navigate away.
+        create_effect(move |started: Option<()>| {
+            if started.is_some() {
+                return;
+            }
+            let conversation_parameters = update_home
+                .try_update(|home| {
+                    home.user_message = "".to_owned();
+                    home.conversation_parameters.clone()
+                })
+                .unwrap_or_default();
+            start_chat(prompt.clone(), Some(conversation_parameters));
+        });
+    }
+
     let on_submit = move |event: SubmitEvent| {
         event.prevent_default();
 
@@ -142,11 +447,9 @@ pub fn Home() -> impl IntoView {
             return;
         };
 
-        start_chat(user_message, conversation_parameters);
+        start_chat(user_message, Some(conversation_parameters));
     };
 
-    let disable_send = Signal::derive(move || is_loading.get());
-
     view! {
         <div class="d-flex flex-column h-100 w-100">
             <div class="d-flex flex-column flex-grow-1 overflow-scroll">
@@ -171,42 +474,116 @@ pub fn Home() -> impl IntoView {
                             "."
                         </p>
                     </div>
+                    {move || with!(|templates| (!templates.is_empty()).then(|| view!{
+                        <div class="d-flex flex-column mb-4">
+                            <h4>"Templates"</h4>
+                            <div class="d-flex flex-row flex-wrap mx-4">
+                                {templates.iter().cloned().map(|template| {
+                                    let model_name = settings.with_untracked(|settings| {
+                                        settings.models
+                                            .get(&template.model_id)
+                                            .map(|model| model.display_name().to_owned())
+                                            .unwrap_or_else(|| template.model_id.to_string())
+                                    });
+                                    let name = template.name.clone();
+                                    let system_prompt = template.conversation_parameters.system_prompt.clone();
+                                    view!{
+                                        <button
+                                            type="button"
+                                            class="card template-card text-start mt-2 me-2 p-0"
+                                            title=system_prompt
+                                            on:click=move |_| apply_template(&template)
+                                        >
+                                            <div class="card-body p-2">
+                                                <h6 class="card-title mb-1">{name}</h6>
+                                                <small class="text-body-secondary">{model_name}</small>
+                                            </div>
+                                        </button>
+                                    }
+                                }).collect_view()}
+                            </div>
+                        </div>
+                    }))}
                     <div class="d-flex flex-column">
                         <h4>"Examples"</h4>
-                        {
-                            BUILD_CONFIG.examples.iter().map(|example| {
+                        {move || {
+                            examples.get().into_iter().map(|example| {
+                                let text = example.prompt.clone();
+                                let model_name = example.model_id.as_ref().map(|model_id| {
+                                    settings.with_untracked(|settings| {
+                                        settings.models
+                                            .get(model_id)
+                                            .map(|model| model.display_name().to_owned())
+                                            .unwrap_or_else(|| model_id.to_string())
+                                    })
+                                });
                                 view!{
                                     <button
                                         type="button"
                                         class="btn btn-outline-secondary p-2 mt-2 mx-4"
-                                        on:click=move |_| {
-                                            log::debug!("example: {example}");
-                                            start_chat(example.to_owned(), Default::default());
-                                        }
+                                        on:click=move |event| use_example(&example, event.shift_key())
                                     >
-                                        {example}
+                                        {text}
+                                        {model_name.map(|model_name| view!{
+                                            <small class="d-block text-body-secondary">{model_name}</small>
+                                        })}
                                     </button>
                                 }
                             }).collect_view()
-                        }
+                        }}
                     </div>
                 </div>
             </div>
 
             <div class="d-flex flex-column px-3 pt-3 shadow-lg">
                 <div class="collapse pb-2" id="startChatAdvancedContainer">
-                    <ConversationParametersInputGroup
-                        value=home.with_untracked(|home| home.conversation_parameters.clone())
-                        on_system_prompt_input=move |value| update_home.update(move |home| home.conversation_parameters.system_prompt = value)
-                        on_start_response_with_input=move |value| update_home.update(move |home| home.conversation_parameters.start_response_with = value)
-                        on_temperature_input=move |value| update_home.update(move |home| home.conversation_parameters.temperature = value)
-                        on_top_k_input=move |value| update_home.update(move |home| home.conversation_parameters.top_k = value)
-                        on_top_p_input=move |value| update_home.update(move |home| home.conversation_parameters.top_p = value)
-                        on_repetition_penalty_input=move |value| update_home.update(move |home| home.conversation_parameters.repetition_penalty = value)
-                        on_token_limit_input=move |value| update_home.update(move |home| home.conversation_parameters.token_limit = value)
-                        hide_system_prompt=hide_system_prompt_input
-                    />
+                    {move || {
+                        parameters_changed.track();
+                        view!{
+                            <ConversationParametersInputGroup
+                                value=home.with_untracked(|home| home.conversation_parameters.clone())
+                                on_system_prompt_input=move |value| update_home.update(move |home| home.conversation_parameters.system_prompt = value)
+                                on_start_response_with_input=move |value| update_home.update(move |home| home.conversation_parameters.start_response_with = value)
+                                on_temperature_input=move |value| update_home.update(move |home| home.conversation_parameters.temperature = value)
+                                on_top_k_input=move |value| update_home.update(move |home| home.conversation_parameters.top_k = value)
+                                on_top_p_input=move |value| update_home.update(move |home| home.conversation_parameters.top_p = value)
+                                on_repetition_penalty_input=move |value| update_home.update(move |home| home.conversation_parameters.repetition_penalty = value)
+                                on_min_p_input=move |value| update_home.update(move |home| home.conversation_parameters.min_p = value)
+                                on_typical_p_input=move |value| update_home.update(move |home| home.conversation_parameters.typical_p = value)
+                                on_no_repeat_ngram_size_input=move |value| update_home.update(move |home| home.conversation_parameters.no_repeat_ngram_size = value)
+                                on_n_input=move |value| update_home.update(move |home| home.conversation_parameters.n = value)
+                                on_do_sample_input=move |value| update_home.update(move |home| home.conversation_parameters.do_sample = value)
+                                on_token_limit_input=move |value| update_home.update(move |home| home.conversation_parameters.token_limit = value)
+                                on_ramp_after_turns_input=move |value| update_home.update(move |home| home.conversation_parameters.ramp_after_turns = value)
+                                on_ramp_temperature_input=move |value| update_home.update(move |home| home.conversation_parameters.ramp_temperature = value)
+                                on_json_schema_input=move |value| update_home.update(move |home| home.conversation_parameters.json_schema = value)
+                                hide_system_prompt=hide_system_prompt_input
+                            />
+                        }
+                    }}
+                    <form class="input-group input-group-sm mt-2" on:submit=save_template>
+                        <input
+                            type="text"
+                            class="form-control"
+                            placeholder="Template name"
+                            aria-label="Template name"
+                            node_ref=template_name_input
+                        />
+                        <button type="submit" class="btn btn-outline-secondary">
+                            <span class="me-1"><BootstrapIcon icon="bookmark-plus" /></span>
+                            "Save as template"
+                        </button>
+                    </form>
                 </div>
+                {move || show_quick_add.get().then(|| view!{
+                    <QuickAddModel
+                        on_added=move |model_id| {
+                            select_model(model_id);
+                            show_quick_add.set(false);
+                        }
+                        on_close=move |_| show_quick_add.set(false)
+                    />
+                })}
                 <div class="mb-3 dropup flex-grow-1">
                     <div class="input-group" data-bs-toggle="dropdown">
                         <span class="input-group-text">"Model"</span>
@@ -239,35 +616,69 @@ pub fn Home() -> impl IntoView {
                         <div class="overflow-y-scroll" style="max-height: 50vh;">
                             <For
                                 each=move || with!(|settings| {
-                                    let mut items = settings.models
-                                        .iter()
-                                        .map(|(id, model)| (id.clone(), model.display_name().to_lowercase()))
-                                        .collect::<Vec<_>>();
-                                    items.sort_by_cached_key(|(_, name)| name.clone());
-                                    items
+                                    settings.sorted_models()
+                                        .into_iter()
+                                        .map(|model| (model.model_id.clone(), model.favorite))
+                                        .collect::<Vec<_>>()
                                 })
-                                key=|(model_id, _)| model_id.clone()
-                                children=move |(model_id, _)| {
-                                    let model_name = settings.with(|settings| settings.models.get(&model_id).unwrap().display_name().to_owned());
+                                key=|item| item.clone()
+                                children=move |(model_id, favorite)| {
+                                    let (model_name, capabilities) = settings.with(|settings| {
+                                        let model = settings.models.get(&model_id).unwrap();
+                                        (model.display_name().to_owned(), model.capabilities())
+                                    });
+                                    let is_broadcast = {
+                                        let model_id = model_id.clone();
+                                        Signal::derive(move || with!(|home| home.broadcast_models.contains(&model_id)))
+                                    };
+                                    let toggle_broadcast = {
+                                        let model_id = model_id.clone();
+                                        move |_| {
+                                            let model_id = model_id.clone();
+                                            update_home.update(move |home| {
+                                                if !home.broadcast_models.remove(&model_id) {
+                                                    home.broadcast_models.insert(model_id);
+                                                }
+                                            });
+                                        }
+                                    };
                                     view!{
-                                        <button
-                                            type="button"
-                                            class="dropdown-item"
-                                            class:active=move || with!(|current_model| current_model == &model_id)
-                                            on:click={
-                                                let model_id = model_id.clone();
-                                                move |_| {
+                                        <div class="d-flex flex-row align-items-center">
+                                            <button
+                                                type="button"
+                                                class="dropdown-item"
+                                                class:active={
                                                     let model_id = model_id.clone();
-                                                    update_home.update(move |home| home.selected_model = model_id);
+                                                    move || with!(|current_model| current_model == &model_id)
                                                 }
-                                            }
-                                        >
-                                            {model_name}
-                                        </button>
+                                                on:click={
+                                                    let model_id = model_id.clone();
+                                                    move |_| select_model(model_id.clone())
+                                                }
+                                            >
+                                                {model_name}
+                                                {favorite.then(|| view!{
+                                                    <span class="ms-1 text-warning"><BootstrapIcon icon="star-fill" /></span>
+                                                })}
+                                                <CapabilityBadges capabilities />
+                                            </button>
+                                            <button
+                                                type="button"
+                                                class="btn btn-sm btn-link link-secondary py-0 px-2"
+                                                title="Also send the first message to this model, in a separate conversation"
+                                                on:click=toggle_broadcast
+                                            >
+                                                {move || view!{ <BootstrapIcon icon=if is_broadcast.get() { "check-square" } else { "plus-square" } /> }}
+                                            </button>
+                                        </div>
                                     }
                                 }
                             />
                         </div>
+                        <hr class="dropdown-divider" />
+                        <button type="button" class="dropdown-item" on:click=move |_| show_quick_add.set(true)>
+                            "+ Add model…"
+                        </button>
                         <small class="dropdown-header my-0 mx-3 p-0">
                             "Add more models under "
                             <A href="/settings/models">
@@ -278,27 +689,59 @@ pub fn Home() -> impl IntoView {
                 </div>
                 <form on:submit=on_submit>
                     <div class="input-group input-group-lg mb-3">
-                        <input
-                            type="text"
-                            class="form-control"
+                        <textarea
+                            class="form-control message-input"
+                            rows="1"
                             placeholder="Ask anything"
-                            value=home.with_untracked(|home| home.user_message.clone())
+                            prop:value=home.with_untracked(|home| home.user_message.clone())
                             node_ref=user_message_input
+                            on:keydown=submit_on_enter
                             on:input=move |event| {
+                                if let Some(user_message_input) = user_message_input.get_untracked() {
+                                    auto_grow(&user_message_input);
+                                }
+                                // the draft of a private conversation isn't saved either.
+                                if private.get_untracked() {
+                                    return;
+                                }
                                 let user_message = event_target_value(&event);
                                 update_home.update(|home| home.user_message = user_message);
                             }
-                        />
+                        ></textarea>
                         <button
-                            class="btn btn-outline-secondary"
+                            class="btn btn-outline-secondary message-form-button"
                             type="submit"
-                            disabled=disable_send
+                            title="Send"
+                            aria-label="Send"
                         >
                             <BootstrapIcon icon="send" />
                         </button>
-                        <button class="btn btn-outline-secondary" type="button" data-bs-toggle="collapse" data-bs-target="#startChatAdvancedContainer"><BootstrapIcon icon="three-dots" /></button>
+                        <button
+                            class="btn btn-outline-secondary"
+                            class:active=private
+                            type="button"
+                            title="Private conversation: it's not saved, and is gone when you reload the page"
+                            aria-pressed=move || private.get().to_string()
+                            on:click=toggle_private
+                        >
+                            <BootstrapIcon icon="incognito" />
+                        </button>
+                        <button class="btn btn-outline-secondary" type="button" title="More options" aria-label="More options" data-bs-toggle="collapse" data-bs-target="#startChatAdvancedContainer"><BootstrapIcon icon="three-dots" /></button>
                     </div>
                 </form>
+                {move || private.get().then(|| view!{
+                    <div class="alert alert-dark py-2 small" role="status">
+                        <span class="me-1"><BootstrapIcon icon="incognito" /></span>
+                        "Private conversation: nothing is saved in this browser, and it's gone when you close or reload the page."
+                    </div>
+                })}
+                <div class="d-flex flex-row justify-content-between align-items-start">
+                    <ResponseLengthButtons
+                        value=Signal::derive(move || with!(|home| home.conversation_parameters.response_length))
+                        on_change=move |value| update_home.update(move |home| home.conversation_parameters.response_length = value)
+                    />
+                    <TokenCount tokens=prompt_tokens context_length=context_length />
+                </div>
             </div>
         </div>
     }
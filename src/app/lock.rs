@@ -0,0 +1,439 @@
+//! Locks the app with a passphrase. The lock screen covers everything, so
+//! conversation titles and messages can't be read until it's unlocked. This
+//! doesn't encrypt anything in storage.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use js_sys::{
+    Array,
+    Object,
+    Reflect,
+    Uint8Array,
+};
+use leptos::{
+    component,
+    create_effect,
+    create_node_ref,
+    create_rw_signal,
+    ev,
+    event_target_value,
+    html::Input,
+    set_interval,
+    spawn_local,
+    store_value,
+    view,
+    window_event_listener,
+    with,
+    IntoView,
+    SignalGet,
+    SignalGetUntracked,
+    SignalSet,
+    SignalUpdate,
+    SignalWith,
+    SignalWithUntracked,
+};
+use uuid::Uuid;
+use wasm_bindgen::{
+    JsCast,
+    JsValue,
+};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    CryptoKey,
+    SubmitEvent,
+    SubtleCrypto,
+};
+
+use super::{
+    expect_context,
+    BootstrapIcon,
+    Context,
+    Error,
+};
+use crate::{
+    state::AppLock,
+    utils::non_empty,
+};
+
+/// How often we check whether the inactivity timeout has passed.
+const INACTIVITY_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+const DEFAULT_INACTIVITY_TIMEOUT_MINUTES: u32 = 15;
+
+/// PBKDF2 iterations for new passphrases. This is what OWASP recommends for
+/// PBKDF2-HMAC-SHA256.
+const PASSPHRASE_ITERATIONS: u32 = 600_000;
+
+fn subtle_crypto() -> Result<SubtleCrypto, Error> {
+    Ok(gloo_utils::window()
+        .crypto()
+        .map_err(|_| Error::HashPassphrase)?
+        .subtle())
+}
+
+async fn await_promise(promise: Result<js_sys::Promise, JsValue>) -> Result<JsValue, Error> {
+    let promise = promise.map_err(|_| Error::HashPassphrase)?;
+    JsFuture::from(promise)
+        .await
+        .map_err(|_| Error::HashPassphrase)
+}
+
+fn to_hex(buffer: &JsValue) -> String {
+    Uint8Array::new(buffer)
+        .to_vec()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The hex-encoded PBKDF2-HMAC-SHA256 of the passphrase.
+async fn hash_passphrase(salt: &str, passphrase: &str, iterations: u32) -> Result<String, Error> {
+    let subtle = subtle_crypto()?;
+
+    let key_data = Uint8Array::from(passphrase.as_bytes());
+    let key_usages = Array::of1(&JsValue::from_str("deriveBits"));
+    let key: CryptoKey = await_promise(subtle.import_key_with_str(
+        "raw",
+        &key_data,
+        "PBKDF2",
+        false,
+        &key_usages,
+    ))
+    .await?
+    .unchecked_into();
+
+    let algorithm = Object::new();
+    let salt: JsValue = Uint8Array::from(salt.as_bytes()).into();
+    for (name, value) in [
+        ("name", JsValue::from_str("PBKDF2")),
+        ("hash", JsValue::from_str("SHA-256")),
+        ("salt", salt),
+        ("iterations", JsValue::from(iterations)),
+    ] {
+        Reflect::set(&algorithm, &JsValue::from_str(name), &value)
+            .map_err(|_| Error::HashPassphrase)?;
+    }
+    let bits = await_promise(subtle.derive_bits_with_object(&algorithm, &key, 256)).await?;
+
+    Ok(to_hex(&bits))
+}
+
+async fn check_passphrase(lock: &AppLock, passphrase: &str) -> Result<bool, Error> {
+    let passphrase_hash = hash_passphrase(&lock.salt, passphrase, lock.iterations).await?;
+    Ok(passphrase_hash == lock.passphrase_hash)
+}
+
+/// Hashes a new passphrase with a new salt.
+async fn new_lock(
+    passphrase: &str,
+    inactivity_timeout_minutes: Option<u32>,
+) -> Result<AppLock, Error> {
+    let salt = Uuid::new_v4().to_string();
+    let passphrase_hash = hash_passphrase(&salt, passphrase, PASSPHRASE_ITERATIONS).await?;
+    Ok(AppLock {
+        salt,
+        passphrase_hash,
+        iterations: PASSPHRASE_ITERATIONS,
+        inactivity_timeout_minutes,
+    })
+}
+
+/// Covers the app while it's locked, and locks it after the inactivity timeout.
+#[component]
+pub fn LockScreen() -> impl IntoView {
+    let Context {
+        settings,
+        locked,
+        errors,
+        ..
+    } = expect_context();
+
+    // the lock may be removed in another tab.
+    create_effect(move |_| {
+        if with!(|settings| settings.lock.is_none()) {
+            locked.set(false);
+        }
+    });
+
+    let last_activity = store_value(Utc::now());
+    let on_activity = move || last_activity.set_value(Utc::now());
+    window_event_listener(ev::keydown, move |_| on_activity());
+    window_event_listener(ev::pointerdown, move |_| on_activity());
+    window_event_listener(ev::pointermove, move |_| on_activity());
+    window_event_listener(ev::wheel, move |_| on_activity());
+    set_interval(
+        move || {
+            if locked.get_untracked() {
+                return;
+            }
+            let Some(timeout) = settings.with_untracked(|settings| {
+                settings
+                    .lock
+                    .as_ref()
+                    .and_then(|lock| lock.inactivity_timeout_minutes)
+            })
+            else {
+                return;
+            };
+            let inactive = Utc::now() - last_activity.get_value();
+            if inactive.num_minutes() >= i64::from(timeout) {
+                log::info!("locking after {timeout} minutes without input");
+                locked.set(true);
+            }
+        },
+        INACTIVITY_CHECK_INTERVAL,
+    );
+
+    let passphrase_input = create_node_ref::<Input>();
+    let wrong_passphrase = create_rw_signal(false);
+    let checking = create_rw_signal(false);
+
+    let on_submit = move |event: SubmitEvent| {
+        event.prevent_default();
+        let Some(input) = passphrase_input.get_untracked()
+        else {
+            return;
+        };
+        let Some(lock) = settings.with_untracked(|settings| settings.lock.clone())
+        else {
+            locked.set(false);
+            return;
+        };
+        let passphrase = input.value();
+
+        checking.set(true);
+        spawn_local(async move {
+            match check_passphrase(&lock, &passphrase).await {
+                Ok(true) => {
+                    input.set_value("");
+                    wrong_passphrase.set(false);
+                    last_activity.set_value(Utc::now());
+                    locked.set(false);
+                }
+                Ok(false) => {
+                    input.select();
+                    wrong_passphrase.set(true);
+                }
+                Err(error) => errors.push(error),
+            }
+            checking.set(false);
+        });
+    };
+
+    // focus the input whenever the app is locked.
+    create_effect(move |_| {
+        if locked.get() {
+            if let Some(input) = passphrase_input.get() {
+                let _ = input.focus();
+            }
+        }
+    });
+
+    view! {
+        <div class="lock-screen d-flex flex-column align-items-center justify-content-center" class:d-none=move || !locked.get()>
+            <form class="d-flex flex-column align-items-center" on:submit=on_submit>
+                <h1 class="display-3 mb-2"><BootstrapIcon icon="lock-fill" /></h1>
+                <h4 class="mb-4">"RustyChat is locked"</h4>
+                <div class="input-group mb-2">
+                    <input
+                        type="password"
+                        class="form-control"
+                        class:is-invalid=wrong_passphrase
+                        placeholder="Passphrase"
+                        aria-label="Passphrase"
+                        autocomplete="current-password"
+                        node_ref=passphrase_input
+                        on:input=move |_| wrong_passphrase.set(false)
+                    />
+                    <button type="submit" class="btn btn-primary" disabled=checking>
+                        "Unlock"
+                    </button>
+                </div>
+                {move || wrong_passphrase.get().then(|| view!{
+                    <small class="text-danger">"Wrong passphrase"</small>
+                })}
+            </form>
+        </div>
+    }
+}
+
+#[component]
+pub fn SecurityTab() -> impl IntoView {
+    let Context {
+        settings,
+        update_settings,
+        locked,
+        toasts,
+        errors,
+        ..
+    } = expect_context();
+
+    let enabled = move || with!(|settings| settings.lock.is_some());
+
+    let new_passphrase_input = create_node_ref::<Input>();
+    let confirm_passphrase_input = create_node_ref::<Input>();
+    let current_passphrase_input = create_node_ref::<Input>();
+    let form_error = create_rw_signal(None::<&'static str>);
+
+    let enable_lock = move |event: SubmitEvent| {
+        event.prevent_default();
+        let (Some(new_passphrase_input), Some(confirm_passphrase_input)) = (
+            new_passphrase_input.get_untracked(),
+            confirm_passphrase_input.get_untracked(),
+        )
+        else {
+            return;
+        };
+        let passphrase = new_passphrase_input.value();
+        if passphrase.is_empty() {
+            form_error.set(Some("Enter a passphrase."));
+            return;
+        }
+        if passphrase != confirm_passphrase_input.value() {
+            form_error.set(Some("The passphrases don't match."));
+            return;
+        }
+        form_error.set(None);
+
+        spawn_local(async move {
+            match new_lock(&passphrase, Some(DEFAULT_INACTIVITY_TIMEOUT_MINUTES)).await {
+                Ok(lock) => {
+                    new_passphrase_input.set_value("");
+                    confirm_passphrase_input.set_value("");
+                    update_settings.update(|settings| {
+                        settings.lock = Some(lock);
+                    });
+                    toasts.success("The app is now locked with the passphrase");
+                }
+                Err(error) => errors.push(error),
+            }
+        });
+    };
+
+    let remove_lock = move |event: SubmitEvent| {
+        event.prevent_default();
+        let Some(current_passphrase_input) = current_passphrase_input.get_untracked()
+        else {
+            return;
+        };
+        let Some(lock) = settings.with_untracked(|settings| settings.lock.clone())
+        else {
+            return;
+        };
+        let passphrase = current_passphrase_input.value();
+
+        spawn_local(async move {
+            match check_passphrase(&lock, &passphrase).await {
+                Ok(true) => {
+                    current_passphrase_input.set_value("");
+                    form_error.set(None);
+                    update_settings.update(|settings| settings.lock = None);
+                    toasts.success("The lock was removed");
+                }
+                Ok(false) => form_error.set(Some("Wrong passphrase")),
+                Err(error) => errors.push(error),
+            }
+        });
+    };
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
+            <h5>"Lock"</h5>
+            <p>
+                "Shows a lock screen on load, and after a while without any input. Conversations can't be read until it's unlocked with the passphrase. "
+                "The conversations are still stored unencrypted in the browser."
+            </p>
+            {move || if enabled() {
+                view!{
+                    <div class="form-floating mb-3">
+                        <input
+                            type="number"
+                            class="form-control"
+                            id="security_inactivity_timeout"
+                            min="1"
+                            placeholder="Only on load"
+                            prop:value=move || with!(|settings| {
+                                settings.lock.as_ref()
+                                    .and_then(|lock| lock.inactivity_timeout_minutes)
+                                    .map(|minutes| minutes.to_string())
+                                    .unwrap_or_default()
+                            })
+                            on:input=move |event| {
+                                let timeout = match non_empty(event_target_value(&event)) {
+                                    Some(value) => {
+                                        let Ok(minutes) = value.parse::<u32>() else { return; };
+                                        Some(minutes)
+                                    }
+                                    None => None,
+                                };
+                                update_settings.update(move |settings| {
+                                    if let Some(lock) = &mut settings.lock {
+                                        lock.inactivity_timeout_minutes = timeout;
+                                    }
+                                });
+                            }
+                        />
+                        <label for="security_inactivity_timeout">"Lock after this many minutes without input"</label>
+                        <div class="form-text">"Leave empty to only lock when the app is loaded."</div>
+                    </div>
+                    <div class="mb-4">
+                        <button type="button" class="btn btn-outline-primary" on:click=move |_| locked.set(true)>
+                            <span class="me-1"><BootstrapIcon icon="lock" /></span>
+                            "Lock now"
+                        </button>
+                    </div>
+                    <form on:submit=remove_lock>
+                        <label for="security_current_passphrase" class="form-label">"Enter the passphrase to remove the lock"</label>
+                        <div class="input-group mb-2">
+                            <input
+                                type="password"
+                                class="form-control"
+                                id="security_current_passphrase"
+                                autocomplete="current-password"
+                                node_ref=current_passphrase_input
+                            />
+                            <button type="submit" class="btn btn-outline-danger">"Remove lock"</button>
+                        </div>
+                    </form>
+                }.into_view()
+            }
+            else {
+                view!{
+                    <form on:submit=enable_lock>
+                        <div class="form-floating mb-3">
+                            <input
+                                type="password"
+                                class="form-control"
+                                id="security_new_passphrase"
+                                placeholder="Passphrase"
+                                autocomplete="new-password"
+                                node_ref=new_passphrase_input
+                            />
+                            <label for="security_new_passphrase">"Passphrase"</label>
+                        </div>
+                        <div class="form-floating mb-3">
+                            <input
+                                type="password"
+                                class="form-control"
+                                id="security_confirm_passphrase"
+                                placeholder="Passphrase"
+                                autocomplete="new-password"
+                                node_ref=confirm_passphrase_input
+                            />
+                            <label for="security_confirm_passphrase">"Passphrase again"</label>
+                            <div class="form-text">"There's no way to recover a forgotten passphrase, other than clearing the site data."</div>
+                        </div>
+                        <button type="submit" class="btn btn-primary">
+                            <span class="me-1"><BootstrapIcon icon="lock" /></span>
+                            "Lock with passphrase"
+                        </button>
+                    </form>
+                }.into_view()
+            }}
+            {move || form_error.get().map(|error| view!{
+                <small class="text-danger mt-2">{error}</small>
+            })}
+        </div>
+    }
+}
@@ -1,25 +1,55 @@
+pub mod archive;
+pub mod artifacts;
+pub mod bookmarks;
+pub mod commands;
 pub mod conversation;
+pub mod feedback;
+pub mod health;
 pub mod home;
+pub mod lock;
+pub mod model_card;
+pub mod print;
+pub mod quick_add;
 pub mod settings;
-
-use std::cmp::Ordering;
+pub mod shared;
+pub mod sidebar;
+pub mod speech;
+pub mod sync;
+pub mod toasts;
+pub mod update;
+
+use std::{
+    cell::Cell,
+    cmp::Ordering,
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    rc::Rc,
+    time::Duration,
+};
 
 use chrono::{
     DateTime,
-    Local,
+    Utc,
 };
 use futures::{
     stream::TryStreamExt,
     FutureExt,
 };
+use gloo_timers::future::sleep;
 use lazy_static::lazy_static;
 use leptos::{
     component,
+    create_effect,
     create_memo,
     create_rw_signal,
     create_trigger,
+    ev,
+    event_target_value,
     spawn_local,
     view,
+    window_event_listener,
     with,
     Children,
     CollectView,
@@ -56,28 +86,127 @@ use leptos_use::{
     UseColorModeReturn,
 };
 use semver::Version;
+use strum::EnumMessage;
 use uuid::Uuid;
 
 use self::{
+    archive::Archive,
+    artifacts::update_artifacts,
+    bookmarks::BookmarkList,
+    commands::CommandError,
     conversation::Conversation,
+    health::BackendStatus,
     home::Home,
+    lock::LockScreen,
+    print::PrintConversation,
     settings::SettingsRoutes,
+    shared::Shared,
+    sidebar::{
+        Selection,
+        SelectionToolbar,
+        SidebarConversation,
+        SidebarDeleteModal,
+    },
+    sync::{
+        start_sync,
+        SyncIndicator,
+        SyncStatus,
+    },
+    toasts::{
+        Toast,
+        ToastContainer,
+        ToastLevel,
+        Toasts,
+    },
+    update::UpdateBanner,
 };
 use crate::{
-    config::GITHUB_PAGE,
+    backend::{
+        huggingface::ModelStatus,
+        local::LoadProgress,
+        retry::{
+            with_retry,
+            RetryPolicy,
+        },
+        BackendError,
+        ChatMessage,
+        ChatPrompt,
+        TextGeneration,
+    },
+    config::{
+        self,
+        load_config,
+        BuildConfig,
+        ConfigError,
+        GITHUB_PAGE,
+    },
+    import::ImportError,
+    migrate::migrate,
+    parameters::{
+        self,
+        GenerationParameters,
+    },
+    search::{
+        semantic_search,
+        SearchError,
+        SearchResult,
+    },
+    share::SHARED_PATH,
     state::{
+        delete_storage,
+        init_memory_storage,
+        is_in_memory,
+        keep_in_memory,
+        purge_conversation,
+        use_audit_log,
         use_conversation,
+        use_debug_log,
         use_message,
         use_storage,
+        AuditLogEntry,
+        Bookmarks,
+        ChatTemplate,
+        CleanupAction,
+        CleanupPolicy,
         ConversationId,
+        ConversationParameters,
+        ConversationSummaries,
+        ConversationSummary,
         Conversations,
+        DebugLogEntry,
+        GenerationMetadata,
         Home,
         Message,
         MessageId,
+        MessageStatus,
+        ModelBackend,
+        ModelId,
+        Outbox,
+        OutboxEntry,
+        RequestOutcome,
         Role,
         Settings,
         StorageKey,
         StorageSignals,
+        Tags,
+        Templates,
+        Theme,
+        TranscriptSummary,
+        Trash,
+        DEBUG_LOG_SIZE,
+    },
+    sync::SyncError,
+    tabs::Tabs,
+    tokenizer::count_tokens,
+    tools::{
+        self,
+        MAX_TOOL_ROUNDS,
+    },
+    utils::{
+        is_online,
+        is_page_hidden,
+        non_empty,
+        notify,
     },
 };
 
@@ -95,33 +224,90 @@ pub enum Error {
     ConversationNotFound(ConversationId),
     #[error("Model ID not set")]
     ModelIdNotSet,
+    #[error("Model not found: {0}")]
+    ModelNotFound(ModelId),
+    #[error("Prompt too long: about {prompt_tokens} tokens, but the model's context length is {context_length}")]
+    ContextLengthExceeded {
+        prompt_tokens: usize,
+        context_length: usize,
+    },
+    #[error("A response is already being generated for this conversation in another tab")]
+    GeneratingInOtherTab,
+    #[error("There are no messages to summarize")]
+    NothingToSummarize,
+    #[error("The passphrase could not be hashed")]
+    HashPassphrase,
+    #[error("You're offline. Messages in private conversations can't be queued, since they aren't saved")]
+    OfflinePrivate,
+    #[error("API error")]
+    Backend(#[from] BackendError),
+    #[error("Request failed, retrying in {seconds:.1}s (attempt {attempt} of {max_attempts})")]
+    Retrying {
+        seconds: f32,
+        attempt: u32,
+        max_attempts: u32,
+        #[source]
+        reason: RetryReason,
+    },
+    #[error("{backend} is unavailable, trying {next} instead")]
+    FailingOver {
+        backend: String,
+        next: String,
+        #[source]
+        reason: RetryReason,
+    },
+    #[error("Another tab took over this conversation, so the response was stopped")]
+    TakenOver,
+    #[error("The model didn't finish loading in time")]
+    WarmUpTimeout,
+    #[error("Import failed")]
+    Import(#[from] ImportError),
+    #[error("Only the last response of a conversation can be continued")]
+    CantContinue,
+    #[error("Config could not be loaded")]
+    Config(#[from] ConfigError),
+    #[error("Sync failed")]
+    Sync(#[from] SyncError),
+    #[error("The image is too large ({size} bytes). Images can be at most {max_size} bytes.")]
+    ImageTooLarge { size: f64, max_size: f64 },
+    #[error("Could not read the image")]
+    ReadImage,
+    #[error("Could not record audio. Is a microphone connected, and did you allow access to it?")]
+    Recording,
+    #[error("Search failed")]
+    Search(#[from] SearchError),
+    #[error("Invalid command")]
+    Command(#[from] CommandError),
 }
 
-#[derive(Clone, Debug)]
-pub struct ErrorMessage {
-    id: Uuid,
-    message: String,
-    trace: Vec<String>,
-}
+/// The error that caused a retry or failover. We only keep the message, since
+/// the original error is still owned by the retry loop.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct RetryReason(String);
 
+/// Reports errors as toasts.
 #[derive(Copy, Clone)]
-pub struct Errors(RwSignal<Vec<ErrorMessage>>);
-
-impl Default for Errors {
-    fn default() -> Self {
-        Self(create_rw_signal(vec![]))
-    }
-}
+pub struct Errors(Toasts);
 
 impl Errors {
     pub fn push(&self, error: impl std::error::Error) {
+        self.0.show(Self::toast(&error));
+    }
+
+    /// Reports an error with a button that tries again.
+    pub fn push_with_retry(&self, error: impl std::error::Error, retry: impl Fn(()) + 'static) {
+        self.0.show(Self::toast(&error).with_action("Retry", retry));
+    }
+
+    fn toast(error: &dyn std::error::Error) -> Toast {
         let message = error.to_string();
 
         log::error!("reporting error: {message}");
 
         let trace = {
             let mut trace = vec![];
-            let mut error: &dyn std::error::Error = &error;
+            let mut error = error;
 
             while let Some(source) = error.source() {
                 trace.push(source.to_string());
@@ -131,18 +317,16 @@ impl Errors {
             trace
         };
 
-        let error = ErrorMessage {
-            id: Uuid::new_v4(),
-            message,
-            trace,
-        };
-        self.0.update(|errors| errors.push(error))
+        Toast::new(ToastLevel::Error, message).with_details(trace)
     }
 }
 
 #[derive(Clone)]
 pub struct Context {
-    pub is_loading: RwSignal<bool>,
+    /// Conversations that are generating a response right now.
+    pub loading: RwSignal<HashSet<ConversationId>>,
+    /// Conversations whose response was asked to stop.
+    pub stopping: RwSignal<HashSet<ConversationId>>,
     pub errors: Errors,
     pub settings: Signal<Settings>,
     pub update_settings: WriteSignal<Settings>,
@@ -150,12 +334,47 @@ pub struct Context {
     pub update_home: WriteSignal<Home>,
     pub conversations: Signal<Conversations>,
     pub update_conversations: WriteSignal<Conversations>,
+    pub summaries: Signal<ConversationSummaries>,
+    pub update_summaries: WriteSignal<ConversationSummaries>,
+    pub tags: Signal<Tags>,
+    pub update_tags: WriteSignal<Tags>,
+    pub templates: Signal<Templates>,
+    pub update_templates: WriteSignal<Templates>,
+    pub trash: Signal<Trash>,
+    pub update_trash: WriteSignal<Trash>,
+    pub bookmarks: Signal<Bookmarks>,
+    pub update_bookmarks: WriteSignal<Bookmarks>,
+    pub toasts: Toasts,
     pub scroll_trigger: Trigger,
+    pub tabs: Tabs,
+    /// Conversations that wait for their model to load.
+    pub warming_up: RwSignal<HashSet<ConversationId>>,
+    /// Conversations that are being summarized.
+    pub summarizing: RwSignal<HashSet<ConversationId>>,
+    /// Whether the lock screen is shown.
+    pub locked: RwSignal<bool>,
+    /// Progress of downloading and loading a model that runs in the browser.
+    pub local_load_progress: RwSignal<Option<LoadProgress>>,
+    /// Conversations that are only kept in memory.
+    pub private_conversations: RwSignal<HashSet<ConversationId>>,
+    /// Progress of the responses that are being generated, by conversation.
+    pub stream_status: RwSignal<HashMap<ConversationId, StreamStatus>>,
+    /// Whether the browser has network access.
+    pub online: Signal<bool>,
+    /// The app config. This is updated once the config of the deployment is
+    /// loaded.
+    pub config: Signal<Rc<BuildConfig>>,
+    /// Messages waiting to be sent until we're online again.
+    pub outbox: Signal<Outbox>,
+    pub update_outbox: WriteSignal<Outbox>,
+    pub sync_status: RwSignal<SyncStatus>,
 }
 
 fn provide_context() {
     log::info!("app version: {}", *VERSION);
 
+    init_memory_storage();
+
     let StorageSignals {
         write: update_version,
         ..
@@ -171,7 +390,8 @@ fn provide_context() {
                 }
                 Ordering::Equal => {}
                 Ordering::Greater => {
-                    todo!("migrate storage");
+                    migrate(storage_version);
+                    *storage_version = VERSION.clone();
                 }
             }
         }
@@ -195,19 +415,90 @@ fn provide_context() {
         write: update_conversations,
         ..
     } = use_storage(StorageKey::Conversations);
+    let StorageSignals {
+        read: summaries,
+        write: update_summaries,
+        ..
+    } = use_storage(StorageKey::ConversationSummaries);
+    let StorageSignals {
+        read: tags,
+        write: update_tags,
+        ..
+    } = use_storage(StorageKey::Tags);
+    let StorageSignals {
+        read: templates,
+        write: update_templates,
+        ..
+    } = use_storage(StorageKey::Templates);
+    let StorageSignals {
+        read: trash,
+        write: update_trash,
+        ..
+    } = use_storage(StorageKey::Trash);
+    let StorageSignals {
+        read: bookmarks,
+        write: update_bookmarks,
+        ..
+    } = use_storage(StorageKey::Bookmarks);
+
+    let StorageSignals {
+        read: outbox,
+        write: update_outbox,
+        ..
+    } = use_storage(StorageKey::Outbox);
 
     let scroll_trigger = create_trigger();
 
+    let config = create_rw_signal(config::config());
+    let config_url: Option<String> =
+        settings.with_untracked(|settings| settings.config_url.clone());
+    spawn_local(async move {
+        match load_config(config_url.as_deref()).await {
+            Ok(loaded) => config.set(loaded),
+            // deployments don't need to have a config.
+            Err(error) => log::warn!("using embedded config: {error}"),
+        }
+    });
+
+    let online = create_rw_signal(is_online());
+    window_event_listener(ev::online, move |_| online.set(true));
+    window_event_listener(ev::offline, move |_| online.set(false));
+
+    let toasts = Toasts::default();
     leptos::provide_context(Context {
-        is_loading: create_rw_signal(false),
-        errors: Errors::default(),
+        loading: create_rw_signal(HashSet::new()),
+        stopping: create_rw_signal(HashSet::new()),
+        errors: Errors(toasts),
         settings,
         update_settings,
         home,
         update_home,
         conversations,
         update_conversations,
+        summaries,
+        update_summaries,
+        tags,
+        update_tags,
+        templates,
+        update_templates,
+        trash,
+        update_trash,
+        bookmarks,
+        update_bookmarks,
+        toasts,
         scroll_trigger,
+        tabs: Tabs::new(),
+        warming_up: create_rw_signal(HashSet::new()),
+        summarizing: create_rw_signal(HashSet::new()),
+        locked: create_rw_signal(settings.with_untracked(|settings| settings.lock.is_some())),
+        local_load_progress: create_rw_signal(None),
+        private_conversations: create_rw_signal(HashSet::new()),
+        stream_status: create_rw_signal(HashMap::new()),
+        online: online.into(),
+        config: config.into(),
+        outbox,
+        update_outbox,
+        sync_status: create_rw_signal(SyncStatus::default()),
     });
 }
 
@@ -215,19 +506,465 @@ pub fn expect_context() -> Context {
     leptos::expect_context::<Context>()
 }
 
-pub fn push_user_message(conversation_id: ConversationId, user_message: String) {
+/// Updates the summary of a conversation from its stored data. This needs to
+/// be called after changing any of the fields in [`ConversationSummary`].
+pub fn refresh_summary(id: ConversationId) {
+    let Context {
+        update_summaries, ..
+    } = expect_context();
+
+    // private conversations don't leave any trace in storage.
+    if is_private(id) {
+        return;
+    }
+
+    let StorageSignals {
+        read: conversation, ..
+    } = use_conversation(id);
+    let summary = conversation
+        .with_untracked(|conversation| conversation.as_ref().map(ConversationSummary::from));
+
+    update_summaries.update(|summaries| {
+        match summary {
+            Some(summary) => {
+                summaries.insert(id, summary);
+            }
+            None => {
+                summaries.remove(&id);
+            }
+        }
+    });
+}
+
+/// Permanently deletes a conversation.
+pub fn delete_conversation_permanently(id: ConversationId) {
+    let Context {
+        update_summaries,
+        update_trash,
+        ..
+    } = expect_context();
+
+    purge_conversation(id);
+    update_summaries.update(|summaries| {
+        summaries.remove(&id);
+    });
+    update_trash.update(|trash| {
+        trash.remove(&id);
+    });
+}
+
+const UNDO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shows a toast that lets the user undo an action for a few seconds.
+pub fn offer_undo(message: impl Into<String>, action: impl Fn(()) + 'static) {
+    let Context { toasts, .. } = expect_context();
+
+    toasts.show(
+        Toast::new(ToastLevel::Info, message)
+            .with_action("Undo", action)
+            .with_timeout(Some(UNDO_TIMEOUT)),
+    );
+}
+
+/// Starts keeping a new conversation only in memory.
+pub fn make_private(id: ConversationId) {
+    let Context {
+        private_conversations,
+        ..
+    } = expect_context();
+
+    keep_in_memory(StorageKey::Conversation(id));
+    keep_in_memory(StorageKey::AuditLog(id));
+    keep_in_memory(StorageKey::Artifacts(id));
+    private_conversations.update(|private_conversations| {
+        private_conversations.insert(id);
+    });
+}
+
+pub fn is_private(id: ConversationId) -> bool {
+    is_in_memory(StorageKey::Conversation(id))
+}
+
+/// Private conversations are dropped right away, instead of going to the
+/// trash.
+fn discard_private_conversation(id: ConversationId) {
+    let Context {
+        private_conversations,
+        ..
+    } = expect_context();
+
+    let message_ids = use_conversation(id).read.with_untracked(|conversation| {
+        conversation
+            .as_ref()
+            .map(|conversation| conversation.messages.clone())
+            .unwrap_or_default()
+    });
+    for message_id in message_ids {
+        delete_storage(StorageKey::Message(message_id));
+    }
+    delete_storage(StorageKey::Conversation(id));
+    delete_storage(StorageKey::AuditLog(id));
+    delete_storage(StorageKey::Artifacts(id));
+
+    private_conversations.update(|private_conversations| {
+        private_conversations.remove(&id);
+    });
+}
+
+/// Moves a conversation to the trash. It can be restored until the trash is
+/// purged.
+pub fn trash_conversation(id: ConversationId) {
+    trash_conversations(vec![id]);
+}
+
+/// Moves several conversations to the trash at once, with one undo for all of
+/// them.
+pub fn trash_conversations(ids: Vec<ConversationId>) {
+    let Context {
+        update_conversations,
+        update_trash,
+        ..
+    } = expect_context();
+
+    let (private, ids): (Vec<_>, Vec<_>) = ids.into_iter().partition(|id| is_private(*id));
+    for id in private {
+        log::info!("discarding private conversation: {id}");
+        discard_private_conversation(id);
+    }
+    if ids.is_empty() {
+        return;
+    }
+
+    log::info!("moving conversations to trash: {ids:?}");
+
+    let now = Utc::now();
+    update_conversations.update(|conversations| {
+        for id in &ids {
+            conversations.remove(id);
+        }
+    });
+    update_trash.update(|trash| {
+        trash.extend(ids.iter().map(|id| (*id, now)));
+    });
+
+    let message = if ids.len() == 1 {
+        "Conversation moved to trash".to_owned()
+    }
+    else {
+        format!("{} conversations moved to trash", ids.len())
+    };
+    offer_undo(message, move |_| {
+        for id in &ids {
+            restore_conversation(*id);
+        }
+    });
+}
+
+pub fn restore_conversation(id: ConversationId) {
+    let Context {
+        update_conversations,
+        update_trash,
+        ..
+    } = expect_context();
+
+    log::info!("restoring conversation from trash: {id}");
+
+    update_trash.update(|trash| {
+        trash.remove(&id);
+    });
+    update_conversations.update(|conversations| {
+        conversations.insert(id);
+    });
+
+    // the tombstone on the sync server is newer than the conversation, so it
+    // has to be marked as modified, or the next sync would delete it again.
+    let StorageSignals {
+        write: update_conversation,
+        ..
+    } = use_conversation(id);
+    update_conversation.update(|conversation| {
+        if let Some(conversation) = conversation {
+            conversation.timestamp_modified = Utc::now();
+        }
+    });
+    refresh_summary(id);
+}
+
+/// Permanently deletes conversations in the trash. If `retention_days` is
+/// given, only conversations that were deleted longer ago are purged.
+pub fn purge_trash(retention_days: Option<u32>) {
+    let Context { trash, .. } = expect_context();
+
+    let now = Utc::now();
+    let expired = trash.with_untracked(|trash| {
+        trash
+            .iter()
+            .filter(|(_, deleted)| {
+                retention_days.map_or(true, |days| {
+                    now - **deleted > chrono::Duration::days(days.into())
+                })
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>()
+    });
+
+    for id in expired {
+        log::info!("purging conversation: {id}");
+        delete_conversation_permanently(id);
+    }
+}
+
+/// The conversations that `policy` would clean up right now.
+pub fn cleanup_candidates(policy: &CleanupPolicy) -> Vec<ConversationId> {
+    let Context {
+        conversations,
+        summaries,
+        ..
+    } = expect_context();
+
+    let now = Utc::now();
+    with!(|conversations, summaries| {
+        conversations
+            .iter()
+            .filter(|id| {
+                summaries
+                    .get(id)
+                    .map_or(false, |summary| policy.applies_to(summary, now))
+            })
+            .copied()
+            .collect()
+    })
+}
+
+/// Archives or trashes the conversations that `policy` applies to.
+pub fn apply_cleanup(policy: &CleanupPolicy) {
+    let Context { toasts, .. } = expect_context();
+
+    let ids = cleanup_candidates(policy);
+    if ids.is_empty() {
+        return;
+    }
+    log::info!("cleaning up old conversations: {ids:?}");
+
+    match policy.action {
+        CleanupAction::Archive => {
+            for id in &ids {
+                use_conversation(*id).write.update(|conversation| {
+                    if let Some(conversation) = conversation {
+                        conversation.archived = true;
+                    }
+                });
+                refresh_summary(*id);
+            }
+            toasts.info(format!("Archived {} old conversations", ids.len()));
+        }
+        CleanupAction::Trash => trash_conversations(ids),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamStatus {
+    pub started: DateTime<Utc>,
+    pub first_token: Option<DateTime<Utc>>,
+    pub tokens: usize,
+}
+
+impl StreamStatus {
+    pub fn tokens_per_second(&self, now: DateTime<Utc>) -> Option<f64> {
+        let seconds = (now - self.first_token?).num_milliseconds() as f64 / 1000.0;
+        (seconds > 0.0).then(|| self.tokens as f64 / seconds)
+    }
+}
+
+/// Overrides for a single message, that don't change the conversation's
+/// defaults.
+#[derive(Clone, Debug, Default)]
+pub struct SendOverrides {
+    pub model_id: Option<ModelId>,
+    pub temperature: Option<f32>,
+    /// Images attached to the message, as data URLs.
+    pub images: Vec<String>,
+}
+
+/// Puts a message into the outbox, to be sent once we're online again, or
+/// once `tab_id` can lock the conversation. Returns whether it was queued.
+fn queue_message(
+    conversation_id: ConversationId,
+    text: String,
+    overrides: SendOverrides,
+    tab_id: Option<Uuid>,
+) -> bool {
+    let Context {
+        update_outbox,
+        errors,
+        ..
+    } = expect_context();
+
+    // the outbox is in storage, so it would keep the message after the
+    // conversation is gone.
+    if is_private(conversation_id) {
+        errors.push(Error::OfflinePrivate);
+        return false;
+    }
+
+    log::info!("queueing message for conversation {conversation_id}");
+
+    update_outbox.update(|outbox| {
+        outbox.push(OutboxEntry {
+            id: Uuid::new_v4(),
+            conversation_id,
+            text,
+            timestamp: Utc::now(),
+            model_id: overrides.model_id,
+            temperature: overrides.temperature,
+            images: overrides.images,
+            tab_id,
+        })
+    });
+
+    true
+}
+
+/// Sends queued messages while we're online. Messages for the same
+/// conversation are sent one after another, and only once no other tab is
+/// generating in it.
+fn send_queued_messages() {
+    let Context {
+        loading,
+        online,
+        outbox,
+        update_outbox,
+        tabs,
+        ..
+    } = expect_context();
+
+    create_effect(move |_| {
+        loading.track();
+        if !online.get() || outbox.with(|outbox| outbox.is_empty()) {
+            return;
+        }
+
+        // this also runs again once the other tabs release their locks.
+        let locked_elsewhere = outbox.with(|outbox| {
+            outbox
+                .iter()
+                .map(|entry| entry.conversation_id)
+                .filter(|conversation_id| tabs.is_locked_elsewhere(*conversation_id))
+                .collect::<HashSet<_>>()
+        });
+
+        // the first queued message of each conversation that isn't generating.
+        while let Some(entry) = update_outbox
+            .try_update(|outbox| {
+                let index = loading.with_untracked(|loading| {
+                    outbox.iter().position(|entry| {
+                        entry.tab_id.map_or(true, |tab_id| tab_id == tabs.id())
+                            && !loading.contains(&entry.conversation_id)
+                            && !locked_elsewhere.contains(&entry.conversation_id)
+                    })
+                })?;
+                Some(outbox.remove(index))
+            })
+            .flatten()
+        {
+            log::info!(
+                "sending queued message for conversation {}",
+                entry.conversation_id
+            );
+            push_user_message(
+                entry.conversation_id,
+                entry.text,
+                SendOverrides {
+                    model_id: entry.model_id,
+                    temperature: entry.temperature,
+                    images: entry.images,
+                },
+            );
+        }
+    });
+}
+
+/// The most responses that are generated for one message. They're generated
+/// one after another, so this can take a while.
+const MAX_CANDIDATES: usize = 8;
+
+/// Returns whether the message was sent or queued. A message that doesn't fit
+/// into the context is put back into the conversation's draft.
+pub fn push_user_message(
+    conversation_id: ConversationId,
+    user_message: String,
+    overrides: SendOverrides,
+) -> bool {
     let Context {
-        is_loading,
+        loading,
         errors,
         settings,
         scroll_trigger,
+        tabs,
+        online,
+        toasts,
         ..
     } = expect_context();
 
-    let api = settings.with_untracked(|settings| settings.api());
+    if !online.get_untracked() {
+        return queue_message(conversation_id, user_message, overrides, None);
+    }
+
+    // hold the lock until the response is complete, so that other tabs can't
+    // generate into this conversation at the same time. if another tab is
+    // generating, the message is sent once it's done.
+    if !tabs.lock(conversation_id) {
+        let queued = queue_message(conversation_id, user_message, overrides, Some(tabs.id()));
+        if queued {
+            toasts.info("Another tab is generating a response. Your message is sent once it's done.");
+        }
+        return queued;
+    }
+
+    let (retry_policy, record_audit_log) =
+        settings.with_untracked(|settings| (settings.retry, settings.audit_log));
+
+    let StorageSignals {
+        read: conversation,
+        write: update_conversation,
+        ..
+    } = use_conversation(conversation_id);
+
+    // check the model before anything is stored, so that a message that can't
+    // be sent doesn't leave a message behind.
+    let model = conversation.with_untracked(|conversation| {
+        let conversation = conversation
+            .as_ref()
+            .ok_or_else(|| Error::ConversationNotFound(conversation_id))?;
+        let model_id = overrides
+            .model_id
+            .clone()
+            .or_else(|| conversation.model_id.clone())
+            .ok_or_else(|| Error::ModelIdNotSet)?;
+        let (stream, context_length) = settings
+            .with_untracked(|settings| {
+                settings
+                    .models
+                    .get(&model_id)
+                    .map(|model| (model.stream, model.context_length))
+            })
+            .ok_or_else(|| Error::ModelNotFound(model_id.clone()))?;
+        Ok::<_, Error>((model_id, stream, context_length))
+    });
+    let (model_id, stream, context_length) = match model {
+        Ok(model) => model,
+        Err(e) => {
+            errors.push(e);
+            tabs.unlock(conversation_id);
+            return false;
+        }
+    };
 
     let message_id = MessageId::new();
-    let now = Local::now();
+    let now = Utc::now();
+    if is_private(conversation_id) {
+        keep_in_memory(StorageKey::Message(message_id));
+    }
 
     // create and store message
     let StorageSignals {
@@ -236,28 +973,26 @@ pub fn push_user_message(conversation_id: ConversationId, user_message: String)
     set_message.set(Some(Message {
         id: message_id,
         role: Role::User,
-        text: user_message,
+        text: user_message.clone(),
         timestamp: now,
+        generation: None,
+        images: overrides.images,
+        tool: None,
+        status: MessageStatus::Complete,
+        note: None,
+        rating: None,
+        candidates: vec![],
     }));
 
-    let StorageSignals {
-        write: update_conversation,
-        ..
-    } = use_conversation(conversation_id);
-
-    // add message to conversation and get model_id and prompt
+    // add message to conversation and get the prompt
     let result = {
+        let model_id = &model_id;
         update_conversation
             .try_update(move |conversation| {
                 let conversation = conversation
                     .as_mut()
                     .ok_or_else(|| Error::ConversationNotFound(conversation_id))?;
 
-                let model_id = conversation
-                    .model_id
-                    .clone()
-                    .ok_or_else(|| Error::ModelIdNotSet)?;
-
                 conversation.messages.push(message_id);
                 conversation.timestamp_last_interaction = now;
 
@@ -270,153 +1005,1370 @@ pub fn push_user_message(conversation_id: ConversationId, user_message: String)
                     })
                     .collect::<Vec<_>>();
 
-                let (chat_template, stream) = settings.with_untracked(|settings| {
-                    let model = settings.models.get(&model_id).unwrap();
-                    (model.chat_template, model.stream)
+                let turn = messages
+                    .iter()
+                    .filter(|message| matches!(message.role, Role::User))
+                    .count();
+                let mut generation_parameters = GenerationParameters::resolve(
+                    &conversation.conversation_parameters,
+                    turn,
+                    stream,
+                );
+                if let Some(temperature) = overrides.temperature {
+                    generation_parameters.temperature = temperature;
+                }
+
+                let (prompt_parameters, messages) = with_summary(&conversation, messages);
+                let (prompt, prompt_tokens) = settings.with_untracked(|settings| {
+                    build_prompt(
+                        settings,
+                        model_id,
+                        &prompt_parameters,
+                        messages,
+                        conversation
+                            .conversation_parameters
+                            .start_response_with
+                            .as_deref(),
+                    )
                 });
 
-                let prompt = chat_template.generate_prompt(
-                    conversation
-                        .conversation_parameters
-                        .system_prompt
-                        .as_ref()
-                        .map(|s| s.as_str()),
-                    &messages,
-                    conversation
-                        .conversation_parameters
-                        .start_response_with
-                        .as_ref()
-                        .map(|s| s.as_str()),
-                );
+                if let Some(context_length) = context_length {
+                    if prompt_tokens >= context_length {
+                        // refuse to send, and don't keep the message in the conversation.
+                        // the input was already cleared, so the text goes back into the draft.
+                        conversation.messages.pop();
+                        delete_storage(StorageKey::Message(message_id));
+                        conversation.user_message = user_message;
+                        return Err(Error::ContextLengthExceeded {
+                            prompt_tokens,
+                            context_length,
+                        });
+                    }
 
-                Ok::<_, Error>((
-                    model_id,
-                    prompt,
-                    conversation.conversation_parameters.clone(),
-                    stream,
-                ))
+                    // don't ask for more tokens than fit into the context.
+                    generation_parameters.max_new_tokens = generation_parameters
+                        .max_new_tokens
+                        .min(context_length - prompt_tokens);
+                }
+
+                // the parameters that are recorded with the response
+                let mut conversation_parameters = conversation.conversation_parameters.clone();
+                if overrides.temperature.is_some() {
+                    conversation_parameters.temperature = overrides.temperature;
+                }
+
+                Ok::<_, Error>((prompt, conversation_parameters, generation_parameters))
             })
             .unwrap()
     };
 
+    refresh_summary(conversation_id);
     scroll_trigger.notify();
 
-    let (model_id, prompt, conversation_parameters, stream) = match result {
+    let (prompt, conversation_parameters, generation_parameters) = match result {
         Ok(x) => x,
         Err(e) => {
             errors.push(e);
-            return;
+            tabs.unlock(conversation_id);
+            return false;
         }
     };
 
-    let mut model = api.text_generation(&model_id.0);
-    let default_token_limit = stream.then_some(2000).unwrap_or(250);
-    model.max_new_tokens = Some(
-        conversation_parameters
-            .token_limit
-            .unwrap_or(default_token_limit),
-    );
-    model.temparature = conversation_parameters.temperature.unwrap_or(1.0);
-    model.top_k = conversation_parameters.top_k;
-    model.top_p = conversation_parameters.top_p;
-    model.repetition_penalty = conversation_parameters.repetition_penalty;
+    let mut model = settings
+        .with_untracked(|settings| settings.text_generation(&model_id, generation_parameters));
+    let start_response_with = conversation_parameters
+        .start_response_with
+        .clone()
+        .unwrap_or_default();
+
+    // set this right away, so that queued messages are sent one after another.
+    loading.update(|loading| {
+        loading.insert(conversation_id);
+    });
 
     spawn_local(
         async move {
-            is_loading.set(true);
-
-            let message_id = MessageId::new();
-            let now = Local::now();
-
-            let StorageSignals {
-                write: set_message, ..
-            } = use_message(message_id);
-            set_message.set(Some(Message {
-                id: message_id,
-                role: Role::Assitant,
-                text: conversation_parameters
-                    .start_response_with
-                    .unwrap_or_default(),
-                timestamp: now,
-            }));
-
-            scroll_trigger.notify();
-
-            update_conversation.update(|conversation| {
-                if let Some(conversation) = conversation {
-                    conversation.messages.push(message_id);
-                    conversation.timestamp_last_interaction = now;
+            warm_up_conversation(conversation_id, &model).await?;
+
+            let mut prompt = prompt;
+            for round in 0.. {
+                let message_id = MessageId::new();
+                let now = Utc::now();
+                if is_private(conversation_id) {
+                    keep_in_memory(StorageKey::Message(message_id));
                 }
-                else {
-                    log::warn!("conversation does not exist: {conversation_id}");
+
+                let StorageSignals {
+                    write: set_message, ..
+                } = use_message(message_id);
+                set_message.set(Some(Message {
+                    id: message_id,
+                    role: Role::Assitant,
+                    text: start_response_with.clone(),
+                    timestamp: now,
+                    generation: None,
+                    images: vec![],
+                    tool: None,
+                    status: MessageStatus::Pending,
+                    note: None,
+                    rating: None,
+                    candidates: vec![],
+                }));
+
+                if record_audit_log {
+                    let StorageSignals {
+                        write: update_audit_log,
+                        ..
+                    } = use_audit_log(conversation_id);
+                    update_audit_log.update(|audit_log| {
+                        audit_log.push(AuditLogEntry {
+                            message_id,
+                            timestamp: now,
+                            model_id: model_id.clone(),
+                            prompt: prompt.clone(),
+                            parameters: model.parameters().clone(),
+                        });
+                    });
                 }
-            });
 
-            if stream {
-                let mut stream = model.generate_stream(&prompt).await?;
+                scroll_trigger.notify();
 
-                while let Some(token) = stream.try_next().await? {
-                    if token.special {
-                        continue;
+                update_conversation.update(|conversation| {
+                    if let Some(conversation) = conversation {
+                        conversation.messages.push(message_id);
+                        conversation.timestamp_last_interaction = now;
+                    }
+                    else {
+                        log::warn!("conversation does not exist: {conversation_id}");
                     }
+                });
+                refresh_summary(conversation_id);
 
-                    set_message.update(move |message| {
-                        let message = message.as_mut().unwrap();
-                        message.text.push_str(&token.text);
-                        scroll_trigger.notify();
-                    });
+                let chain = settings.with_untracked(|settings| {
+                    settings.failover_chain(&model_id, model.parameters().clone())
+                });
+                let timing = generate_with_failover(
+                    conversation_id,
+                    message_id,
+                    &model_id,
+                    &chain,
+                    &prompt,
+                    stream,
+                    set_message,
+                    &start_response_with,
+                    &retry_policy,
+                )
+                .await?;
+
+                if !tabs.holds_lock(conversation_id) {
+                    return Err(Error::TakenOver);
                 }
-            }
-            else {
-                let response = model.generate(&prompt).await?;
 
+                let generation = timing.metadata(
+                    model_id.clone(),
+                    conversation_parameters.clone(),
+                    model.parameters().max_new_tokens,
+                );
                 set_message.update(move |message| {
-                    let message = message.as_mut().unwrap();
-                    message.text = response;
-                    scroll_trigger.notify();
+                    if let Some(message) = message {
+                        message.generation = Some(generation);
+                        message.status = MessageStatus::Complete;
+                    }
                 });
+
+                // further candidates are generated one after another, since not every
+                // backend can return several at once. a response that calls a tool
+                // doesn't get any, since the tool result follows it.
+                let candidates = conversation_parameters.n.unwrap_or(1).min(MAX_CANDIDATES);
+                let calls_tool = use_message(message_id).read.with_untracked(|message| {
+                    message.as_ref().map_or(false, |message| {
+                        tools::parse_tool_call(&message.text).is_some()
+                    })
+                });
+                if round == 0 && candidates > 1 && !calls_tool {
+                    set_message.update(|message| {
+                        if let Some(message) = message {
+                            message.push_candidate();
+                        }
+                    });
+                    for _ in 1..candidates {
+                        if is_stopping(conversation_id) {
+                            break;
+                        }
+                        set_message.update(|message| {
+                            if let Some(message) = message {
+                                message.status = MessageStatus::Pending;
+                            }
+                        });
+                        let timing = generate_with_failover(
+                            conversation_id,
+                            message_id,
+                            &model_id,
+                            &chain,
+                            &prompt,
+                            stream,
+                            set_message,
+                            &start_response_with,
+                            &retry_policy,
+                        )
+                        .await?;
+                        if !tabs.holds_lock(conversation_id) {
+                            return Err(Error::TakenOver);
+                        }
+                        let generation = timing.metadata(
+                            model_id.clone(),
+                            conversation_parameters.clone(),
+                            model.parameters().max_new_tokens,
+                        );
+                        set_message.update(move |message| {
+                            if let Some(message) = message {
+                                message.generation = Some(generation);
+                                message.status = MessageStatus::Complete;
+                                message.push_candidate();
+                            }
+                        });
+                    }
+                    // the first one is shown until another one is chosen.
+                    set_message.update(|message| {
+                        if let Some(message) = message {
+                            message.select_candidate(0);
+                        }
+                    });
+                }
+
+                // later responses can revise artifacts
+                if let Some(message) = use_message(message_id).read.get_untracked() {
+                    update_artifacts(conversation_id, &message);
+                }
+
+                // if the model called a tool, it answers again with the result.
+                if round == MAX_TOOL_ROUNDS
+                    || is_stopping(conversation_id)
+                    || !run_tool_call(conversation_id, message_id, &model_id).await
+                {
+                    break;
+                }
+                if !tabs.holds_lock(conversation_id) {
+                    return Err(Error::TakenOver);
+                }
+
+                let (next_prompt, prompt_tokens) = conversation_prompt(conversation_id, &model_id)?;
+                let context_length = settings.with_untracked(|settings| {
+                    settings
+                        .models
+                        .get(&model_id)
+                        .and_then(|model| model.context_length)
+                });
+                if let Some(context_length) = context_length {
+                    if prompt_tokens >= context_length {
+                        return Err(Error::ContextLengthExceeded {
+                            prompt_tokens,
+                            context_length,
+                        });
+                    }
+                    let mut parameters = model.parameters().clone();
+                    parameters.max_new_tokens = parameters
+                        .max_new_tokens
+                        .min(context_length - prompt_tokens);
+                    model = settings
+                        .with_untracked(|settings| settings.text_generation(&model_id, parameters));
+                }
+                prompt = next_prompt;
             }
 
             Ok(())
         }
-        .map(move |result: Result<(), Error>| {
-            if let Err(e) = result {
-                log::error!("response stream failed: {e}");
+        .map(move |result| finish_generation(conversation_id, result)),
+    );
+
+    true
+}
+
+/// If a response calls a tool, this cuts off whatever the model generated
+/// after the call, runs the tool, and adds its result to the conversation.
+/// Returns whether a tool was called.
+async fn run_tool_call(
+    conversation_id: ConversationId,
+    message_id: MessageId,
+    model_id: &ModelId,
+) -> bool {
+    let Context {
+        settings,
+        scroll_trigger,
+        ..
+    } = expect_context();
+
+    let Some((tools, search_url)) = settings.with_untracked(|settings| {
+        let model = settings.models.get(model_id)?;
+        model
+            .tools
+            .then(|| (settings.tools.clone(), settings.search_url.clone()))
+    })
+    else {
+        return false;
+    };
+
+    let StorageSignals {
+        read: message,
+        write: set_message,
+        ..
+    } = use_message(message_id);
+    let Some((call, cut_off)) = message.with_untracked(|message| {
+        message
+            .as_ref()
+            .and_then(|message| tools::parse_tool_call(&message.text))
+    })
+    else {
+        return false;
+    };
+    set_message.update(|message| {
+        if let Some(message) = message {
+            message.text.truncate(cut_off);
+        }
+    });
+
+    let result = tools::run(&tools, search_url.as_deref(), &call).await;
+    if let Err(error) = &result {
+        log::warn!("tool call failed: {error}");
+    }
+
+    let result_id = MessageId::new();
+    if is_private(conversation_id) {
+        keep_in_memory(StorageKey::Message(result_id));
+    }
+    let StorageSignals {
+        write: set_result, ..
+    } = use_message(result_id);
+    set_result.set(Some(Message {
+        id: result_id,
+        role: Role::User,
+        text: tools::tool_response(result),
+        timestamp: Utc::now(),
+        generation: None,
+        images: vec![],
+        tool: Some(call.name),
+        status: MessageStatus::Complete,
+        note: None,
+        rating: None,
+        candidates: vec![],
+    }));
+
+    let StorageSignals {
+        write: update_conversation,
+        ..
+    } = use_conversation(conversation_id);
+    update_conversation.update(|conversation| {
+        if let Some(conversation) = conversation {
+            conversation.messages.push(result_id);
+        }
+    });
+    scroll_trigger.notify();
+
+    true
+}
+
+/// The prompt for the next response of a conversation, and its estimated
+/// number of tokens.
+fn conversation_prompt(
+    conversation_id: ConversationId,
+    model_id: &ModelId,
+) -> Result<(String, usize), Error> {
+    let Context { settings, .. } = expect_context();
+
+    let conversation = use_conversation(conversation_id)
+        .read
+        .get_untracked()
+        .ok_or_else(|| Error::ConversationNotFound(conversation_id))?;
+    let messages = conversation
+        .messages
+        .iter()
+        .filter_map(|message_id| use_message(*message_id).read.get_untracked())
+        .collect::<Vec<_>>();
+    let (prompt_parameters, messages) = with_summary(&conversation, messages);
+
+    Ok(settings.with_untracked(|settings| {
+        build_prompt(
+            settings,
+            model_id,
+            &prompt_parameters,
+            messages,
+            conversation
+                .conversation_parameters
+                .start_response_with
+                .as_deref(),
+        )
+    }))
+}
+
+/// The prompt for generating `message_id` with a fallback backend, from the
+/// messages before it.
+fn fallback_prompt(
+    conversation_id: ConversationId,
+    message_id: MessageId,
+    model_id: &ModelId,
+    backend: ModelBackend,
+) -> Result<String, Error> {
+    let Context { settings, .. } = expect_context();
+
+    let conversation = use_conversation(conversation_id)
+        .read
+        .get_untracked()
+        .ok_or_else(|| Error::ConversationNotFound(conversation_id))?;
+    let messages = conversation
+        .messages
+        .iter()
+        .take_while(|id| **id != message_id)
+        .filter_map(|message_id| use_message(*message_id).read.get_untracked())
+        .collect::<Vec<_>>();
+
+    let (prompt_parameters, messages) = with_summary(&conversation, messages);
+    let (prompt, _) = settings.with_untracked(|settings| {
+        build_prompt_on(
+            settings,
+            model_id,
+            backend,
+            &prompt_parameters,
+            messages,
+            conversation
+                .conversation_parameters
+                .start_response_with
+                .as_deref(),
+        )
+    });
+    Ok(prompt)
+}
+
+/// If the conversation's summary is used as context, the messages it covers
+/// are left out of the prompt, and the summary is added to the system prompt.
+fn with_summary(
+    conversation: &crate::state::Conversation,
+    mut messages: Vec<Message>,
+) -> (ConversationParameters, Vec<Message>) {
+    let mut parameters = conversation.conversation_parameters.clone();
+    let Some(summary) = conversation
+        .summary
+        .as_ref()
+        .filter(|summary| summary.use_as_context)
+    else {
+        return (parameters, messages);
+    };
+    let Some(index) = messages
+        .iter()
+        .position(|message| message.id == summary.up_to)
+    else {
+        return (parameters, messages);
+    };
+
+    messages.drain(..=index);
+    let context = format!("Summary of the conversation so far:\n{}", summary.text);
+    parameters.system_prompt = Some(match parameters.system_prompt {
+        Some(system_prompt) => format!("{system_prompt}\n\n{context}"),
+        None => context,
+    });
+    (parameters, messages)
+}
+
+/// Builds the prompt from the messages, and estimates its number of tokens.
+/// This describes the tools, if the model can call them, and adds the images
+/// for vision models.
+fn build_prompt(
+    settings: &Settings,
+    model_id: &ModelId,
+    conversation_parameters: &ConversationParameters,
+    messages: Vec<Message>,
+    start_response_with: Option<&str>,
+) -> (String, usize) {
+    let backend = settings.models.get(model_id).unwrap().backend;
+    build_prompt_on(
+        settings,
+        model_id,
+        backend,
+        conversation_parameters,
+        messages,
+        start_response_with,
+    )
+}
+
+/// Like [`build_prompt`], but for generating on `backend` instead of the
+/// model's own one.
+fn build_prompt_on(
+    settings: &Settings,
+    model_id: &ModelId,
+    backend: ModelBackend,
+    conversation_parameters: &ConversationParameters,
+    messages: Vec<Message>,
+    start_response_with: Option<&str>,
+) -> (String, usize) {
+    let model = settings.models.get(model_id).unwrap();
+    let messages = without_reasoning(messages, settings, model_id);
+
+    let mut system_prompt = parameters::system_prompt(conversation_parameters);
+    if model.tools {
+        if let Some(instructions) = tools::instructions(&settings.tools) {
+            system_prompt = Some(match system_prompt {
+                Some(system_prompt) => format!("{system_prompt}\n\n{instructions}"),
+                None => instructions,
+            });
+        }
+    }
+
+    if backend.is_chat() {
+        return chat_prompt(system_prompt, &messages, start_response_with, model.vision);
+    }
+
+    let prompt = model.chat_template.generate_prompt(
+        system_prompt.as_deref(),
+        &messages,
+        start_response_with,
+    );
+    let prompt_tokens = count_tokens(&prompt);
+
+    if model.vision {
+        if let Some(with_images) = prompt_with_images(
+            model.chat_template,
+            system_prompt.as_deref(),
+            &messages,
+            start_response_with,
+        ) {
+            return (with_images, prompt_tokens);
+        }
+    }
+
+    (prompt, prompt_tokens)
+}
+
+/// The prompt for chat APIs. The response so far is sent as a trailing
+/// assistant message.
+fn chat_prompt(
+    system: Option<String>,
+    messages: &[Message],
+    start_response_with: Option<&str>,
+    with_images: bool,
+) -> (String, usize) {
+    let mut messages = messages
+        .iter()
+        .map(|message| {
+            ChatMessage {
+                role: message.role,
+                text: message.text.clone(),
+                images: with_images
+                    .then(|| message.images.clone())
+                    .unwrap_or_default(),
+            }
+        })
+        .collect::<Vec<_>>();
+    if let Some(start_response_with) = non_empty(start_response_with.unwrap_or_default()) {
+        messages.push(ChatMessage {
+            role: Role::Assitant,
+            text: start_response_with.to_owned(),
+            images: vec![],
+        });
+    }
+
+    let prompt_tokens = system.as_deref().map_or(0, count_tokens)
+        + messages
+            .iter()
+            .map(|message| count_tokens(&message.text))
+            .sum::<usize>();
+    let prompt = ChatPrompt { system, messages }.to_prompt();
+
+    (prompt, prompt_tokens)
+}
+
+/// Removes the reasoning from responses, since the model doesn't need to see
+/// it again in later turns.
+fn without_reasoning(
+    mut messages: Vec<Message>,
+    settings: &Settings,
+    model_id: &ModelId,
+) -> Vec<Message> {
+    for message in &mut messages {
+        if let Some(delimiters) = settings.reasoning_delimiters(message, Some(model_id)) {
+            message.text = delimiters.split(&message.text).1.to_owned();
+        }
+    }
+    messages
+}
+
+/// The prompt for a vision model, with the images of the messages inline. The
+/// token estimate is done without them, since they're counted differently.
+/// Returns `None` if there are no images.
+fn prompt_with_images(
+    chat_template: ChatTemplate,
+    system_prompt: Option<&str>,
+    messages: &[Message],
+    start_response_with: Option<&str>,
+) -> Option<String> {
+    if messages.iter().all(|message| message.images.is_empty()) {
+        return None;
+    }
+    let messages = messages
+        .iter()
+        .map(Message::with_inline_images)
+        .collect::<Vec<_>>();
+    Some(chat_template.generate_prompt(system_prompt, &messages, start_response_with))
+}
+
+/// Stops the response that is being generated. What was generated so far is
+/// kept.
+pub fn stop_generation(conversation_id: ConversationId) {
+    let Context {
+        loading, stopping, ..
+    } = expect_context();
+
+    if loading.with_untracked(|loading| loading.contains(&conversation_id)) {
+        log::info!("stopping generation: {conversation_id}");
+        stopping.update(|stopping| {
+            stopping.insert(conversation_id);
+        });
+    }
+}
+
+fn is_stopping(conversation_id: ConversationId) -> bool {
+    let Context { stopping, .. } = expect_context();
+    stopping.with_untracked(|stopping| stopping.contains(&conversation_id))
+}
+
+/// Generates more text for a response that was cut off by the token limit. The
+/// text is appended to the same message, which has to be the last one in the
+/// conversation.
+pub fn continue_response(conversation_id: ConversationId, message_id: MessageId) {
+    generate_again(conversation_id, message_id, false);
+}
+
+/// Generates a response that failed again, with the same prompt. It replaces
+/// the failed message, which has to be the last one in the conversation.
+pub fn retry_response(conversation_id: ConversationId, message_id: MessageId) {
+    generate_again(conversation_id, message_id, true);
+}
+
+/// Generates into the last message of a conversation. If `retry` is set, the
+/// message starts over, otherwise the model continues it.
+fn generate_again(conversation_id: ConversationId, message_id: MessageId, retry: bool) {
+    let Context {
+        loading,
+        errors,
+        settings,
+        tabs,
+        ..
+    } = expect_context();
+
+    if !tabs.lock(conversation_id) {
+        errors.push(Error::GeneratingInOtherTab);
+        return;
+    }
+
+    let (retry_policy, record_audit_log) =
+        settings.with_untracked(|settings| (settings.retry, settings.audit_log));
+
+    let StorageSignals {
+        read: message,
+        write: set_message,
+        ..
+    } = use_message(message_id);
+    let StorageSignals {
+        read: conversation, ..
+    } = use_conversation(conversation_id);
+
+    let result = conversation.with_untracked(|conversation| {
+        let conversation = conversation
+            .as_ref()
+            .ok_or_else(|| Error::ConversationNotFound(conversation_id))?;
+        let message = message
+            .get_untracked()
+            .filter(|message| {
+                conversation.messages.last() == Some(&message_id)
+                    && matches!(message.role, Role::Assitant)
+            })
+            .ok_or_else(|| Error::CantContinue)?;
+
+        let model_id = message
+            .generation
+            .as_ref()
+            .map(|generation| generation.model_id.clone())
+            .or_else(|| conversation.model_id.clone())
+            .ok_or_else(|| Error::ModelIdNotSet)?;
+
+        let messages = conversation.messages[..conversation.messages.len() - 1]
+            .iter()
+            .filter_map(|message_id| use_message(*message_id).read.get_untracked())
+            .collect::<Vec<_>>();
+        let (stream, context_length) = settings.with_untracked(|settings| {
+            let model = settings.models.get(&model_id).unwrap();
+            (model.stream, model.context_length)
+        });
+
+        let turn = messages
+            .iter()
+            .filter(|message| matches!(message.role, Role::User))
+            .count();
+        let mut generation_parameters =
+            GenerationParameters::resolve(&conversation.conversation_parameters, turn, stream);
+
+        // the response so far is where the model continues from.
+        let prefix = if retry {
+            conversation
+                .conversation_parameters
+                .start_response_with
+                .clone()
+                .unwrap_or_default()
+        }
+        else {
+            message.text.clone()
+        };
+        let (prompt_parameters, messages) = with_summary(&conversation, messages);
+        let (prompt, prompt_tokens) = settings.with_untracked(|settings| {
+            build_prompt(
+                settings,
+                &model_id,
+                &prompt_parameters,
+                messages,
+                non_empty(prefix.as_str()),
+            )
+        });
+
+        if let Some(context_length) = context_length {
+            if prompt_tokens >= context_length {
+                return Err(Error::ContextLengthExceeded {
+                    prompt_tokens,
+                    context_length,
+                });
+            }
+            generation_parameters.max_new_tokens = generation_parameters
+                .max_new_tokens
+                .min(context_length - prompt_tokens);
+        }
+
+        Ok::<_, Error>((
+            model_id,
+            prompt,
+            generation_parameters,
+            stream,
+            message,
+            prefix,
+            conversation.conversation_parameters.clone(),
+        ))
+    });
+
+    let (model_id, prompt, generation_parameters, stream, message, prefix, conversation_parameters) =
+        match result {
+            Ok(x) => x,
+            Err(e) => {
                 errors.push(e);
+                tabs.unlock(conversation_id);
+                return;
             }
-            log::debug!("response stream finished");
-            is_loading.set(false);
-        }),
+        };
+
+    let model = settings.with_untracked(|settings| {
+        settings.text_generation(&model_id, generation_parameters.clone())
+    });
+    let max_new_tokens = generation_parameters.max_new_tokens;
+
+    loading.update(|loading| {
+        loading.insert(conversation_id);
+    });
+    if retry {
+        set_message.update(|message| {
+            if let Some(message) = message {
+                message.status = MessageStatus::Pending;
+                // the rating and candidates were for the old response.
+                message.rating = None;
+                message.candidates.clear();
+            }
+        });
+    }
+
+    spawn_local(
+        async move {
+            warm_up_conversation(conversation_id, &model).await?;
+
+            if record_audit_log {
+                let StorageSignals {
+                    write: update_audit_log,
+                    ..
+                } = use_audit_log(conversation_id);
+                update_audit_log.update(|audit_log| {
+                    audit_log.push(AuditLogEntry {
+                        message_id,
+                        timestamp: Utc::now(),
+                        model_id: model_id.clone(),
+                        prompt: prompt.clone(),
+                        parameters: generation_parameters,
+                    });
+                });
+            }
+
+            let timing = generate_into_message(
+                conversation_id,
+                &model,
+                &prompt,
+                stream,
+                set_message,
+                &prefix,
+                &retry_policy,
+            )
+            .await?;
+
+            if !tabs.holds_lock(conversation_id) {
+                return Err(Error::TakenOver);
+            }
+
+            // the metadata of a continued response covers the whole response,
+            // including the earlier parts.
+            let previous = message.generation.filter(|_| !retry);
+            let conversation_parameters = previous
+                .as_ref()
+                .map(|generation| generation.conversation_parameters.clone())
+                .unwrap_or(conversation_parameters);
+            let mut generation = timing.metadata(model_id, conversation_parameters, max_new_tokens);
+            if let Some(previous) = previous {
+                generation.tokens += previous.tokens;
+                generation.duration_ms += previous.duration_ms;
+                generation.time_to_first_token_ms = previous.time_to_first_token_ms;
+            }
+            set_message.update(move |message| {
+                if let Some(message) = message {
+                    message.generation = Some(generation);
+                    message.status = MessageStatus::Complete;
+                }
+            });
+
+            if let Some(message) = use_message(message_id).read.get_untracked() {
+                update_artifacts(conversation_id, &message);
+            }
+
+            Ok(())
+        }
+        .map(move |result| finish_generation(conversation_id, result)),
     );
 }
 
-fn request_conversation_title(conversation_id: ConversationId, user_message: &str) {
+/// Defers generating until a cold model is loaded, and makes sure we still hold
+/// the lock on the conversation afterwards.
+async fn warm_up_conversation(
+    conversation_id: ConversationId,
+    model: &TextGeneration,
+) -> Result<(), Error> {
+    let Context {
+        tabs, warming_up, ..
+    } = expect_context();
+
+    warming_up.update(|warming_up| {
+        warming_up.insert(conversation_id);
+    });
+    let warm_up_result = warm_up(model).await;
+    warming_up.update(|warming_up| {
+        warming_up.remove(&conversation_id);
+    });
+    warm_up_result?;
+
+    if !tabs.holds_lock(conversation_id) {
+        return Err(Error::TakenOver);
+    }
+
+    Ok(())
+}
+
+/// Timing and token count of a generated response.
+struct GenerationTiming {
+    started: DateTime<Utc>,
+    first_token: Option<DateTime<Utc>>,
+    tokens: usize,
+    backend: ModelBackend,
+}
+
+impl GenerationTiming {
+    fn metadata(
+        &self,
+        model_id: ModelId,
+        conversation_parameters: ConversationParameters,
+        max_new_tokens: usize,
+    ) -> GenerationMetadata {
+        let milliseconds_since_start =
+            |time: DateTime<Utc>| (time - self.started).num_milliseconds().max(0) as u64;
+        GenerationMetadata {
+            model_id,
+            conversation_parameters,
+            tokens: self.tokens,
+            duration_ms: milliseconds_since_start(Utc::now()),
+            time_to_first_token_ms: self.first_token.map(milliseconds_since_start),
+            truncated: self.tokens >= max_new_tokens,
+            backend: Some(self.backend),
+        }
+    }
+}
+
+/// Generates the response with the model's backends in turn. If one is
+/// unavailable, e.g. rate-limited, the next one is tried right away. Only the
+/// last one is retried according to `retry_policy`.
+async fn generate_with_failover(
+    conversation_id: ConversationId,
+    message_id: MessageId,
+    model_id: &ModelId,
+    chain: &[TextGeneration],
+    prompt: &str,
+    stream: bool,
+    set_message: WriteSignal<Option<Message>>,
+    prefix: &str,
+    retry_policy: &RetryPolicy,
+) -> Result<GenerationTiming, Error> {
+    let Context { errors, .. } = expect_context();
+
+    let is_chat = chain[0].backend().is_chat();
+    let no_retries = RetryPolicy {
+        max_attempts: 1,
+        ..*retry_policy
+    };
+
+    let mut index = 0;
+    loop {
+        let model = &chain[index];
+        let next = chain.get(index + 1);
+
+        let result = async {
+            let mut rebuilt_prompt = None;
+            // the model's own backend was warmed up before the message was added.
+            if index > 0 {
+                warm_up_conversation(conversation_id, model).await?;
+                // the prompt only has to be rebuilt for a different kind of API.
+                if model.backend().is_chat() != is_chat {
+                    rebuilt_prompt = Some(fallback_prompt(
+                        conversation_id,
+                        message_id,
+                        model_id,
+                        model.backend(),
+                    )?);
+                }
+            }
+            let retry_policy = if next.is_some() {
+                &no_retries
+            }
+            else {
+                retry_policy
+            };
+            generate_into_message(
+                conversation_id,
+                model,
+                rebuilt_prompt.as_deref().unwrap_or(prompt),
+                stream,
+                set_message,
+                prefix,
+                retry_policy,
+            )
+            .await
+        }
+        .await;
+
+        let Some(next) = next
+        else {
+            return result;
+        };
+        let reason = match result {
+            Err(Error::Backend(error)) if error.is_unavailable() => error.to_string(),
+            Err(Error::WarmUpTimeout) => Error::WarmUpTimeout.to_string(),
+            result => return result,
+        };
+
+        let name =
+            |model: &TextGeneration| model.backend().get_message().unwrap_or_default().to_owned();
+        errors.push(Error::FailingOver {
+            backend: name(model),
+            next: name(next),
+            reason: RetryReason(reason),
+        });
+        index += 1;
+    }
+}
+
+/// Generates the response to `prompt` into the message, after `prefix`. Failed
+/// attempts are retried according to `retry_policy`.
+async fn generate_into_message(
+    conversation_id: ConversationId,
+    model: &TextGeneration,
+    prompt: &str,
+    stream: bool,
+    set_message: WriteSignal<Option<Message>>,
+    prefix: &str,
+    retry_policy: &RetryPolicy,
+) -> Result<GenerationTiming, Error> {
+    let Context {
+        errors,
+        settings,
+        scroll_trigger,
+        tabs,
+        stream_status,
+        ..
+    } = expect_context();
+
+    let timeouts = settings.with_untracked(|settings| settings.timeouts(model.backend()));
+    let start_response_with = prefix;
+    let on_retry = |error: &BackendError, attempt, backoff: Duration| {
+        errors.push(Error::Retrying {
+            seconds: backoff.as_secs_f32(),
+            attempt,
+            max_attempts: retry_policy.max_attempts,
+            reason: RetryReason(error.to_string()),
+        });
+    };
+
+    // timing and token count of the current attempt.
+    let started = Cell::new(Utc::now());
+    let first_token = Cell::new(None);
+    let tokens = Cell::new(0);
+
+    with_retry(retry_policy, on_retry, || {
+        // the futures only capture references, so every attempt can be
+        // created from the same closure.
+        let started = &started;
+        let first_token = &first_token;
+        let tokens = &tokens;
+        async move {
+            // start over with a clean response on every attempt.
+            set_message.update(|message| {
+                if let Some(message) = message {
+                    message.text = start_response_with.to_owned();
+                }
+            });
+            started.set(Utc::now());
+            first_token.set(None);
+            tokens.set(0);
+            stream_status.update(|stream_status| {
+                stream_status.insert(
+                    conversation_id,
+                    StreamStatus {
+                        started: started.get(),
+                        first_token: None,
+                        tokens: 0,
+                    },
+                );
+            });
+
+            if stream {
+                let mut stream = timeouts.request(model.generate_stream(&prompt)).await?;
+
+                while let Some(token) = timeouts.next_token(stream.try_next()).await? {
+                    if !tabs.holds_lock(conversation_id) || is_stopping(conversation_id) {
+                        break;
+                    }
+
+                    if first_token.get().is_none() {
+                        first_token.set(Some(Utc::now()));
+                    }
+                    tokens.set(tokens.get() + 1);
+                    stream_status.update(|stream_status| {
+                        if let Some(status) = stream_status.get_mut(&conversation_id) {
+                            status.first_token = first_token.get();
+                            status.tokens = tokens.get();
+                        }
+                    });
+
+                    if token.special {
+                        continue;
+                    }
+
+                    set_message.update(move |message| {
+                        let message = message.as_mut().unwrap();
+                        message.text.push_str(&token.text);
+                        if message.status == MessageStatus::Pending {
+                            message.status = MessageStatus::Streaming;
+                        }
+                        scroll_trigger.notify();
+                    });
+                }
+            }
+            else {
+                let response = timeouts.request(model.generate(&prompt)).await?;
+
+                // a stopped response can't be cut short, so it's dropped.
+                if !tabs.holds_lock(conversation_id) || is_stopping(conversation_id) {
+                    return Ok(());
+                }
+                tokens.set(count_tokens(&response));
+
+                set_message.update(move |message| {
+                    let message = message.as_mut().unwrap();
+                    message.text = format!("{start_response_with}{response}");
+                    scroll_trigger.notify();
+                });
+            }
+
+            Ok(())
+        }
+        .map(|result| {
+            let tokens = result.as_ref().map(|_| tokens.get());
+            record_debug_log(model, prompt, stream, started.get(), tokens);
+            result
+        })
+    })
+    .await?;
+
+    Ok(GenerationTiming {
+        started: started.get(),
+        first_token: first_token.get(),
+        tokens: tokens.get(),
+        backend: model.backend(),
+    })
+}
+
+/// Records a generation request in the debug log, if debug mode is on.
+/// `result` has the number of generated tokens.
+fn record_debug_log(
+    model: &TextGeneration,
+    prompt: &str,
+    stream: bool,
+    started: DateTime<Utc>,
+    result: Result<usize, &BackendError>,
+) {
+    let Context { settings, .. } = expect_context();
+    if !settings.with_untracked(|settings| settings.debug_mode) {
+        return;
+    }
+
+    let outcome = match result {
+        Ok(tokens) => RequestOutcome::Success { tokens },
+        Err(error) => {
+            RequestOutcome::Failed {
+                status: error.status(),
+                error: error.to_string(),
+            }
+        }
+    };
+    let entry = DebugLogEntry {
+        timestamp: started,
+        model_id: model.model_id().clone(),
+        parameters: model.parameters().clone(),
+        stream,
+        prompt_length: prompt.chars().count(),
+        duration_ms: (Utc::now() - started).num_milliseconds(),
+        outcome,
+    };
+
+    let StorageSignals {
+        write: update_debug_log,
+        ..
+    } = use_debug_log();
+    update_debug_log.update(move |debug_log| {
+        debug_log.push(entry);
+        let excess = debug_log.len().saturating_sub(DEBUG_LOG_SIZE);
+        debug_log.drain(..excess);
+    });
+}
+
+/// Cleans up after a response was generated, or failed.
+fn finish_generation(conversation_id: ConversationId, result: Result<(), Error>) {
+    let Context {
+        errors,
+        tabs,
+        stream_status,
+        loading,
+        stopping,
+        ..
+    } = expect_context();
+
+    notify_finished(conversation_id, &result);
+    if let Err(e) = result {
+        log::error!("response stream failed: {e}");
+        match failed_response(conversation_id) {
+            Some(message_id) if !matches!(e, Error::TakenOver) => {
+                use_message(message_id).write.update(|message| {
+                    if let Some(message) = message {
+                        message.status = MessageStatus::Failed {
+                            reason: e.to_string(),
+                        };
+                    }
+                });
+                errors.push_with_retry(e, move |_| retry_response(conversation_id, message_id));
+            }
+            _ => errors.push(e),
+        }
+    }
+    log::debug!("response stream finished");
+    stream_status.update(|stream_status| {
+        stream_status.remove(&conversation_id);
+    });
+    loading.update(|loading| {
+        loading.remove(&conversation_id);
+    });
+    stopping.update(|stopping| {
+        stopping.remove(&conversation_id);
+    });
+    tabs.unlock(conversation_id);
+}
+
+/// The response that was left behind by a failed generation: the last message,
+/// if it's from the assistant and isn't done.
+fn failed_response(conversation_id: ConversationId) -> Option<MessageId> {
+    let message_id = use_conversation(conversation_id)
+        .read
+        .with_untracked(|conversation| conversation.as_ref()?.messages.last().copied())?;
+    use_message(message_id)
+        .read
+        .with_untracked(|message| {
+            message.as_ref().map_or(false, |message| {
+                matches!(message.role, Role::Assitant) && !message.status.is_done()
+            })
+        })
+        .then_some(message_id)
+}
+
+/// Lets the user know that a response finished, if they're not looking at the
+/// app.
+fn notify_finished(conversation_id: ConversationId, result: &Result<(), Error>) {
+    let Context { settings, .. } = expect_context();
+
+    if !settings.with_untracked(|settings| settings.notifications) || !is_page_hidden() {
+        return;
+    }
+
+    let title = use_conversation(conversation_id)
+        .read
+        .with_untracked(|conversation| {
+            conversation
+                .as_ref()
+                .and_then(|conversation| conversation.title.clone())
+        })
+        .unwrap_or_else(|| "RustyChat".to_owned());
+    let body = match result {
+        Ok(()) => "The response is ready.".to_owned(),
+        Err(error) => format!("The response failed: {error}"),
+    };
+    notify(&title, &body);
+}
+
+const WARM_UP_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const WARM_UP_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Waits until the model is loaded, if the API reports that it's cold. Models
+/// that run in the browser are downloaded and loaded here. The chat APIs and
+/// self-hosted servers don't have cold models.
+async fn warm_up(model: &TextGeneration) -> Result<(), Error> {
+    let model = match model {
+        TextGeneration::HuggingFace(model) => model,
+        TextGeneration::Local(model) => {
+            let Context {
+                local_load_progress,
+                ..
+            } = expect_context();
+            let result = model
+                .load(move |progress| local_load_progress.set(Some(progress)))
+                .await;
+            local_load_progress.set(None);
+            return result.map_err(Into::into);
+        }
+        TextGeneration::Anthropic(_) | TextGeneration::OpenRouter(_) | TextGeneration::Tgi(_) => {
+            return Ok(())
+        }
+    };
+
+    let is_cold = |status: Result<ModelStatus, BackendError>| {
+        status
+            .map_err(|error| log::warn!("model status failed: {error}"))
+            .map_or(false, |status| status.is_cold())
+    };
+
+    if !is_cold(model.status().await) {
+        return Ok(());
+    }
+
+    log::info!("model is cold, warming up");
+    model.request_load().await?;
+
+    let mut waited = Duration::ZERO;
+    while waited < WARM_UP_TIMEOUT {
+        sleep(WARM_UP_POLL_INTERVAL).await;
+        waited += WARM_UP_POLL_INTERVAL;
+
+        if !is_cold(model.status().await) {
+            log::info!("model is warm after {waited:?}");
+            return Ok(());
+        }
+    }
+
+    Err(Error::WarmUpTimeout)
+}
+
+const TITLE_INSTRUCTIONS: &'static str = "Your job is to generate a short descriptive title of a chat conversation between an user and an AI assistant, given the first message from the user.
+Start the title with a fitting emoji. Please respond only with the title and nothing else.";
+
+fn title_prompt(chat_template: ChatTemplate, user_message: &str) -> String {
+    let example = "Message: Write a short poem about AI.".to_owned();
+
+    // if the template has no system prompt, we put the instructions into the first
+    // message.
+    let (system_prompt, example) = if chat_template.supports_system_prompt() {
+        (Some(TITLE_INSTRUCTIONS), example)
+    }
+    else {
+        (None, format!("{TITLE_INSTRUCTIONS}\n\n{example}"))
+    };
+
+    let now = Utc::now();
+    let message = |role, text| {
+        Message {
+            id: MessageId::new(),
+            role,
+            text,
+            timestamp: now,
+            generation: None,
+            images: vec![],
+            tool: None,
+            status: MessageStatus::Complete,
+            note: None,
+            rating: None,
+            candidates: vec![],
+        }
+    };
+    let messages = [
+        message(Role::User, example),
+        message(Role::Assitant, "✨ A Modern Muse".to_owned()),
+        message(Role::User, format!("Message: {user_message}")),
+    ];
+
+    chat_template.generate_prompt(system_prompt, &messages, None)
+}
+
+/// Generates a title for a conversation. Unless `regenerate` is set, this
+/// respects the global and per-conversation settings for automatic titles.
+fn request_conversation_title(
+    conversation_id: ConversationId,
+    user_message: &str,
+    regenerate: bool,
+) {
     let Context {
         errors, settings, ..
     } = expect_context();
 
-    let mut model = settings
-        .with_untracked(|settings| settings.api())
-        .text_generation("NousResearch/Nous-Hermes-2-Mixtral-8x7B-DPO");
-    model.max_new_tokens = Some(20);
-
-    let prompt = format!(
-        r#"<|im_start|>system
-Your job is to generate a short descriptive title of a chat conversation between an user and an AI assistant, given the first message from the user.
-Start the title with a fitting emoji. Please respond only with the title and nothing else.
-<|im_end|>
-<|im_start|>user
-Message: Write a short poem about AI.
-<|im_end|>
-<|im_start|>assistant
-✨ A Modern Muse
-<|im_end|>
-<|im_start|>user
-Message: {user_message}
-<|im_end|>
-<|im_start|>assistant
-"#
-    );
+    let Some((model, chat_template, retry_policy)) = settings.with_untracked(|settings| {
+        if !regenerate && !settings.generate_titles {
+            return None;
+        }
+
+        let chat_template = settings
+            .models
+            .get(&settings.title_model)
+            .map(|model| model.chat_template)
+            .unwrap_or_else(|| {
+                log::warn!("title model not found: {}", settings.title_model);
+                ChatTemplate::default()
+            });
+
+        let parameters = GenerationParameters {
+            max_new_tokens: 20,
+            ..GenerationParameters::resolve(&Default::default(), 0, false)
+        };
+
+        Some((
+            settings.text_generation(&settings.title_model, parameters),
+            chat_template,
+            settings.retry,
+        ))
+    })
+    else {
+        return;
+    };
+
+    let prompt = title_prompt(chat_template, user_message);
 
     let StorageSignals {
         write: update_conversation,
@@ -425,22 +2377,42 @@ Message: {user_message}
 
     spawn_local(
         async move {
-            let response = model.generate(&prompt).await?;
+            let response = with_retry(
+                &retry_policy,
+                |_, _, _| {},
+                || {
+                    let (model, prompt) = (&model, &prompt);
+                    let started = Utc::now();
+                    model.generate(prompt).map(move |result| {
+                        let tokens = result.as_ref().map(|response| count_tokens(response));
+                        record_debug_log(model, prompt, false, started, tokens);
+                        result
+                    })
+                },
+            )
+            .await?;
 
             // only use the first line.
-            let mut lines = response.lines();
-            let title = lines.next().unwrap().to_owned();
+            let Some(title) = non_empty(response.lines().next().unwrap_or_default().trim())
+            else {
+                log::warn!("generated title is empty");
+                return Ok(());
+            };
+            let title = title.to_owned();
 
             log::debug!("generated title: '{title}'");
 
             update_conversation.update(move |conversation| {
                 if let Some(conversation) = conversation {
-                    conversation.title = Some(title);
+                    if regenerate || conversation.auto_title {
+                        conversation.title = Some(title);
+                    }
                 }
                 else {
                     log::warn!("conversation does not exist: {conversation_id}");
                 }
             });
+            refresh_summary(conversation_id);
 
             Ok(())
         }
@@ -453,9 +2425,180 @@ Message: {user_message}
     )
 }
 
+const SUMMARY_INSTRUCTIONS: &'static str = "Summarize the following conversation between a user and an AI assistant. Keep the facts, decisions and open questions, so that the conversation can be continued from the summary. Respond only with the summary.";
+
+/// Summarizes a conversation with the summary model, or the conversation's
+/// own model, and stores the summary on the conversation.
+pub fn summarize_conversation(conversation_id: ConversationId) {
+    let Context {
+        errors,
+        settings,
+        summarizing,
+        ..
+    } = expect_context();
+
+    let StorageSignals {
+        read: conversation,
+        write: update_conversation,
+        ..
+    } = use_conversation(conversation_id);
+
+    let result = conversation.with_untracked(|conversation| {
+        let conversation = conversation
+            .as_ref()
+            .ok_or_else(|| Error::ConversationNotFound(conversation_id))?;
+        let up_to = *conversation
+            .messages
+            .last()
+            .ok_or_else(|| Error::NothingToSummarize)?;
+
+        settings.with_untracked(|settings| {
+            let model_id = settings
+                .summary_model
+                .clone()
+                .filter(|model_id| settings.models.contains_key(model_id))
+                .or_else(|| conversation.model_id.clone())
+                .ok_or_else(|| Error::ModelIdNotSet)?;
+            let model = settings
+                .models
+                .get(&model_id)
+                .ok_or_else(|| Error::ModelIdNotSet)?;
+
+            let messages = conversation
+                .messages
+                .iter()
+                .filter_map(|message_id| use_message(*message_id).read.get_untracked())
+                .filter(|message| message.tool.is_none())
+                .collect::<Vec<_>>();
+            let mut transcript = String::new();
+            for message in without_reasoning(messages, settings, &model_id) {
+                let role = match message.role {
+                    Role::User => "User",
+                    Role::Assitant => "Assistant",
+                };
+                transcript.push_str(&format!("{role}: {}\n\n", message.text.trim()));
+            }
+
+            let request = Message {
+                id: MessageId::new(),
+                role: Role::User,
+                text: transcript,
+                timestamp: Utc::now(),
+                generation: None,
+                images: vec![],
+                tool: None,
+                status: MessageStatus::Complete,
+                note: None,
+                rating: None,
+                candidates: vec![],
+            };
+            let prompt = if model.backend.is_chat() {
+                chat_prompt(
+                    Some(SUMMARY_INSTRUCTIONS.to_owned()),
+                    &[request],
+                    None,
+                    false,
+                )
+                .0
+            }
+            else if model.chat_template.supports_system_prompt() {
+                model
+                    .chat_template
+                    .generate_prompt(Some(SUMMARY_INSTRUCTIONS), &[request], None)
+            }
+            else {
+                let request = Message {
+                    text: format!("{SUMMARY_INSTRUCTIONS}\n\n{}", request.text),
+                    ..request
+                };
+                model.chat_template.generate_prompt(None, &[request], None)
+            };
+
+            let parameters = GenerationParameters {
+                max_new_tokens: 500,
+                ..GenerationParameters::resolve(&Default::default(), 0, false)
+            };
+            Ok::<_, Error>((
+                settings.text_generation(&model_id, parameters),
+                prompt,
+                settings.retry,
+                up_to,
+            ))
+        })
+    });
+    let (model, prompt, retry_policy, up_to) = match result {
+        Ok(x) => x,
+        Err(error) => {
+            errors.push(error);
+            return;
+        }
+    };
+
+    summarizing.update(|summarizing| {
+        summarizing.insert(conversation_id);
+    });
+
+    spawn_local(
+        async move {
+            let response = with_retry(
+                &retry_policy,
+                |_, _, _| {},
+                || {
+                    let (model, prompt) = (&model, &prompt);
+                    let started = Utc::now();
+                    model.generate(prompt).map(move |result| {
+                        let tokens = result.as_ref().map(|response| count_tokens(response));
+                        record_debug_log(model, prompt, false, started, tokens);
+                        result
+                    })
+                },
+            )
+            .await?;
+
+            let Some(text) = non_empty(response.trim())
+            else {
+                log::warn!("generated summary is empty");
+                return Ok(());
+            };
+            let text = text.to_owned();
+
+            update_conversation.update(move |conversation| {
+                if let Some(conversation) = conversation {
+                    // keep using it as context, if the previous summary was.
+                    let use_as_context = conversation
+                        .summary
+                        .as_ref()
+                        .map_or(false, |summary| summary.use_as_context);
+                    conversation.summary = Some(TranscriptSummary {
+                        text,
+                        up_to,
+                        timestamp: Utc::now(),
+                        use_as_context,
+                    });
+                }
+                else {
+                    log::warn!("conversation does not exist: {conversation_id}");
+                }
+            });
+
+            Ok(())
+        }
+        .map(move |result: Result<(), Error>| {
+            summarizing.update(|summarizing| {
+                summarizing.remove(&conversation_id);
+            });
+            if let Err(e) = result {
+                log::error!("summarizing failed: {e}");
+                errors.push(e);
+            }
+        }),
+    )
+}
+
 #[component]
 pub fn BootstrapIcon(#[prop(into)] icon: Oco<'static, str>) -> impl IntoView {
-    view! { <i class={format!("bi bi-{icon}")}></i> }
+    // icons are decoration, buttons with only an icon need a label.
+    view! { <i class={format!("bi bi-{icon}")} aria-hidden="true"></i> }
 }
 
 #[component]
@@ -474,125 +2617,345 @@ pub fn App() -> impl IntoView {
     provide_meta_context();
 
     provide_context();
+    send_queued_messages();
+    start_sync();
+
+    let Context {
+        conversations,
+        summaries,
+        tags,
+        settings,
+        update_settings,
+        private_conversations,
+        online,
+        errors,
+        locked,
+        ..
+    } = expect_context();
 
-    let Context { conversations, .. } = expect_context();
+    // purge conversations that have been in the trash for too long.
+    if let Some(retention_days) = settings.with_untracked(|settings| settings.trash_retention_days)
+    {
+        purge_trash(Some(retention_days));
+    }
+    if let Some(policy) = settings.with_untracked(|settings| settings.cleanup) {
+        apply_cleanup(&policy);
+    }
+
+    // only show conversations with this tag in the sidebar
+    let tag_filter = create_rw_signal(None::<String>);
+
+    // typing filters by title. with an embedding model, Enter searches the
+    // messages by meaning, and the results replace the list.
+    let search_query = create_rw_signal(String::new());
+    let search_results = create_rw_signal(None::<Vec<SearchResult>>);
+    let sidebar_delete_targets = create_rw_signal(Vec::<ConversationId>::new());
+    let selection = Selection::default();
+    let searching = create_rw_signal(false);
+    let semantic_search_enabled =
+        Signal::derive(move || with!(|settings| settings.embedding_model.is_some()));
+
+    let run_semantic_search = move || {
+        let query = search_query.get_untracked().trim().to_owned();
+        if query.is_empty() || searching.get_untracked() {
+            return;
+        }
+        let (backend, model_id) = settings
+            .with_untracked(|settings| (settings.backend(), settings.embedding_model.clone()));
+        let conversation_ids = conversations
+            .with_untracked(|conversations| conversations.iter().copied().collect::<Vec<_>>());
+
+        searching.set(true);
+        spawn_local(async move {
+            match semantic_search(&backend, model_id.as_ref(), &conversation_ids, &query).await {
+                Ok(results) => search_results.set(Some(results)),
+                Err(error) => errors.push(Error::from(error)),
+            }
+            searching.set(false);
+        });
+    };
 
     #[derive(Copy, Clone, Debug, PartialEq)]
     struct Item {
-        timestamp: DateTime<Local>,
+        timestamp: DateTime<Utc>,
         id: ConversationId,
+        group: Option<Uuid>,
+        pinned: bool,
     }
 
     let sorted_items = create_memo(move |_| {
-        with!(|conversations| {
+        with!(|conversations, summaries, tag_filter, search_query| {
             let mut sorted_items = vec![];
+            let search_query = search_query.trim().to_lowercase();
 
             for id in conversations {
-                let StorageSignals {
-                    read: conversation, ..
-                } = use_conversation(*id);
-                let Some(timestamp) = with!(|conversation| {
-                    conversation
-                        .as_ref()
-                        .map(|conversation| conversation.timestamp_last_interaction)
-                })
+                let Some(summary) = summaries.get(id)
                 else {
                     log::warn!("dangling conversation entry: {id}");
                     continue;
                 };
-                sorted_items.push(Item { id: *id, timestamp });
+                let has_tag = tag_filter
+                    .as_ref()
+                    .map_or(true, |tag| summary.tags.contains(tag));
+                let matches_query = search_query.is_empty()
+                    || summary
+                        .title
+                        .as_ref()
+                        .map_or(false, |title| title.to_lowercase().contains(&search_query));
+                if summary.archived || !has_tag || !matches_query {
+                    continue;
+                }
+                sorted_items.push(Item {
+                    id: *id,
+                    timestamp: summary.timestamp_last_interaction,
+                    group: summary.group,
+                    pinned: summary.pinned,
+                });
             }
 
-            sorted_items.sort_by_cached_key(|item| item.timestamp);
+            // pinned conversations come first. conversations of a group are listed
+            // together, where the most recent one of them would be.
+            let mut group_timestamps = HashMap::new();
+            for item in &sorted_items {
+                if let Some(group) = item.group {
+                    let timestamp = group_timestamps.entry(group).or_insert(item.timestamp);
+                    *timestamp = item.timestamp.max(*timestamp);
+                }
+            }
+            sorted_items.sort_by_cached_key(|item| {
+                let group_timestamp = item
+                    .group
+                    .and_then(|group| group_timestamps.get(&group).copied())
+                    .unwrap_or(item.timestamp);
+                (item.pinned, group_timestamp, item.group, item.timestamp)
+            });
             sorted_items.reverse();
 
             sorted_items
         })
     });
 
-    let (bs_theme, toggle_theme, theme_icon) = {
-        let UseColorModeReturn { mode, set_mode, .. } = use_color_mode();
+    let (bs_theme, toggle_theme, theme_icon, theme_title) = {
+        // we only use this to detect the system's preference. the chosen theme
+        // is kept in the settings.
+        let UseColorModeReturn { system, .. } = use_color_mode();
+        let theme = Signal::derive(move || with!(|settings| settings.theme));
         let bs_theme = Signal::derive(move || {
-            match mode.get() {
-                ColorMode::Dark => "dark",
+            match (theme.get(), system.get()) {
+                (Theme::Dark, _) | (Theme::Auto, ColorMode::Dark) => "dark",
                 _ => "light",
             }
         });
         let toggle_theme = move || {
-            let current = mode.get();
-            let new = match current {
-                ColorMode::Dark => ColorMode::Light,
-                _ => ColorMode::Dark,
-            };
-            set_mode.set(new);
+            update_settings.update(|settings| settings.theme = settings.theme.next());
         };
         let theme_icon = Signal::derive(move || {
-            match mode.get() {
-                ColorMode::Dark => "moon-stars-fill",
-                _ => "sun-fill",
+            match theme.get() {
+                Theme::Auto => "circle-half",
+                Theme::Light => "sun-fill",
+                Theme::Dark => "moon-stars-fill",
             }
         });
-        (bs_theme, toggle_theme, theme_icon)
+        let theme_title = Signal::derive(move || {
+            match theme.get() {
+                Theme::Auto => "Theme: same as the system",
+                Theme::Light => "Theme: light",
+                Theme::Dark => "Theme: dark",
+            }
+        });
+        (bs_theme, toggle_theme, theme_icon, theme_title)
     };
 
-    let Context { errors, .. } = expect_context();
+    let appearance_style = Signal::derive(move || with!(|settings| settings.appearance.style()));
+    let density = Signal::derive(move || {
+        with!(|settings| settings.appearance.density.as_ref().to_lowercase())
+    });
 
     view! {
         <Html
             attr:data-bs-theme=bs_theme
+            attr:data-density=density
+            attr:style=appearance_style
         />
         <Router>
-            <div class="d-flex flex-row" style="height: 100vh; width: 100%">
+            <LockScreen />
+            <div class="d-flex flex-row app-layout" style="height: 100vh; width: 100%">
                 <nav class="d-flex flex-column flex-shrink-0 p-3 text-white shadow-lg sidebar">
                     <div class="d-flex flex-row">
                         <A class="d-flex mb-3 mb-md-0 me-md-auto text-white text-decoration-none" href="/">
                             <span class="fs-4">"🦀 RustyChat"</span>
                         </A>
+                        {move || (!online.get()).then(|| view!{
+                            <span class="badge text-bg-warning m-auto me-1" title="You're offline. Messages are queued until you're back online.">
+                                <BootstrapIcon icon="wifi-off" />
+                            </span>
+                        })}
                         <small class="d-flex flex-row">
-                            <button type="button" class="btn py-0 px-1 m-auto" style="color: white;" on:click=move |_| toggle_theme()>
+                            <button type="button" class="btn py-0 px-1 m-auto" style="color: white;" title=theme_title aria-label=theme_title on:click=move |_| toggle_theme()>
                                 {move || {
                                     view!{<BootstrapIcon icon=theme_icon.get() />}
                                 }}
                             </button>
-                            <a href=GITHUB_PAGE target="_blank" class="py-0 px-1 m-auto" style="color: white;">
+                            {move || with!(|settings| settings.lock.is_some()).then(|| view!{
+                                <button type="button" class="btn py-0 px-1 m-auto" style="color: white;" title="Lock" aria-label="Lock" on:click=move |_| locked.set(true)>
+                                    <BootstrapIcon icon="lock" />
+                                </button>
+                            })}
+                            <a href=GITHUB_PAGE target="_blank" class="py-0 px-1 m-auto" style="color: white;" title="Source code on GitHub" aria-label="Source code on GitHub">
                                 <BootstrapIcon icon="github" />
                             </a>
                         </small>
                     </div>
                     <hr />
-                    <div class="d-flex flex-column flex-grow-1 overflow-y-scroll">
+                    {move || with!(|tags| (!tags.is_empty()).then(|| view!{
+                        <div class="d-flex flex-row flex-wrap mb-2">
+                            {tags.iter().cloned().map(|tag| {
+                                let is_selected = {
+                                    let tag = tag.clone();
+                                    move || with!(|tag_filter| tag_filter.as_ref() == Some(&tag))
+                                };
+                                let toggle = {
+                                    let tag = tag.clone();
+                                    move |_| tag_filter.update(|tag_filter| {
+                                        *tag_filter = (tag_filter.as_ref() != Some(&tag)).then(|| tag.clone());
+                                    })
+                                };
+                                view!{
+                                    <button
+                                        type="button"
+                                        class="btn btn-sm rounded-pill py-0 me-1 mb-1"
+                                        class:btn-light=is_selected.clone()
+                                        class:btn-outline-light=move || !is_selected()
+                                        on:click=toggle
+                                    >
+                                        {tag}
+                                    </button>
+                                }
+                            }).collect_view()}
+                        </div>
+                    }))}
+                    <form
+                        class="input-group input-group-sm mb-2"
+                        on:submit=move |event| {
+                            event.prevent_default();
+                            if semantic_search_enabled.get_untracked() {
+                                run_semantic_search();
+                            }
+                        }
+                    >
+                        <input
+                            type="search"
+                            class="form-control"
+                            placeholder=move || if semantic_search_enabled.get() { "Search (Enter to search messages)" } else { "Search" }
+                            prop:value=search_query
+                            on:input=move |event| {
+                                search_query.set(event_target_value(&event));
+                                search_results.set(None);
+                            }
+                        />
+                        {move || searching.get().then(|| view!{
+                            <span class="input-group-text">
+                                <div class="spinner-border spinner-border-sm" role="status">
+                                    <span class="visually-hidden">"Searching..."</span>
+                                </div>
+                            </span>
+                        })}
+                        <button
+                            type="button"
+                            class="btn btn-outline-light"
+                            class:active=move || selection.active.get()
+                            title="Select conversations"
+                            aria-label="Select conversations"
+                            aria-pressed=move || selection.active.get().to_string()
+                            on:click=move |_| {
+                                if selection.active.get_untracked() {
+                                    selection.finish();
+                                }
+                                else {
+                                    selection.active.set(true);
+                                }
+                            }
+                        >
+                            <BootstrapIcon icon="check2-square" />
+                        </button>
+                    </form>
+                    {move || selection.active.get().then(|| view!{
+                        <SelectionToolbar
+                            selection
+                            listed=Signal::derive(move || with!(|sorted_items| sorted_items.iter().map(|item| item.id).collect::<Vec<_>>()))
+                            delete_targets=sidebar_delete_targets
+                        />
+                    })}
+                    {move || search_results.get().map(|results| view!{
+                        <div class="d-flex flex-column flex-grow-1 overflow-y-scroll">
+                            <ul class="d-flex flex-column nav nav-pills mb-auto">
+                                {results.is_empty().then(|| view!{
+                                    <li class="nav-item small opacity-75 px-3">"No matching conversations."</li>
+                                })}
+                                {results.into_iter().map(|result| {
+                                    let title = with!(|summaries| summaries.get(&result.conversation_id).and_then(|summary| summary.title.clone()))
+                                        .unwrap_or_else(|| "Untitled".to_owned());
+                                    view!{
+                                        <NavLink href=format!("/conversation/{}", result.conversation_id)>
+                                            <div class="text-truncate" style="width: 200px" title=format!("{:.0}% match", result.score * 100.0)>
+                                                {title}
+                                            </div>
+                                            <small class="d-block opacity-75 search-snippet" style="width: 200px">{result.snippet}</small>
+                                        </NavLink>
+                                    }
+                                }).collect_view()}
+                            </ul>
+                        </div>
+                    })}
+                    <div class="d-flex flex-column flex-grow-1 overflow-y-scroll" class:d-none=move || with!(|search_results| search_results.is_some())>
                         <ul class="d-flex flex-column nav nav-pills mb-auto">
+                            <For
+                                each=move || private_conversations.get()
+                                key=|id| *id
+                                children=move |id| {
+                                    let StorageSignals { read: conversation, .. } = use_conversation(id);
+                                    let title = move || with!(|conversation| {
+                                        conversation.as_ref()
+                                            .and_then(|conversation| conversation.title.clone())
+                                            .unwrap_or_else(|| "Private conversation".to_owned())
+                                    });
+                                    view! {
+                                        <NavLink href=format!("/conversation/{id}")>
+                                            <div class="text-nowrap text-truncate" style="width: 200px" title="Private conversation">
+                                                <span class="me-2"><BootstrapIcon icon="incognito" /></span>
+                                                {title}
+                                            </div>
+                                        </NavLink>
+                                    }
+                                }
+                            />
                             <For
                                 each=sorted_items
                                 key=|item| item.id
                                 children=move |item| {
-                                    // note: i can't make this work, if we put the title signal into the memo.
-                                    let StorageSignals { read: conversation, .. } = use_conversation(item.id);
-                                    let title = Signal::derive(move || with!(|conversation| conversation.as_ref().and_then(|conversation| conversation.title.clone())));
-
                                     view! {
-                                        <NavLink href=format!("/conversation/{}", item.id)>
-                                            <div class="text-nowrap text-truncate" style="width: 200px">
-                                                {move || {
-                                                    if let Some(title) = title.get() {
-                                                        view!{{title}}.into_view()
-                                                    }
-                                                    else {
-                                                        view!{
-                                                            <span class="me-2"><BootstrapIcon icon="question-lg" /></span>
-                                                            "Untitled"
-                                                        }.into_view()
-                                                    }
-                                                }}
-                                            </div>
-                                        </NavLink>
+                                        <SidebarConversation id=item.id group=item.group delete_targets=sidebar_delete_targets selection />
                                     }
                                 }
                             />
                         </ul>
                     </div>
+                    <SidebarDeleteModal targets=sidebar_delete_targets selection />
                     <hr />
+                    <SyncIndicator />
                     <ul class="nav nav-pills flex-column">
+                        <NavLink href="/archive">
+                            <span class="me-2"><BootstrapIcon icon="archive" /></span>
+                            "Archived"
+                        </NavLink>
+                        <NavLink href="/bookmarks">
+                            <span class="me-2"><BootstrapIcon icon="star" /></span>
+                            "Bookmarks"
+                        </NavLink>
+                        <NavLink href="/status">
+                            <span class="me-2"><BootstrapIcon icon="activity" /></span>
+                            "Backend status"
+                        </NavLink>
                         <NavLink href="/settings">
                             <span class="me-2"><BootstrapIcon icon="gear" /></span>
                             "Settings"
@@ -600,44 +2963,17 @@ pub fn App() -> impl IntoView {
                     </ul>
                 </nav>
                 <main class="main d-flex flex-column w-100 h-100 mw-100 mh-100 position-relative">
-                    // error message
-                    <div class="z-1 position-absolute top-0 start-50 translate-middle-x w-50">
-                        <div
-                            class="alert alert-danger alert-dismissible fade show mt-4"
-                            class:visually-hidden=move || errors.0.with(|errors| errors.is_empty())
-                            role="alert"
-                        >
-                            <h5>"Error"</h5>
-                            <For
-                                each=move || errors.0.get()
-                                key=|error| error.id
-                                children=|error| view!{
-                                    <hr />
-                                    <h5>
-                                        <span class="me-2"><BootstrapIcon icon="exclamation-circle" /></span>
-                                        {error.message}
-                                    </h5>
-                                    <ol>
-                                        {
-                                            error.trace
-                                                .into_iter()
-                                                .map(|message| view!{ <li>{message}</li> })
-                                                .collect_view()
-                                        }
-                                    </ol>
-                                }
-                            />
-                            <button
-                                type="button"
-                                class="btn-close"
-                                aria-label="Close"
-                                on:click=move |_| errors.0.update(|errors| errors.clear())
-                            ></button>
-                        </div>
-                    </div>
+                    <UpdateBanner />
+
+                    <ToastContainer />
 
                     <Routes>
-                        <Route path="/" view=Home />
+                        <Route path="/" view=|| view!{ <Home /> } />
+                        <Route path="/new" view=|| view!{ <Home start=true /> } />
+                        <Route path="/archive" view=Archive />
+                        <Route path="/bookmarks" view=BookmarkList />
+                        <Route path="/status" view=BackendStatus />
+                        <Route path=SHARED_PATH view=Shared />
                         <Route path="/conversation/:id" view=move || {
                             let params = use_params_map();
                             let id = Signal::derive(move || {
@@ -648,6 +2984,15 @@ pub fn App() -> impl IntoView {
                             });
                             view!{ <Conversation id=id /> }
                         } />
+                        <Route path="/conversation/:id/print" view=move || {
+                            let params = use_params_map();
+                            let id = Signal::derive(move || {
+                                let id = params.with(|p| p.get("id").cloned().unwrap());
+                                let id = Uuid::parse_str(&id).expect("invalid conversation id");
+                                ConversationId::from(id)
+                            });
+                            view!{ <PrintConversation id=id /> }
+                        } />
                         <SettingsRoutes />
                         <Route path="/*any" view=NotFound />
                     </Routes>
@@ -0,0 +1,146 @@
+use leptos::{
+    component,
+    create_local_resource,
+    view,
+    CollectView,
+    IntoView,
+    Signal,
+    SignalGet,
+};
+use strum::EnumMessage;
+
+use super::BootstrapIcon;
+use crate::{
+    hub::{
+        format_parameter_count,
+        model_info,
+        model_readme,
+        strip_front_matter,
+        ModelInfo,
+    },
+    render::render_markdown,
+    state::{
+        Capability,
+        ModelId,
+    },
+};
+
+/// Small icons for what a model can do, with the description as tooltip.
+#[component]
+pub fn CapabilityBadges(capabilities: Vec<Capability>) -> impl IntoView {
+    capabilities
+        .into_iter()
+        .map(|capability| {
+            let description = capability.get_message().unwrap_or_default();
+            view! {
+                <span
+                    class="badge rounded-pill text-bg-light border ms-1"
+                    title=description
+                    aria-label=description
+                >
+                    <BootstrapIcon icon=capability.icon() />
+                </span>
+            }
+        })
+        .collect_view()
+}
+
+pub const MODEL_CARD_OFFCANVAS_ID: &'static str = "model_card_offcanvas";
+
+#[derive(Clone, Debug)]
+struct ModelCardData {
+    model_id: ModelId,
+    info: ModelInfo,
+    readme_html: Option<String>,
+}
+
+/// An offcanvas panel showing the model card of a model. The model card is
+/// only fetched once `open` is set.
+#[component]
+pub fn ModelCard(
+    #[prop(into)] model_id: Signal<Option<ModelId>>,
+    #[prop(into)] open: Signal<bool>,
+) -> impl IntoView {
+    let card = create_local_resource(
+        move || open.get().then(|| model_id.get()).flatten(),
+        |model_id| {
+            async move {
+                let model_id = model_id?;
+
+                let info = model_info(&model_id)
+                    .await
+                    .map_err(|error| log::error!("fetching model info failed: {error}"))
+                    .ok()?;
+
+                let readme_html = model_readme(&model_id)
+                    .await
+                    .map_err(|error| log::warn!("fetching model readme failed: {error}"))
+                    .ok()
+                    .map(|readme| render_markdown(strip_front_matter(&readme)));
+
+                Some(ModelCardData {
+                    model_id,
+                    info,
+                    readme_html,
+                })
+            }
+        },
+    );
+
+    view! {
+        <div class="offcanvas offcanvas-end model-card" tabindex="-1" id=MODEL_CARD_OFFCANVAS_ID>
+            <div class="offcanvas-header">
+                <h5 class="offcanvas-title text-truncate">
+                    {move || model_id.get().map(|model_id| model_id.to_string())}
+                </h5>
+                <button type="button" class="btn-close" data-bs-dismiss="offcanvas" aria-label="Close"></button>
+            </div>
+            <div class="offcanvas-body">
+                {move || {
+                    if card.loading().get() {
+                        return view!{
+                            <div class="d-flex">
+                                <div class="spinner-border m-auto" role="status">
+                                    <span class="visually-hidden">"Loading..."</span>
+                                </div>
+                            </div>
+                        }.into_view();
+                    }
+
+                    let Some(Some(card)) = card.get()
+                    else {
+                        return view!{ <p>"Could not load the model card."</p> }.into_view();
+                    };
+
+                    view!{
+                        <dl class="row">
+                            <dt class="col-4">"License"</dt>
+                            <dd class="col-8">{card.info.license().map(ToOwned::to_owned).unwrap_or_else(|| "unknown".to_owned())}</dd>
+                            <dt class="col-4">"Parameters"</dt>
+                            <dd class="col-8">{card.info.parameter_count().map(format_parameter_count).unwrap_or_else(|| "unknown".to_owned())}</dd>
+                            <dt class="col-4">"Task"</dt>
+                            <dd class="col-8">{card.info.pipeline_tag.clone().unwrap_or_else(|| "unknown".to_owned())}</dd>
+                            <dt class="col-4">"Downloads"</dt>
+                            <dd class="col-8">{card.info.downloads}</dd>
+                            <dt class="col-4">"Likes"</dt>
+                            <dd class="col-8">{card.info.likes}</dd>
+                        </dl>
+                        <div class="mb-3">
+                            {card.info.display_tags().map(|tag| view!{
+                                <span class="badge text-bg-secondary me-1">{tag.to_owned()}</span>
+                            }).collect_view()}
+                        </div>
+                        <p>
+                            <a href=card.model_id.url() target="_blank">
+                                "Open on Hugging Face"
+                                <BootstrapIcon icon="link-45deg" />
+                            </a>
+                        </p>
+                        <hr />
+                        {card.readme_html.map(|html| view!{ <div class="markdown" inner_html=html></div> })}
+                    }.into_view()
+                }}
+            </div>
+        </div>
+    }
+}
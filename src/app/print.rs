@@ -0,0 +1,111 @@
+//! A print-friendly view of a conversation. The sidebar and toolbar are
+//! hidden by the print styles in `app.css`.
+
+use chrono::Local;
+use leptos::{
+    component,
+    view,
+    with,
+    CollectView,
+    IntoView,
+    MaybeSignal,
+    SignalGet,
+    SignalGetUntracked,
+    SignalWith,
+};
+use leptos_router::A;
+
+use super::{
+    expect_context,
+    BootstrapIcon,
+    Context,
+};
+use crate::{
+    render::render_markdown,
+    state::{
+        use_conversation,
+        use_message,
+        ConversationId,
+        Role,
+        StorageSignals,
+    },
+};
+
+#[component]
+pub fn PrintConversation(#[prop(into)] id: MaybeSignal<ConversationId>) -> impl IntoView {
+    let Context { settings, .. } = expect_context();
+
+    let print = |_| {
+        gloo_utils::window().print().ok();
+    };
+
+    move || {
+        let id = id.get();
+        let StorageSignals {
+            read: conversation, ..
+        } = use_conversation(id);
+
+        let Some(conversation) = conversation.get()
+        else {
+            return view! {
+                <div class="h-100 w-100 pt-3 px-4">
+                    <h4>"Conversation not found"</h4>
+                </div>
+            }
+            .into_view();
+        };
+
+        let model_name = conversation.model_id.as_ref().map(|model_id| {
+            with!(|settings| {
+                settings
+                    .models
+                    .get(model_id)
+                    .map(|model| model.display_name().to_owned())
+                    .unwrap_or_else(|| model_id.to_string())
+            })
+        });
+        let started = conversation
+            .timestamp_started
+            .with_timezone(&Local)
+            .format("%c")
+            .to_string();
+        let messages = conversation
+            .messages
+            .iter()
+            .filter_map(|message_id| use_message(*message_id).read.get_untracked())
+            .collect::<Vec<_>>();
+
+        view! {
+            <div class="d-flex flex-column w-100 overflow-y-scroll p-4 print-view">
+                <div class="d-flex flex-row mb-3 no-print">
+                    <A class="btn btn-outline-secondary me-2" href=format!("/conversation/{id}")>
+                        <span class="me-2"><BootstrapIcon icon="arrow-left" /></span>
+                        "Back"
+                    </A>
+                    <button type="button" class="btn btn-primary" on:click=print>
+                        <span class="me-2"><BootstrapIcon icon="printer" /></span>
+                        "Print"
+                    </button>
+                </div>
+                <h2>{conversation.title.clone().unwrap_or_else(|| "Untitled".to_owned())}</h2>
+                <p class="text-body-secondary">
+                    {model_name.clone().map(|model_name| format!("{model_name} · "))}
+                    {started}
+                </p>
+                {messages.into_iter().map(|message| {
+                    let is_assistant = matches!(message.role, Role::Assitant);
+                    view!{
+                        <div class="d-flex flex-column w-75 mw-75 my-2 message-container" class:ms-auto=is_assistant>
+                            <div
+                                class="rounded rounded-3 p-2 message markdown"
+                                inner_html=render_markdown(&message.text)
+                            >
+                            </div>
+                        </div>
+                    }
+                }).collect_view()}
+            </div>
+        }
+        .into_view()
+    }
+}
@@ -0,0 +1,238 @@
+//! Adds a Hugging Face model from the model dropdown on the home page, with the
+//! same checks as in the models tab, so that the draft isn't left behind.
+
+use futures::FutureExt;
+use leptos::{
+    component,
+    create_rw_signal,
+    event_target_value,
+    spawn_local,
+    view,
+    with,
+    Callback,
+    CollectView,
+    IntoView,
+    SignalGet,
+    SignalGetUntracked,
+    SignalSet,
+    SignalUpdate,
+    SignalWithUntracked,
+};
+use leptos_use::use_debounce_fn_with_arg_and_options;
+use strum::{
+    EnumMessage,
+    VariantArray,
+};
+
+use super::{
+    expect_context,
+    settings::{
+        search_model_id,
+        test_model,
+        ModelIdInvalidReason,
+        ModelIdState,
+    },
+    BootstrapIcon,
+    Context,
+    Error,
+};
+use crate::{
+    hub::fetch_model_metadata,
+    state::{
+        ChatTemplate,
+        Model,
+        ModelBackend,
+        ModelId,
+        ModelMetadata,
+    },
+    utils::non_empty,
+};
+
+#[component]
+pub fn QuickAddModel(
+    /// Called with the ID of the model once it's added.
+    #[prop(into)]
+    on_added: Callback<ModelId>,
+    #[prop(into)] on_close: Callback<()>,
+) -> impl IntoView {
+    let Context {
+        errors,
+        settings,
+        update_settings,
+        ..
+    } = expect_context();
+
+    let model_id = create_rw_signal(None::<ModelId>);
+    let model_id_state = create_rw_signal(ModelIdState::default());
+    let search_results = create_rw_signal(vec![]);
+    let metadata = create_rw_signal(None::<ModelMetadata>);
+    let chat_template = create_rw_signal(ChatTemplate::default());
+
+    let check_model = move |input: String| {
+        let Some(input) = non_empty(input.trim().to_owned())
+        else {
+            model_id.set(None);
+            model_id_state.set(ModelIdState::default());
+            search_results.set(vec![]);
+            return;
+        };
+        let input = ModelId(input);
+        model_id.set(Some(input.clone()));
+        model_id_state.set(ModelIdState::Checking);
+
+        spawn_local(
+            async move {
+                let (api, hf_token) =
+                    settings.with_untracked(|settings| (settings.api(), settings.hf_token()));
+
+                let (results, exact_match) = search_model_id(&api, &input).await?;
+                search_results.set(results);
+
+                let state = if !exact_match {
+                    ModelIdState::Invalid {
+                        reason: ModelIdInvalidReason::NotFound,
+                    }
+                }
+                else if settings.with_untracked(|settings| settings.models.contains_key(&input)) {
+                    ModelIdState::Invalid {
+                        reason: ModelIdInvalidReason::AlreadyExists,
+                    }
+                }
+                else {
+                    match fetch_model_metadata(&input, hf_token.as_deref()).await {
+                        Ok(fetched) => metadata.set(Some(fetched)),
+                        Err(error) => {
+                            log::error!("failed to fetch model metadata: {error}");
+                            metadata.set(None);
+                        }
+                    }
+                    test_model(&api, &input).await
+                };
+
+                // the input might have changed in the meantime.
+                if model_id.get_untracked().as_ref() == Some(&input) {
+                    model_id_state.set(state);
+                }
+
+                Ok::<(), Error>(())
+            }
+            .map(move |result| {
+                if let Err(error) = result {
+                    model_id_state.set(ModelIdState::default());
+                    errors.push(error);
+                }
+            }),
+        );
+    };
+    let check_model_debounced =
+        use_debounce_fn_with_arg_and_options(check_model, 300.0, Default::default());
+
+    let add_model = move |_| {
+        let Some(model_id) = model_id.get_untracked()
+        else {
+            return;
+        };
+        let metadata = metadata.get_untracked();
+        // the model ID without the organization is a good enough name.
+        let name = model_id
+            .0
+            .rsplit('/')
+            .next()
+            .unwrap_or(&model_id.0)
+            .to_owned();
+
+        let model = Model {
+            model_id: model_id.clone(),
+            name: Some(name),
+            chat_template: chat_template.get_untracked(),
+            stream: true,
+            context_length: metadata
+                .as_ref()
+                .and_then(|metadata| metadata.context_length),
+            default_parameters: Default::default(),
+            metadata,
+            favorite: false,
+            position: None,
+            vision: false,
+            reasoning: None,
+            tools: false,
+            backend: ModelBackend::HuggingFace,
+            fallbacks: vec![],
+            credential: None,
+        };
+        update_settings.update(|settings| {
+            settings.models.insert(model_id.clone(), model);
+        });
+        on_added(model_id);
+    };
+
+    view! {
+        <div class="card mb-3">
+            <div class="card-body">
+                <div class="d-flex flex-row align-items-center mb-2">
+                    <h6 class="card-title mb-0">"Add a model"</h6>
+                    <button type="button" class="btn-close ms-auto" aria-label="Close" on:click=move |_| on_close(())></button>
+                </div>
+                <div class="form-floating mb-2">
+                    <input
+                        type="text"
+                        class="form-control"
+                        class:is-valid=move || model_id_state.get().is_valid()
+                        class:is-invalid=move || model_id_state.get().is_invalid()
+                        id="quick_add_model_id"
+                        list="quick_add_model_id_results"
+                        placeholder="mistralai/Mistral-7B-Instruct-v0.2"
+                        on:input=move |event| {
+                            check_model_debounced(event_target_value(&event));
+                        }
+                    />
+                    <label for="quick_add_model_id">
+                        {move || match model_id_state.get() {
+                            ModelIdState::Checking => view!{
+                                "Checking"
+                                <div class="spinner-border spinner-border-sm ms-1" role="status"></div>
+                            }.into_view(),
+                            ModelIdState::Valid => "Model ID".into_view(),
+                            ModelIdState::Invalid { reason } => reason.get_message().into_view()
+                        }}
+                    </label>
+                    <datalist id="quick_add_model_id_results">
+                        {move || with!(|search_results| search_results.iter().map(|result| view!{
+                            <option value=result.clone()></option>
+                        }).collect_view())}
+                    </datalist>
+                </div>
+                <div class="input-group">
+                    <select
+                        class="form-select"
+                        aria-label="Select chat template"
+                        on:input=move |event| {
+                            if let Ok(template) = event_target_value(&event).parse::<ChatTemplate>() {
+                                chat_template.set(template);
+                            }
+                        }
+                    >
+                        {<ChatTemplate as VariantArray>::VARIANTS.iter().map(|template| view!{
+                            <option
+                                value=template.as_ref()
+                                selected=move || *template == chat_template.get()
+                            >
+                                {template.get_message()}
+                            </option>
+                        }).collect_view()}
+                    </select>
+                    <button
+                        type="button"
+                        class="btn btn-primary"
+                        disabled=move || !model_id_state.get().is_valid()
+                        on:click=add_model
+                    >
+                        <span class="me-1"><BootstrapIcon icon="plus-lg" /></span>
+                        "Add"
+                    </button>
+                </div>
+                <div class="form-text">"The model is checked with the Inference API before it can be added. Everything else can be changed in the settings."</div>
+            </div>
+        </div>
+    }
+}
@@ -1,26 +1,40 @@
+use chrono::{
+    Local,
+    Utc,
+};
 use futures::FutureExt;
 use hf_textgen::ModelState;
 use leptos::{
     component,
+    create_effect,
     create_memo,
     create_node_ref,
     create_rw_signal,
+    ev::SubmitEvent,
     event_target_checked,
     event_target_value,
     html::{
         Input,
         Select,
+        Textarea,
     },
     spawn_local,
     view,
     with,
+    Callback,
     Children,
+    CollectView,
     For,
     IntoView,
+    RwSignal,
+    Signal,
     SignalGet,
+    SignalGetUntracked,
     SignalSet,
     SignalUpdate,
+    SignalWith,
     SignalWithUntracked,
+    WriteSignal,
 };
 use leptos_router::{
     Outlet,
@@ -35,24 +49,112 @@ use strum::{
     EnumMessage,
     VariantArray,
 };
-use web_sys::Event;
+use uuid::Uuid;
+use web_sys::{
+    Event,
+    MouseEvent,
+};
 
 use super::{
+    conversation::ConversationParametersInputGroup,
+    feedback::FeedbackTab,
+    lock::SecurityTab,
+    model_card::CapabilityBadges,
     BootstrapIcon,
     Error,
 };
 use crate::{
     app::{
+        apply_cleanup,
+        cleanup_candidates,
+        delete_conversation_permanently,
         expect_context,
+        purge_trash,
+        refresh_summary,
+        restore_conversation,
+        sync::sync_conversations,
         Context,
     },
+    backend::{
+        anthropic::ANTHROPIC_API_URL,
+        connection::{
+            Connection,
+            Header,
+        },
+        huggingface::DEFAULT_TGI_URL,
+        local,
+        openrouter::{
+            OpenRouter,
+            OpenRouterModel,
+        },
+        timeout::Timeouts,
+    },
+    config::{
+        export_models,
+        fetch_recommended_models,
+        import_models,
+        ModelsFormat,
+        CONFIG_PATH,
+    },
+    diagnostics::{
+        check_storage,
+        IssueKind,
+    },
+    hub::{
+        fetch_model_metadata,
+        whoami,
+        WhoAmI,
+        HF_TOKENS_PAGE,
+    },
+    import::{
+        parse_chatgpt_export,
+        ImportError,
+    },
+    parameters,
     state::{
         clear_storage,
+        delete_storage_by_name,
+        list_storage_raw,
+        read_storage_by_name,
+        use_conversation,
+        use_debug_log,
+        use_message,
+        write_storage_by_name,
+        Appearance,
         ChatTemplate,
+        CleanupAction,
+        CleanupPolicy,
+        ConversationId,
+        ConversationParameters,
+        ConversationSummary,
+        Density,
+        ExamplePrompt,
+        Fallback,
+        Message,
+        MessageId,
+        MessageStatus,
         Model,
+        ModelBackend,
         ModelId,
+        ModelMetadata,
+        ReasoningDelimiters,
+        RequestOutcome,
+        Role,
+        StorageSignals,
+        Theme,
+    },
+    sync::SyncSettings,
+    tokenizer::count_tokens,
+    tools::{
+        Implementation,
+        Tool,
+    },
+    utils::{
+        loaded_resources,
+        non_empty,
+        request_notification_permission,
+        startup_time_ms,
     },
-    utils::non_empty,
 };
 
 #[component(transparent)]
@@ -60,8 +162,18 @@ pub fn SettingsRoutes() -> impl IntoView {
     view! {
         <Route path="/settings" view=Settings>
             <Route path="general" view=GeneralTab />
+            <Route path="credentials" view=CredentialsTab />
+            <Route path="appearance" view=AppearanceTab />
             <Route path="backends" view=BackendsTab />
             <Route path="models" view=ModelsTab />
+            <Route path="tags" view=TagsTab />
+            <Route path="tools" view=ToolsTab />
+            <Route path="templates" view=TemplatesTab />
+            <Route path="examples" view=ExamplesTab />
+            <Route path="feedback" view=FeedbackTab />
+            <Route path="security" view=SecurityTab />
+            <Route path="trash" view=TrashTab />
+            <Route path="import" view=ImportTab />
             <Route path="debug" view=DebugTab />
             <Route path="" view=|| view!{ <Redirect path="/settings/general" /> } />
         </Route>
@@ -92,7 +204,17 @@ fn Settings() -> impl IntoView {
         </div>
         <ul class="nav nav-tabs px-4 mt-2">
             <Tab href="/settings/general">"General"</Tab>
+            <Tab href="/settings/credentials">"Credentials"</Tab>
+            <Tab href="/settings/appearance">"Appearance"</Tab>
             <Tab href="/settings/models">"Models"</Tab>
+            <Tab href="/settings/tags">"Tags"</Tab>
+            <Tab href="/settings/tools">"Tools"</Tab>
+            <Tab href="/settings/templates">"Templates"</Tab>
+            <Tab href="/settings/examples">"Examples"</Tab>
+            <Tab href="/settings/feedback">"Feedback"</Tab>
+            <Tab href="/settings/security">"Security"</Tab>
+            <Tab href="/settings/trash">"Trash"</Tab>
+            <Tab href="/settings/import">"Import"</Tab>
             {move || {
                 with!(|settings| settings.debug_mode)
                     .then(|| view!{
@@ -113,16 +235,26 @@ fn GeneralTab() -> impl IntoView {
         ..
     } = expect_context();
 
+    let notifications_denied = create_rw_signal(false);
+
     #[derive(Clone, Copy, Debug, EnumMessage, EnumIs)]
     enum HfTokenState {
         #[strum(
             message = "Enter your Hugging Face API token. This increases your API rate limit."
         )]
         None,
+        #[strum(message = "Checking token")]
+        Checking,
         #[strum(message = "Hugging Face API token")]
         Valid,
+        #[strum(message = "Hugging Face API token (could not be verified)")]
+        Unverified,
         #[strum(message = "The entered API token is invalid")]
         Invalid,
+        #[strum(message = "The entered API token was rejected by Hugging Face")]
+        Unauthorized,
+        #[strum(message = "The entered API token is missing permissions")]
+        MissingPermissions,
     }
 
     impl HfTokenState {
@@ -139,10 +271,85 @@ fn GeneralTab() -> impl IntoView {
                 Self::None
             }
         }
+
+        pub fn is_ok(&self) -> bool {
+            matches!(self, Self::Valid | Self::Unverified)
+        }
+
+        pub fn is_error(&self) -> bool {
+            matches!(
+                self,
+                Self::Invalid | Self::Unauthorized | Self::MissingPermissions
+            )
+        }
     }
 
     let hf_token_state =
-        settings.with_untracked(|settings| create_rw_signal(HfTokenState::new(&settings.hf_token)));
+        settings.with_untracked(|settings| create_rw_signal(HfTokenState::new(&settings.hf_token())));
+    let missing_permissions = create_rw_signal(vec![]);
+    // the account that the token belongs to, once it's checked.
+    let hf_account = create_rw_signal(None::<WhoAmI>);
+
+    let check_token = move |hf_token: Option<String>| {
+        let state = HfTokenState::new(&hf_token);
+        hf_token_state.set(state);
+        missing_permissions.set(vec![]);
+        hf_account.set(None);
+
+        let Some(hf_token) = state.is_valid().then_some(hf_token).flatten()
+        else {
+            update_settings.update(move |settings| {
+                settings.set_hf_token(None);
+            });
+            return;
+        };
+
+        // check with the hub that the token can actually be used for inference.
+        // fine-grained tokens might lack the permission.
+        hf_token_state.set(HfTokenState::Checking);
+
+        spawn_local(async move {
+            let state = match whoami(&hf_token).await {
+                Ok(whoami) => {
+                    let missing = whoami.missing_permissions();
+                    let state = if missing.is_empty() {
+                        HfTokenState::Valid
+                    }
+                    else {
+                        log::warn!("token is missing permissions: {missing:?}");
+                        missing_permissions.set(missing);
+                        HfTokenState::MissingPermissions
+                    };
+                    hf_account.set(Some(whoami));
+                    state
+                }
+                Err(error) if error.status() == Some(reqwest::StatusCode::UNAUTHORIZED) => {
+                    log::warn!("token was rejected: {error}");
+                    HfTokenState::Unauthorized
+                }
+                Err(error) => {
+                    log::warn!("could not verify token: {error}");
+                    HfTokenState::Unverified
+                }
+            };
+
+            if state.is_ok() {
+                update_settings.update(move |settings| {
+                    settings.set_hf_token(Some(hf_token));
+                });
+            }
+
+            hf_token_state.set(state);
+        });
+    };
+
+    let check_token_debounced =
+        use_debounce_fn_with_arg_and_options(check_token, 500.0, Default::default());
+
+    // shows the account of the saved token.
+    if let Some(hf_token) = settings.with_untracked(|settings| settings.hf_token()) {
+        check_token(Some(hf_token));
+    }
 
     view! {
         <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
@@ -151,136 +358,1166 @@ fn GeneralTab() -> impl IntoView {
                     <input
                         type="password"
                         class="form-control"
-                        class:is-valid=move || hf_token_state.get().is_valid()
-                        class:is-invalid=move || hf_token_state.get().is_invalid()
+                        class:is-valid=move || hf_token_state.get().is_ok()
+                        class:is-invalid=move || hf_token_state.get().is_error()
                         id="general_hf_token"
                         aria-label="Enter your Hugging Face token"
-                        value=move || with!(|settings| settings.hf_token.clone())
+                        value=move || with!(|settings| settings.hf_token())
                         on:input=move |event| {
-                            let hf_token = non_empty(event_target_value(&event));
-                            let new_state = HfTokenState::new(&hf_token);
-                            let set_token = new_state.is_valid().then_some(hf_token).flatten();
-
-                            update_settings.update(move |settings| {
-                                settings.hf_token = set_token;
-                            });
+                            check_token_debounced(non_empty(event_target_value(&event)));
                         }
                     />
                     <label
                         for="hf_token_input"
-                        class:text-success-emphasis=move || hf_token_state.get().is_valid()
-                        class:text-danger-emphasis=move || hf_token_state.get().is_invalid()
+                        class:text-success-emphasis=move || hf_token_state.get().is_ok()
+                        class:text-danger-emphasis=move || hf_token_state.get().is_error()
                     >
                         {move || hf_token_state.get().get_message()}
+                        {move || hf_token_state.get().is_checking().then(|| view!{
+                            <div class="spinner-border spinner-border-sm ms-1" role="status"></div>
+                        })}
                     </label>
                 </div>
-                <div class="form-text">"Your token is stored in your browser and is only sent to the Hugging Face API."</div>
+                {move || with!(|hf_account| hf_account.as_ref().map(|whoami| {
+                    let token_name = whoami.auth
+                        .as_ref()
+                        .and_then(|auth| auth.access_token.as_ref())
+                        .and_then(|access_token| access_token.display_name.clone());
+                    let scopes = whoami.scopes();
+                    view!{
+                        <div class="form-text">
+                            <span class="me-1"><BootstrapIcon icon="person-check" /></span>
+                            "Signed in as "
+                            <strong>{whoami.name.clone()}</strong>
+                            {token_name.map(|token_name| view!{
+                                " with token "
+                                <em>{token_name}</em>
+                            })}
+                            {(!scopes.is_empty()).then(|| view!{
+                                ". Scopes: "
+                                {scopes.into_iter().map(|scope| view!{
+                                    <code class="me-1">{scope}</code>
+                                }).collect_view()}
+                            })}
+                        </div>
+                    }
+                }))}
+                {move || hf_token_state.get().is_unauthorized().then(|| view!{
+                    <div class="form-text text-danger-emphasis">
+                        "Hugging Face doesn't accept this token. It might have been deleted or mistyped. You can check your tokens "
+                        <a href=HF_TOKENS_PAGE target="_blank">
+                            "here"
+                            <BootstrapIcon icon="link-45deg" />
+                        </a>
+                        "."
+                    </div>
+                })}
+                {move || with!(|missing_permissions| {
+                    (!missing_permissions.is_empty()).then(|| view!{
+                        <div class="form-text text-danger-emphasis">
+                            "Fine-grained tokens need these permissions: "
+                            {missing_permissions.iter().map(|permission| view!{
+                                <code class="me-1">{permission.to_string()}</code>
+                            }).collect_view()}
+                            "(\"Make calls to the serverless Inference API\"). You can edit the token "
+                            <a href=HF_TOKENS_PAGE target="_blank">
+                                "here"
+                                <BootstrapIcon icon="link-45deg" />
+                            </a>
+                            "."
+                        </div>
+                    })
+                })}
+                <div class="form-text">"Your token is stored in your browser and is only sent to the Hugging Face API. Other tokens and keys can be added in the Credentials tab."</div>
+            </div>
+
+            <div class="form-check form-switch mb-3">
+                <input
+                    class="form-check-input"
+                    type="checkbox"
+                    role="switch"
+                    id="general_send_examples"
+                    prop:checked=move || with!(|settings| settings.send_examples)
+                    on:input=move |event| update_settings.update(move |settings| settings.send_examples = event_target_checked(&event))
+                />
+                <label class="form-check-label" for="general_send_examples">"Send examples right away when clicked"</label>
+                <div class="form-text">"Hold Shift while clicking an example to do the opposite."</div>
+            </div>
+            <div class="form-check form-switch mb-3">
+                <input
+                    class="form-check-input"
+                    type="checkbox"
+                    role="switch"
+                    id="general_generate_titles"
+                    prop:checked=move || with!(|settings| settings.generate_titles)
+                    on:input=move |event| update_settings.update(move |settings| settings.generate_titles = event_target_checked(&event))
+                />
+                <label class="form-check-label" for="general_generate_titles">"Automatically generate conversation titles"</label>
+            </div>
+            <div class="form-check form-switch mb-3">
+                <input
+                    class="form-check-input"
+                    type="checkbox"
+                    role="switch"
+                    id="general_check_for_updates"
+                    prop:checked=move || with!(|settings| settings.check_for_updates)
+                    on:input=move |event| update_settings.update(move |settings| settings.check_for_updates = event_target_checked(&event))
+                />
+                <label class="form-check-label" for="general_check_for_updates">"Check for new versions of RustyChat"</label>
+            </div>
+            <div class="form-check form-switch mb-3">
+                <input
+                    class="form-check-input"
+                    type="checkbox"
+                    role="switch"
+                    id="general_notifications"
+                    prop:checked=move || with!(|settings| settings.notifications)
+                    on:input=move |event| {
+                        let enabled = event_target_checked(&event);
+                        if !enabled {
+                            update_settings.update(|settings| settings.notifications = false);
+                            return;
+                        }
+                        spawn_local(async move {
+                            let granted = request_notification_permission().await;
+                            notifications_denied.set(!granted);
+                            update_settings.update(move |settings| settings.notifications = granted);
+                        });
+                    }
+                />
+                <label class="form-check-label" for="general_notifications">"Notify me when a response finishes in the background"</label>
+                {move || notifications_denied.get().then(|| view!{
+                    <div class="form-text text-danger">"Notifications are blocked. You can allow them in your browser's site settings."</div>
+                })}
+            </div>
+            <div class="form-floating mb-3">
+                <select
+                    class="form-select"
+                    id="general_title_model"
+                    aria-label="Select the model used to generate titles"
+                    disabled=move || with!(|settings| !settings.generate_titles)
+                    on:change=move |event| {
+                        let model_id = ModelId(event_target_value(&event));
+                        update_settings.update(move |settings| settings.title_model = model_id);
+                    }
+                >
+                    <For
+                        each=move || with!(|settings| {
+                            let mut items = settings.models
+                                .iter()
+                                .map(|(id, model)| (id.clone(), model.display_name().to_owned()))
+                                .collect::<Vec<_>>();
+                            items.sort_by_cached_key(|(_, name)| name.to_lowercase());
+                            items
+                        })
+                        key=|(model_id, _)| model_id.clone()
+                        children=move |(model_id, display_name)| {
+                            let value = model_id.to_string();
+                            view!{
+                                <option
+                                    value=value
+                                    selected=move || with!(|settings| settings.title_model == model_id)
+                                >
+                                    {display_name}
+                                </option>
+                            }
+                        }
+                    />
+                </select>
+                <label for="general_title_model">"Model used to generate titles"</label>
+            </div>
+            <div class="form-floating mb-3">
+                <select
+                    class="form-select"
+                    id="general_summary_model"
+                    aria-label="Select the model used to summarize conversations"
+                    on:change=move |event| {
+                        let summary_model = non_empty(event_target_value(&event)).map(ModelId);
+                        update_settings.update(move |settings| settings.summary_model = summary_model);
+                    }
+                >
+                    <option value="" selected=move || with!(|settings| settings.summary_model.is_none())>
+                        "The conversation's model"
+                    </option>
+                    <For
+                        each=move || with!(|settings| {
+                            let mut items = settings.models
+                                .iter()
+                                .map(|(id, model)| (id.clone(), model.display_name().to_owned()))
+                                .collect::<Vec<_>>();
+                            items.sort_by_cached_key(|(_, name)| name.to_lowercase());
+                            items
+                        })
+                        key=|(model_id, _)| model_id.clone()
+                        children=move |(model_id, display_name)| {
+                            let value = model_id.to_string();
+                            view!{
+                                <option
+                                    value=value
+                                    selected=move || with!(|settings| settings.summary_model.as_ref() == Some(&model_id))
+                                >
+                                    {display_name}
+                                </option>
+                            }
+                        }
+                    />
+                </select>
+                <label for="general_summary_model">"Model used to summarize conversations"</label>
+            </div>
+            <div class="form-floating mb-3">
+                <input
+                    type="text"
+                    class="form-control"
+                    id="general_speech_model"
+                    placeholder="openai/whisper-large-v3"
+                    prop:value=move || with!(|settings| settings.speech_model.as_ref().map(|model_id| model_id.to_string()).unwrap_or_default())
+                    on:change=move |event| {
+                        let speech_model = non_empty(event_target_value(&event).trim().to_owned()).map(ModelId);
+                        update_settings.update(move |settings| settings.speech_model = speech_model);
+                    }
+                />
+                <label for="general_speech_model">"Speech recognition model for voice input"</label>
+                <div class="form-text">"A Whisper model on the Hugging Face Inference API, e.g. openai/whisper-large-v3. A microphone button is shown next to the message input if this is set."</div>
             </div>
+            <div class="form-floating mb-3">
+                <input
+                    type="text"
+                    class="form-control"
+                    id="general_embedding_model"
+                    placeholder="sentence-transformers/all-MiniLM-L6-v2"
+                    prop:value=move || with!(|settings| settings.embedding_model.as_ref().map(|model_id| model_id.to_string()).unwrap_or_default())
+                    on:change=move |event| {
+                        let embedding_model = non_empty(event_target_value(&event).trim().to_owned()).map(ModelId);
+                        update_settings.update(move |settings| settings.embedding_model = embedding_model);
+                    }
+                />
+                <label for="general_embedding_model">"Embedding model for semantic search"</label>
+                <div class="form-text">"A sentence embedding model on the Hugging Face Inference API, e.g. sentence-transformers/all-MiniLM-L6-v2. If this is set, pressing Enter in the sidebar search looks for messages by meaning. Your past messages are sent to the model to index them. Private conversations are never indexed."</div>
+            </div>
+            <div class="form-floating mb-3">
+                <input
+                    type="url"
+                    class="form-control"
+                    id="general_config_url"
+                    placeholder=CONFIG_PATH
+                    prop:value=move || with!(|settings| settings.config_url.clone().unwrap_or_default())
+                    on:change=move |event| {
+                        let config_url = non_empty(event_target_value(&event).trim().to_owned());
+                        update_settings.update(move |settings| settings.config_url = config_url);
+                    }
+                />
+                <label for="general_config_url">"Load the app config from this URL"</label>
+                <div class="form-text">"The config has the same format as config.toml (or JSON), and can update the examples and built-in models. Leave empty to use the config of this deployment. Takes effect after a reload."</div>
+            </div>
+
+            <SyncSettingsForm />
         </div>
     }
 }
 
 #[component]
-fn BackendsTab() -> impl IntoView {
+fn AppearanceTab() -> impl IntoView {
+    let Context {
+        settings,
+        update_settings,
+        ..
+    } = expect_context();
+
+    // a color input that can also be reset to the theme's color.
+    let color_input = move |id: &'static str,
+                            label: &'static str,
+                            get: fn(&Appearance) -> Option<String>,
+                            set: fn(&mut Appearance, Option<String>)| {
+        let value = Signal::derive(move || with!(|settings| get(&settings.appearance)));
+        view! {
+            <div class="d-flex flex-row align-items-center mb-3">
+                <input
+                    type="color"
+                    class="form-control form-control-color me-2"
+                    id=id
+                    prop:value=move || value.get().unwrap_or_else(|| "#000000".to_owned())
+                    on:change=move |event| {
+                        let color = event_target_value(&event);
+                        update_settings.update(move |settings| set(&mut settings.appearance, Some(color)));
+                    }
+                />
+                <label for=id class="me-auto">{label}</label>
+                {move || value.get().is_none().then(|| view!{
+                    <small class="text-body-secondary me-2">"Theme default"</small>
+                })}
+                <button
+                    type="button"
+                    class="btn btn-sm btn-outline-secondary"
+                    disabled=move || value.get().is_none()
+                    on:click=move |_| update_settings.update(move |settings| set(&mut settings.appearance, None))
+                >
+                    "Reset"
+                </button>
+            </div>
+        }
+    };
+
     view! {
         <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
-            "Backends tab"
+            <div class="form-floating mb-3">
+                <select
+                    class="form-select"
+                    id="appearance_theme"
+                    on:change=move |event| {
+                        if let Ok(theme) = event_target_value(&event).parse::<Theme>() {
+                            update_settings.update(move |settings| settings.theme = theme);
+                        }
+                    }
+                >
+                    {<Theme as VariantArray>::VARIANTS.iter().map(|theme| view!{
+                        <option
+                            value=theme.as_ref()
+                            selected=move || with!(|settings| settings.theme == *theme)
+                        >
+                            {theme.get_message()}
+                        </option>
+                    }).collect_view()}
+                </select>
+                <label for="appearance_theme">"Theme"</label>
+            </div>
+            {color_input("appearance_accent_color", "Accent color", |appearance| appearance.accent_color.clone(), |appearance, color| appearance.accent_color = color)}
+            {color_input("appearance_user_bubble_color", "Your messages", |appearance| appearance.user_bubble_color.clone(), |appearance, color| appearance.user_bubble_color = color)}
+            {color_input("appearance_assistant_bubble_color", "Responses", |appearance| appearance.assistant_bubble_color.clone(), |appearance, color| appearance.assistant_bubble_color = color)}
+            <div class="mb-3">
+                <label for="appearance_font_size" class="form-label">
+                    {move || format!("Font size: {}%", with!(|settings| settings.appearance.font_size))}
+                </label>
+                <input
+                    type="range"
+                    class="form-range"
+                    id="appearance_font_size"
+                    min="75"
+                    max="150"
+                    step="5"
+                    prop:value=move || with!(|settings| settings.appearance.font_size)
+                    on:input=move |event| {
+                        if let Ok(font_size) = event_target_value(&event).parse::<u32>() {
+                            update_settings.update(move |settings| settings.appearance.font_size = font_size);
+                        }
+                    }
+                />
+            </div>
+            <div class="form-floating mb-3">
+                <select
+                    class="form-select"
+                    id="appearance_density"
+                    on:change=move |event| {
+                        if let Ok(density) = event_target_value(&event).parse::<Density>() {
+                            update_settings.update(move |settings| settings.appearance.density = density);
+                        }
+                    }
+                >
+                    {<Density as VariantArray>::VARIANTS.iter().map(|density| view!{
+                        <option
+                            value=density.as_ref()
+                            selected=move || with!(|settings| settings.appearance.density == *density)
+                        >
+                            {density.get_message()}
+                        </option>
+                    }).collect_view()}
+                </select>
+                <label for="appearance_density">"Density"</label>
+            </div>
+            <div class="d-flex flex-row">
+                <button
+                    type="button"
+                    class="btn btn-outline-secondary ms-auto"
+                    on:click=move |_| update_settings.update(|settings| settings.appearance = Default::default())
+                >
+                    <span class="me-1"><BootstrapIcon icon="arrow-counterclockwise" /></span>
+                    "Reset appearance"
+                </button>
+            </div>
         </div>
     }
 }
 
 #[component]
-fn ModelsTab() -> impl IntoView {
+fn SyncSettingsForm() -> impl IntoView {
     let Context {
-        errors,
         settings,
         update_settings,
         ..
     } = expect_context();
 
-    #[derive(Debug, EnumIs)]
-    enum SelectedModel {
-        Edit(ModelId),
-        New,
-    }
+    let url_input = create_node_ref::<Input>();
+    let username_input = create_node_ref::<Input>();
+    let password_input = create_node_ref::<Input>();
 
-    impl SelectedModel {
-        fn is_model(&self, model_id: &ModelId) -> bool {
-            match self {
-                Self::Edit(id) => id == model_id,
-                _ => false,
-            }
-        }
+    let sync_settings = move || with!(|settings| settings.sync.clone());
 
-        fn get_model_id(&self) -> Option<&ModelId> {
-            match self {
-                SelectedModel::Edit(model_id) => Some(model_id),
-                SelectedModel::New => None,
-            }
+    // syncing is turned off by clearing the URL
+    let save = move |_| {
+        let sync =
+            non_empty(url_input.get_untracked().unwrap().value().trim().to_owned()).map(|url| {
+                SyncSettings {
+                    url,
+                    username: non_empty(username_input.get_untracked().unwrap().value()),
+                    password: non_empty(password_input.get_untracked().unwrap().value()),
+                }
+            });
+        let enabled = sync.is_some();
+        update_settings.update(move |settings| settings.sync = sync);
+        if enabled {
+            sync_conversations();
         }
-    }
-
-    #[derive(Copy, Clone, Debug, EnumMessage)]
-    enum ModelIdInvalidReason {
-        #[strum(message = "Enter a Hugging Face model ID")]
-        Empty,
-        #[strum(message = "This model already exists")]
-        AlreadyExists,
-        #[strum(message = "Model not found")]
-        NotFound,
-        #[strum(message = "Model not available in the free API")]
-        NotLoadable,
-        #[strum(message = "Inference test failed")]
-        InferenceFailed,
-    }
+    };
 
-    #[derive(Copy, Clone, Debug, EnumIs)]
-    enum ModelIdState {
-        Checking,
-        Valid,
-        Invalid { reason: ModelIdInvalidReason },
+    view! {
+        <h5 class="mt-3">"Sync"</h5>
+        <div class="form-text mb-2">"Syncs your conversations with a folder on a WebDAV server, so that you can use them on other devices. If a conversation was changed on two devices, the newer one is kept. Private conversations are never synced."</div>
+        <div class="form-floating mb-3">
+            <input
+                type="url"
+                class="form-control"
+                id="general_sync_url"
+                placeholder="https://example.com/webdav/rusty-chat"
+                node_ref=url_input
+                prop:value=move || sync_settings().map(|sync| sync.url).unwrap_or_default()
+            />
+            <label for="general_sync_url">"WebDAV folder URL"</label>
+        </div>
+        <div class="d-flex flex-row mb-3">
+            <div class="form-floating flex-grow-1 me-2">
+                <input
+                    type="text"
+                    class="form-control"
+                    id="general_sync_username"
+                    autocomplete="off"
+                    node_ref=username_input
+                    prop:value=move || sync_settings().and_then(|sync| sync.username).unwrap_or_default()
+                />
+                <label for="general_sync_username">"Username (optional)"</label>
+            </div>
+            <div class="form-floating flex-grow-1">
+                <input
+                    type="password"
+                    class="form-control"
+                    id="general_sync_password"
+                    autocomplete="off"
+                    node_ref=password_input
+                    prop:value=move || sync_settings().and_then(|sync| sync.password).unwrap_or_default()
+                />
+                <label for="general_sync_password">"Password (optional)"</label>
+            </div>
+        </div>
+        <div class="mb-3">
+            <button type="button" class="btn btn-primary" on:click=save>
+                <span class="me-2"><BootstrapIcon icon="cloud-arrow-up" /></span>
+                "Save and sync"
+            </button>
+        </div>
     }
+}
 
-    impl Default for ModelIdState {
-        fn default() -> Self {
-            Self::Invalid {
-                reason: ModelIdInvalidReason::Empty,
-            }
-        }
-    }
+#[component]
+fn BackendsTab() -> impl IntoView {
+    let Context {
+        settings,
+        update_settings,
+        ..
+    } = expect_context();
 
-    impl ModelIdState {
-        fn is_valid_model_id(&self) -> bool {
-            match self {
-                Self::Checking | Self::Valid => true,
-                Self::Invalid { reason } => {
-                    match reason {
-                        ModelIdInvalidReason::AlreadyExists
-                        | ModelIdInvalidReason::NotLoadable
-                        | ModelIdInvalidReason::InferenceFailed => true,
-                        _ => false,
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
+            <h5>"Retries"</h5>
+            <div class="form-text mb-2">"Requests that fail because the API is busy or the model is still loading are retried, waiting twice as long after each attempt."</div>
+            <div class="form-floating mb-3">
+                <input
+                    type="number"
+                    class="form-control"
+                    id="backends_retry_attempts"
+                    min="1"
+                    prop:value=move || with!(|settings| settings.retry.max_attempts.to_string())
+                    on:input=move |event| {
+                        if let Ok(max_attempts) = event_target_value(&event).parse::<u32>() {
+                            update_settings.update(move |settings| settings.retry.max_attempts = max_attempts.max(1));
+                        }
+                    }
+                />
+                <label for="backends_retry_attempts">"Maximum number of attempts"</label>
+            </div>
+            <div class="form-floating mb-3">
+                <input
+                    type="number"
+                    class="form-control"
+                    id="backends_retry_backoff"
+                    min="0"
+                    step="100"
+                    prop:value=move || with!(|settings| settings.retry.initial_backoff_ms.to_string())
+                    on:input=move |event| {
+                        if let Ok(initial_backoff_ms) = event_target_value(&event).parse::<u32>() {
+                            update_settings.update(move |settings| settings.retry.initial_backoff_ms = initial_backoff_ms);
+                        }
+                    }
+                />
+                <label for="backends_retry_backoff">"Delay before the first retry (ms)"</label>
+            </div>
+            <h5>"Rate limiting"</h5>
+            <div class="form-text mb-2">"Requests to the same backend wait for each other, so that generating titles and responses at the same time doesn't get rate-limited."</div>
+            <div class="form-floating mb-3">
+                <input
+                    type="number"
+                    class="form-control"
+                    id="backends_max_concurrent_requests"
+                    min="1"
+                    prop:value=move || with!(|settings| settings.max_concurrent_requests.to_string())
+                    on:input=move |event| {
+                        if let Ok(max_concurrent_requests) = event_target_value(&event).parse::<usize>() {
+                            update_settings.update(move |settings| settings.max_concurrent_requests = max_concurrent_requests.max(1));
+                        }
                     }
+                />
+                <label for="backends_max_concurrent_requests">"Maximum number of concurrent requests"</label>
+            </div>
+            <h5>"Timeouts"</h5>
+            <div class="form-text mb-2">"Requests that get no response in time fail, and so do streams that stall. 0 waits forever."</div>
+            <table class="table table-sm align-middle mb-3">
+                <thead>
+                    <tr>
+                        <th scope="col">"Backend"</th>
+                        <th scope="col">"Response (s)"</th>
+                        <th scope="col">"Between tokens (s)"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {<ModelBackend as VariantArray>::VARIANTS.iter().map(|backend| {
+                        let backend = *backend;
+                        let timeouts = move || with!(|settings| settings.timeouts(backend));
+                        let update_timeouts = move |update: fn(&mut Timeouts, u32), value: String| {
+                            if let Ok(value) = value.parse::<u32>() {
+                                update_settings.update(move |settings| {
+                                    let mut timeouts = settings.timeouts(backend);
+                                    update(&mut timeouts, value);
+                                    settings.timeouts.insert(backend, timeouts);
+                                });
+                            }
+                        };
+                        view!{
+                            <tr>
+                                <td>{backend.get_message()}</td>
+                                <td>
+                                    <input
+                                        type="number"
+                                        class="form-control form-control-sm"
+                                        min="0"
+                                        aria-label="Response timeout in seconds"
+                                        prop:value=move || timeouts().request_secs.to_string()
+                                        on:change=move |event| update_timeouts(|timeouts, value| timeouts.request_secs = value, event_target_value(&event))
+                                    />
+                                </td>
+                                <td>
+                                    <input
+                                        type="number"
+                                        class="form-control form-control-sm"
+                                        min="0"
+                                        aria-label="Stall timeout in seconds"
+                                        prop:value=move || timeouts().stall_secs.to_string()
+                                        on:change=move |event| update_timeouts(|timeouts, value| timeouts.stall_secs = value, event_target_value(&event))
+                                    />
+                                </td>
+                            </tr>
+                        }
+                    }).collect_view()}
+                </tbody>
+            </table>
+            <h5>"Connections"</h5>
+            <div class="form-text mb-2">"Self-hosted gateways might need other headers, e.g. "<code>"X-Api-Key"</code>" or "<code>"Authorization: Basic ..."</code>". A CORS proxy is used by setting the base URL to the proxy's prefix followed by the backend's URL."</div>
+            {<ModelBackend as VariantArray>::VARIANTS.iter().filter(|backend| **backend != ModelBackend::Local).map(|backend| {
+                let backend = *backend;
+                let base_url_id = format!("backends_{}_base_url_override", backend.as_ref().to_lowercase());
+                let headers_id = format!("backends_{}_headers", backend.as_ref().to_lowercase());
+                let update_connection = move |update: Box<dyn FnOnce(&mut Connection)>| {
+                    update_settings.update(move |settings| {
+                        let mut connection = settings.connection(backend);
+                        update(&mut connection);
+                        if connection == Connection::default() {
+                            settings.connections.remove(&backend);
+                        }
+                        else {
+                            settings.connections.insert(backend, connection);
+                        }
+                    });
+                };
+                view!{
+                    <h6>{backend.get_message()}</h6>
+                    <div class="form-floating mb-2">
+                        <input
+                            type="url"
+                            class="form-control"
+                            id=base_url_id.clone()
+                            placeholder="https://proxy.example.org/"
+                            prop:value=move || with!(|settings| settings.connection(backend).base_url.unwrap_or_default())
+                            on:change=move |event| {
+                                let base_url = non_empty(event_target_value(&event).trim().to_owned());
+                                update_connection(Box::new(move |connection| connection.base_url = base_url));
+                            }
+                        />
+                        <label for=base_url_id>"Base URL (optional)"</label>
+                    </div>
+                    <div class="form-floating mb-3">
+                        <textarea
+                            class="form-control font-monospace"
+                            id=headers_id.clone()
+                            style="height: 5em"
+                            placeholder="X-Api-Key: ..."
+                            prop:value=move || with!(|settings| format_headers(&settings.connection(backend).headers))
+                            on:change=move |event| {
+                                let headers = parse_headers(&event_target_value(&event));
+                                update_connection(Box::new(move |connection| connection.headers = headers));
+                            }
+                        ></textarea>
+                        <label for=headers_id>"Headers (optional)"</label>
+                    </div>
                 }
-            }
-        }
+            }).collect_view()}
+            <div class="form-text mb-3">"One header per line, as name and value separated by a colon. The hub requests for checking new models don't use these."</div>
+            <h5>"Anthropic"</h5>
+            <div class="form-text mb-2">"Models with the Anthropic backend use the Messages API. Their model ID is the name of the Claude model, e.g. claude-3-5-sonnet-latest."</div>
+            <div class="form-floating mb-3">
+                <CredentialSelect
+                    id="backends_anthropic_credential"
+                    value=Signal::derive(move || with!(|settings| settings.backend_credentials.get(&ModelBackend::Anthropic).cloned()))
+                    on_change=move |name| update_settings.update(move |settings| set_backend_credential(settings, ModelBackend::Anthropic, name))
+                    none_label="None"
+                />
+                <label for="backends_anthropic_credential">"API key"</label>
+                <div class="form-text">"API keys are added in the Credentials tab."</div>
+            </div>
+            <div class="form-floating mb-3">
+                <input
+                    type="url"
+                    class="form-control"
+                    id="backends_anthropic_base_url"
+                    placeholder=ANTHROPIC_API_URL
+                    prop:value=move || with!(|settings| settings.anthropic.base_url.clone())
+                    on:change=move |event| {
+                        let base_url = non_empty(event_target_value(&event).trim().to_owned())
+                            .unwrap_or_else(|| ANTHROPIC_API_URL.to_owned());
+                        update_settings.update(move |settings| settings.anthropic.base_url = base_url);
+                    }
+                />
+                <label for="backends_anthropic_base_url">"Base URL"</label>
+                <div class="form-text">"Only change this if you use a proxy."</div>
+            </div>
+            <h5>"OpenRouter"</h5>
+            <div class="form-text mb-2">"Models with the OpenRouter backend are sent to "<a href="https://openrouter.ai" target="_blank">"OpenRouter"</a>", which routes them to many model providers."</div>
+            <div class="form-floating mb-3">
+                <CredentialSelect
+                    id="backends_openrouter_credential"
+                    value=Signal::derive(move || with!(|settings| settings.backend_credentials.get(&ModelBackend::OpenRouter).cloned()))
+                    on_change=move |name| update_settings.update(move |settings| set_backend_credential(settings, ModelBackend::OpenRouter, name))
+                    none_label="None"
+                />
+                <label for="backends_openrouter_credential">"API key"</label>
+                <div class="form-text">"API keys are added in the Credentials tab."</div>
+            </div>
+            <OpenRouterModels />
+            <h5>"Text generation inference"</h5>
+            <div class="form-text mb-2">"Models with the self-hosted backend are sent to your own "<a href="https://github.com/huggingface/text-generation-inference" target="_blank">"text-generation-inference"</a>" server, e.g. one that runs locally with docker."</div>
+            <div class="form-floating mb-3">
+                <input
+                    type="url"
+                    class="form-control"
+                    id="backends_tgi_url"
+                    placeholder=DEFAULT_TGI_URL
+                    prop:value=move || with!(|settings| settings.tgi.url.clone())
+                    on:change=move |event| {
+                        let url = non_empty(event_target_value(&event).trim().to_owned())
+                            .unwrap_or_else(|| DEFAULT_TGI_URL.to_owned());
+                        update_settings.update(move |settings| settings.tgi.url = url);
+                    }
+                />
+                <label for="backends_tgi_url">"Server URL"</label>
+                <div class="form-text">"The server has to allow requests from this page with its "<code>"--cors-allow-origin"</code>" option."</div>
+            </div>
+            <div class="form-floating mb-3">
+                <input
+                    type="password"
+                    class="form-control"
+                    id="backends_tgi_authorization"
+                    placeholder="Bearer ..."
+                    prop:value=move || with!(|settings| settings.tgi.authorization.clone().unwrap_or_default())
+                    on:change=move |event| {
+                        let authorization = non_empty(event_target_value(&event).trim().to_owned());
+                        update_settings.update(move |settings| settings.tgi.authorization = authorization);
+                    }
+                />
+                <label for="backends_tgi_authorization">"Authorization header"</label>
+                <div class="form-text">"Only needed if the server is behind a proxy that checks it. If it isn't set, the backend's credential from the Credentials tab is sent as a bearer token."</div>
+            </div>
+            <LocalModelsSettings />
+        </div>
     }
+}
 
-    let selected_model = create_rw_signal(SelectedModel::New);
-    let model_name_invalid = create_rw_signal(true);
-    let model_id_state = create_rw_signal(ModelIdState::default());
-    let model_search_results = create_rw_signal(vec![]);
-    let model_name_input_field = create_node_ref::<Input>();
-    let model_id_input_field = create_node_ref::<Input>();
-    let model_chat_template_input_field = create_node_ref::<Select>();
-    let model_stream_input_field = create_node_ref::<Input>();
-    let changes_saved = create_rw_signal(false);
-
-    let check_model = {
-        let api = settings.with_untracked(|settings| settings.api());
+/// How many OpenRouter models are listed at once. The filter narrows it down.
+const MAX_LISTED_OPENROUTER_MODELS: usize = 50;
 
-        move |model_id: ModelId| {
-            async move {
-                model_id_state.set(ModelIdState::Checking);
+/// Lists the models on OpenRouter, so that they can be added with one click.
+#[component]
+fn OpenRouterModels() -> impl IntoView {
+    let Context {
+        errors,
+        settings,
+        update_settings,
+        ..
+    } = expect_context();
+
+    let models = create_rw_signal(None::<Vec<OpenRouterModel>>);
+    let loading = create_rw_signal(false);
+    let filter = create_rw_signal(String::new());
+
+    let load_models = move |_| {
+        loading.set(true);
+        let openrouter = settings.with_untracked(|settings| {
+            OpenRouter::new(
+                settings.credential(ModelBackend::OpenRouter, None),
+                &settings.connection(ModelBackend::OpenRouter),
+                settings.max_concurrent_requests,
+            )
+        });
+        spawn_local(async move {
+            match openrouter.models().await {
+                Ok(loaded) => models.set(Some(loaded)),
+                Err(error) => errors.push(Error::from(error)),
+            }
+            loading.set(false);
+        });
+    };
+
+    let add_model = move |model: OpenRouterModel| {
+        let model_id = ModelId(model.id.clone());
+        let vision = model.vision();
+        update_settings.update(move |settings| {
+            settings.models.entry(model_id.clone()).or_insert_with(|| {
+                Model {
+                    model_id,
+                    name: Some(model.name),
+                    chat_template: ChatTemplate::default(),
+                    stream: true,
+                    context_length: model.context_length,
+                    default_parameters: Default::default(),
+                    metadata: None,
+                    favorite: false,
+                    position: None,
+                    vision,
+                    reasoning: None,
+                    tools: false,
+                    backend: ModelBackend::OpenRouter,
+                    fallbacks: vec![],
+                    credential: None,
+                }
+            });
+        });
+    };
+
+    let filtered_models = create_memo(move |_| {
+        with!(|models, filter| {
+            let filter = filter.trim().to_lowercase();
+            models.as_ref().map(|models| {
+                models
+                    .iter()
+                    .filter(|model| {
+                        model.id.to_lowercase().contains(&filter)
+                            || model.name.to_lowercase().contains(&filter)
+                    })
+                    .take(MAX_LISTED_OPENROUTER_MODELS)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+        })
+    });
+
+    view! {
+        {move || with!(|models| models.is_none()).then(|| view! {
+            <div class="mb-3">
+                <button type="button" class="btn btn-outline-secondary btn-sm" disabled=loading on:click=load_models>
+                    {move || loading.get().then(|| view!{ <div class="spinner-border spinner-border-sm me-1" role="status"></div> })}
+                    "Browse models"
+                </button>
+            </div>
+        })}
+        {move || with!(|models| models.is_some()).then(|| view! {
+            <div class="mb-3">
+                <input
+                    type="search"
+                    class="form-control form-control-sm mb-2"
+                    placeholder="Filter models"
+                    prop:value=filter
+                    on:input=move |event| filter.set(event_target_value(&event))
+                />
+                <div class="list-group overflow-y-scroll" style="max-height: 20em">
+                    {move || filtered_models.get().unwrap_or_default().into_iter().map(|model| {
+                        let added = {
+                            let model_id = ModelId(model.id.clone());
+                            Signal::derive(move || with!(|settings| settings.models.contains_key(&model_id)))
+                        };
+                        let context_length = model.context_length.map(|context_length| format!("{context_length} tokens"));
+                        let vision = model.vision();
+                        let (name, id, description) = (model.name.clone(), model.id.clone(), model.description.clone());
+                        view! {
+                            <div class="list-group-item d-flex flex-row align-items-center" title=description>
+                                <div class="me-auto text-truncate">
+                                    <div class="text-truncate">{name}</div>
+                                    <small class="text-body-secondary">
+                                        <code>{id}</code>
+                                        {context_length.map(|context_length| view!{ <span class="ms-2">{context_length}</span> })}
+                                        {vision.then(|| view!{ <span class="ms-2" title="Accepts images"><BootstrapIcon icon="image" /></span> })}
+                                    </small>
+                                </div>
+                                {move || if added.get() {
+                                    view!{ <span class="text-success" title="Added"><BootstrapIcon icon="check-circle" /></span> }.into_view()
+                                }
+                                else {
+                                    let model = model.clone();
+                                    view!{
+                                        <button type="button" class="btn btn-outline-primary btn-sm" title="Add to your models" on:click=move |_| add_model(model.clone())>
+                                            <BootstrapIcon icon="plus-circle" />
+                                        </button>
+                                    }.into_view()
+                                }}
+                            </div>
+                        }
+                    }).collect_view()}
+                </div>
+            </div>
+        })}
+    }
+}
+
+/// Lists the models that are downloaded for running in the browser, so that
+/// they can be deleted.
+#[component]
+fn LocalModelsSettings() -> impl IntoView {
+    let Context { errors, .. } = expect_context();
+
+    // listing them loads WebLLM, so this is only done when asked for.
+    let cached_models = create_rw_signal(None::<Vec<ModelId>>);
+    let loading = create_rw_signal(false);
+
+    let refresh = move || {
+        loading.set(true);
+        spawn_local(async move {
+            match local::cached_models().await {
+                Ok(models) => cached_models.set(Some(models)),
+                Err(error) => errors.push(Error::from(error)),
+            }
+            loading.set(false);
+        });
+    };
+
+    let delete = move |model_id: ModelId| {
+        spawn_local(async move {
+            match local::delete_from_cache(&model_id).await {
+                Ok(()) => {
+                    cached_models.update(|models| {
+                        if let Some(models) = models {
+                            models.retain(|cached| *cached != model_id);
+                        }
+                    })
+                }
+                Err(error) => errors.push(Error::from(error)),
+            }
+        });
+    };
+
+    view! {
+        <h5>"Models in the browser"</h5>
+        <div class="form-text mb-2">
+            "Models with the \"In the browser\" backend run on your GPU with WebLLM, and work without any API access. Their IDs are the ones of "
+            <a href="https://github.com/mlc-ai/web-llm#built-in-models" target="_blank">"WebLLM's prebuilt models"</a>
+            ", e.g. Llama-3.2-1B-Instruct-q4f16_1-MLC. They're downloaded when they're first used, and stored in the browser."
+        </div>
+        {(!local::has_webgpu()).then(|| view! {
+            <div class="alert alert-warning" role="alert">
+                <span class="me-2"><BootstrapIcon icon="exclamation-triangle" /></span>
+                "This browser doesn't support WebGPU, so models can't run in it."
+            </div>
+        })}
+        {move || {
+            match cached_models.get() {
+                None => view! {
+                    <div class="mb-3">
+                        <button type="button" class="btn btn-outline-secondary btn-sm" disabled=loading on:click=move |_| refresh()>
+                            "Show downloaded models"
+                        </button>
+                    </div>
+                }.into_view(),
+                Some(models) if models.is_empty() => view! {
+                    <div class="mb-3 text-body-secondary">"No models are downloaded."</div>
+                }.into_view(),
+                Some(models) => view! {
+                    <ul class="list-group mb-3">
+                        {models.into_iter().map(|model_id| view! {
+                            <li class="list-group-item d-flex flex-row align-items-center">
+                                <span class="me-auto">{model_id.to_string()}</span>
+                                <button type="button" class="btn btn-outline-danger btn-sm" title="Delete the downloaded model" on:click={
+                                    let model_id = model_id.clone();
+                                    move |_| delete(model_id.clone())
+                                }>
+                                    <BootstrapIcon icon="trash" />
+                                </button>
+                            </li>
+                        }).collect_view()}
+                    </ul>
+                }.into_view(),
+            }
+        }}
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumMessage)]
+pub enum ModelIdInvalidReason {
+    #[strum(message = "Enter a Hugging Face model ID")]
+    Empty,
+    #[strum(message = "This model already exists")]
+    AlreadyExists,
+    #[strum(message = "Model not found")]
+    NotFound,
+    #[strum(message = "Model not available in the free API")]
+    NotLoadable,
+    #[strum(message = "Inference test failed")]
+    InferenceFailed,
+}
+
+#[derive(Copy, Clone, Debug, EnumIs)]
+pub enum ModelIdState {
+    Checking,
+    Valid,
+    Invalid { reason: ModelIdInvalidReason },
+}
+
+impl Default for ModelIdState {
+    fn default() -> Self {
+        Self::Invalid {
+            reason: ModelIdInvalidReason::Empty,
+        }
+    }
+}
+
+impl ModelIdState {
+    /// Whether the model ID can be saved, even if the model might not work.
+    pub fn is_valid_model_id(&self) -> bool {
+        match self {
+            Self::Checking | Self::Valid => true,
+            Self::Invalid { reason } => {
+                match reason {
+                    ModelIdInvalidReason::AlreadyExists
+                    | ModelIdInvalidReason::NotLoadable
+                    | ModelIdInvalidReason::InferenceFailed => true,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+/// Searches the hub for a model ID. Returns a few matching IDs, and whether
+/// the ID itself was found.
+pub async fn search_model_id(
+    api: &hf_textgen::Api,
+    model_id: &ModelId,
+) -> Result<(Vec<String>, bool), Error> {
+    let search_results = api.quick_search(&model_id.0, Some(5)).await?;
+    let exact_match = search_results.models.iter().any(|id| *id == model_id.0);
+    Ok((search_results.models, exact_match))
+}
+
+/// Checks with the status endpoint whether the model is loadable, and then
+/// tests inference, since some models are loadable in theory, but always fail.
+pub async fn test_model(api: &hf_textgen::Api, model_id: &ModelId) -> ModelIdState {
+    let mut model = api.text_generation(&model_id.0);
+
+    let is_loadable = model
+        .status()
+        .await
+        .map_err(|error| log::error!("model status failed: {error}"))
+        .ok()
+        .map(|status| status.state == ModelState::Loadable)
+        .unwrap_or_default();
+    if !is_loadable {
+        return ModelIdState::Invalid {
+            reason: ModelIdInvalidReason::NotLoadable,
+        };
+    }
+
+    log::debug!("testing inference...");
+    model.max_new_tokens = Some(10);
+    let inference_worked = model
+        .generate("Hello!")
+        .await
+        .map_err(|error| log::debug!("inference error: {error}"))
+        .is_ok();
+    log::debug!("inference_worked = {inference_worked:?}");
+
+    if inference_worked {
+        ModelIdState::Valid
+    }
+    else {
+        ModelIdState::Invalid {
+            reason: ModelIdInvalidReason::InferenceFailed,
+        }
+    }
+}
+
+#[component]
+fn ModelsTab() -> impl IntoView {
+    let Context {
+        errors,
+        settings,
+        update_settings,
+        toasts,
+        ..
+    } = expect_context();
+
+    #[derive(Debug, EnumIs)]
+    enum SelectedModel {
+        Edit(ModelId),
+        New,
+    }
+
+    impl SelectedModel {
+        fn is_model(&self, model_id: &ModelId) -> bool {
+            match self {
+                Self::Edit(id) => id == model_id,
+                _ => false,
+            }
+        }
+
+        fn get_model_id(&self) -> Option<&ModelId> {
+            match self {
+                SelectedModel::Edit(model_id) => Some(model_id),
+                SelectedModel::New => None,
+            }
+        }
+    }
+
+    let selected_model = create_rw_signal(SelectedModel::New);
+    let model_name_invalid = create_rw_signal(true);
+    let model_id_state = create_rw_signal(ModelIdState::default());
+    let model_search_results = create_rw_signal(vec![]);
+    let model_name_input_field = create_node_ref::<Input>();
+    let model_id_input_field = create_node_ref::<Input>();
+    let model_chat_template_input_field = create_node_ref::<Select>();
+    let model_backend = create_rw_signal(ModelBackend::default());
+    let model_credential = create_rw_signal(None::<String>);
+    let model_stream_input_field = create_node_ref::<Input>();
+    let model_vision_input_field = create_node_ref::<Input>();
+    let model_tools_input_field = create_node_ref::<Input>();
+    let model_context_length_input_field = create_node_ref::<Input>();
+    let model_context_length_invalid = create_rw_signal(false);
+    let model_position_input_field = create_node_ref::<Input>();
+    let model_reasoning_start_input_field = create_node_ref::<Input>();
+    let model_reasoning_end_input_field = create_node_ref::<Input>();
+    let model_fallbacks_input_field = create_node_ref::<Textarea>();
+    let model_position_invalid = create_rw_signal(false);
+    let model_default_parameters = create_rw_signal(ConversationParameters::default());
+    let model_metadata = create_rw_signal(None::<ModelMetadata>);
+    let changes_saved = create_rw_signal(false);
+    let test_prompt = create_rw_signal(String::new());
+    let test_raw_prompt = create_rw_signal(None::<String>);
+    let test_response = create_rw_signal(None::<Result<String, String>>);
+    let testing = create_rw_signal(false);
+
+    // models from the curated list that the user doesn't have yet
+    let recommended_models = create_rw_signal(None::<Vec<Model>>);
+    let checking_recommended_models = create_rw_signal(false);
+
+    let check_recommended_models = move |_| {
+        checking_recommended_models.set(true);
+        let config_url = settings.with_untracked(|settings| settings.config_url.clone());
+
+        spawn_local(async move {
+            let result = fetch_recommended_models(config_url.as_deref()).await;
+            checking_recommended_models.set(false);
+
+            match result {
+                Ok(models) => {
+                    let new_models = settings.with_untracked(|settings| {
+                        models
+                            .into_iter()
+                            .filter(|model| !settings.models.contains_key(&model.model_id))
+                            .collect()
+                    });
+                    recommended_models.set(Some(new_models));
+                }
+                Err(error) => errors.push(Error::from(error)),
+            }
+        });
+    };
+
+    // never overwrites a model that the user already has.
+    let add_recommended_models = move |only: Option<ModelId>| {
+        let Some(models) = recommended_models
+            .try_update(|recommended_models| {
+                let recommended_models = recommended_models.as_mut()?;
+                let (added, kept) =
+                    std::mem::take(recommended_models)
+                        .into_iter()
+                        .partition(|model: &Model| {
+                            only.as_ref()
+                                .map_or(true, |model_id| *model_id == model.model_id)
+                        });
+                *recommended_models = kept;
+                Some(added)
+            })
+            .flatten()
+        else {
+            return;
+        };
+
+        update_settings.update(move |settings| {
+            for model in models {
+                settings
+                    .models
+                    .entry(model.model_id.clone())
+                    .or_insert(model);
+            }
+        });
+    };
+
+    let show_model_transfer = create_rw_signal(false);
+    let model_transfer_text = create_rw_signal(String::new());
+
+    let export_all_models = move |format| {
+        let result = settings.with_untracked(|settings| {
+            export_models(settings.sorted_models(), format)
+        });
+        match result {
+            Ok(text) => model_transfer_text.set(text),
+            Err(error) => errors.push(Error::from(error)),
+        }
+    };
+
+    // imported models replace the ones with the same ID, like in the config.
+    let import_pasted_models = move |_| {
+        match with!(|model_transfer_text| import_models(model_transfer_text)) {
+            Ok(models) => {
+                let count = models.len();
+                update_settings.update(move |settings| {
+                    for model in models {
+                        settings.models.insert(model.model_id.clone(), model);
+                    }
+                });
+                model_transfer_text.set(String::new());
+                toasts.success(format!("Imported {count} models"));
+            }
+            Err(error) => errors.push(Error::from(error)),
+        }
+    };
+
+    let check_model = {
+        let api = settings.with_untracked(|settings| settings.api());
+
+        move |model_id: ModelId| {
+            async move {
+                model_id_state.set(ModelIdState::Checking);
 
                 // check if this is the model that we're editing
 
@@ -302,46 +1539,29 @@ fn ModelsTab() -> impl IntoView {
                     return Ok(());
                 }
 
-                // check the status endpoint for whether the model is loadable
-
-                let mut model = api.text_generation(&model_id.0);
-
-                let is_loadable = model
-                    .status()
-                    .await
-                    .map_err(|error| log::error!("model status failed: {error}"))
-                    .ok()
-                    .map(|status| status.state == ModelState::Loadable)
-                    .unwrap_or_default();
-
-                if is_loadable {
-                    // some models are in theory loadable but will always fail to do inference. so
-                    // we check if we can do inference
+                // fetch the metadata that is saved with the model
 
-                    log::debug!("testing inference...");
-                    model.max_new_tokens = Some(10);
-                    let inference_worked = model
-                        .generate("Hello!")
-                        .await
-                        .map_err(|error| log::debug!("inference error: {error}"))
-                        .is_ok();
-                    log::debug!("inference_worked = {inference_worked:?}");
-
-                    if inference_worked {
-                        // yay!
-                        model_id_state.set(ModelIdState::Valid);
+                let hf_token = settings.with_untracked(|settings| settings.hf_token());
+                match fetch_model_metadata(&model_id, hf_token.as_deref()).await {
+                    Ok(metadata) => {
+                        // prefill the context length, unless the user already entered one
+                        let field = model_context_length_input_field.get_untracked().unwrap();
+                        if let Some(context_length) = metadata.context_length {
+                            if field.value().is_empty() {
+                                field.set_value(&context_length.to_string());
+                            }
+                        }
+                        model_metadata.set(Some(metadata));
                     }
-                    else {
-                        model_id_state.set(ModelIdState::Invalid {
-                            reason: ModelIdInvalidReason::InferenceFailed,
-                        });
+                    Err(error) => {
+                        log::error!("failed to fetch model metadata: {error}");
+                        model_metadata.set(None);
                     }
                 }
-                else {
-                    model_id_state.set(ModelIdState::Invalid {
-                        reason: ModelIdInvalidReason::NotLoadable,
-                    });
-                }
+
+                // check whether the model can actually be used
+
+                model_id_state.set(test_model(&api, &model_id).await);
 
                 Ok::<(), Error>(())
             }
@@ -367,13 +1587,8 @@ fn ModelsTab() -> impl IntoView {
             spawn_local(
                 async move {
                     let api = settings.with_untracked(|settings| settings.api());
-                    let search_results = api.quick_search(&model_id.0, Some(5)).await?;
+                    let (search_results, exact_match) = search_model_id(&api, &model_id).await?;
 
-                    let exact_match = search_results
-                        .models
-                        .iter()
-                        .find(|id| **id == model_id.0)
-                        .is_some();
                     if exact_match {
                         check_model(model_id).await?;
                     }
@@ -383,7 +1598,7 @@ fn ModelsTab() -> impl IntoView {
                         });
                     }
 
-                    model_search_results.set(search_results.models);
+                    model_search_results.set(search_results);
 
                     Ok::<(), Error>(())
                 }
@@ -432,6 +1647,16 @@ fn ModelsTab() -> impl IntoView {
                 })
         })
     });
+    create_effect(move |_| {
+        let (backend, credential) = with!(|selected_model_data| {
+            selected_model_data
+                .as_ref()
+                .map(|model| (model.backend, model.credential.clone()))
+                .unwrap_or_default()
+        });
+        model_backend.set(backend);
+        model_credential.set(credential);
+    });
 
     let select_model = move |model| {
         match model {
@@ -445,9 +1670,35 @@ fn ModelsTab() -> impl IntoView {
             }
         }
         changes_saved.set(false);
+        let (default_parameters, metadata) = model
+            .get_model_id()
+            .and_then(|model_id| {
+                settings.with_untracked(|settings| {
+                    settings
+                        .models
+                        .get(model_id)
+                        .map(|model| (model.default_parameters.clone(), model.metadata.clone()))
+                })
+            })
+            .unwrap_or_default();
+        model_default_parameters.set(default_parameters);
+        model_metadata.set(metadata);
+        test_raw_prompt.set(None);
+        test_response.set(None);
         selected_model.set(model);
     };
 
+    fn update_default_parameter<T>(
+        parameters: RwSignal<ConversationParameters>,
+        changes_saved: RwSignal<bool>,
+        update: impl Fn(&mut ConversationParameters, T) + Copy,
+    ) -> impl Fn(T) + Copy {
+        move |value| {
+            parameters.update(move |parameters| update(parameters, value));
+            changes_saved.set(false);
+        }
+    }
+
     let delete_selected_model = move |_| {
         selected_model.try_update(|selected_model| {
             let selected_model = std::mem::replace(selected_model, SelectedModel::New);
@@ -477,13 +1728,61 @@ fn ModelsTab() -> impl IntoView {
             .value()
             .parse::<ChatTemplate>()
             .unwrap();
+        let backend = model_backend.get_untracked();
+        let credential = model_credential.get_untracked();
         let stream = model_stream_input_field.get_untracked().unwrap().checked();
+        let vision = model_vision_input_field.get_untracked().unwrap().checked();
+        let tools = model_tools_input_field.get_untracked().unwrap().checked();
+        let context_length = non_empty(
+            model_context_length_input_field
+                .get_untracked()
+                .unwrap()
+                .value(),
+        )
+        .and_then(|value| value.parse().ok());
+        let position = non_empty(model_position_input_field.get_untracked().unwrap().value())
+            .and_then(|value| value.parse().ok());
+        let reasoning = non_empty(
+            model_reasoning_start_input_field
+                .get_untracked()
+                .unwrap()
+                .value(),
+        )
+        .zip(non_empty(
+            model_reasoning_end_input_field
+                .get_untracked()
+                .unwrap()
+                .value(),
+        ))
+        .map(|(start, end)| ReasoningDelimiters { start, end });
+        let fallbacks =
+            parse_fallbacks(&model_fallbacks_input_field.get_untracked().unwrap().value());
+        // toggled in the model list, not in the form
+        let favorite = old_model_id.as_ref().map_or(false, |model_id| {
+            settings.with_untracked(|settings| {
+                settings
+                    .models
+                    .get(model_id)
+                    .map_or(false, |model| model.favorite)
+            })
+        });
 
         let model = Model {
             model_id: new_model_id.clone(),
             name: Some(name),
             chat_template,
             stream,
+            vision,
+            tools,
+            backend,
+            credential,
+            fallbacks,
+            reasoning,
+            context_length,
+            default_parameters: model_default_parameters.get_untracked(),
+            metadata: model_metadata.get_untracked(),
+            favorite,
+            position,
         };
         log::debug!("{model:#?}");
 
@@ -499,6 +1798,64 @@ fn ModelsTab() -> impl IntoView {
         changes_saved.set(true);
     };
 
+    // runs the prompt with the values in the form, so they can be tried before
+    // they're saved.
+    let run_model_test = move |_| {
+        let Some(model_id) = non_empty(model_id_input_field.get_untracked().unwrap().value())
+            .map(ModelId)
+        else {
+            return;
+        };
+        let chat_template = model_chat_template_input_field
+            .get_untracked()
+            .unwrap()
+            .value()
+            .parse::<ChatTemplate>()
+            .unwrap_or_default();
+        let backend = model_backend.get_untracked();
+        let conversation_parameters = model_default_parameters.get_untracked();
+
+        let message = Message {
+            id: MessageId::new(),
+            role: Role::User,
+            text: test_prompt.get_untracked(),
+            timestamp: Utc::now(),
+            generation: None,
+            images: vec![],
+            tool: None,
+            status: MessageStatus::Complete,
+            note: None,
+            rating: None,
+            candidates: vec![],
+        };
+        let system_prompt = parameters::system_prompt(&conversation_parameters);
+        let prompt = chat_template.generate_prompt(
+            system_prompt.as_deref(),
+            &[message],
+            conversation_parameters.start_response_with.as_deref(),
+        );
+
+        let model = settings.with_untracked(|settings| {
+            settings.text_generation_on(
+                backend,
+                &model_id,
+                parameters::GenerationParameters::resolve(&conversation_parameters, 1, false),
+            )
+        });
+
+        test_raw_prompt.set(Some(prompt.clone()));
+        test_response.set(None);
+        testing.set(true);
+        spawn_local(async move {
+            let response = model
+                .generate(&prompt)
+                .await
+                .map_err(|error| error.to_string());
+            test_response.set(Some(response));
+            testing.set(false);
+        });
+    };
+
     view! {
         // delete model modal
         <div class="modal fade" id="settings_delete_model_modal" tabindex="-1">
@@ -546,23 +1903,126 @@ fn ModelsTab() -> impl IntoView {
                         <span class="me-1"><BootstrapIcon icon="plus-circle-fill" /></span>
                         "New model"
                     </button>
+                    <button
+                        type="button"
+                        class="btn btn-sm btn-outline-secondary mb-2 w-100"
+                        disabled=checking_recommended_models
+                        on:click=check_recommended_models
+                    >
+                        {move || if checking_recommended_models.get() {
+                            view!{ <div class="spinner-border spinner-border-sm me-1" role="status"></div> }.into_view()
+                        }
+                        else {
+                            view!{ <span class="me-1"><BootstrapIcon icon="cloud-download" /></span> }.into_view()
+                        }}
+                        "Check for new recommended models"
+                    </button>
+                    {move || recommended_models.get().map(|models| {
+                        if models.is_empty() {
+                            view!{
+                                <small class="d-block text-body-secondary mb-2">"You already have all recommended models."</small>
+                            }.into_view()
+                        }
+                        else {
+                            view!{
+                                <div class="list-group mb-2">
+                                    {models.into_iter().map(|model| {
+                                        let name = model.display_name().to_owned();
+                                        let model_id = model.model_id;
+                                        view!{
+                                            <button
+                                                type="button"
+                                                class="list-group-item list-group-item-action d-flex flex-row align-items-center"
+                                                title=format!("Add {model_id}")
+                                                on:click=move |_| add_recommended_models(Some(model_id.clone()))
+                                            >
+                                                <span class="text-truncate">{name}</span>
+                                                <span class="ms-auto"><BootstrapIcon icon="plus-circle" /></span>
+                                            </button>
+                                        }
+                                    }).collect_view()}
+                                    <button
+                                        type="button"
+                                        class="list-group-item list-group-item-action list-group-item-secondary"
+                                        on:click=move |_| add_recommended_models(None)
+                                    >
+                                        "Add all"
+                                    </button>
+                                </div>
+                            }.into_view()
+                        }
+                    })}
+                    <button
+                        type="button"
+                        class="btn btn-sm btn-outline-secondary mb-2 w-100"
+                        on:click=move |_| show_model_transfer.update(|show| *show = !*show)
+                    >
+                        <span class="me-1"><BootstrapIcon icon="arrow-down-up" /></span>
+                        "Import/export models"
+                    </button>
+                    {move || show_model_transfer.get().then(|| view!{
+                        <div class="mb-2">
+                            <textarea
+                                class="form-control font-monospace small mb-1"
+                                style="height: 12em"
+                                placeholder="[[model]]\nmodel_id = \"...\""
+                                aria-label="Model definitions"
+                                prop:value=model_transfer_text
+                                on:input=move |event| model_transfer_text.set(event_target_value(&event))
+                            ></textarea>
+                            <div class="btn-group btn-group-sm w-100 mb-1">
+                                <button type="button" class="btn btn-outline-secondary" on:click=move |_| export_all_models(ModelsFormat::Toml)>
+                                    "Export TOML"
+                                </button>
+                                <button type="button" class="btn btn-outline-secondary" on:click=move |_| export_all_models(ModelsFormat::Json)>
+                                    "Export JSON"
+                                </button>
+                                <button
+                                    type="button"
+                                    class="btn btn-outline-primary"
+                                    disabled=move || with!(|model_transfer_text| model_transfer_text.trim().is_empty())
+                                    on:click=import_pasted_models
+                                >
+                                    "Import"
+                                </button>
+                            </div>
+                            <div class="form-text">"The same [[model]] tables as in config.toml. Imported models replace the ones with the same ID. Credentials aren't exported."</div>
+                        </div>
+                    })}
                 </div>
                 <div class="flex-grow-1">
                     <div class="overflow-y-scroll h-75 pe-1 w-100">
                         <div class="list-group w-100">
                             <For
-                                each=move || {
-                                    let mut items = with!(|settings| settings.models.iter().map(|(id, model)| (id.clone(), model.display_name().to_lowercase())).collect::<Vec<_>>());
-                                    items.sort_by_cached_key(|(_, display_name)| display_name.clone());
-                                    items
-                                }
-                                key=|(id, _)| id.clone()
-                                children=move |(id, _)| {
+                                each=move || with!(|settings| settings.sorted_models().into_iter().map(|model| model.model_id.clone()).collect::<Vec<_>>())
+                                key=|id| id.clone()
+                                children=move |id| {
                                     let id2 = id.clone();
+                                    let capabilities = {
+                                        let id = id.clone();
+                                        move || with!(|settings| settings.models.get(&id).map(|model| model.capabilities()))
+                                    };
+                                    let favorite = {
+                                        let id = id.clone();
+                                        Signal::derive(move || with!(|settings| settings.models.get(&id).map_or(false, |model| model.favorite)))
+                                    };
+                                    let toggle_favorite = {
+                                        let id = id.clone();
+                                        move |event: MouseEvent| {
+                                            // don't select the model
+                                            event.stop_propagation();
+                                            let id = id.clone();
+                                            update_settings.update(move |settings| {
+                                                if let Some(model) = settings.models.get_mut(&id) {
+                                                    model.favorite = !model.favorite;
+                                                }
+                                            });
+                                        }
+                                    };
                                     view!{
                                         <button
                                             type="button"
-                                            class="list-group-item list-group-item-action text-truncate"
+                                            class="list-group-item list-group-item-action d-flex flex-row align-items-center"
                                             class:active=move || {
                                                 let id = id2.clone();
                                                 with!(|selected_model| selected_model.is_model(&id))
@@ -572,9 +2032,23 @@ fn ModelsTab() -> impl IntoView {
                                                 move |_| select_model(SelectedModel::Edit(id.clone()))
                                             }
                                         >
-                                            {move || {
-                                                with!(|settings| settings.models.get(&id).map(|model| model.display_name().to_owned()))
-                                            }}
+                                            <span class="text-truncate">
+                                                {move || {
+                                                    with!(|settings| settings.models.get(&id).map(|model| model.display_name().to_owned()))
+                                                }}
+                                            </span>
+                                            <span class="text-nowrap">
+                                                {move || capabilities().map(|capabilities| view!{ <CapabilityBadges capabilities /> })}
+                                            </span>
+                                            <span
+                                                class="ms-auto ps-1"
+                                                class:text-warning=favorite
+                                                role="button"
+                                                title=move || if favorite.get() { "Remove from favorites" } else { "Add to favorites" }
+                                                on:click=toggle_favorite
+                                            >
+                                                {move || view!{ <BootstrapIcon icon=if favorite.get() { "star-fill" } else { "star" } /> }}
+                                            </span>
                                         </button>
                                     }
                                 }
@@ -719,25 +2193,127 @@ fn ModelsTab() -> impl IntoView {
                             }
                         })
                     }}
+                    {move || model_metadata.get().map(|metadata| {
+                        view!{
+                            <div class="d-flex flex-row flex-wrap gap-1 mt-1">
+                                {metadata.pipeline_tag.map(|pipeline_tag| view!{
+                                    <span class="badge text-bg-light border">{pipeline_tag}</span>
+                                })}
+                                {metadata.license.map(|license| view!{
+                                    <span class="badge text-bg-light border">
+                                        <span class="me-1"><BootstrapIcon icon="file-earmark-text" /></span>
+                                        {license}
+                                    </span>
+                                })}
+                                {metadata.gated.then(|| view!{
+                                    <span class="badge text-bg-warning" title="You need to request access on the model page">
+                                        <span class="me-1"><BootstrapIcon icon="lock" /></span>
+                                        "Gated"
+                                    </span>
+                                })}
+                                {metadata.private.then(|| view!{
+                                    <span class="badge text-bg-secondary">
+                                        <span class="me-1"><BootstrapIcon icon="eye-slash" /></span>
+                                        "Private"
+                                    </span>
+                                })}
+                            </div>
+                        }
+                    })}
                 </div>
 
-                // chat template input
+                // backend input
                 <div class="form-floating mb-3">
                     <select
                         class="form-select"
-                        id="model_chat_template_select"
-                        node_ref=model_chat_template_input_field
-                        aria-label="Select chat template"
-                        on:input=move |_| changes_saved.set(false)
+                        id="model_backend_select"
+                        aria-label="Select backend"
+                        on:input=move |event| {
+                            if let Ok(backend) = event_target_value(&event).parse::<ModelBackend>() {
+                                model_backend.set(backend);
+                            }
+                            changes_saved.set(false);
+                        }
                     >
-                        <For
-                            each=move || { <ChatTemplate as VariantArray>::VARIANTS.into_iter() }
-                            key=|chat_template| *chat_template
-                            children=move |chat_template| view!{
-                                <option
-                                    value={chat_template.as_ref()}
-                                    selected=move || {
-                                        *chat_template == with!(|selected_model_data| {
+                        {<ModelBackend as VariantArray>::VARIANTS.iter().map(|backend| view!{
+                            <option
+                                value={backend.as_ref()}
+                                selected=move || *backend == model_backend.get()
+                            >
+                                {backend.get_message()}
+                            </option>
+                        }).collect_view()}
+                    </select>
+                    <label for="model_backend_select">"Where the model runs"</label>
+                    {move || {
+                        match model_backend.get() {
+                            ModelBackend::HuggingFace => None,
+                            ModelBackend::Local => Some("The model ID is the one of a WebLLM prebuilt model. It's downloaded the first time it's used. Downloaded models can be deleted in the Backends tab."),
+                            ModelBackend::Anthropic => Some("The model ID is the name of the Claude model. The chat template isn't used."),
+                            ModelBackend::OpenRouter => Some("The model ID is the one on OpenRouter, e.g. meta-llama/llama-3.1-70b-instruct. The chat template isn't used. Models can be browsed and added in the Backends tab."),
+                            ModelBackend::Tgi => Some("The server serves a single model, so the model ID is only used to tell them apart. The server's URL is set in the Backends tab."),
+                        }
+                        .map(|text| view!{ <div class="form-text">{text}</div> })
+                    }}
+                </div>
+
+                // credential input
+                {move || (model_backend.get() != ModelBackend::Local).then(|| view!{
+                    <div class="form-floating mb-3">
+                        <CredentialSelect
+                            id="model_credential_select"
+                            value=model_credential
+                            on_change=move |credential| {
+                                model_credential.set(credential);
+                                changes_saved.set(false);
+                            }
+                            none_label="The backend's credential"
+                        />
+                        <label for="model_credential_select">"Credential"</label>
+                        <div class="form-text">"Credentials are added in the Credentials tab."</div>
+                    </div>
+                })}
+
+                // fallbacks input
+                <div class="form-floating mb-3">
+                    <textarea
+                        class="form-control font-monospace"
+                        id="model_fallbacks_input"
+                        style="height: 6em"
+                        placeholder="OpenRouter meta-llama/llama-3.1-8b-instruct"
+                        node_ref=model_fallbacks_input_field
+                        prop:value=move || with!(|selected_model_data| {
+                            selected_model_data.as_ref()
+                                .map(|model| format_fallbacks(&model.fallbacks))
+                                .unwrap_or_default()
+                        })
+                        on:input=move |_| changes_saved.set(false)
+                    ></textarea>
+                    <label for="model_fallbacks_input">"Fallbacks (optional)"</label>
+                    <div class="form-text">
+                        "If the backend is rate-limited or unavailable, these are tried in order. One per line: the backend ("
+                        {<ModelBackend as VariantArray>::VARIANTS.iter().map(|backend| backend.as_ref()).collect::<Vec<_>>().join(", ")}
+                        "), and the model's ID on it, if it's a different one."
+                    </div>
+                </div>
+
+                // chat template input
+                <div class="form-floating mb-3">
+                    <select
+                        class="form-select"
+                        id="model_chat_template_select"
+                        node_ref=model_chat_template_input_field
+                        aria-label="Select chat template"
+                        on:input=move |_| changes_saved.set(false)
+                    >
+                        <For
+                            each=move || { <ChatTemplate as VariantArray>::VARIANTS.into_iter() }
+                            key=|chat_template| *chat_template
+                            children=move |chat_template| view!{
+                                <option
+                                    value={chat_template.as_ref()}
+                                    selected=move || {
+                                        *chat_template == with!(|selected_model_data| {
                                             selected_model_data.as_ref()
                                                 .map(|model| model.chat_template)
                                                 .unwrap_or_default()
@@ -752,52 +2328,1306 @@ fn ModelsTab() -> impl IntoView {
                     <label for="model_chat_template_select">"Select a chat template"</label>
                 </div>
 
-                // stream toggle
-                <div class="form-check form-switch mb-3">
-                    <input
-                        class="form-check-input"
-                        type="checkbox"
-                        role="switch"
-                        id="model_stream_switch"
-                        node_ref=model_stream_input_field
-                        prop:checked=move || with!(|selected_model_data| {
-                            selected_model_data.as_ref()
-                                .map(|model| model.stream)
-                                .unwrap_or(true)
-                        })
-                    />
-                    <label class="form-check-label" for="model_stream_switch">"Stream assistant response (this is not supported by some models)"</label>
+                // stream toggle
+                <div class="form-check form-switch mb-3">
+                    <input
+                        class="form-check-input"
+                        type="checkbox"
+                        role="switch"
+                        id="model_stream_switch"
+                        node_ref=model_stream_input_field
+                        prop:checked=move || with!(|selected_model_data| {
+                            selected_model_data.as_ref()
+                                .map(|model| model.stream)
+                                .unwrap_or(true)
+                        })
+                    />
+                    <label class="form-check-label" for="model_stream_switch">"Stream assistant response (this is not supported by some models)"</label>
+                </div>
+
+                // vision toggle
+                <div class="form-check form-switch mb-3">
+                    <input
+                        class="form-check-input"
+                        type="checkbox"
+                        role="switch"
+                        id="model_vision_switch"
+                        node_ref=model_vision_input_field
+                        prop:checked=move || with!(|selected_model_data| {
+                            selected_model_data.as_ref()
+                                .map_or(false, |model| model.vision)
+                        })
+                    />
+                    <label class="form-check-label" for="model_vision_switch">"Accepts images (paste or drop them into the message input)"</label>
+                </div>
+
+                // tools toggle
+                <div class="form-check form-switch mb-3">
+                    <input
+                        class="form-check-input"
+                        type="checkbox"
+                        role="switch"
+                        id="model_tools_switch"
+                        node_ref=model_tools_input_field
+                        prop:checked=move || with!(|selected_model_data| {
+                            selected_model_data.as_ref()
+                                .map_or(false, |model| model.tools)
+                        })
+                    />
+                    <label class="form-check-label" for="model_tools_switch">"Can call tools (the tools from the Tools tab are described in the system prompt)"</label>
+                </div>
+
+                // context length input
+                <div class="form-floating mb-3">
+                    <input
+                        type="text"
+                        class="form-control"
+                        class:is-invalid=model_context_length_invalid
+                        id="model_context_length_input"
+                        node_ref=model_context_length_input_field
+                        prop:value=move || with!(|selected_model_data| {
+                            selected_model_data.as_ref()
+                                .and_then(|model| model.context_length)
+                                .map(|context_length| context_length.to_string())
+                                .unwrap_or_default()
+                        })
+                        on:input=move |event| {
+                            let value = event_target_value(&event);
+                            model_context_length_invalid.set(non_empty(value).map_or(false, |value| value.parse::<usize>().is_err()));
+                            changes_saved.set(false);
+                        }
+                    />
+                    <label for="model_context_length_input">"Context length in tokens (optional)"</label>
+                </div>
+
+                // position input
+                <div class="form-floating mb-3">
+                    <input
+                        type="text"
+                        class="form-control"
+                        class:is-invalid=model_position_invalid
+                        id="model_position_input"
+                        node_ref=model_position_input_field
+                        prop:value=move || with!(|selected_model_data| {
+                            selected_model_data.as_ref()
+                                .and_then(|model| model.position)
+                                .map(|position| position.to_string())
+                                .unwrap_or_default()
+                        })
+                        on:input=move |event| {
+                            let value = event_target_value(&event);
+                            model_position_invalid.set(non_empty(value).map_or(false, |value| value.parse::<u32>().is_err()));
+                            changes_saved.set(false);
+                        }
+                    />
+                    <label for="model_position_input">"Position in model lists (optional, favorites always come first)"</label>
+                </div>
+
+                // reasoning delimiter inputs
+                <div class="row g-2 mb-3">
+                    <div class="col form-floating">
+                        <input
+                            type="text"
+                            class="form-control"
+                            id="model_reasoning_start_input"
+                            placeholder="<think>"
+                            node_ref=model_reasoning_start_input_field
+                            prop:value=move || with!(|selected_model_data| {
+                                selected_model_data.as_ref()
+                                    .and_then(|model| model.reasoning.as_ref())
+                                    .map(|reasoning| reasoning.start.clone())
+                                    .unwrap_or_default()
+                            })
+                            on:input=move |_| changes_saved.set(false)
+                        />
+                        <label for="model_reasoning_start_input">"Reasoning starts with (optional)"</label>
+                    </div>
+                    <div class="col form-floating">
+                        <input
+                            type="text"
+                            class="form-control"
+                            id="model_reasoning_end_input"
+                            placeholder="</think>"
+                            node_ref=model_reasoning_end_input_field
+                            prop:value=move || with!(|selected_model_data| {
+                                selected_model_data.as_ref()
+                                    .and_then(|model| model.reasoning.as_ref())
+                                    .map(|reasoning| reasoning.end.clone())
+                                    .unwrap_or_default()
+                            })
+                            on:input=move |_| changes_saved.set(false)
+                        />
+                        <label for="model_reasoning_end_input">"Reasoning ends with"</label>
+                    </div>
+                    <div class="form-text">"The reasoning at the start of responses is folded, and not sent back to the model in later turns."</div>
+                </div>
+
+                // default parameters
+                <h5 class="mt-2">"Default parameters"</h5>
+                <div class="form-text mb-2">"New conversations with this model start with these parameters, instead of the ones set on the home page."</div>
+                {move || {
+                    // rebuild the inputs when another model is selected
+                    selected_model.track();
+                    view!{
+                        <ConversationParametersInputGroup
+                            value=model_default_parameters.get_untracked()
+                            on_system_prompt_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.system_prompt = value)
+                            on_start_response_with_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.start_response_with = value)
+                            on_temperature_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.temperature = value)
+                            on_top_k_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.top_k = value)
+                            on_top_p_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.top_p = value)
+                            on_repetition_penalty_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.repetition_penalty = value)
+                            on_min_p_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.min_p = value)
+                            on_typical_p_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.typical_p = value)
+                            on_no_repeat_ngram_size_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.no_repeat_ngram_size = value)
+                            on_n_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.n = value)
+                            on_do_sample_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.do_sample = value)
+                            on_token_limit_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.token_limit = value)
+                            on_ramp_after_turns_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.ramp_after_turns = value)
+                            on_ramp_temperature_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.ramp_temperature = value)
+                            on_json_schema_input=update_default_parameter(model_default_parameters, changes_saved, |params, value| params.json_schema = value)
+                        />
+                    }
+                }}
+
+                // test panel
+                <h5 class="mt-2">"Test"</h5>
+                <div class="form-text mb-2">"Runs a prompt with the chat template, backend and default parameters in the form, without saving them."</div>
+                <div class="input-group mb-2">
+                    <input
+                        type="text"
+                        class="form-control"
+                        placeholder="Hello!"
+                        aria-label="Test prompt"
+                        prop:value=test_prompt
+                        on:input=move |event| test_prompt.set(event_target_value(&event))
+                    />
+                    <button
+                        type="button"
+                        class="btn btn-outline-secondary text-nowrap"
+                        disabled=move || testing.get() || with!(|test_prompt| test_prompt.is_empty())
+                        on:click=run_model_test
+                    >
+                        {move || if testing.get() {
+                            view!{ <div class="spinner-border spinner-border-sm me-2" role="status"></div> }.into_view()
+                        }
+                        else {
+                            view!{ <span class="me-2"><BootstrapIcon icon="play-fill" /></span> }.into_view()
+                        }}
+                        "Run"
+                    </button>
+                </div>
+                {move || test_raw_prompt.get().map(|prompt| view!{
+                    <div class="row g-2 mb-4">
+                        <div class="col">
+                            <small class="text-body-secondary">"Raw prompt"</small>
+                            <pre class="border rounded p-2 small mb-0" style="white-space: pre-wrap;">{prompt}</pre>
+                        </div>
+                        <div class="col">
+                            <small class="text-body-secondary">"Raw response"</small>
+                            {move || match test_response.get() {
+                                None => view!{
+                                    <pre class="border rounded p-2 small mb-0 text-body-secondary">"Waiting for the response"</pre>
+                                }.into_view(),
+                                Some(Ok(response)) => view!{
+                                    <pre class="border rounded p-2 small mb-0" style="white-space: pre-wrap;">{response}</pre>
+                                }.into_view(),
+                                Some(Err(error)) => view!{
+                                    <pre class="border border-danger rounded p-2 small mb-0 text-danger-emphasis" style="white-space: pre-wrap;">{error}</pre>
+                                }.into_view(),
+                            }}
+                        </div>
+                    </div>
+                })}
+
+                // buttons
+                <div class="d-flex flex-row w-100 justify-content-end">
+                    {move || with!(|selected_model| {
+                        selected_model.is_edit().then(move || {
+                            view!{
+                                <button
+                                    type="button"
+                                    class="btn btn-danger w-25"
+                                    data-bs-toggle="modal"
+                                    data-bs-target="#settings_delete_model_modal"
+                                >
+                                    <span class="me-1"><BootstrapIcon icon="trash-fill" /></span>
+                                    "Delete model"
+                                </button>
+                            }
+                        })
+                    })}
+                    <button
+                        type="button"
+                        class="btn btn-primary w-25 ms-2"
+                        disabled=move || !model_id_state.get().is_valid() || model_name_invalid.get() || model_context_length_invalid.get()
+                        on:click=move |_| save_model()
+                    >
+                        <span class="me-1"><BootstrapIcon icon="floppy-fill" /></span>
+                        "Save changes"
+                    </button>
+                </div>
+            </form>
+        </div>
+    }
+}
+
+/// Formats fallbacks for the model form, one per line.
+fn format_fallbacks(fallbacks: &[Fallback]) -> String {
+    fallbacks
+        .iter()
+        .map(|fallback| {
+            match &fallback.model_id {
+                Some(model_id) => format!("{} {model_id}", fallback.backend.as_ref()),
+                None => fallback.backend.as_ref().to_owned(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_headers(headers: &[Header]) -> String {
+    headers
+        .iter()
+        .map(|header| format!("{}: {}", header.name, header.value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses the headers from the connection form. Lines without a colon are
+/// ignored.
+fn parse_headers(text: &str) -> Vec<Header> {
+    text.lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            let name = non_empty(name.trim().to_owned())?;
+            Some(Header {
+                name,
+                value: value.trim().to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Parses the fallbacks from the model form. Lines with an unknown backend are
+/// ignored.
+fn parse_fallbacks(text: &str) -> Vec<Fallback> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let backend = parts.next()?.parse::<ModelBackend>().ok()?;
+            let model_id = parts.next().map(ModelId::from);
+            Some(Fallback { backend, model_id })
+        })
+        .collect()
+}
+
+#[component]
+fn TagsTab() -> impl IntoView {
+    let Context {
+        summaries,
+        tags,
+        update_tags,
+        ..
+    } = expect_context();
+
+    let new_tag_input = create_node_ref::<Input>();
+
+    // applies a change to the tags of every conversation that has the tag
+    let update_conversation_tags = move |tag: &str, f: &dyn Fn(&mut Vec<String>)| {
+        let ids = summaries.with_untracked(|summaries| {
+            summaries
+                .values()
+                .filter(|summary| summary.tags.iter().any(|other| other == tag))
+                .map(|summary| summary.id)
+                .collect::<Vec<_>>()
+        });
+        for id in ids {
+            let StorageSignals {
+                write: update_conversation,
+                ..
+            } = use_conversation(id);
+            update_conversation.update(|conversation| {
+                if let Some(conversation) = conversation {
+                    f(&mut conversation.tags);
+                }
+            });
+            refresh_summary(id);
+        }
+    };
+
+    let create_tag = move || {
+        let input = new_tag_input.get_untracked().unwrap();
+        let Some(tag) = non_empty(input.value().trim().to_owned())
+        else {
+            return;
+        };
+        update_tags.update(|tags| {
+            tags.insert(tag);
+        });
+        input.set_value("");
+    };
+
+    let rename_tag = move |old: String, new: String| {
+        if old == new || new.is_empty() {
+            return;
+        }
+        update_tags.update(|tags| {
+            tags.remove(&old);
+            tags.insert(new.clone());
+        });
+        update_conversation_tags(&old, &|tags| {
+            if tags.contains(&old) {
+                tags.retain(|tag| tag != &old && tag != &new);
+                tags.push(new.clone());
+            }
+        });
+    };
+
+    let delete_tag = move |tag: String| {
+        update_tags.update(|tags| {
+            tags.remove(&tag);
+        });
+        update_conversation_tags(&tag, &|tags| tags.retain(|other| other != &tag));
+    };
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
+            <form
+                class="input-group mb-3"
+                on:submit=move |event| {
+                    event.prevent_default();
+                    create_tag();
+                }
+            >
+                <input
+                    type="text"
+                    class="form-control"
+                    placeholder="New tag"
+                    node_ref=new_tag_input
+                />
+                <button type="submit" class="btn btn-outline-primary">
+                    <span class="me-1"><BootstrapIcon icon="plus-lg" /></span>
+                    "Create"
+                </button>
+            </form>
+            <For
+                each=move || tags.get()
+                key=|tag| tag.clone()
+                children=move |tag| {
+                    let on_rename = {
+                        let tag = tag.clone();
+                        move |event: Event| rename_tag(tag.clone(), event_target_value(&event).trim().to_owned())
+                    };
+                    let on_delete = {
+                        let tag = tag.clone();
+                        move |_| delete_tag(tag.clone())
+                    };
+                    view!{
+                        <div class="input-group mb-2">
+                            <span class="input-group-text"><BootstrapIcon icon="tag" /></span>
+                            <input
+                                type="text"
+                                class="form-control"
+                                aria-label="Tag name"
+                                value=tag
+                                on:change=on_rename
+                            />
+                            <button type="button" class="btn btn-outline-danger" title="Delete tag" on:click=on_delete>
+                                <BootstrapIcon icon="trash-fill" />
+                            </button>
+                        </div>
+                    }
+                }
+            />
+            <div class="form-text">"Renaming or deleting a tag changes it for all conversations."</div>
+        </div>
+    }
+}
+
+#[component]
+fn ToolsTab() -> impl IntoView {
+    let Context {
+        settings,
+        update_settings,
+        ..
+    } = expect_context();
+
+    let new_tool_implementation = create_rw_signal(Implementation::WebSearch);
+
+    let update_tool = move |id: Uuid, f: Box<dyn FnOnce(&mut Tool)>| {
+        update_settings.update(|settings| {
+            if let Some(tool) = settings.tools.iter_mut().find(|tool| tool.id == id) {
+                f(tool);
+            }
+        });
+    };
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
+            <div class="form-floating mb-3">
+                <input
+                    type="url"
+                    class="form-control"
+                    id="tools_search_url"
+                    placeholder="https://searx.example.org"
+                    prop:value=move || with!(|settings| settings.search_url.clone().unwrap_or_default())
+                    on:change=move |event| {
+                        let search_url = non_empty(event_target_value(&event).trim().to_owned());
+                        update_settings.update(move |settings| settings.search_url = search_url);
+                    }
+                />
+                <label for="tools_search_url">"SearxNG instance for web searches"</label>
+                <div class="form-text">"The instance needs to have the JSON format enabled, and allow requests from this site."</div>
+            </div>
+            <form
+                class="input-group mb-3"
+                on:submit=move |event| {
+                    event.prevent_default();
+                    let tool = new_tool_implementation.get_untracked().default_tool();
+                    update_settings.update(|settings| settings.tools.push(tool));
+                }
+            >
+                <select
+                    class="form-select"
+                    aria-label="What the new tool does"
+                    on:change=move |event| {
+                        let index = event_target_value(&event).parse::<usize>().unwrap_or_default();
+                        if let Some(implementation) = <Implementation as VariantArray>::VARIANTS.get(index) {
+                            new_tool_implementation.set(*implementation);
+                        }
+                    }
+                >
+                    {<Implementation as VariantArray>::VARIANTS.iter().enumerate().map(|(i, implementation)| view!{
+                        <option value=i.to_string()>{implementation.label()}</option>
+                    }).collect_view()}
+                </select>
+                <button type="submit" class="btn btn-outline-primary">
+                    <span class="me-1"><BootstrapIcon icon="plus-lg" /></span>
+                    "Add tool"
+                </button>
+            </form>
+            <For
+                each=move || with!(|settings| settings.tools.clone())
+                key=|tool| tool.id
+                children=move |tool| {
+                    let id = tool.id;
+                    let schema_invalid = create_rw_signal(false);
+                    let on_rename = move |event: Event| {
+                        let Some(name) = non_empty(event_target_value(&event).trim().to_owned()) else { return; };
+                        update_tool(id, Box::new(move |tool| tool.name = name));
+                    };
+                    let on_description = move |event: Event| {
+                        let description = event_target_value(&event);
+                        update_tool(id, Box::new(move |tool| tool.description = description));
+                    };
+                    let on_schema = move |event: Event| {
+                        match serde_json::from_str(&event_target_value(&event)) {
+                            Ok(parameters) => {
+                                schema_invalid.set(false);
+                                update_tool(id, Box::new(move |tool| tool.parameters = parameters));
+                            }
+                            Err(_) => schema_invalid.set(true),
+                        }
+                    };
+                    let on_delete = move |_| {
+                        update_settings.update(|settings| settings.tools.retain(|tool| tool.id != id));
+                    };
+                    view!{
+                        <div class="card mb-3">
+                            <div class="card-body">
+                                <div class="input-group mb-2">
+                                    <span class="input-group-text"><BootstrapIcon icon="tools" /></span>
+                                    <input
+                                        type="text"
+                                        class="form-control font-monospace"
+                                        aria-label="Tool name"
+                                        value=tool.name
+                                        on:change=on_rename
+                                    />
+                                    <span class="input-group-text">{tool.implementation.label()}</span>
+                                    <button type="button" class="btn btn-outline-danger" title="Delete tool" on:click=on_delete>
+                                        <BootstrapIcon icon="trash-fill" />
+                                    </button>
+                                </div>
+                                <input
+                                    type="text"
+                                    class="form-control mb-2"
+                                    placeholder="Description for the model"
+                                    value=tool.description
+                                    on:change=on_description
+                                />
+                                <textarea
+                                    class="form-control font-monospace"
+                                    class:is-invalid=schema_invalid
+                                    rows="4"
+                                    aria-label="JSON schema of the arguments"
+                                    on:change=on_schema
+                                >
+                                    {serde_json::to_string_pretty(&tool.parameters).unwrap_or_default()}
+                                </textarea>
+                            </div>
+                        </div>
+                    }
+                }
+            />
+            <div class="form-text">"Tools are offered to models that can call tools, which is set for each model. They run in your browser. Fetching URLs only works for sites that allow it (CORS)."</div>
+        </div>
+    }
+}
+
+#[component]
+fn TemplatesTab() -> impl IntoView {
+    let Context {
+        settings,
+        templates,
+        update_templates,
+        ..
+    } = expect_context();
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
+            {move || with!(|templates| templates.is_empty()).then(|| view!{
+                <p class="text-body-secondary">"No templates yet."</p>
+            })}
+            <For
+                each=move || templates.get()
+                key=|template| template.id
+                children=move |template| {
+                    let id = template.id;
+                    let model_name = settings.with_untracked(|settings| {
+                        settings.models
+                            .get(&template.model_id)
+                            .map(|model| model.display_name().to_owned())
+                            .unwrap_or_else(|| template.model_id.to_string())
+                    });
+                    let on_rename = move |event: Event| {
+                        let Some(name) = non_empty(event_target_value(&event).trim().to_owned()) else { return; };
+                        update_templates.update(|templates| {
+                            if let Some(template) = templates.iter_mut().find(|template| template.id == id) {
+                                template.name = name;
+                            }
+                        });
+                    };
+                    let on_delete = move |_| {
+                        update_templates.update(|templates| templates.retain(|template| template.id != id));
+                    };
+                    view!{
+                        <div class="input-group mb-2">
+                            <span class="input-group-text"><BootstrapIcon icon="bookmark" /></span>
+                            <input
+                                type="text"
+                                class="form-control"
+                                aria-label="Template name"
+                                value=template.name
+                                on:change=on_rename
+                            />
+                            <span class="input-group-text">{model_name}</span>
+                            <button type="button" class="btn btn-outline-danger" title="Delete template" on:click=on_delete>
+                                <BootstrapIcon icon="trash-fill" />
+                            </button>
+                        </div>
+                    }
+                }
+            />
+            <div class="form-text">"Save the selected model and parameters as a template from the advanced options on the home page."</div>
+        </div>
+    }
+}
+
+fn set_backend_credential(
+    settings: &mut crate::state::Settings,
+    backend: ModelBackend,
+    name: Option<String>,
+) {
+    match name {
+        Some(name) => settings.backend_credentials.insert(backend, name),
+        None => settings.backend_credentials.remove(&backend),
+    };
+}
+
+/// Selects a credential by its name.
+#[component]
+fn CredentialSelect(
+    #[prop(into)] value: Signal<Option<String>>,
+    #[prop(into)] on_change: Callback<Option<String>>,
+    /// The option for not using a credential.
+    none_label: &'static str,
+    #[prop(optional)] id: Option<&'static str>,
+) -> impl IntoView {
+    let Context { settings, .. } = expect_context();
+
+    view! {
+        <select
+            class="form-select"
+            id=id
+            aria-label="Select a credential"
+            on:change=move |event| on_change(non_empty(event_target_value(&event)))
+        >
+            <option value="" selected=move || with!(|value| value.is_none())>{none_label}</option>
+            <For
+                each=move || with!(|settings| settings.credentials.keys().cloned().collect::<Vec<_>>())
+                key=|name| name.clone()
+                children=move |name| {
+                    let value_name = name.clone();
+                    view!{
+                        <option
+                            value=name.clone()
+                            selected=move || with!(|value| value.as_ref() == Some(&value_name))
+                        >
+                            {name}
+                        </option>
+                    }
+                }
+            />
+        </select>
+    }
+}
+
+#[component]
+fn CredentialsTab() -> impl IntoView {
+    let Context {
+        settings,
+        update_settings,
+        ..
+    } = expect_context();
+
+    let new_credential_name_input = create_node_ref::<Input>();
+    let new_credential_secret_input = create_node_ref::<Input>();
+
+    let add_credential = move |event: SubmitEvent| {
+        event.prevent_default();
+
+        let name_input = new_credential_name_input.get_untracked().unwrap();
+        let secret_input = new_credential_secret_input.get_untracked().unwrap();
+        let Some((name, secret)) = non_empty(name_input.value().trim().to_owned())
+            .zip(non_empty(secret_input.value().trim().to_owned()))
+        else {
+            return;
+        };
+
+        update_settings.update(|settings| {
+            settings.credentials.insert(name, secret);
+        });
+        name_input.set_value("");
+        secret_input.set_value("");
+    };
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
+            {move || with!(|settings| settings.credentials.is_empty()).then(|| view!{
+                <p class="text-body-secondary">"No credentials yet."</p>
+            })}
+            <For
+                each=move || with!(|settings| settings.credentials.keys().cloned().collect::<Vec<_>>())
+                key=|name| name.clone()
+                children=move |name| {
+                    let rename_from = name.clone();
+                    let on_rename = move |event: Event| {
+                        let Some(new_name) = non_empty(event_target_value(&event).trim().to_owned()) else { return; };
+                        let name = rename_from.clone();
+                        update_settings.update(move |settings| settings.rename_credential(&name, new_name));
+                    };
+                    let secret_name = name.clone();
+                    let on_secret = move |event: Event| {
+                        let Some(secret) = non_empty(event_target_value(&event).trim().to_owned()) else { return; };
+                        let name = secret_name.clone();
+                        update_settings.update(move |settings| {
+                            settings.credentials.insert(name, secret);
+                        });
+                    };
+                    let delete_name = name.clone();
+                    let on_delete = move |_| {
+                        let name = delete_name.clone();
+                        update_settings.update(move |settings| settings.remove_credential(&name));
+                    };
+                    let secret = settings.with_untracked(|settings| settings.credentials.get(&name).cloned().unwrap_or_default());
+                    view!{
+                        <div class="input-group mb-2">
+                            <span class="input-group-text"><BootstrapIcon icon="key" /></span>
+                            <input
+                                type="text"
+                                class="form-control"
+                                aria-label="Credential name"
+                                value=name
+                                on:change=on_rename
+                            />
+                            <input
+                                type="password"
+                                class="form-control font-monospace"
+                                aria-label="Token or key"
+                                value=secret
+                                on:change=on_secret
+                            />
+                            <button type="button" class="btn btn-outline-danger" title="Delete credential" on:click=on_delete>
+                                <BootstrapIcon icon="trash-fill" />
+                            </button>
+                        </div>
+                    }
+                }
+            />
+            <form class="input-group mb-3" on:submit=add_credential>
+                <input
+                    type="text"
+                    class="form-control"
+                    placeholder="Work HF token"
+                    aria-label="New credential name"
+                    node_ref=new_credential_name_input
+                />
+                <input
+                    type="password"
+                    class="form-control font-monospace"
+                    placeholder="hf_..."
+                    aria-label="New token or key"
+                    node_ref=new_credential_secret_input
+                />
+                <button type="submit" class="btn btn-outline-primary">
+                    <span class="me-1"><BootstrapIcon icon="plus-lg" /></span>
+                    "Add credential"
+                </button>
+            </form>
+            <h5>"Backends"</h5>
+            <div class="form-text mb-2">"The credential that requests to a backend are sent with. Models can use a different one, which is set when editing them."</div>
+            <table class="table table-sm align-middle mb-3">
+                <thead>
+                    <tr>
+                        <th scope="col">"Backend"</th>
+                        <th scope="col">"Credential"</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {<ModelBackend as VariantArray>::VARIANTS.iter().filter(|backend| **backend != ModelBackend::Local).map(|backend| {
+                        let backend = *backend;
+                        view!{
+                            <tr>
+                                <td>{backend.get_message()}</td>
+                                <td>
+                                    <CredentialSelect
+                                        value=Signal::derive(move || with!(|settings| settings.backend_credentials.get(&backend).cloned()))
+                                        on_change=move |name| update_settings.update(move |settings| set_backend_credential(settings, backend, name))
+                                        none_label="None"
+                                    />
+                                </td>
+                            </tr>
+                        }
+                    }).collect_view()}
+                </tbody>
+            </table>
+            <div class="form-text">"Credentials are stored in your browser and are only sent to the backends that use them."</div>
+        </div>
+    }
+}
+
+#[component]
+fn ExamplesTab() -> impl IntoView {
+    let Context {
+        settings,
+        update_settings,
+        config,
+        ..
+    } = expect_context();
+
+    let new_example_input = create_node_ref::<Textarea>();
+
+    let update_example = move |id: Uuid, f: Box<dyn FnOnce(&mut ExamplePrompt)>| {
+        update_settings.update(|settings| {
+            if let Some(example) = settings.examples.iter_mut().find(|example| example.id == id) {
+                f(example);
+            }
+        });
+    };
+
+    fn update_parameter<T>(
+        update_settings: WriteSignal<crate::state::Settings>,
+        id: Uuid,
+        update: impl Fn(&mut ConversationParameters, T) + Copy,
+    ) -> impl Fn(T) + Copy {
+        move |value| {
+            update_settings.update(move |settings| {
+                if let Some(parameters) = settings
+                    .examples
+                    .iter_mut()
+                    .find(|example| example.id == id)
+                    .and_then(|example| example.conversation_parameters.as_mut())
+                {
+                    update(parameters, value);
+                }
+            });
+        }
+    }
+
+    let add_example = move |event: SubmitEvent| {
+        event.prevent_default();
+
+        let input = new_example_input.get_untracked().unwrap();
+        let Some(prompt) = non_empty(input.value().trim().to_owned())
+        else {
+            return;
+        };
+
+        update_settings.update(|settings| {
+            settings.examples.push(ExamplePrompt {
+                id: Uuid::new_v4(),
+                prompt,
+                model_id: None,
+                conversation_parameters: None,
+            });
+        });
+        input.set_value("");
+    };
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
+            <h6>"Built-in examples"</h6>
+            <ul class="mb-3">
+                {move || with!(|config| config.examples.iter().map(|example| view!{
+                    <li class="text-body-secondary">{example.clone()}</li>
+                }).collect_view())}
+            </ul>
+            <h6>"Your examples"</h6>
+            <For
+                each=move || with!(|settings| settings.examples.clone())
+                key=|example| example.id
+                children=move |example| {
+                    let id = example.id;
+                    let custom_parameters = create_rw_signal(example.conversation_parameters.is_some());
+                    let on_prompt = move |event: Event| {
+                        let Some(prompt) = non_empty(event_target_value(&event).trim().to_owned()) else { return; };
+                        update_example(id, Box::new(move |example| example.prompt = prompt));
+                    };
+                    let on_model = move |event: Event| {
+                        let model_id = non_empty(event_target_value(&event)).map(ModelId);
+                        update_example(id, Box::new(move |example| example.model_id = model_id));
+                    };
+                    let on_custom_parameters = move |event: Event| {
+                        let checked = event_target_checked(&event);
+                        custom_parameters.set(checked);
+                        update_example(id, Box::new(move |example| {
+                            example.conversation_parameters = checked.then(Default::default);
+                        }));
+                    };
+                    let on_delete = move |_| {
+                        update_settings.update(|settings| settings.examples.retain(|example| example.id != id));
+                    };
+                    let model_id = example.model_id.clone();
+                    view!{
+                        <div class="card mb-3">
+                            <div class="card-body">
+                                <div class="input-group mb-2">
+                                    <span class="input-group-text"><BootstrapIcon icon="chat-left-text" /></span>
+                                    <textarea
+                                        class="form-control"
+                                        rows="2"
+                                        aria-label="Example prompt"
+                                        on:change=on_prompt
+                                    >
+                                        {example.prompt}
+                                    </textarea>
+                                    <button type="button" class="btn btn-outline-danger" title="Delete example" on:click=on_delete>
+                                        <BootstrapIcon icon="trash-fill" />
+                                    </button>
+                                </div>
+                                <select
+                                    class="form-select mb-2"
+                                    aria-label="Model used for the example"
+                                    on:change=on_model
+                                >
+                                    <option value="" selected=model_id.is_none()>"The selected model"</option>
+                                    {settings.with_untracked(|settings| {
+                                        settings.sorted_models().into_iter().map(|model| {
+                                            let selected = model_id.as_ref() == Some(&model.model_id);
+                                            view!{
+                                                <option value=model.model_id.to_string() selected=selected>
+                                                    {model.display_name().to_owned()}
+                                                </option>
+                                            }
+                                        }).collect_view()
+                                    })}
+                                </select>
+                                <div class="form-check form-switch mb-2">
+                                    <input
+                                        class="form-check-input"
+                                        type="checkbox"
+                                        role="switch"
+                                        id=format!("example_custom_parameters_{id}")
+                                        prop:checked=custom_parameters
+                                        on:input=on_custom_parameters
+                                    />
+                                    <label class="form-check-label" for=format!("example_custom_parameters_{id}")>"Custom parameters"</label>
+                                </div>
+                                {move || custom_parameters.get().then(|| {
+                                    let value = settings.with_untracked(|settings| {
+                                        settings.examples
+                                            .iter()
+                                            .find(|example| example.id == id)
+                                            .and_then(|example| example.conversation_parameters.clone())
+                                            .unwrap_or_default()
+                                    });
+                                    view!{
+                                        <ConversationParametersInputGroup
+                                            value=value
+                                            on_system_prompt_input=update_parameter(update_settings, id, |params, value| params.system_prompt = value)
+                                            on_start_response_with_input=update_parameter(update_settings, id, |params, value| params.start_response_with = value)
+                                            on_temperature_input=update_parameter(update_settings, id, |params, value| params.temperature = value)
+                                            on_top_k_input=update_parameter(update_settings, id, |params, value| params.top_k = value)
+                                            on_top_p_input=update_parameter(update_settings, id, |params, value| params.top_p = value)
+                                            on_repetition_penalty_input=update_parameter(update_settings, id, |params, value| params.repetition_penalty = value)
+                                            on_min_p_input=update_parameter(update_settings, id, |params, value| params.min_p = value)
+                                            on_typical_p_input=update_parameter(update_settings, id, |params, value| params.typical_p = value)
+                                            on_no_repeat_ngram_size_input=update_parameter(update_settings, id, |params, value| params.no_repeat_ngram_size = value)
+                                            on_n_input=update_parameter(update_settings, id, |params, value| params.n = value)
+                                            on_do_sample_input=update_parameter(update_settings, id, |params, value| params.do_sample = value)
+                                            on_token_limit_input=update_parameter(update_settings, id, |params, value| params.token_limit = value)
+                                            on_ramp_after_turns_input=update_parameter(update_settings, id, |params, value| params.ramp_after_turns = value)
+                                            on_ramp_temperature_input=update_parameter(update_settings, id, |params, value| params.ramp_temperature = value)
+                                            on_json_schema_input=update_parameter(update_settings, id, |params, value| params.json_schema = value)
+                                        />
+                                    }
+                                })}
+                            </div>
+                        </div>
+                    }
+                }
+            />
+            <form class="input-group mb-3" on:submit=add_example>
+                <textarea
+                    class="form-control"
+                    rows="2"
+                    placeholder="Write a limerick about Rust."
+                    aria-label="New example prompt"
+                    node_ref=new_example_input
+                ></textarea>
+                <button type="submit" class="btn btn-outline-primary">
+                    <span class="me-1"><BootstrapIcon icon="plus-lg" /></span>
+                    "Add example"
+                </button>
+            </form>
+            <div class="form-text">"Your examples are shown on the home page after the built-in ones. Without a model or parameters, they start with the ones that are selected there."</div>
+        </div>
+    }
+}
+
+#[component]
+fn ImportTab() -> impl IntoView {
+    let Context {
+        errors,
+        home,
+        update_conversations,
+        update_summaries,
+        ..
+    } = expect_context();
+
+    let file_input = create_node_ref::<Input>();
+    let importing = create_rw_signal(false);
+    let imported_count = create_rw_signal(None::<usize>);
+
+    let import = move |_| {
+        let Some(file) = file_input
+            .get_untracked()
+            .and_then(|input| input.files())
+            .and_then(|files| files.get(0))
+        else {
+            return;
+        };
+        let model_id = home.with_untracked(|home| home.selected_model.clone());
+
+        importing.set(true);
+        imported_count.set(None);
+
+        spawn_local(async move {
+            let result = async {
+                let json = wasm_bindgen_futures::JsFuture::from(file.text())
+                    .await
+                    .map_err(|error| ImportError::ReadFile(format!("{error:?}")))?
+                    .as_string()
+                    .unwrap_or_default();
+                parse_chatgpt_export(&json, &model_id)
+            }
+            .await;
+
+            match result {
+                Ok(imported) => {
+                    log::info!("importing {} conversations", imported.len());
+                    for imported in &imported {
+                        imported.store();
+                    }
+                    update_summaries.update(|summaries| {
+                        for imported in &imported {
+                            summaries.insert(
+                                imported.conversation.id,
+                                ConversationSummary::from(&imported.conversation),
+                            );
+                        }
+                    });
+                    update_conversations.update(|conversations| {
+                        conversations
+                            .extend(imported.iter().map(|imported| imported.conversation.id));
+                    });
+                    imported_count.set(Some(imported.len()));
+                }
+                Err(error) => errors.push(Error::from(error)),
+            }
+
+            importing.set(false);
+        });
+    };
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
+            <h5>"Import from ChatGPT"</h5>
+            <p>
+                "Request a data export in the ChatGPT settings, and select the "
+                <code>"conversations.json"</code>
+                " from the downloaded archive. Imported conversations will continue with the model selected on the home page."
+            </p>
+            <div class="input-group mb-3">
+                <input type="file" class="form-control" accept=".json,application/json" node_ref=file_input />
+                <button type="button" class="btn btn-outline-primary" disabled=importing on:click=import>
+                    <span class="me-1"><BootstrapIcon icon="upload" /></span>
+                    "Import"
+                </button>
+            </div>
+            {move || imported_count.get().map(|count| view!{
+                <div class="alert alert-success" role="status">
+                    {format!("Imported {count} conversations.")}
                 </div>
+            })}
+        </div>
+    }
+}
 
-                // buttons
-                <div class="d-flex flex-row w-100 justify-content-end">
-                    {move || with!(|selected_model| {
-                        selected_model.is_edit().then(move || {
-                            view!{
+#[component]
+fn TrashTab() -> impl IntoView {
+    let Context {
+        settings,
+        update_settings,
+        summaries,
+        trash,
+        ..
+    } = expect_context();
+
+    let trash_items = create_memo(move |_| {
+        with!(|trash| {
+            let mut items = trash
+                .iter()
+                .map(|(id, deleted)| (*deleted, *id))
+                .collect::<Vec<_>>();
+            items.sort();
+            items.reverse();
+            items
+        })
+    });
+
+    view! {
+        <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100 w-75 mx-auto">
+            <div class="form-floating mb-3">
+                <input
+                    type="number"
+                    class="form-control"
+                    id="trash_retention_days"
+                    min="1"
+                    placeholder="Keep forever"
+                    prop:value=move || with!(|settings| settings.trash_retention_days.map(|days| days.to_string()).unwrap_or_default())
+                    on:input=move |event| {
+                        let value = event_target_value(&event);
+                        let retention_days = match non_empty(value) {
+                            Some(value) => {
+                                let Ok(days) = value.parse::<u32>() else { return; };
+                                Some(days)
+                            }
+                            None => None,
+                        };
+                        update_settings.update(move |settings| settings.trash_retention_days = retention_days);
+                    }
+                />
+                <label for="trash_retention_days">"Delete conversations in the trash after this many days"</label>
+                <div class="form-text">"Leave empty to keep them until the trash is emptied."</div>
+            </div>
+            <div class="d-flex flex-row mb-3">
+                <button
+                    type="button"
+                    class="btn btn-outline-danger ms-auto"
+                    disabled=move || with!(|trash| trash.is_empty())
+                    on:click=move |_| purge_trash(None)
+                >
+                    <span class="me-1"><BootstrapIcon icon="trash-fill" /></span>
+                    "Empty trash"
+                </button>
+            </div>
+            {move || with!(|trash| trash.is_empty()).then(|| view!{
+                <p class="text-body-secondary">"The trash is empty."</p>
+            })}
+            <ul class="list-group">
+                <For
+                    each=trash_items
+                    key=|(_, id)| *id
+                    children=move |(deleted, id)| {
+                        let title = move || with!(|summaries| {
+                            summaries.get(&id)
+                                .and_then(|summary| summary.title.clone())
+                                .unwrap_or_else(|| "Untitled".to_owned())
+                        });
+                        let deleted = deleted.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string();
+
+                        view!{
+                            <li class="list-group-item d-flex flex-row align-items-center">
+                                <span class="text-truncate me-auto">{title}</span>
+                                <small class="text-body-secondary text-nowrap mx-3">"Deleted " {deleted}</small>
                                 <button
                                     type="button"
-                                    class="btn btn-danger w-25"
-                                    data-bs-toggle="modal"
-                                    data-bs-target="#settings_delete_model_modal"
+                                    class="btn btn-sm btn-outline-secondary me-2"
+                                    title="Restore"
+                                    on:click=move |_| restore_conversation(id)
                                 >
-                                    <span class="me-1"><BootstrapIcon icon="trash-fill" /></span>
-                                    "Delete model"
+                                    <BootstrapIcon icon="arrow-counterclockwise" />
+                                </button>
+                                <button
+                                    type="button"
+                                    class="btn btn-sm btn-outline-danger"
+                                    title="Delete permanently"
+                                    on:click=move |_| delete_conversation_permanently(id)
+                                >
+                                    <BootstrapIcon icon="x-lg" />
                                 </button>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+            <CleanupSettings />
+        </div>
+    }
+}
+
+/// The policy for old conversations, with a preview of the conversations it
+/// applies to.
+#[component]
+fn CleanupSettings() -> impl IntoView {
+    let Context {
+        settings,
+        update_settings,
+        summaries,
+        ..
+    } = expect_context();
+
+    let policy = Signal::derive(move || with!(|settings| settings.cleanup));
+    let update_policy = move |update: Box<dyn FnOnce(&mut CleanupPolicy)>| {
+        update_settings.update(move |settings| {
+            if let Some(policy) = &mut settings.cleanup {
+                update(policy);
+            }
+        });
+    };
+
+    // what the policy would clean up if it was applied now.
+    let candidates = create_memo(move |_| {
+        policy
+            .get()
+            .map(|policy| cleanup_candidates(&policy))
+            .unwrap_or_default()
+    });
+
+    view! {
+        <hr />
+        <h5>"Old conversations"</h5>
+        <div class="form-check form-switch mb-3">
+            <input
+                class="form-check-input"
+                type="checkbox"
+                role="switch"
+                id="trash_cleanup_enabled"
+                prop:checked=move || policy.with(|policy| policy.is_some())
+                on:input=move |event| {
+                    let enabled = event_target_checked(&event);
+                    update_settings.update(move |settings| {
+                        settings.cleanup = enabled.then(CleanupPolicy::default);
+                    });
+                }
+            />
+            <label class="form-check-label" for="trash_cleanup_enabled">"Clean up old conversations when the app starts"</label>
+        </div>
+        {move || policy.get().map(|current| view!{
+            <div class="row g-2 mb-3">
+                <div class="col form-floating">
+                    <input
+                        type="number"
+                        class="form-control"
+                        id="trash_cleanup_days"
+                        min="1"
+                        prop:value=current.older_than_days
+                        on:change=move |event| {
+                            if let Ok(days) = event_target_value(&event).parse::<u32>() {
+                                update_policy(Box::new(move |policy| policy.older_than_days = days.max(1)));
                             }
-                        })
-                    })}
-                    <button
-                        type="button"
-                        class="btn btn-primary w-25 ms-2"
-                        disabled=move || !model_id_state.get().is_valid() || model_name_invalid.get()
-                        on:click=move |_| save_model()
+                        }
+                    />
+                    <label for="trash_cleanup_days">"Unused for this many days"</label>
+                </div>
+                <div class="col form-floating">
+                    <select
+                        class="form-select"
+                        id="trash_cleanup_action"
+                        on:change=move |event| {
+                            if let Ok(action) = event_target_value(&event).parse::<CleanupAction>() {
+                                update_policy(Box::new(move |policy| policy.action = action));
+                            }
+                        }
                     >
-                        <span class="me-1"><BootstrapIcon icon="floppy-fill" /></span>
-                        "Save changes"
-                    </button>
+                        {<CleanupAction as VariantArray>::VARIANTS.iter().map(|action| view!{
+                            <option value=action.as_ref() selected=*action == current.action>
+                                {action.get_message()}
+                            </option>
+                        }).collect_view()}
+                    </select>
+                    <label for="trash_cleanup_action">"What to do with them"</label>
                 </div>
-            </form>
-        </div>
+            </div>
+            <div class="form-check mb-3">
+                <input
+                    class="form-check-input"
+                    type="checkbox"
+                    id="trash_cleanup_keep_pinned"
+                    prop:checked=current.keep_pinned
+                    on:input=move |event| {
+                        let keep_pinned = event_target_checked(&event);
+                        update_policy(Box::new(move |policy| policy.keep_pinned = keep_pinned));
+                    }
+                />
+                <label class="form-check-label" for="trash_cleanup_keep_pinned">"Keep pinned conversations"</label>
+            </div>
+            <details class="mb-3">
+                <summary>
+                    {move || match with!(|candidates| candidates.len()) {
+                        0 => "Nothing would be cleaned up right now".to_owned(),
+                        1 => "1 conversation would be cleaned up right now".to_owned(),
+                        count => format!("{count} conversations would be cleaned up right now"),
+                    }}
+                </summary>
+                <ul class="list-group list-group-flush overflow-y-scroll" style="max-height: 20em">
+                    {move || with!(|candidates, summaries| candidates.iter().filter_map(|id| summaries.get(id)).map(|summary| {
+                        let title = summary.title.clone().unwrap_or_else(|| "Untitled".to_owned());
+                        let last_interaction = summary.timestamp_last_interaction.with_timezone(&Local).format("%Y-%m-%d").to_string();
+                        view!{
+                            <li class="list-group-item d-flex flex-row">
+                                <span class="text-truncate me-auto">{title}</span>
+                                <small class="text-body-secondary text-nowrap ms-3">"Last used " {last_interaction}</small>
+                            </li>
+                        }
+                    }).collect_view())}
+                </ul>
+            </details>
+            <div class="d-flex flex-row mb-3">
+                <button
+                    type="button"
+                    class="btn btn-outline-secondary ms-auto"
+                    disabled=move || with!(|candidates| candidates.is_empty())
+                    on:click=move |_| apply_cleanup(&current)
+                >
+                    <span class="me-1"><BootstrapIcon icon="stars" /></span>
+                    "Clean up now"
+                </button>
+            </div>
+        })}
     }
 }
 
@@ -852,6 +3682,27 @@ fn DebugTab() -> impl IntoView {
                 />
                 <label class="form-check-label">"Debug mode"</label>
             </div>
+            <div class="form-check form-switch mb-2">
+                <input
+                    class="form-check-input"
+                    type="checkbox"
+                    role="switch"
+                    id="debug_audit_log"
+                    checked=move || with!(|settings| settings.audit_log)
+                    on:input=move |event| update_settings.update(move |settings| settings.audit_log = event_target_checked(&event))
+                />
+                <label class="form-check-label" for="debug_audit_log">"Record the prompts sent to the API (can be exported from the conversation header)"</label>
+            </div>
+
+            <StartupReport />
+
+            <SchemaReport />
+
+            <PromptInspector />
+
+            <RequestLog />
+
+            <StorageBrowser />
 
             <div class="d-flex flex-row mb-3">
                 <button
@@ -915,3 +3766,444 @@ fn DebugTab() -> impl IntoView {
         </div>
     }
 }
+
+fn format_size(bytes: f64) -> String {
+    if bytes >= 1024.0 * 1024.0 {
+        format!("{:.1} MiB", bytes / (1024.0 * 1024.0))
+    }
+    else {
+        format!("{:.1} KiB", bytes / 1024.0)
+    }
+}
+
+/// Shows how long the app took to start, and the size of the files it loaded.
+#[component]
+fn StartupReport() -> impl IntoView {
+    let resources = loaded_resources();
+    let total_transfer_size = resources
+        .iter()
+        .map(|resource| resource.transfer_size)
+        .sum::<f64>();
+    let total_decoded_size = resources
+        .iter()
+        .map(|resource| resource.decoded_size)
+        .sum::<f64>();
+    let startup_time = startup_time_ms()
+        .map(|ms| format!("{:.0} ms", ms))
+        .unwrap_or_else(|| "unknown".to_owned());
+
+    view! {
+        <h5 class="mt-3">"Startup"</h5>
+        <p>"App mounted after " {startup_time}</p>
+        <table class="table table-sm small mb-4">
+            <thead>
+                <tr>
+                    <th>"File"</th>
+                    <th class="text-end">"Transferred"</th>
+                    <th class="text-end">"Size"</th>
+                    <th class="text-end">"Load time"</th>
+                </tr>
+            </thead>
+            <tbody>
+                {resources.into_iter().map(|resource| {
+                    let name = resource.name.rsplit('/').next().unwrap_or_default().to_owned();
+                    view!{
+                        <tr>
+                            <td class="text-truncate" style="max-width: 20em;" title=resource.name>{name}</td>
+                            <td class="text-end">{format_size(resource.transfer_size)}</td>
+                            <td class="text-end">{format_size(resource.decoded_size)}</td>
+                            <td class="text-end">{format!("{:.0} ms", resource.duration_ms)}</td>
+                        </tr>
+                    }
+                }).collect_view()}
+            </tbody>
+            <tfoot>
+                <tr>
+                    <th>"Total"</th>
+                    <th class="text-end">{format_size(total_transfer_size)}</th>
+                    <th class="text-end">{format_size(total_decoded_size)}</th>
+                    <th></th>
+                </tr>
+            </tfoot>
+        </table>
+    }
+}
+
+/// Shows the exact prompt that would be sent for the next response in a
+/// conversation.
+#[component]
+fn PromptInspector() -> impl IntoView {
+    let Context {
+        settings,
+        summaries,
+        ..
+    } = expect_context();
+
+    let conversation_id = create_rw_signal(None::<ConversationId>);
+    // `None` uses the template of the conversation's model
+    let chat_template = create_rw_signal(None::<ChatTemplate>);
+    let prompt = create_rw_signal(None::<String>);
+
+    let conversations = move || {
+        with!(|summaries| {
+            let mut conversations = summaries
+                .values()
+                .map(|summary| {
+                    (
+                        summary.id,
+                        summary.timestamp_last_interaction,
+                        summary
+                            .title
+                            .clone()
+                            .unwrap_or_else(|| "Untitled".to_owned()),
+                    )
+                })
+                .collect::<Vec<_>>();
+            conversations.sort_by(|a, b| b.1.cmp(&a.1));
+            conversations
+        })
+    };
+
+    let generate = move |_| {
+        let Some(id) = conversation_id.get_untracked()
+        else {
+            return;
+        };
+        let StorageSignals {
+            read: conversation, ..
+        } = use_conversation(id);
+        let Some(conversation) = conversation.get_untracked()
+        else {
+            return;
+        };
+
+        let messages = conversation
+            .messages
+            .iter()
+            .filter_map(|message_id| {
+                let StorageSignals { read: message, .. } = use_message(*message_id);
+                message.get_untracked()
+            })
+            .collect::<Vec<_>>();
+
+        let template = chat_template.get_untracked().unwrap_or_else(|| {
+            settings.with_untracked(|settings| {
+                conversation
+                    .model_id
+                    .as_ref()
+                    .and_then(|model_id| settings.models.get(model_id))
+                    .map(|model| model.chat_template)
+                    .unwrap_or_default()
+            })
+        });
+
+        let conversation_parameters = &conversation.conversation_parameters;
+        let system_prompt = parameters::system_prompt(conversation_parameters);
+        prompt.set(Some(template.generate_prompt(
+            system_prompt.as_deref(),
+            &messages,
+            conversation_parameters.start_response_with.as_deref(),
+        )));
+    };
+
+    view! {
+        <h5 class="mt-3">"Prompt inspector"</h5>
+        <div class="d-flex flex-row mb-2">
+            <select
+                class="form-select me-2"
+                aria-label="Conversation"
+                on:input=move |event| {
+                    let id = Uuid::parse_str(&event_target_value(&event)).ok().map(ConversationId::from);
+                    conversation_id.set(id);
+                    prompt.set(None);
+                }
+            >
+                <option value="" selected>"Select a conversation"</option>
+                {move || conversations().into_iter().map(|(id, _, title)| view!{
+                    <option value=id.to_string() selected=move || conversation_id.get() == Some(id)>{title}</option>
+                }).collect_view()}
+            </select>
+            <select
+                class="form-select me-2"
+                aria-label="Chat template"
+                on:input=move |event| {
+                    chat_template.set(event_target_value(&event).parse().ok());
+                    prompt.set(None);
+                }
+            >
+                <option value="" selected>"Model's chat template"</option>
+                {<ChatTemplate as VariantArray>::VARIANTS.iter().map(|template| view!{
+                    <option value=template.as_ref()>{template.get_message()}</option>
+                }).collect_view()}
+            </select>
+            <button
+                type="button"
+                class="btn btn-outline-secondary text-nowrap"
+                disabled=move || conversation_id.get().is_none()
+                on:click=generate
+            >
+                <span class="me-2"><BootstrapIcon icon="eye" /></span>
+                "Show prompt"
+            </button>
+        </div>
+        {move || prompt.get().map(|prompt| {
+            let characters = prompt.chars().count();
+            let tokens = count_tokens(&prompt);
+            view!{
+                <pre class="border rounded p-2 small mb-1" style="white-space: pre-wrap;">{prompt}</pre>
+                <small class="d-block text-body-secondary mb-4">
+                    {format!("{characters} characters, ≈ {tokens} tokens")}
+                </small>
+            }
+        })}
+    }
+}
+
+/// The generation requests recorded in debug mode.
+#[component]
+fn RequestLog() -> impl IntoView {
+    let StorageSignals {
+        read: debug_log,
+        write: update_debug_log,
+        ..
+    } = use_debug_log();
+
+    view! {
+        <h5 class="mt-3">"Requests"</h5>
+        <div class="d-flex flex-row align-items-center mb-2">
+            <small class="text-body-secondary">"Generation requests are recorded while debug mode is on."</small>
+            <button
+                type="button"
+                class="btn btn-sm btn-outline-secondary ms-auto"
+                disabled=move || with!(|debug_log| debug_log.is_empty())
+                on:click=move |_| update_debug_log.set(vec![])
+            >
+                <span class="me-2"><BootstrapIcon icon="trash" /></span>
+                "Clear"
+            </button>
+        </div>
+        <table class="table table-sm small mb-4">
+            <thead>
+                <tr>
+                    <th>"Time"</th>
+                    <th>"Model"</th>
+                    <th>"Parameters"</th>
+                    <th class="text-end">"Prompt"</th>
+                    <th class="text-end">"Duration"</th>
+                    <th>"Response"</th>
+                </tr>
+            </thead>
+            <tbody>
+                {move || with!(|debug_log| debug_log.iter().rev().map(|entry| {
+                    let parameters = format!(
+                        "{}, temperature {}, max. {} tokens",
+                        if entry.stream { "stream" } else { "no stream" },
+                        entry.parameters.temperature,
+                        entry.parameters.max_new_tokens,
+                    );
+                    let outcome = match &entry.outcome {
+                        RequestOutcome::Success { tokens } => {
+                            view!{ <span class="text-success">{format!("{tokens} tokens")}</span> }
+                        }
+                        RequestOutcome::Failed { status, error } => {
+                            view!{
+                                <span class="text-danger" title=error.clone()>
+                                    {status.map_or_else(|| "Failed".to_owned(), |status| format!("Status {status}"))}
+                                </span>
+                            }
+                        }
+                    };
+                    view!{
+                        <tr>
+                            <td class="text-nowrap">{entry.timestamp.with_timezone(&Local).format("%H:%M:%S").to_string()}</td>
+                            <td class="text-truncate" style="max-width: 15em;" title=entry.model_id.to_string()>{entry.model_id.to_string()}</td>
+                            <td>{parameters}</td>
+                            <td class="text-end text-nowrap">{format!("{} chars", entry.prompt_length)}</td>
+                            <td class="text-end text-nowrap">{format!("{} ms", entry.duration_ms)}</td>
+                            <td>{outcome}</td>
+                        </tr>
+                    }
+                }).collect_view())}
+            </tbody>
+        </table>
+    }
+}
+
+/// Lists the entries in local storage, and lets the user edit or delete them,
+/// e.g. to fix a corrupted record.
+#[component]
+fn StorageBrowser() -> impl IntoView {
+    let entries = create_rw_signal(None::<Vec<(String, usize)>>);
+    let selected_key = create_rw_signal(None::<String>);
+    let json_invalid = create_rw_signal(false);
+    let saved = create_rw_signal(false);
+    let editor = create_node_ref::<Textarea>();
+
+    let refresh = move || entries.set(Some(list_storage_raw()));
+
+    // shows the value pretty-printed, if it's valid JSON.
+    let open = move |key: String| {
+        let value = read_storage_by_name(&key).unwrap_or_default();
+        let value = serde_json::from_str::<serde_json::Value>(&value)
+            .ok()
+            .and_then(|json| serde_json::to_string_pretty(&json).ok())
+            .unwrap_or(value);
+        if let Some(editor) = editor.get_untracked() {
+            editor.set_value(&value);
+        }
+        json_invalid.set(false);
+        saved.set(false);
+        selected_key.set(Some(key));
+    };
+
+    let save = move |_| {
+        let Some(key) = selected_key.get_untracked()
+        else {
+            return;
+        };
+        let value = editor.get_untracked().unwrap().value();
+        match serde_json::from_str::<serde_json::Value>(&value) {
+            Ok(json) => {
+                write_storage_by_name(&key, &json.to_string());
+                json_invalid.set(false);
+                saved.set(true);
+                refresh();
+            }
+            Err(_) => json_invalid.set(true),
+        }
+    };
+
+    let delete = move |key: String| {
+        log::warn!("deleting storage key: {key}");
+        delete_storage_by_name(&key);
+        if selected_key.get_untracked().as_ref() == Some(&key) {
+            selected_key.set(None);
+        }
+        refresh();
+    };
+
+    view! {
+        <h5 class="mt-3">"Storage"</h5>
+        <div class="mb-2">
+            <button
+                type="button"
+                class="btn btn-outline-secondary"
+                on:click=move |_| refresh()
+            >
+                <span class="me-2"><BootstrapIcon icon="database" /></span>
+                "Browse stored data"
+            </button>
+        </div>
+        {move || entries.get().map(|entries| view!{
+            <small class="d-block text-body-secondary mb-2">"Changes are written to storage directly. Reload the app afterwards, so that it picks them up."</small>
+            <div class="overflow-y-scroll mb-2" style="max-height: 30vh;">
+                <table class="table table-sm table-hover small mb-0">
+                    <thead>
+                        <tr>
+                            <th>"Key"</th>
+                            <th class="text-end">"Size"</th>
+                            <th></th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {entries.into_iter().map(|(key, size)| {
+                            let is_selected = {
+                                let key = key.clone();
+                                move || selected_key.with(|selected_key| selected_key.as_ref() == Some(&key))
+                            };
+                            let open_key = key.clone();
+                            let delete_key = key.clone();
+                            view!{
+                                <tr class:table-active=is_selected>
+                                    <td style="cursor: pointer;" on:click=move |_| open(open_key.clone())>{key}</td>
+                                    <td class="text-end text-nowrap">{format_size(size as f64)}</td>
+                                    <td class="text-end">
+                                        <button
+                                            type="button"
+                                            class="btn btn-sm btn-link link-danger p-0"
+                                            title="Delete"
+                                            on:click=move |_| delete(delete_key.clone())
+                                        >
+                                            <BootstrapIcon icon="trash" />
+                                        </button>
+                                    </td>
+                                </tr>
+                            }
+                        }).collect_view()}
+                    </tbody>
+                </table>
+            </div>
+        })}
+        <div class="mb-4" class:visually-hidden=move || selected_key.with(|selected_key| selected_key.is_none())>
+            <textarea
+                class="form-control font-monospace small mb-2"
+                class:is-invalid=json_invalid
+                rows="12"
+                node_ref=editor
+                on:input=move |_| saved.set(false)
+            ></textarea>
+            <div class="d-flex flex-row align-items-center">
+                <button type="button" class="btn btn-primary me-2" on:click=save>
+                    <span class="me-2"><BootstrapIcon icon="floppy" /></span>
+                    "Save"
+                </button>
+                {move || json_invalid.get().then(|| view!{ <small class="text-danger-emphasis">"Invalid JSON"</small> })}
+                {move || saved.get().then(|| view!{ <small class="text-success-emphasis">"Saved"</small> })}
+            </div>
+        </div>
+    }
+}
+
+/// Lists fields in stored records that don't match the current types.
+#[component]
+fn SchemaReport() -> impl IntoView {
+    let issues = create_rw_signal(None);
+
+    view! {
+        <h5 class="mt-3">"Stored data"</h5>
+        <div class="mb-2">
+            <button
+                type="button"
+                class="btn btn-outline-secondary"
+                on:click=move |_| issues.set(Some(check_storage()))
+            >
+                <span class="me-2"><BootstrapIcon icon="clipboard-check" /></span>
+                "Check stored data"
+            </button>
+        </div>
+        {move || issues.get().map(|issues| {
+            if issues.is_empty() {
+                view!{ <p class="text-success">"All records match the current schema."</p> }.into_view()
+            }
+            else {
+                view!{
+                    <table class="table table-sm small mb-4">
+                        <thead>
+                            <tr>
+                                <th>"Key"</th>
+                                <th>"Field"</th>
+                                <th>"Issue"</th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {issues.into_iter().map(|issue| {
+                                let description = match issue.kind {
+                                    IssueKind::Unknown => "Unknown field, will be dropped".to_owned(),
+                                    IssueKind::Missing => "Missing field, uses the default".to_owned(),
+                                    IssueKind::Invalid(error) => format!("Invalid record: {error}"),
+                                };
+                                view!{
+                                    <tr>
+                                        <td><code>{issue.key}</code></td>
+                                        <td><code>{issue.path}</code></td>
+                                        <td>{description}</td>
+                                    </tr>
+                                }
+                            }).collect_view()}
+                        </tbody>
+                    </table>
+                }.into_view()
+            }
+        })}
+    }
+}
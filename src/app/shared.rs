@@ -0,0 +1,102 @@
+//! Shows a conversation that was shared as a link.
+
+use chrono::Local;
+use leptos::{
+    component,
+    create_memo,
+    view,
+    with,
+    CollectView,
+    IntoView,
+    SignalWith,
+};
+use leptos_router::use_location;
+
+use super::{
+    expect_context,
+    BootstrapIcon,
+    Context,
+};
+use crate::{
+    render::render_shared_markdown,
+    share::SharedConversation,
+    state::Role,
+};
+
+#[component]
+pub fn Shared() -> impl IntoView {
+    let Context { settings, .. } = expect_context();
+
+    let hash = use_location().hash;
+    let shared = create_memo(move |_| {
+        with!(|hash| {
+            SharedConversation::decode(hash.trim_start_matches('#'))
+                .map_err(|error| error.to_string())
+        })
+    });
+
+    move || {
+        with!(|shared| {
+            match shared {
+                Ok(shared) => {
+                    let model_name = shared.model_id.as_ref().map(|model_id| {
+                        settings.with(|settings| {
+                            settings
+                                .models
+                                .get(model_id)
+                                .map(|model| model.display_name().to_owned())
+                                .unwrap_or_else(|| model_id.to_string())
+                        })
+                    });
+                    let timestamp = shared
+                        .timestamp
+                        .with_timezone(&Local)
+                        .format("%c")
+                        .to_string();
+
+                    view! {
+                        <div class="d-flex flex-column h-100 w-100">
+                            <div class="d-flex flex-row pt-3 px-4 pb-2 shadow-sm">
+                                <h4>{shared.title.clone().unwrap_or_else(|| "Untitled".to_owned())}</h4>
+                                {model_name.map(|model_name| view!{
+                                    <h6 class="mt-auto ms-4"><span class="badge bg-secondary">{model_name}</span></h6>
+                                })}
+                                <small class="mt-auto ms-auto text-body-secondary" title="This is a read-only copy of a conversation.">
+                                    <span class="me-1"><BootstrapIcon icon="share" /></span>
+                                    "Shared conversation from "
+                                    {timestamp}
+                                </small>
+                            </div>
+                            <div class="d-flex flex-column overflow-y-scroll mb-auto p-4 mw-100">
+                                {shared.messages.iter().map(|message| {
+                                    let is_assistant = matches!(message.role, Role::Assitant);
+                                    view!{
+                                        <div class="d-flex flex-column w-75 mw-75 my-2 message-container" class:ms-auto=is_assistant>
+                                            <div
+                                                class="rounded rounded-3 p-2 shadow-sm message markdown"
+                                                class:message-user=!is_assistant
+                                                class:message-assistant=is_assistant
+                                                inner_html=render_shared_markdown(&message.text)
+                                            >
+                                            </div>
+                                        </div>
+                                    }
+                                }).collect_view()}
+                            </div>
+                        </div>
+                    }
+                    .into_view()
+                }
+                Err(error) => {
+                    view! {
+                        <div class="h-100 w-100 pt-3 px-4">
+                            <h4>"Can't show the shared conversation"</h4>
+                            <p class="text-body-secondary">{error.clone()}</p>
+                        </div>
+                    }
+                    .into_view()
+                }
+            }
+        })
+    }
+}
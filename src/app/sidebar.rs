@@ -0,0 +1,522 @@
+//! Entries of the conversation list in the sidebar, with a menu for the
+//! common actions, so that a conversation doesn't have to be opened first.
+//! In selection mode the actions work on many conversations at once.
+
+use std::collections::HashSet;
+
+use leptos::{
+    component,
+    create_effect,
+    create_node_ref,
+    create_rw_signal,
+    html::{
+        Button,
+        Input,
+    },
+    view,
+    with,
+    For,
+    IntoView,
+    RwSignal,
+    Signal,
+    SignalGet,
+    SignalGetUntracked,
+    SignalSet,
+    SignalUpdate,
+    SignalWith,
+    SignalWithUntracked,
+};
+use leptos_router::{
+    use_location,
+    use_navigate,
+    NavigateOptions,
+    A,
+};
+use serde::Serialize;
+use uuid::Uuid;
+use web_sys::{
+    KeyboardEvent,
+    MouseEvent,
+    SubmitEvent,
+};
+
+use super::{
+    conversation::{
+        export_conversation_html,
+        DeleteConversationModal,
+    },
+    expect_context,
+    refresh_summary,
+    trash_conversations,
+    BootstrapIcon,
+    Context,
+};
+use crate::{
+    state::{
+        use_conversation,
+        use_message,
+        Conversation,
+        ConversationId,
+        Message,
+        StorageSignals,
+    },
+    utils::{
+        download_file,
+        non_empty,
+    },
+};
+
+pub const SIDEBAR_DELETE_MODAL_ID: &'static str = "sidebar_delete_modal";
+
+pub fn update_conversation(id: ConversationId, update: impl FnOnce(&mut Conversation)) {
+    let StorageSignals {
+        write: update_conversation,
+        ..
+    } = use_conversation(id);
+    update_conversation.update(|conversation| {
+        if let Some(conversation) = conversation {
+            update(conversation);
+        }
+    });
+    refresh_summary(id);
+}
+
+/// Which conversations are selected in the sidebar for a batch operation.
+#[derive(Copy, Clone)]
+pub struct Selection {
+    pub active: RwSignal<bool>,
+    pub selected: RwSignal<HashSet<ConversationId>>,
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Self {
+            active: create_rw_signal(false),
+            selected: create_rw_signal(HashSet::new()),
+        }
+    }
+}
+
+impl Selection {
+    pub fn is_selected(&self, id: ConversationId) -> bool {
+        self.selected.with(|selected| selected.contains(&id))
+    }
+
+    pub fn toggle(&self, id: ConversationId) {
+        self.selected.update(|selected| {
+            if !selected.remove(&id) {
+                selected.insert(id);
+            }
+        });
+    }
+
+    pub fn ids(&self) -> Vec<ConversationId> {
+        self.selected
+            .with_untracked(|selected| selected.iter().copied().collect())
+    }
+
+    /// Leaves selection mode.
+    pub fn finish(&self) {
+        self.active.set(false);
+        self.selected.update(|selected| selected.clear());
+    }
+}
+
+#[derive(Serialize)]
+struct ExportedConversation {
+    conversation: Conversation,
+    messages: Vec<Message>,
+}
+
+/// Downloads conversations with all their messages as one JSON file.
+fn export_conversations_json(ids: &[ConversationId]) {
+    let Context { errors, .. } = expect_context();
+
+    let exported = ids
+        .iter()
+        .filter_map(|id| use_conversation(*id).read.get_untracked())
+        .map(|conversation| {
+            let messages = conversation
+                .messages
+                .iter()
+                .filter_map(|message_id| use_message(*message_id).read.get_untracked())
+                .collect();
+            ExportedConversation {
+                conversation,
+                messages,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    match serde_json::to_string_pretty(&exported) {
+        Ok(json) => download_file("conversations.json", "application/json", &json),
+        Err(error) => errors.push(error),
+    }
+}
+
+#[component]
+pub fn SidebarConversation(
+    id: ConversationId,
+    group: Option<Uuid>,
+    /// Set to this conversation when deleting it is chosen from the menu.
+    delete_targets: RwSignal<Vec<ConversationId>>,
+    selection: Selection,
+) -> impl IntoView {
+    let Context {
+        summaries,
+        settings,
+        ..
+    } = expect_context();
+
+    let title = Signal::derive(move || {
+        with!(|summaries| summaries.get(&id).and_then(|summary| summary.title.clone()))
+    });
+    let pinned = Signal::derive(move || {
+        with!(|summaries| summaries.get(&id).map_or(false, |summary| summary.pinned))
+    });
+    // in a group the titles are usually the same, so we show the model too.
+    let group_model_name = group
+        .is_some()
+        .then(|| {
+            with!(|summaries, settings| {
+                let model_id = summaries.get(&id)?.model_id.as_ref()?;
+                Some(
+                    settings
+                        .models
+                        .get(model_id)
+                        .map(|model| model.display_name().to_owned())
+                        .unwrap_or_else(|| model_id.to_string()),
+                )
+            })
+        })
+        .flatten();
+
+    let renaming = create_rw_signal(false);
+    let menu_toggle = create_node_ref::<Button>();
+
+    // right-clicking the entry opens the same menu as the button.
+    let open_menu = move |event: MouseEvent| {
+        event.prevent_default();
+        if let Some(menu_toggle) = menu_toggle.get_untracked() {
+            menu_toggle.click();
+        }
+    };
+
+    view! {
+        {move || if renaming.get() {
+            let title_input = create_node_ref::<Input>();
+
+            create_effect(move |_| {
+                if let Some(title_input) = title_input.get() {
+                    title_input.focus().ok();
+                }
+            });
+
+            let set_title = move || {
+                // submitting removes the input, which makes it lose focus too.
+                if !renaming.get_untracked() {
+                    return;
+                }
+                renaming.set(false);
+                let Some(title_input) = title_input.get_untracked() else { return; };
+                let Some(new_title) = non_empty(title_input.value()) else { return; };
+                update_conversation(id, move |conversation| {
+                    conversation.title = Some(new_title);
+                    // the user chose a title, so don't override it with a generated one.
+                    conversation.auto_title = false;
+                });
+            };
+
+            view! {
+                <li class="nav-item">
+                    <form on:submit=move |event: SubmitEvent| {
+                        event.prevent_default();
+                        set_title();
+                    }>
+                        <input
+                            type="text"
+                            class="form-control form-control-sm"
+                            placeholder="Conversation title"
+                            value=title.get_untracked()
+                            node_ref=title_input
+                            on:focusout=move |_| set_title()
+                            on:keydown=move |event: KeyboardEvent| {
+                                if event.key() == "Escape" {
+                                    renaming.set(false);
+                                }
+                            }
+                        />
+                    </form>
+                </li>
+            }.into_view()
+        }
+        else {
+            // the entry is rebuilt when renaming ends, so it gets its own copies.
+            let group_model_name = group_model_name.clone();
+            let toggle_pinned = move |_| {
+                update_conversation(id, |conversation| {
+                    conversation.pinned = !conversation.pinned
+                });
+            };
+            let archive = move |_| {
+                update_conversation(id, |conversation| conversation.archived = true);
+            };
+
+            view! {
+                <li class="nav-item d-flex flex-row align-items-center sidebar-conversation" on:contextmenu=open_menu>
+                    {move || selection.active.get().then(|| view!{
+                        <input
+                            type="checkbox"
+                            class="form-check-input ms-1 me-2 flex-shrink-0"
+                            title="Select"
+                            prop:checked=move || selection.is_selected(id)
+                            on:change=move |_| selection.toggle(id)
+                        />
+                    })}
+                    <A href=format!("/conversation/{id}") active_class="active" class="nav-link text-light flex-grow-1">
+                        <div class="text-nowrap text-truncate" class:ps-2=group.is_some() class:border-start=group.is_some() style="width: 176px">
+                            {group_model_name.map(|model_name| view!{
+                                <small class="d-block text-truncate opacity-75" title="Started together with other models">
+                                    <span class="me-1"><BootstrapIcon icon="diagram-3" /></span>
+                                    {model_name}
+                                </small>
+                            })}
+                            {move || pinned.get().then(|| view!{
+                                <span class="me-2" title="Pinned"><BootstrapIcon icon="pin-angle-fill" /></span>
+                            })}
+                            {move || {
+                                if let Some(title) = title.get() {
+                                    view!{{title}}.into_view()
+                                }
+                                else {
+                                    view!{
+                                        <span class="me-2"><BootstrapIcon icon="question-lg" /></span>
+                                        "Untitled"
+                                    }.into_view()
+                                }
+                            }}
+                        </div>
+                    </A>
+                    <div class="dropdown">
+                        <button
+                            type="button"
+                            class="btn btn-sm text-light px-1 conversation-menu-toggle"
+                            title="More actions"
+                            aria-label="More actions"
+                            data-bs-toggle="dropdown"
+                            aria-expanded="false"
+                            node_ref=menu_toggle
+                        >
+                            <BootstrapIcon icon="three-dots-vertical" />
+                        </button>
+                        <ul class="dropdown-menu">
+                            <li>
+                                <button type="button" class="dropdown-item" on:click=move |_| renaming.set(true)>
+                                    <span class="me-2"><BootstrapIcon icon="pencil-square" /></span>
+                                    "Rename"
+                                </button>
+                            </li>
+                            <li>
+                                <button type="button" class="dropdown-item" on:click=toggle_pinned>
+                                    {move || if pinned.get() {
+                                        view!{
+                                            <span class="me-2"><BootstrapIcon icon="pin-angle" /></span>
+                                            "Unpin"
+                                        }.into_view()
+                                    }
+                                    else {
+                                        view!{
+                                            <span class="me-2"><BootstrapIcon icon="pin-angle-fill" /></span>
+                                            "Pin"
+                                        }.into_view()
+                                    }}
+                                </button>
+                            </li>
+                            <li>
+                                <button type="button" class="dropdown-item" on:click=archive>
+                                    <span class="me-2"><BootstrapIcon icon="archive" /></span>
+                                    "Archive"
+                                </button>
+                            </li>
+                            <li>
+                                <button type="button" class="dropdown-item" on:click=move |_| export_conversation_html(id)>
+                                    <span class="me-2"><BootstrapIcon icon="filetype-html" /></span>
+                                    "Export as HTML"
+                                </button>
+                            </li>
+                            <li><hr class="dropdown-divider" /></li>
+                            <li>
+                                <button
+                                    type="button"
+                                    class="dropdown-item text-danger"
+                                    data-bs-toggle="modal"
+                                    data-bs-target=format!("#{SIDEBAR_DELETE_MODAL_ID}")
+                                    on:click=move |_| delete_targets.set(vec![id])
+                                >
+                                    <span class="me-2"><BootstrapIcon icon="trash-fill" /></span>
+                                    "Delete"
+                                </button>
+                            </li>
+                        </ul>
+                    </div>
+                </li>
+            }.into_view()
+        }}
+    }
+}
+
+/// The actions for the selected conversations, shown in selection mode.
+#[component]
+pub fn SelectionToolbar(
+    selection: Selection,
+    /// The conversations that are listed, for selecting all of them.
+    #[prop(into)]
+    listed: Signal<Vec<ConversationId>>,
+    delete_targets: RwSignal<Vec<ConversationId>>,
+) -> impl IntoView {
+    let Context { tags, .. } = expect_context();
+
+    let count = Signal::derive(move || selection.selected.with(|selected| selected.len()));
+    let none_selected = Signal::derive(move || count.get() == 0);
+
+    let select_all = move |_| {
+        let all_selected = selection.selected.with_untracked(|selected| {
+            listed.with_untracked(|listed| listed.iter().all(|id| selected.contains(id)))
+        });
+        selection.selected.update(|selected| {
+            if all_selected {
+                selected.clear();
+            }
+            else {
+                selected.extend(listed.get_untracked());
+            }
+        });
+    };
+
+    let archive = move |_| {
+        for id in selection.ids() {
+            update_conversation(id, |conversation| conversation.archived = true);
+        }
+        selection.finish();
+    };
+
+    let export = move |_| export_conversations_json(&selection.ids());
+
+    view! {
+        <div class="d-flex flex-row align-items-center mb-2">
+            <small class="me-auto">{move || format!("{} selected", count.get())}</small>
+            <div class="btn-group btn-group-sm">
+                <button type="button" class="btn btn-outline-light" title="Select all" aria-label="Select all" on:click=select_all>
+                    <BootstrapIcon icon="check-all" />
+                </button>
+                <button type="button" class="btn btn-outline-light" title="Archive" aria-label="Archive" disabled=none_selected on:click=archive>
+                    <BootstrapIcon icon="archive" />
+                </button>
+                <div class="btn-group btn-group-sm">
+                    <button
+                        type="button"
+                        class="btn btn-outline-light"
+                        title="Add tag"
+                        aria-label="Add tag"
+                        data-bs-toggle="dropdown"
+                        disabled=none_selected
+                    >
+                        <BootstrapIcon icon="tag" />
+                    </button>
+                    <ul class="dropdown-menu">
+                        <For
+                            each={move || tags.get().into_iter().collect::<Vec<_>>()}
+                            key=|tag| tag.clone()
+                            children=move |tag| {
+                                let label = tag.clone();
+                                let add = move |_| {
+                                    for id in selection.ids() {
+                                        let tag = tag.clone();
+                                        update_conversation(id, move |conversation| {
+                                            if !conversation.tags.contains(&tag) {
+                                                conversation.tags.push(tag);
+                                            }
+                                        });
+                                    }
+                                };
+                                view!{
+                                    <li>
+                                        <button type="button" class="dropdown-item" on:click=add>{label}</button>
+                                    </li>
+                                }
+                            }
+                        />
+                        {move || with!(|tags| tags.is_empty()).then(|| view!{
+                            <li><span class="dropdown-item-text text-body-secondary">"No tags yet"</span></li>
+                        })}
+                    </ul>
+                </div>
+                <button type="button" class="btn btn-outline-light" title="Export as JSON" aria-label="Export as JSON" disabled=none_selected on:click=export>
+                    <BootstrapIcon icon="download" />
+                </button>
+                <button
+                    type="button"
+                    class="btn btn-outline-danger"
+                    title="Delete"
+                    aria-label="Delete"
+                    disabled=none_selected
+                    data-bs-toggle="modal"
+                    data-bs-target=format!("#{SIDEBAR_DELETE_MODAL_ID}")
+                    on:click=move |_| delete_targets.set(selection.ids())
+                >
+                    <BootstrapIcon icon="trash-fill" />
+                </button>
+            </div>
+        </div>
+    }
+}
+
+/// The delete confirmation for conversations in the sidebar. There's only one
+/// of these, and `targets` says which conversations it is for.
+#[component]
+pub fn SidebarDeleteModal(
+    targets: RwSignal<Vec<ConversationId>>,
+    selection: Selection,
+) -> impl IntoView {
+    let location = use_location();
+    let navigate = use_navigate();
+
+    let delete_conversations = move |()| {
+        let ids = targets.get_untracked();
+        if ids.is_empty() {
+            return;
+        }
+        targets.set(vec![]);
+        selection.selected.update(|selected| {
+            for id in &ids {
+                selected.remove(id);
+            }
+        });
+
+        // if the open conversation is deleted, browse to home, but don't
+        // remember it in the history.
+        let pathname = location.pathname.get_untracked();
+        if ids
+            .iter()
+            .any(|id| pathname == format!("/conversation/{id}"))
+        {
+            navigate(
+                "/",
+                NavigateOptions {
+                    replace: true,
+                    ..Default::default()
+                },
+            );
+        }
+
+        trash_conversations(ids);
+    };
+
+    view! {
+        <DeleteConversationModal
+            id=SIDEBAR_DELETE_MODAL_ID
+            on_delete=delete_conversations
+            count=Signal::derive(move || with!(|targets| targets.len()))
+        />
+    }
+}
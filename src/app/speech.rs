@@ -0,0 +1,233 @@
+//! Voice input: records audio from the microphone and transcribes it with a
+//! speech recognition model (e.g. Whisper) on the Inference API.
+
+use js_sys::{
+    Array,
+    Uint8Array,
+};
+use leptos::{
+    component,
+    create_rw_signal,
+    spawn_local,
+    store_value,
+    view,
+    with,
+    Callback,
+    IntoView,
+    MaybeSignal,
+    Signal,
+    SignalGet,
+    SignalGetUntracked,
+    SignalSet,
+    SignalWithUntracked,
+};
+use wasm_bindgen::{
+    closure::Closure,
+    JsCast,
+    JsValue,
+};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob,
+    BlobEvent,
+    BlobPropertyBag,
+    MediaRecorder,
+    MediaStream,
+    MediaStreamConstraints,
+    MediaStreamTrack,
+};
+
+use super::{
+    expect_context,
+    BootstrapIcon,
+    Context,
+    Error,
+};
+use crate::{
+    backend::huggingface::HuggingFace,
+    state::ModelId,
+};
+
+/// What browsers record in, if the recorder doesn't tell us.
+const DEFAULT_CONTENT_TYPE: &'static str = "audio/webm";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RecordingState {
+    Idle,
+    Recording,
+    Transcribing,
+}
+
+/// Asks for the microphone and creates a recorder for it.
+async fn open_microphone() -> Result<MediaRecorder, JsValue> {
+    let media_devices = gloo_utils::window().navigator().media_devices()?;
+    let mut constraints = MediaStreamConstraints::new();
+    constraints.audio(&JsValue::TRUE);
+    let stream: MediaStream =
+        JsFuture::from(media_devices.get_user_media_with_constraints(&constraints)?)
+            .await?
+            .unchecked_into();
+    MediaRecorder::new_with_media_stream(&stream)
+}
+
+/// Stops the microphone, so the browser doesn't show it as in use anymore.
+fn close_microphone(stream: &MediaStream) {
+    for track in stream.get_tracks().iter() {
+        track.unchecked_into::<MediaStreamTrack>().stop();
+    }
+}
+
+async fn transcribe(
+    backend: HuggingFace,
+    model_id: ModelId,
+    chunks: Array,
+    content_type: String,
+) -> Result<String, Error> {
+    let mut options = BlobPropertyBag::new();
+    options.type_(&content_type);
+    let blob = Blob::new_with_blob_sequence_and_options(&chunks, &options)
+        .map_err(|_| Error::Recording)?;
+    let buffer = JsFuture::from(blob.array_buffer())
+        .await
+        .map_err(|_| Error::Recording)?;
+    let audio = Uint8Array::new(&buffer).to_vec();
+
+    Ok(backend.transcribe(&model_id, audio, &content_type).await?)
+}
+
+/// A microphone button that records while toggled on, and then calls
+/// `on_transcript` with the transcribed text. Only shown if a speech model is
+/// set.
+#[component]
+pub fn VoiceInput(
+    #[prop(into)] on_transcript: Callback<String>,
+    #[prop(into, optional)] disabled: MaybeSignal<bool>,
+) -> impl IntoView {
+    let Context {
+        settings, errors, ..
+    } = expect_context();
+
+    let state = create_rw_signal(RecordingState::Idle);
+    let recorder = store_value(None::<MediaRecorder>);
+    let disabled = Signal::derive(move || disabled.get());
+    let enabled = Signal::derive(move || with!(|settings| settings.speech_model.is_some()));
+
+    let start_recording = move || {
+        state.set(RecordingState::Recording);
+
+        spawn_local(async move {
+            let media_recorder = match open_microphone().await {
+                Ok(media_recorder) => media_recorder,
+                Err(error) => {
+                    log::error!("failed to open microphone: {error:?}");
+                    errors.push(Error::Recording);
+                    state.set(RecordingState::Idle);
+                    return;
+                }
+            };
+
+            let chunks = Array::new();
+            let on_data = Closure::<dyn FnMut(BlobEvent)>::new({
+                let chunks = chunks.clone();
+                move |event: BlobEvent| {
+                    if let Some(data) = event.data() {
+                        chunks.push(&data);
+                    }
+                }
+            });
+            media_recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+            on_data.forget();
+
+            let on_stop = Closure::once_into_js({
+                let media_recorder = media_recorder.clone();
+                move || {
+                    close_microphone(&media_recorder.stream());
+
+                    let Some((backend, model_id)) = settings.with_untracked(|settings| {
+                        Some((settings.backend(), settings.speech_model.clone()?))
+                    })
+                    else {
+                        state.set(RecordingState::Idle);
+                        return;
+                    };
+                    let content_type = Some(media_recorder.mime_type())
+                        .filter(|content_type| !content_type.is_empty())
+                        .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_owned());
+
+                    state.set(RecordingState::Transcribing);
+                    spawn_local(async move {
+                        match transcribe(backend, model_id, chunks, content_type).await {
+                            Ok(transcript) => on_transcript(transcript.trim().to_owned()),
+                            Err(error) => errors.push(error),
+                        }
+                        state.set(RecordingState::Idle);
+                    });
+                }
+            });
+            media_recorder.set_onstop(Some(on_stop.unchecked_ref()));
+
+            if let Err(error) = media_recorder.start() {
+                log::error!("failed to start recording: {error:?}");
+                close_microphone(&media_recorder.stream());
+                errors.push(Error::Recording);
+                state.set(RecordingState::Idle);
+                return;
+            }
+            recorder.set_value(Some(media_recorder));
+        });
+    };
+
+    let stop_recording = move || {
+        if let Some(media_recorder) = recorder.get_value() {
+            media_recorder.stop().ok();
+        }
+        recorder.set_value(None);
+    };
+
+    let on_click = move |_| {
+        match state.get_untracked() {
+            RecordingState::Idle => start_recording(),
+            RecordingState::Recording => stop_recording(),
+            RecordingState::Transcribing => {}
+        }
+    };
+
+    move || {
+        enabled.get().then(|| {
+            view! {
+                <button
+                    class="btn btn-outline-secondary"
+                    class:text-danger=move || state.get() == RecordingState::Recording
+                    type="button"
+                    title=move || {
+                        match state.get() {
+                            RecordingState::Idle => "Record a message",
+                            RecordingState::Recording => "Stop recording",
+                            RecordingState::Transcribing => "Transcribing...",
+                        }
+                    }
+                    disabled=move || {
+                        state.get() == RecordingState::Transcribing
+                            || (state.get() == RecordingState::Idle && disabled.get())
+                    }
+                    on:click=on_click
+                >
+                    {move || {
+                        match state.get() {
+                            RecordingState::Transcribing => {
+                                view! {
+                                    <div class="spinner-border spinner-border-sm" role="status">
+                                        <span class="visually-hidden">"Transcribing..."</span>
+                                    </div>
+                                }
+                                .into_view()
+                            }
+                            RecordingState::Recording => view! { <BootstrapIcon icon="stop-circle" /> }.into_view(),
+                            RecordingState::Idle => view! { <BootstrapIcon icon="mic" /> }.into_view(),
+                        }
+                    }}
+                </button>
+            }
+        })
+    }
+}
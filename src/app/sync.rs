@@ -0,0 +1,216 @@
+//! Keeps the conversations in sync with the server from the settings, and
+//! shows how that went in the sidebar.
+
+use std::time::Duration;
+
+use chrono::{
+    DateTime,
+    Local,
+    Utc,
+};
+use leptos::{
+    component,
+    set_interval,
+    spawn_local,
+    view,
+    IntoView,
+    SignalGet,
+    SignalSet,
+    SignalUpdate,
+    SignalWith,
+    SignalWithUntracked,
+};
+
+use super::{
+    expect_context,
+    refresh_summary,
+    BootstrapIcon,
+    Context,
+    Error,
+};
+use crate::{
+    state::{
+        use_conversation,
+        use_message,
+        StorageSignals,
+    },
+    sync::{
+        download,
+        local_snapshot,
+        merge,
+        upload,
+        SyncError,
+    },
+};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How often we download and merge again, when other devices keep uploading
+/// while we sync.
+const MAX_SYNC_ATTEMPTS: usize = 3;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SyncStatus {
+    #[default]
+    Idle,
+    Syncing,
+    Synced(DateTime<Utc>),
+    Failed(String),
+}
+
+/// Syncs the conversations, if a sync server is set.
+pub fn sync_conversations() {
+    let Context {
+        settings,
+        conversations,
+        update_conversations,
+        trash,
+        update_trash,
+        loading,
+        sync_status,
+        errors,
+        ..
+    } = expect_context();
+
+    let Some(sync_settings) = settings.with_untracked(|settings| settings.sync.clone())
+    else {
+        return;
+    };
+    if sync_status.with_untracked(|status| *status == SyncStatus::Syncing) {
+        return;
+    }
+    // only report an error once, and not on every periodic sync.
+    let failed_before =
+        sync_status.with_untracked(|status| matches!(status, SyncStatus::Failed(_)));
+    sync_status.set(SyncStatus::Syncing);
+
+    spawn_local(async move {
+        let mut attempts = 0;
+        let result = loop {
+            attempts += 1;
+            let result = async {
+                let (remote, precondition) = download(&sync_settings).await?;
+                let local = conversations.with_untracked(|conversations| {
+                    trash.with_untracked(|trash| local_snapshot(conversations, trash))
+                });
+                let merged = merge(local, remote);
+                log::info!(
+                    "sync: pulled {}, deleted {}",
+                    merged.pulled.len(),
+                    merged.deleted.len()
+                );
+
+                for synced in merged.pulled {
+                    let id = synced.conversation.id;
+                    // the local version will be newer after the response is done.
+                    if loading.with_untracked(|loading| loading.contains(&id)) {
+                        continue;
+                    }
+
+                    for message in synced.messages {
+                        let StorageSignals {
+                            write: set_message, ..
+                        } = use_message(message.id);
+                        set_message.set(Some(message));
+                    }
+                    let StorageSignals {
+                        write: set_conversation,
+                        ..
+                    } = use_conversation(id);
+                    set_conversation.set(Some(synced.conversation));
+                    refresh_summary(id);
+
+                    update_trash.update(|trash| {
+                        trash.remove(&id);
+                    });
+                    update_conversations.update(|conversations| {
+                        conversations.insert(id);
+                    });
+                }
+
+                for (id, deleted) in merged.deleted {
+                    update_conversations.update(|conversations| {
+                        conversations.remove(&id);
+                    });
+                    update_trash.update(|trash| {
+                        trash.insert(id, deleted);
+                    });
+                }
+
+                upload(&sync_settings, &merged.snapshot, &precondition).await?;
+
+                Ok::<(), SyncError>(())
+            }
+            .await;
+
+            match result {
+                // another device synced in between, so we merge with its snapshot.
+                Err(SyncError::Conflict) if attempts < MAX_SYNC_ATTEMPTS => {
+                    log::info!("sync: snapshot changed on the server, merging again");
+                }
+                result => break result,
+            }
+        };
+
+        match result {
+            Ok(()) => sync_status.set(SyncStatus::Synced(Utc::now())),
+            Err(error) => {
+                sync_status.set(SyncStatus::Failed(error.to_string()));
+                if failed_before {
+                    log::error!("sync failed: {error}");
+                }
+                else {
+                    errors.push(Error::from(error));
+                }
+            }
+        }
+    });
+}
+
+/// Syncs now, and then periodically.
+pub fn start_sync() {
+    sync_conversations();
+    set_interval(sync_conversations, SYNC_INTERVAL);
+}
+
+/// The sync status in the sidebar footer. Clicking it syncs right away.
+#[component]
+pub fn SyncIndicator() -> impl IntoView {
+    let Context {
+        settings,
+        sync_status,
+        ..
+    } = expect_context();
+
+    move || {
+        settings.with(|settings| settings.sync.is_some()).then(|| {
+            let (icon, text, title) = match sync_status.get() {
+                SyncStatus::Idle => ("cloud", "Not synced yet".to_owned(), None),
+                SyncStatus::Syncing => ("arrow-repeat", "Syncing...".to_owned(), None),
+                SyncStatus::Synced(timestamp) => {
+                    (
+                        "cloud-check",
+                        format!(
+                            "Synced {}",
+                            timestamp.with_timezone(&Local).format("%H:%M")
+                        ),
+                        None,
+                    )
+                }
+                SyncStatus::Failed(error) => ("cloud-slash", "Sync failed".to_owned(), Some(error)),
+            };
+            view! {
+                <button
+                    type="button"
+                    class="btn btn-sm btn-link link-light text-decoration-none text-start p-0 mb-2 opacity-75"
+                    title=title.unwrap_or_else(|| "Sync now".to_owned())
+                    disabled=move || sync_status.with(|status| *status == SyncStatus::Syncing)
+                    on:click=|_| sync_conversations()
+                >
+                    <span class="me-2"><BootstrapIcon icon=icon /></span>
+                    {text}
+                </button>
+            }
+        })
+    }
+}
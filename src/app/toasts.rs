@@ -0,0 +1,222 @@
+//! Toast notifications. They stack in the corner of the page, and go away on
+//! their own after a while, except for errors, which stay until they are
+//! closed.
+
+use std::time::Duration;
+
+use leptos::{
+    component,
+    create_rw_signal,
+    set_timeout,
+    view,
+    Callback,
+    CollectView,
+    For,
+    IntoView,
+    RwSignal,
+    SignalGet,
+    SignalUpdate,
+    SignalWith,
+};
+use uuid::Uuid;
+
+use super::{
+    expect_context,
+    BootstrapIcon,
+    Context,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    /// How long a toast is shown if it isn't closed, or `None` if it stays.
+    fn default_timeout(&self) -> Option<Duration> {
+        match self {
+            Self::Info | Self::Success => Some(Duration::from_secs(5)),
+            Self::Warning => Some(Duration::from_secs(10)),
+            Self::Error => None,
+        }
+    }
+
+    fn icon(&self) -> &'static str {
+        match self {
+            Self::Info => "info-circle",
+            Self::Success => "check-circle",
+            Self::Warning => "exclamation-triangle",
+            Self::Error => "exclamation-circle",
+        }
+    }
+
+    /// The bootstrap color of the toast.
+    fn color(&self) -> &'static str {
+        match self {
+            Self::Info => "text-bg-light",
+            Self::Success => "text-bg-success",
+            Self::Warning => "text-bg-warning",
+            Self::Error => "text-bg-danger",
+        }
+    }
+}
+
+/// A button on a toast, e.g. "Undo". Clicking it closes the toast.
+#[derive(Clone)]
+pub struct ToastAction {
+    pub label: String,
+    pub action: Callback<()>,
+}
+
+#[derive(Clone)]
+pub struct Toast {
+    id: Uuid,
+    pub level: ToastLevel,
+    pub message: String,
+    /// Shown below the message, e.g. the causes of an error.
+    pub details: Vec<String>,
+    pub actions: Vec<ToastAction>,
+    pub timeout: Option<Duration>,
+}
+
+impl Toast {
+    pub fn new(level: ToastLevel, message: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            level,
+            message: message.into(),
+            details: vec![],
+            actions: vec![],
+            timeout: level.default_timeout(),
+        }
+    }
+
+    pub fn with_details(mut self, details: Vec<String>) -> Self {
+        self.details = details;
+        self
+    }
+
+    pub fn with_action(mut self, label: impl Into<String>, action: impl Fn(()) + 'static) -> Self {
+        self.actions.push(ToastAction {
+            label: label.into(),
+            action: Callback::new(action),
+        });
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Toasts(RwSignal<Vec<Toast>>);
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self(create_rw_signal(vec![]))
+    }
+}
+
+impl Toasts {
+    /// Shows a toast, and returns its ID so that it can be dismissed early.
+    pub fn show(&self, toast: Toast) -> Uuid {
+        let id = toast.id;
+        let timeout = toast.timeout;
+        self.0.update(|toasts| toasts.push(toast));
+
+        if let Some(timeout) = timeout {
+            let toasts = *self;
+            set_timeout(move || toasts.dismiss(id), timeout);
+        }
+
+        id
+    }
+
+    pub fn info(&self, message: impl Into<String>) -> Uuid {
+        self.show(Toast::new(ToastLevel::Info, message))
+    }
+
+    pub fn success(&self, message: impl Into<String>) -> Uuid {
+        self.show(Toast::new(ToastLevel::Success, message))
+    }
+
+    pub fn warning(&self, message: impl Into<String>) -> Uuid {
+        self.show(Toast::new(ToastLevel::Warning, message))
+    }
+
+    pub fn dismiss(&self, id: Uuid) {
+        self.0
+            .update(|toasts| toasts.retain(|toast| toast.id != id));
+    }
+
+    pub fn dismiss_all(&self) {
+        self.0.update(|toasts| toasts.clear());
+    }
+}
+
+#[component]
+pub fn ToastContainer() -> impl IntoView {
+    let Context { toasts, .. } = expect_context();
+
+    view! {
+        <div class="toast-container position-absolute bottom-0 end-0 p-3">
+            {move || (toasts.0.with(|toasts| toasts.len()) > 1).then(|| view!{
+                <div class="d-flex justify-content-end">
+                    <button type="button" class="btn btn-sm btn-light border" on:click=move |_| toasts.dismiss_all()>
+                        "Close all"
+                    </button>
+                </div>
+            })}
+            <For
+                each=move || toasts.0.get()
+                key=|toast| toast.id
+                children=move |toast| {
+                    let id = toast.id;
+                    let level = toast.level;
+                    view!{
+                        <div
+                            class=format!("toast show {}", level.color())
+                            role=if level == ToastLevel::Error { "alert" } else { "status" }
+                            aria-live=if level == ToastLevel::Error { "assertive" } else { "polite" }
+                        >
+                            <div class="d-flex flex-row align-items-start">
+                                <div class="toast-body me-auto">
+                                    <span class="me-2"><BootstrapIcon icon=level.icon() /></span>
+                                    {toast.message}
+                                    {(!toast.details.is_empty()).then(|| view!{
+                                        <ol class="toast-details mb-0 mt-1">
+                                            {toast.details.into_iter().map(|detail| view!{ <li>{detail}</li> }).collect_view()}
+                                        </ol>
+                                    })}
+                                </div>
+                                {toast.actions.into_iter().map(|ToastAction { label, action }| view!{
+                                    <button
+                                        type="button"
+                                        class="btn btn-sm btn-link text-reset mt-1"
+                                        on:click=move |_| {
+                                            toasts.dismiss(id);
+                                            action(());
+                                        }
+                                    >
+                                        {label}
+                                    </button>
+                                }).collect_view()}
+                                <button
+                                    type="button"
+                                    class="btn-close me-2 mt-2"
+                                    aria-label="Close"
+                                    on:click=move |_| toasts.dismiss(id)
+                                ></button>
+                            </div>
+                        </div>
+                    }
+                }
+            />
+        </div>
+    }
+}
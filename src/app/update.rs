@@ -0,0 +1,111 @@
+//! Checks whether a newer build of the app has been deployed.
+//!
+//! The build writes a `version.json` next to the app (see `Trunk.toml`). We
+//! fetch it periodically and offer to reload when its version is newer than the
+//! one that's running.
+
+use std::time::Duration;
+
+use leptos::{
+    component,
+    create_rw_signal,
+    set_interval,
+    spawn_local,
+    view,
+    IntoView,
+    RwSignal,
+    SignalGet,
+    SignalSet,
+    SignalWithUntracked,
+};
+use semver::Version;
+use serde::Deserialize;
+
+use super::{
+    expect_context,
+    BootstrapIcon,
+    Context,
+    VERSION,
+};
+
+const MANIFEST_PATH: &'static str = "/version.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    version: Version,
+}
+
+async fn fetch_manifest() -> Result<Manifest, reqwest::Error> {
+    let origin = gloo_utils::window().location().origin().unwrap_or_default();
+    // bypass the HTTP cache, so that we actually see new deployments.
+    let url = format!(
+        "{origin}{MANIFEST_PATH}?t={}",
+        chrono::Utc::now().timestamp_millis()
+    );
+    reqwest::get(url).await?.error_for_status()?.json().await
+}
+
+fn check_for_update(available: RwSignal<Option<Version>>) {
+    spawn_local(async move {
+        match fetch_manifest().await {
+            Ok(manifest) => {
+                if manifest.version > *VERSION {
+                    log::info!("new version available: {}", manifest.version);
+                    available.set(Some(manifest.version));
+                }
+            }
+            // this is expected when running without the build hook, so we don't show it.
+            Err(error) => log::debug!("update check failed: {error}"),
+        }
+    });
+}
+
+/// Shows a banner when a newer version of the app is deployed.
+#[component]
+pub fn UpdateBanner() -> impl IntoView {
+    let Context { settings, .. } = expect_context();
+
+    let available = create_rw_signal(None::<Version>);
+    let dismissed = create_rw_signal(false);
+
+    let enabled = move || settings.with_untracked(|settings| settings.check_for_updates);
+    if enabled() {
+        check_for_update(available);
+    }
+    set_interval(
+        move || {
+            if enabled() && available.with_untracked(|version| version.is_none()) {
+                check_for_update(available);
+            }
+        },
+        CHECK_INTERVAL,
+    );
+
+    move || {
+        let version = available.get()?;
+        (!dismissed.get()).then(|| {
+            view! {
+                <div class="alert alert-info d-flex flex-row align-items-center m-2 mb-0 py-2" role="status">
+                    <span class="me-2"><BootstrapIcon icon="arrow-up-circle" /></span>
+                    {format!("RustyChat {version} is available.")}
+                    <button
+                        type="button"
+                        class="btn btn-sm btn-primary ms-auto"
+                        on:click=|_| {
+                            gloo_utils::window().location().reload().ok();
+                        }
+                    >
+                        "Reload to update"
+                    </button>
+                    <button
+                        type="button"
+                        class="btn-close ms-2"
+                        aria-label="Close"
+                        on:click=move |_| dismissed.set(true)
+                    ></button>
+                </div>
+            }
+        })
+    }
+}
@@ -0,0 +1,318 @@
+//! Client for the Anthropic Messages API.
+
+use futures::StreamExt;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    connection::Connection,
+    huggingface::parse_events,
+    queue::{
+        Permit,
+        RequestQueue,
+    },
+    BackendError,
+    ChatMessage,
+    ChatPrompt,
+    Token,
+    TokenStream,
+};
+use crate::{
+    parameters::GenerationParameters,
+    state::{
+        ModelId,
+        Role,
+    },
+};
+
+pub const ANTHROPIC_API_URL: &'static str = "https://api.anthropic.com";
+
+const API_VERSION: &'static str = "2023-06-01";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnthropicSettings {
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
+}
+
+impl Default for AnthropicSettings {
+    fn default() -> Self {
+        Self {
+            base_url: default_base_url(),
+        }
+    }
+}
+
+fn default_base_url() -> String {
+    ANTHROPIC_API_URL.to_owned()
+}
+
+#[derive(Clone, Debug)]
+pub struct Anthropic {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    queue: RequestQueue,
+}
+
+impl Anthropic {
+    /// At most `max_concurrency` requests are sent at the same time.
+    pub fn new(
+        settings: &AnthropicSettings,
+        api_key: Option<String>,
+        connection: &Connection,
+        max_concurrency: usize,
+    ) -> Self {
+        let base_url = connection.base_url(&settings.base_url);
+        Self {
+            client: connection.client(),
+            queue: RequestQueue::for_backend(&base_url, max_concurrency),
+            base_url,
+            api_key,
+        }
+    }
+
+    pub fn text_generation(
+        &self,
+        model_id: &ModelId,
+        parameters: GenerationParameters,
+    ) -> TextGeneration {
+        TextGeneration {
+            client: self.clone(),
+            model_id: model_id.clone(),
+            parameters,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TextGeneration {
+    client: Anthropic,
+    pub model_id: ModelId,
+    pub parameters: GenerationParameters,
+}
+
+impl TextGeneration {
+    async fn send(
+        &self,
+        prompt: &str,
+        stream: bool,
+    ) -> Result<(reqwest::Response, Permit), BackendError> {
+        let prompt = ChatPrompt::from_prompt(prompt);
+        let body = Request {
+            model: &self.model_id.0,
+            max_tokens: self.parameters.max_new_tokens,
+            system: prompt.system.as_deref(),
+            messages: messages(&prompt.messages),
+            // the API only allows temperatures up to 1.
+            temperature: self.parameters.temperature.min(1.0),
+            top_k: self.parameters.top_k,
+            top_p: self.parameters.top_p,
+            stream,
+        };
+
+        let mut request = self
+            .client
+            .client
+            .post(format!("{}/v1/messages", self.client.base_url))
+            .header("anthropic-version", API_VERSION)
+            // the key is the user's own, so it's fine to use it from the browser.
+            .header("anthropic-dangerous-direct-browser-access", "true")
+            .json(&body);
+        if let Some(api_key) = &self.client.api_key {
+            request = request.header("x-api-key", api_key);
+        }
+
+        let permit = self.client.queue.acquire().await;
+        let response = request.send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<ErrorResponse>(&body)
+                .map(|response| response.error.message)
+                .unwrap_or(body);
+            return Err(BackendError::Status {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        Ok((response, permit))
+    }
+
+    pub async fn generate(&self, prompt: &str) -> Result<String, BackendError> {
+        let (response, _permit) = self.send(prompt, false).await?;
+        let response = response.json::<Response>().await?;
+        Ok(response
+            .content
+            .into_iter()
+            .filter_map(|block| block.text)
+            .collect())
+    }
+
+    pub async fn generate_stream(&self, prompt: &str) -> Result<TokenStream, BackendError> {
+        let (response, permit) = self.send(prompt, true).await?;
+        // hold the slot until the stream is done or dropped.
+        let tokens = parse_events(response.bytes_stream(), parse_event).map(move |token| {
+            let _permit = &permit;
+            token
+        });
+        Ok(tokens.boxed_local())
+    }
+}
+
+/// The API wants the roles to alternate, starting with the user, so
+/// consecutive messages of the same role (e.g. tool results) are merged. A
+/// trailing assistant message is continued by the model.
+fn messages(messages: &[ChatMessage]) -> Vec<RequestMessage> {
+    let mut merged: Vec<RequestMessage> = vec![];
+
+    for message in messages {
+        let role = match message.role {
+            Role::User => "user",
+            Role::Assitant => "assistant",
+        };
+        let mut content = message
+            .images
+            .iter()
+            .filter_map(|image| image_block(image))
+            .collect::<Vec<_>>();
+        if !message.text.trim().is_empty() {
+            content.push(ContentBlock::Text {
+                text: message.text.clone(),
+            });
+        }
+        if content.is_empty() {
+            continue;
+        }
+
+        match merged.last().map(|last| last.role) {
+            Some(last_role) if last_role == role => {
+                merged.last_mut().unwrap().content.extend(content);
+            }
+            None if role == "assistant" => {}
+            _ => merged.push(RequestMessage { role, content }),
+        }
+    }
+
+    // a prefilled response can't end with whitespace.
+    if let Some(last) = merged.last_mut().filter(|last| last.role == "assistant") {
+        if let Some(ContentBlock::Text { text }) = last.content.last_mut() {
+            text.truncate(text.trim_end().len());
+        }
+    }
+
+    merged
+}
+
+/// Turns a data URL into an image block.
+fn image_block(data_url: &str) -> Option<ContentBlock> {
+    let (media_type, data) = data_url.strip_prefix("data:")?.split_once(";base64,")?;
+    Some(ContentBlock::Image {
+        source: ImageSource {
+            kind: "base64",
+            media_type: media_type.to_owned(),
+            data: data.to_owned(),
+        },
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct Request<'a> {
+    model: &'a str,
+    max_tokens: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<&'a str>,
+    messages: Vec<RequestMessage>,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    stream: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestMessage {
+    role: &'static str,
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    Text { text: String },
+    Image { source: ImageSource },
+}
+
+#[derive(Debug, Serialize)]
+struct ImageSource {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    media_type: String,
+    data: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    content: Vec<ResponseBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseBlock {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ApiError,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    ContentBlockDelta {
+        delta: Delta,
+    },
+    Error {
+        error: ApiError,
+    },
+    /// `message_start`, `ping`, etc.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Delta {
+    TextDelta {
+        text: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+fn parse_event(data: &str) -> Option<Result<Token, BackendError>> {
+    match serde_json::from_str::<StreamEvent>(data) {
+        Ok(StreamEvent::ContentBlockDelta {
+            delta: Delta::TextDelta { text },
+        }) => {
+            Some(Ok(Token {
+                text,
+                special: false,
+            }))
+        }
+        Ok(StreamEvent::Error { error }) => Some(Err(BackendError::Api(error.message))),
+        Ok(_) => None,
+        Err(error) => Some(Err(error.into())),
+    }
+}
@@ -0,0 +1,63 @@
+//! How a backend is reached: extra headers and another base URL, e.g. for a
+//! self-hosted gateway, or a CORS proxy.
+
+use reqwest::header::{
+    HeaderMap,
+    HeaderName,
+    HeaderValue,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Connection {
+    /// Replaces the backend's base URL. For a CORS proxy, this is the proxy's
+    /// prefix followed by the backend's URL.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Sent with every request, e.g. `X-Api-Key`.
+    #[serde(default)]
+    pub headers: Vec<Header>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    pub name: String,
+    pub value: String,
+}
+
+impl Connection {
+    /// The base URL without a trailing slash, or `default` if it isn't
+    /// replaced.
+    pub fn base_url(&self, default: &str) -> String {
+        self.base_url
+            .as_deref()
+            .unwrap_or(default)
+            .trim_end_matches('/')
+            .to_owned()
+    }
+
+    /// A client that sends the headers with every request. Invalid headers
+    /// are left out.
+    pub fn client(&self) -> reqwest::Client {
+        let mut headers = HeaderMap::new();
+        for header in &self.headers {
+            match (
+                HeaderName::from_bytes(header.name.as_bytes()),
+                HeaderValue::from_str(&header.value),
+            ) {
+                (Ok(name), Ok(value)) => {
+                    headers.append(name, value);
+                }
+                _ => log::warn!("invalid header: {}", header.name),
+            }
+        }
+
+        reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap_or_default()
+    }
+}
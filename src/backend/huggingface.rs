@@ -0,0 +1,474 @@
+//! Client for the Hugging Face Inference API.
+
+use futures::{
+    stream,
+    Stream,
+    StreamExt,
+};
+use reqwest::header::AUTHORIZATION;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    connection::Connection,
+    queue::{
+        Permit,
+        RequestQueue,
+    },
+    BackendError,
+    Token,
+    TokenStream,
+};
+use crate::{
+    parameters::GenerationParameters,
+    state::ModelId,
+};
+
+pub const INFERENCE_API_URL: &'static str = "https://api-inference.huggingface.co";
+
+/// Where text-generation-inference listens, if it's started with docker as in
+/// its README.
+pub const DEFAULT_TGI_URL: &'static str = "http://localhost:8080";
+
+/// A self-hosted text-generation-inference server. See
+/// [`HuggingFace::endpoint`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TgiSettings {
+    #[serde(default = "default_tgi_url")]
+    pub url: String,
+    /// Sent as the `Authorization` header, e.g. `Bearer ...`.
+    pub authorization: Option<String>,
+}
+
+impl Default for TgiSettings {
+    fn default() -> Self {
+        Self {
+            url: default_tgi_url(),
+            authorization: None,
+        }
+    }
+}
+
+fn default_tgi_url() -> String {
+    DEFAULT_TGI_URL.to_owned()
+}
+
+#[derive(Clone, Debug)]
+pub struct HuggingFace {
+    client: reqwest::Client,
+    base_url: String,
+    /// The value of the `Authorization` header.
+    authorization: Option<String>,
+    /// Whether `base_url` is a text-generation-inference server, which serves
+    /// a single model, instead of the Inference API.
+    endpoint: bool,
+    queue: RequestQueue,
+}
+
+impl HuggingFace {
+    /// At most `max_concurrency` requests are sent at the same time.
+    pub fn new(hf_token: Option<String>, connection: &Connection, max_concurrency: usize) -> Self {
+        let base_url = connection.base_url(INFERENCE_API_URL);
+        Self {
+            client: connection.client(),
+            queue: RequestQueue::for_backend(&base_url, max_concurrency),
+            base_url,
+            authorization: hf_token.map(|hf_token| format!("Bearer {hf_token}")),
+            endpoint: false,
+        }
+    }
+
+    /// A self-hosted text-generation-inference server, e.g. one that runs
+    /// locally with docker. It takes the same requests as the Inference API,
+    /// but serves its model at `base_url`.
+    pub fn endpoint(
+        base_url: &str,
+        authorization: Option<String>,
+        connection: &Connection,
+        max_concurrency: usize,
+    ) -> Self {
+        let base_url = connection.base_url(base_url);
+        Self {
+            client: connection.client(),
+            queue: RequestQueue::for_backend(&base_url, max_concurrency),
+            base_url,
+            authorization,
+            endpoint: true,
+        }
+    }
+
+    pub fn text_generation(
+        &self,
+        model_id: &ModelId,
+        parameters: GenerationParameters,
+    ) -> TextGeneration {
+        let url = if self.endpoint {
+            self.base_url.clone()
+        }
+        else {
+            format!("{}/models/{model_id}", self.base_url)
+        };
+        TextGeneration {
+            client: self.clone(),
+            model_id: model_id.clone(),
+            url,
+            status_url: if self.endpoint {
+                format!("{}/health", self.base_url)
+            }
+            else {
+                format!("{}/status/{model_id}", self.base_url)
+            },
+            parameters,
+        }
+    }
+
+    /// Transcribes a recording with a speech recognition model, e.g. Whisper.
+    pub async fn transcribe(
+        &self,
+        model_id: &ModelId,
+        audio: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String, BackendError> {
+        let request = self
+            .client
+            .post(format!("{}/models/{model_id}", self.base_url))
+            .header("Content-Type", content_type)
+            .body(audio);
+        let body = self.send_waiting(request).await?;
+        Ok(serde_json::from_str::<Transcription>(&body)?.text)
+    }
+
+    /// Computes sentence embeddings with a feature extraction model, e.g.
+    /// `sentence-transformers/all-MiniLM-L6-v2`. Returns one vector per input.
+    pub async fn embed(
+        &self,
+        model_id: &ModelId,
+        inputs: &[String],
+    ) -> Result<Vec<Vec<f32>>, BackendError> {
+        let request = self
+            .client
+            .post(format!("{}/models/{model_id}", self.base_url))
+            .json(&serde_json::json!({ "inputs": inputs }));
+        let body = self.send_waiting(request).await?;
+        let embeddings: Vec<Vec<f32>> = serde_json::from_str(&body)?;
+        if embeddings.len() != inputs.len() {
+            return Err(BackendError::Api(format!(
+                "expected {} embeddings, but got {}",
+                inputs.len(),
+                embeddings.len()
+            )));
+        }
+        Ok(embeddings)
+    }
+
+    /// Sends a request that waits for the model to load, and returns the
+    /// response body.
+    async fn send_waiting(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<String, BackendError> {
+        request = request.header("X-Wait-For-Model", "true");
+        if let Some(authorization) = &self.authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+
+        let _permit = self.queue.acquire().await;
+        let response = request.send().await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<ErrorResponse>(&body)
+                .map(|response| response.error)
+                .unwrap_or(body);
+            return Err(BackendError::Status {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        Ok(body)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TextGeneration {
+    client: HuggingFace,
+    pub model_id: ModelId,
+    url: String,
+    status_url: String,
+    pub parameters: GenerationParameters,
+}
+
+impl TextGeneration {
+    /// Sends the request once the queue lets us. The permit has to be kept
+    /// until the response body is read.
+    async fn send(
+        &self,
+        prompt: &str,
+        stream: bool,
+    ) -> Result<(reqwest::Response, Permit), BackendError> {
+        let body = Request {
+            inputs: prompt,
+            parameters: RequestParameters {
+                max_new_tokens: self.parameters.max_new_tokens,
+                temperature: self.parameters.temperature,
+                top_k: self.parameters.top_k,
+                top_p: self.parameters.top_p,
+                repetition_penalty: self.parameters.repetition_penalty,
+                min_p: self.parameters.min_p,
+                typical_p: self.parameters.typical_p,
+                no_repeat_ngram_size: self.parameters.no_repeat_ngram_size,
+                do_sample: self.parameters.do_sample,
+                grammar: self
+                    .parameters
+                    .json_schema
+                    .clone()
+                    .map(|value| Grammar::Json { value }),
+                return_full_text: false,
+            },
+            stream,
+            options: RequestOptions {
+                use_cache: false,
+                wait_for_model: false,
+            },
+        };
+
+        let mut request = self.client.client.post(&self.url).json(&body);
+        if let Some(authorization) = &self.client.authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+
+        let permit = self.client.queue.acquire().await;
+        let response = request.send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<ErrorResponse>(&body)
+                .map(|response| response.error)
+                .unwrap_or(body);
+            return Err(BackendError::Status {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        Ok((response, permit))
+    }
+
+    pub async fn status(&self) -> Result<ModelStatus, BackendError> {
+        let mut request = self.client.client.get(&self.status_url);
+        if let Some(authorization) = &self.client.authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+
+        let _permit = self.client.queue.acquire().await;
+        let response = request.send().await?.error_for_status()?;
+
+        // text-generation-inference only tells whether it's healthy, and then its
+        // model is loaded.
+        if self.client.endpoint {
+            return Ok(ModelStatus {
+                loaded: true,
+                state: ModelLoadState::Loaded,
+            });
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Asks the API to load the model, without waiting for it to finish.
+    pub async fn request_load(&self) -> Result<(), BackendError> {
+        let mut load = self.clone();
+        load.parameters.max_new_tokens = 1;
+
+        match load.generate("Hello").await {
+            Err(error) if error.status() != Some(503) => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn generate(&self, prompt: &str) -> Result<String, BackendError> {
+        let (response, _permit) = self.send(prompt, false).await?;
+        let generated = response.json::<Vec<GeneratedText>>().await?;
+        generated
+            .into_iter()
+            .next()
+            .map(|generated| generated.generated_text)
+            .ok_or_else(|| BackendError::Api("no text was generated".to_owned()))
+    }
+
+    pub async fn generate_stream(&self, prompt: &str) -> Result<TokenStream, BackendError> {
+        let (response, permit) = self.send(prompt, true).await?;
+        // hold the slot until the stream is done or dropped.
+        let tokens = parse_events(response.bytes_stream(), parse_event).map(move |token| {
+            let _permit = &permit;
+            token
+        });
+        Ok(tokens.boxed_local())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ModelStatus {
+    pub loaded: bool,
+    pub state: ModelLoadState,
+}
+
+impl ModelStatus {
+    /// The model can be used, but first needs to be loaded by the API.
+    pub fn is_cold(&self) -> bool {
+        !self.loaded && self.state == ModelLoadState::Loadable
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+pub enum ModelLoadState {
+    Loadable,
+    Loaded,
+    TooBig,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+struct Request<'a> {
+    inputs: &'a str,
+    parameters: RequestParameters,
+    stream: bool,
+    options: RequestOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestParameters {
+    max_new_tokens: usize,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repetition_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    typical_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    no_repeat_ngram_size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    do_sample: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grammar: Option<Grammar>,
+    return_full_text: bool,
+}
+
+/// Constrains the output of text-generation-inference.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Grammar {
+    Json { value: serde_json::Value },
+}
+
+#[derive(Debug, Serialize)]
+struct RequestOptions {
+    use_cache: bool,
+    wait_for_model: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeneratedText {
+    generated_text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Transcription {
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StreamEvent {
+    Token { token: Token },
+    Error { error: String },
+}
+
+/// Parses a stream of server-sent events into tokens. `parse_event` parses the
+/// data of an event, and can skip events that have no token.
+pub(super) fn parse_events<S, B>(
+    bytes: S,
+    parse_event: fn(&str) -> Option<Result<Token, BackendError>>,
+) -> impl Stream<Item = Result<Token, BackendError>>
+where
+    S: Stream<Item = Result<B, reqwest::Error>> + 'static,
+    B: AsRef<[u8]>,
+{
+    struct State<S> {
+        bytes: std::pin::Pin<Box<S>>,
+        buffer: Vec<u8>,
+        done: bool,
+    }
+
+    let state = State {
+        bytes: Box::pin(bytes),
+        buffer: vec![],
+        done: false,
+    };
+
+    stream::unfold(state, move |mut state| {
+        async move {
+            loop {
+                // emit complete events from the buffer first.
+                if let Some(end) = state.buffer.windows(2).position(|window| window == b"\n\n") {
+                    let event = state.buffer.drain(..end + 2).collect::<Vec<u8>>();
+                    let event = String::from_utf8_lossy(&event);
+                    let data = event
+                        .lines()
+                        .filter_map(|line| line.strip_prefix("data:"))
+                        .collect::<String>();
+                    let data = data.trim();
+                    if data.is_empty() {
+                        continue;
+                    }
+                    if let Some(item) = parse_event(data) {
+                        return Some((item, state));
+                    }
+                    continue;
+                }
+
+                if state.done {
+                    return None;
+                }
+
+                match state.bytes.next().await {
+                    Some(Ok(chunk)) => state.buffer.extend_from_slice(chunk.as_ref()),
+                    Some(Err(error)) => {
+                        state.done = true;
+                        return Some((Err(error.into()), state));
+                    }
+                    None => {
+                        // terminate the last event, if the server didn't.
+                        state.done = true;
+                        state.buffer.extend_from_slice(b"\n\n");
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn parse_event(data: &str) -> Option<Result<Token, BackendError>> {
+    let item = match serde_json::from_str::<StreamEvent>(data) {
+        Ok(StreamEvent::Token { token }) => Ok(token),
+        Ok(StreamEvent::Error { error }) => Err(BackendError::Api(error)),
+        Err(error) => Err(error.into()),
+    };
+
+    Some(item)
+}
@@ -0,0 +1,80 @@
+// Glue for running models in the browser with WebLLM. It's imported by
+// `local.rs`, and loads WebLLM from a CDN the first time it's needed.
+
+const WEBLLM_URL = "https://esm.run/@mlc-ai/web-llm@0.2.46";
+
+let webllm = null;
+// only one model is kept in GPU memory at a time.
+let engine = null;
+let engineModelId = null;
+
+async function loadWebLlm() {
+    if (!webllm) {
+        webllm = await import(WEBLLM_URL);
+    }
+    return webllm;
+}
+
+function appConfig(webllm) {
+    // keep the weights in IndexedDB instead of the Cache API, so that they can
+    // be listed and deleted per model.
+    return { ...webllm.prebuiltAppConfig, useIndexedDBCache: true };
+}
+
+export function hasWebGpu() {
+    return "gpu" in navigator;
+}
+
+export async function loadEngine(modelId, onProgress) {
+    const webllm = await loadWebLlm();
+    if (engine && engineModelId === modelId) {
+        return;
+    }
+    if (engine) {
+        await engine.unload();
+        engine = null;
+        engineModelId = null;
+    }
+    engine = await webllm.CreateMLCEngine(modelId, {
+        appConfig: appConfig(webllm),
+        initProgressCallback: (report) => onProgress(report.progress, report.text),
+    });
+    engineModelId = modelId;
+}
+
+// Completes a prompt with the loaded model, and calls onToken with each piece
+// of text. Resolves once the completion is done.
+export async function complete(modelId, request, onToken) {
+    if (!engine || engineModelId !== modelId) {
+        throw new Error(`model is not loaded: ${modelId}`);
+    }
+    const chunks = await engine.completions.create({ ...request, stream: true });
+    for await (const chunk of chunks) {
+        const text = chunk.choices[0]?.text;
+        if (text) {
+            onToken(text);
+        }
+    }
+}
+
+export async function deleteFromCache(modelId) {
+    const webllm = await loadWebLlm();
+    if (engineModelId === modelId) {
+        await engine.unload();
+        engine = null;
+        engineModelId = null;
+    }
+    await webllm.deleteModelAllInfoInCache(modelId, appConfig(webllm));
+}
+
+export async function cachedModels() {
+    const webllm = await loadWebLlm();
+    const config = appConfig(webllm);
+    const cached = [];
+    for (const model of config.model_list) {
+        if (await webllm.hasModelInCache(model.model_id, config)) {
+            cached.push(model.model_id);
+        }
+    }
+    return cached;
+}
@@ -0,0 +1,193 @@
+//! Runs small quantized models in the browser with WebGPU, using WebLLM.
+//!
+//! WebLLM is loaded from a CDN the first time a local model is used (see
+//! `local.js`). The weights are downloaded once and cached in IndexedDB, so
+//! after that the model works without any API access. Model IDs are the ones
+//! of WebLLM's prebuilt models, e.g. `Llama-3.2-1B-Instruct-q4f16_1-MLC`.
+
+use futures::{
+    channel::mpsc,
+    StreamExt,
+};
+use serde::Serialize;
+use wasm_bindgen::{
+    closure::Closure,
+    prelude::wasm_bindgen,
+    JsCast,
+    JsValue,
+};
+use wasm_bindgen_futures::{
+    spawn_local,
+    JsFuture,
+};
+
+use super::{
+    BackendError,
+    Token,
+    TokenStream,
+};
+use crate::{
+    parameters::GenerationParameters,
+    state::ModelId,
+};
+
+#[wasm_bindgen(module = "/src/backend/local.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = hasWebGpu)]
+    fn js_has_webgpu() -> bool;
+
+    #[wasm_bindgen(js_name = loadEngine, catch)]
+    fn js_load_engine(
+        model_id: &str,
+        on_progress: &Closure<dyn FnMut(f64, String)>,
+    ) -> Result<js_sys::Promise, JsValue>;
+
+    #[wasm_bindgen(js_name = complete, catch)]
+    fn js_complete(
+        model_id: &str,
+        request: JsValue,
+        on_token: &Closure<dyn FnMut(String)>,
+    ) -> Result<js_sys::Promise, JsValue>;
+
+    #[wasm_bindgen(js_name = cachedModels, catch)]
+    fn js_cached_models() -> Result<js_sys::Promise, JsValue>;
+
+    #[wasm_bindgen(js_name = deleteFromCache, catch)]
+    fn js_delete_from_cache(model_id: &str) -> Result<js_sys::Promise, JsValue>;
+}
+
+/// How far downloading and loading a model is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadProgress {
+    /// From 0 to 1.
+    pub progress: f64,
+    pub text: String,
+}
+
+fn js_error(error: JsValue) -> BackendError {
+    let message = error
+        .dyn_ref::<js_sys::Error>()
+        .map(|error| String::from(error.message()))
+        .or_else(|| error.as_string())
+        .unwrap_or_else(|| format!("{error:?}"));
+    BackendError::Api(message)
+}
+
+/// Whether the browser supports WebGPU, which local models need.
+pub fn has_webgpu() -> bool {
+    js_has_webgpu()
+}
+
+/// The models whose weights are downloaded.
+pub async fn cached_models() -> Result<Vec<ModelId>, BackendError> {
+    let cached = JsFuture::from(js_cached_models().map_err(js_error)?)
+        .await
+        .map_err(js_error)?;
+    Ok(js_sys::Array::from(&cached)
+        .iter()
+        .filter_map(|model_id| model_id.as_string())
+        .map(ModelId)
+        .collect())
+}
+
+/// Deletes the downloaded weights of a model.
+pub async fn delete_from_cache(model_id: &ModelId) -> Result<(), BackendError> {
+    JsFuture::from(js_delete_from_cache(&model_id.0).map_err(js_error)?)
+        .await
+        .map_err(js_error)?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct CompletionRequest<'a> {
+    prompt: &'a str,
+    max_tokens: usize,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repetition_penalty: Option<f32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TextGeneration {
+    pub model_id: ModelId,
+    pub parameters: GenerationParameters,
+}
+
+impl TextGeneration {
+    pub fn new(model_id: &ModelId, parameters: GenerationParameters) -> Self {
+        Self {
+            model_id: model_id.clone(),
+            parameters,
+        }
+    }
+
+    /// Downloads the model if it isn't cached yet, and loads it onto the GPU.
+    /// This does nothing if the model is loaded already.
+    pub async fn load(
+        &self,
+        on_progress: impl Fn(LoadProgress) + 'static,
+    ) -> Result<(), BackendError> {
+        if !has_webgpu() {
+            return Err(BackendError::Api(
+                "This browser doesn't support WebGPU".to_owned(),
+            ));
+        }
+
+        let on_progress = Closure::<dyn FnMut(f64, String)>::new(move |progress, text| {
+            on_progress(LoadProgress { progress, text })
+        });
+        JsFuture::from(js_load_engine(&self.model_id.0, &on_progress).map_err(js_error)?)
+            .await
+            .map_err(js_error)?;
+        Ok(())
+    }
+
+    fn request(&self, prompt: &str) -> Result<JsValue, BackendError> {
+        let request = CompletionRequest {
+            prompt,
+            max_tokens: self.parameters.max_new_tokens,
+            temperature: self.parameters.temperature,
+            top_p: self.parameters.top_p,
+            repetition_penalty: self.parameters.repetition_penalty,
+        };
+        js_sys::JSON::parse(&serde_json::to_string(&request)?).map_err(js_error)
+    }
+
+    pub async fn generate(&self, prompt: &str) -> Result<String, BackendError> {
+        let mut tokens = self.generate_stream(prompt).await?;
+        let mut text = String::new();
+        while let Some(token) = tokens.next().await {
+            text.push_str(&token?.text);
+        }
+        Ok(text)
+    }
+
+    pub async fn generate_stream(&self, prompt: &str) -> Result<TokenStream, BackendError> {
+        let request = self.request(prompt)?;
+        let (sender, receiver) = mpsc::unbounded();
+
+        let on_token = Closure::<dyn FnMut(String)>::new({
+            let sender = sender.clone();
+            move |text: String| {
+                let token = Token {
+                    text,
+                    special: false,
+                };
+                sender.unbounded_send(Ok(token)).ok();
+            }
+        });
+        let completion = js_complete(&self.model_id.0, request, &on_token).map_err(js_error)?;
+
+        spawn_local(async move {
+            if let Err(error) = JsFuture::from(completion).await {
+                sender.unbounded_send(Err(js_error(error))).ok();
+            }
+            // the stream ends once the callback and sender are dropped.
+            drop(on_token);
+        });
+
+        Ok(receiver.boxed_local())
+    }
+}
@@ -0,0 +1,193 @@
+//! Clients for the text generation APIs.
+
+pub mod anthropic;
+pub mod connection;
+pub mod huggingface;
+pub mod local;
+pub mod openrouter;
+pub mod queue;
+pub mod retry;
+pub mod timeout;
+
+use futures::stream::LocalBoxStream;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    parameters::GenerationParameters,
+    state::{
+        ModelBackend,
+        ModelId,
+        Role,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackendError {
+    #[error("Request failed")]
+    Request(#[from] reqwest::Error),
+    #[error("API returned status {status}: {message}")]
+    Status { status: u16, message: String },
+    #[error("API error: {0}")]
+    Api(String),
+    #[error("Invalid response")]
+    InvalidResponse(#[from] serde_json::Error),
+    #[error("No response within {seconds}s")]
+    Timeout { seconds: u32 },
+    #[error("No token arrived within {seconds}s, so the response was stopped")]
+    Stalled { seconds: u32 },
+}
+
+impl BackendError {
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Request(error) => error.status().map(|status| status.as_u16()),
+            Self::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Whether the request might succeed if we try again later, e.g. because
+    /// we are rate-limited, or the model is still loading.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.status(), Some(429 | 502 | 503 | 504)) || self.is_timeout()
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout { .. } | Self::Stalled { .. })
+    }
+
+    /// Whether another backend might do better: we are rate-limited, the
+    /// server failed, or it didn't respond (in time).
+    pub fn is_unavailable(&self) -> bool {
+        match (self, self.status()) {
+            (_, Some(status)) => status == 429 || status >= 500,
+            (Self::Request(error), None) => !error.is_decode(),
+            _ => self.is_timeout(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Token {
+    pub text: String,
+    #[serde(default)]
+    pub special: bool,
+}
+
+pub type TokenStream = LocalBoxStream<'static, Result<Token, BackendError>>;
+
+/// The prompt for chat APIs, which take the messages instead of a prompt that
+/// was formatted with a chat template. It's passed to the backend as JSON, so
+/// that the audit log records it like any other prompt.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatPrompt {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    pub messages: Vec<ChatMessage>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub text: String,
+    /// Data URLs of attached images.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+}
+
+impl ChatPrompt {
+    pub fn to_prompt(&self) -> String {
+        serde_json::to_string(self).expect("failed to serialize chat prompt")
+    }
+
+    /// Parses a prompt from [`Self::to_prompt`]. Any other prompt, e.g. for
+    /// titles, is sent as a single user message.
+    pub fn from_prompt(prompt: &str) -> Self {
+        serde_json::from_str(prompt).unwrap_or_else(|_| {
+            Self {
+                system: None,
+                messages: vec![ChatMessage {
+                    role: Role::User,
+                    text: prompt.to_owned(),
+                    images: vec![],
+                }],
+            }
+        })
+    }
+}
+
+/// A model on one of the backends, and the parameters to generate with.
+#[derive(Clone, Debug)]
+pub enum TextGeneration {
+    HuggingFace(huggingface::TextGeneration),
+    Local(local::TextGeneration),
+    Anthropic(anthropic::TextGeneration),
+    OpenRouter(openrouter::TextGeneration),
+    /// A self-hosted text-generation-inference server.
+    Tgi(huggingface::TextGeneration),
+}
+
+impl TextGeneration {
+    pub fn model_id(&self) -> &ModelId {
+        match self {
+            Self::HuggingFace(model) => &model.model_id,
+            Self::Local(model) => &model.model_id,
+            Self::Anthropic(model) => &model.model_id,
+            Self::OpenRouter(model) => &model.model_id,
+            Self::Tgi(model) => &model.model_id,
+        }
+    }
+
+    pub fn parameters(&self) -> &GenerationParameters {
+        match self {
+            Self::HuggingFace(model) => &model.parameters,
+            Self::Local(model) => &model.parameters,
+            Self::Anthropic(model) => &model.parameters,
+            Self::OpenRouter(model) => &model.parameters,
+            Self::Tgi(model) => &model.parameters,
+        }
+    }
+
+    pub fn backend(&self) -> ModelBackend {
+        match self {
+            Self::HuggingFace(_) => ModelBackend::HuggingFace,
+            Self::Local(_) => ModelBackend::Local,
+            Self::Anthropic(_) => ModelBackend::Anthropic,
+            Self::OpenRouter(_) => ModelBackend::OpenRouter,
+            Self::Tgi(_) => ModelBackend::Tgi,
+        }
+    }
+
+    /// Whether the model is loaded. The chat APIs don't tell, and models that
+    /// run in the browser are loaded when they're used, so they have no
+    /// status.
+    pub async fn status(&self) -> Option<Result<huggingface::ModelStatus, BackendError>> {
+        match self {
+            Self::HuggingFace(model) | Self::Tgi(model) => Some(model.status().await),
+            Self::Local(_) | Self::Anthropic(_) | Self::OpenRouter(_) => None,
+        }
+    }
+
+    pub async fn generate(&self, prompt: &str) -> Result<String, BackendError> {
+        match self {
+            Self::HuggingFace(model) => model.generate(prompt).await,
+            Self::Local(model) => model.generate(prompt).await,
+            Self::Anthropic(model) => model.generate(prompt).await,
+            Self::OpenRouter(model) => model.generate(prompt).await,
+            Self::Tgi(model) => model.generate(prompt).await,
+        }
+    }
+
+    pub async fn generate_stream(&self, prompt: &str) -> Result<TokenStream, BackendError> {
+        match self {
+            Self::HuggingFace(model) => model.generate_stream(prompt).await,
+            Self::Local(model) => model.generate_stream(prompt).await,
+            Self::Anthropic(model) => model.generate_stream(prompt).await,
+            Self::OpenRouter(model) => model.generate_stream(prompt).await,
+            Self::Tgi(model) => model.generate_stream(prompt).await,
+        }
+    }
+}
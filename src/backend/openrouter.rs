@@ -0,0 +1,320 @@
+//! Client for OpenRouter, which routes OpenAI-style chat completions to many
+//! model providers.
+
+use futures::StreamExt;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    connection::Connection,
+    huggingface::parse_events,
+    queue::{
+        Permit,
+        RequestQueue,
+    },
+    BackendError,
+    ChatPrompt,
+    Token,
+    TokenStream,
+};
+use crate::{
+    parameters::GenerationParameters,
+    state::{
+        ModelId,
+        Role,
+    },
+};
+
+pub const OPENROUTER_API_URL: &'static str = "https://openrouter.ai/api/v1";
+
+/// Sent so that OpenRouter can show where requests come from.
+const APP_TITLE: &'static str = "RustyChat";
+
+#[derive(Clone, Debug)]
+pub struct OpenRouter {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    queue: RequestQueue,
+}
+
+impl OpenRouter {
+    /// At most `max_concurrency` requests are sent at the same time.
+    pub fn new(api_key: Option<String>, connection: &Connection, max_concurrency: usize) -> Self {
+        let base_url = connection.base_url(OPENROUTER_API_URL);
+        Self {
+            client: connection.client(),
+            api_key,
+            queue: RequestQueue::for_backend(&base_url, max_concurrency),
+            base_url,
+        }
+    }
+
+    pub fn text_generation(
+        &self,
+        model_id: &ModelId,
+        parameters: GenerationParameters,
+    ) -> TextGeneration {
+        TextGeneration {
+            client: self.clone(),
+            model_id: model_id.clone(),
+            parameters,
+        }
+    }
+
+    /// Lists the models that are available on OpenRouter. This doesn't need an
+    /// API key.
+    pub async fn models(&self) -> Result<Vec<OpenRouterModel>, BackendError> {
+        let response = self
+            .client
+            .get(format!("{}/models", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<ModelList>()
+            .await?;
+        Ok(response.data)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct OpenRouterModel {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub context_length: Option<usize>,
+    #[serde(default)]
+    pub architecture: Architecture,
+}
+
+impl OpenRouterModel {
+    pub fn vision(&self) -> bool {
+        self.architecture
+            .input_modalities
+            .iter()
+            .any(|modality| modality == "image")
+            || self.architecture.modality.contains("image")
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct Architecture {
+    /// e.g. `text+image->text`
+    #[serde(default)]
+    pub modality: String,
+    #[serde(default)]
+    pub input_modalities: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<OpenRouterModel>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TextGeneration {
+    client: OpenRouter,
+    pub model_id: ModelId,
+    pub parameters: GenerationParameters,
+}
+
+impl TextGeneration {
+    async fn send(
+        &self,
+        prompt: &str,
+        stream: bool,
+    ) -> Result<(reqwest::Response, Permit), BackendError> {
+        let prompt = ChatPrompt::from_prompt(prompt);
+        let body = Request {
+            model: &self.model_id.0,
+            messages: messages(&prompt),
+            max_tokens: self.parameters.max_new_tokens,
+            temperature: self.parameters.temperature,
+            top_k: self.parameters.top_k,
+            top_p: self.parameters.top_p,
+            min_p: self.parameters.min_p,
+            repetition_penalty: self.parameters.repetition_penalty,
+            stream,
+        };
+
+        let mut request = self
+            .client
+            .client
+            .post(format!("{}/chat/completions", self.client.base_url))
+            .header("X-Title", APP_TITLE)
+            .json(&body);
+        if let Some(api_key) = &self.client.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let permit = self.client.queue.acquire().await;
+        let response = request.send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<ErrorResponse>(&body)
+                .map(|response| response.error.message)
+                .unwrap_or(body);
+            return Err(BackendError::Status {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        Ok((response, permit))
+    }
+
+    pub async fn generate(&self, prompt: &str) -> Result<String, BackendError> {
+        let (response, _permit) = self.send(prompt, false).await?;
+        let response = response.json::<Response>().await?;
+        response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| BackendError::Api("no text was generated".to_owned()))
+    }
+
+    pub async fn generate_stream(&self, prompt: &str) -> Result<TokenStream, BackendError> {
+        let (response, permit) = self.send(prompt, true).await?;
+        // hold the slot until the stream is done or dropped.
+        let tokens = parse_events(response.bytes_stream(), parse_event).map(move |token| {
+            let _permit = &permit;
+            token
+        });
+        Ok(tokens.boxed_local())
+    }
+}
+
+fn messages(prompt: &ChatPrompt) -> Vec<RequestMessage> {
+    let system = prompt.system.iter().map(|system| {
+        RequestMessage {
+            role: "system",
+            content: vec![ContentPart::Text {
+                text: system.clone(),
+            }],
+        }
+    });
+    let messages = prompt.messages.iter().map(|message| {
+        let role = match message.role {
+            Role::User => "user",
+            Role::Assitant => "assistant",
+        };
+        let mut content = vec![ContentPart::Text {
+            text: message.text.clone(),
+        }];
+        content.extend(message.images.iter().map(|url| {
+            ContentPart::ImageUrl {
+                image_url: ImageUrl { url: url.clone() },
+            }
+        }));
+        RequestMessage { role, content }
+    });
+    system.chain(messages).collect()
+}
+
+#[derive(Debug, Serialize)]
+struct Request<'a> {
+    model: &'a str,
+    messages: Vec<RequestMessage>,
+    max_tokens: usize,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repetition_penalty: Option<f32>,
+    stream: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestMessage {
+    role: &'static str,
+    content: Vec<ContentPart>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+#[derive(Debug, Serialize)]
+struct ImageUrl {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    choices: Vec<Choice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseMessage {
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ApiError,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    error: Option<ApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    delta: Delta,
+}
+
+#[derive(Debug, Deserialize)]
+struct Delta {
+    content: Option<String>,
+}
+
+fn parse_event(data: &str) -> Option<Result<Token, BackendError>> {
+    if data == "[DONE]" {
+        return None;
+    }
+
+    match serde_json::from_str::<StreamChunk>(data) {
+        Ok(StreamChunk {
+            error: Some(error), ..
+        }) => Some(Err(BackendError::Api(error.message))),
+        Ok(chunk) => {
+            let text = chunk
+                .choices
+                .into_iter()
+                .next()
+                .and_then(|choice| choice.delta.content)
+                .filter(|text| !text.is_empty())?;
+            Some(Ok(Token {
+                text,
+                special: false,
+            }))
+        }
+        Err(error) => Some(Err(error.into())),
+    }
+}
@@ -0,0 +1,167 @@
+//! Schedules API requests, so that we don't get rate-limited.
+//!
+//! The free Inference API answers with 429 when several requests arrive at
+//! once, e.g. when a title is generated while a response is still streaming.
+//! Requests to the same backend wait for a free slot, and are started at least
+//! [`MIN_SPACING`] apart.
+
+use std::{
+    cell::{
+        Cell,
+        RefCell,
+    },
+    collections::{
+        HashMap,
+        VecDeque,
+    },
+    rc::Rc,
+    time::Duration,
+};
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+use futures::channel::oneshot;
+use gloo_timers::future::sleep;
+
+pub const DEFAULT_MAX_CONCURRENCY: usize = 2;
+const MIN_SPACING: Duration = Duration::from_millis(250);
+
+thread_local! {
+    static QUEUES: RefCell<HashMap<String, RequestQueue>> = RefCell::new(HashMap::new());
+}
+
+#[derive(Debug)]
+struct Waiter {
+    granted: Rc<Cell<bool>>,
+    wake: oneshot::Sender<()>,
+}
+
+#[derive(Debug)]
+struct State {
+    max_concurrency: usize,
+    running: usize,
+    waiting: VecDeque<Waiter>,
+    next_start: Option<DateTime<Utc>>,
+}
+
+impl State {
+    /// Hands free slots to the waiting requests, in the order they arrived.
+    fn grant(&mut self) {
+        while self.running < self.max_concurrency {
+            let Some(waiter) = self.waiting.pop_front()
+            else {
+                break;
+            };
+            // the request was dropped while waiting.
+            if waiter.wake.is_canceled() {
+                continue;
+            }
+            waiter.granted.set(true);
+            self.running += 1;
+            waiter.wake.send(()).ok();
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RequestQueue {
+    state: Rc<RefCell<State>>,
+}
+
+impl RequestQueue {
+    /// Returns the queue for the backend at `base_url`. All clients for the
+    /// same backend share it.
+    pub fn for_backend(base_url: &str, max_concurrency: usize) -> Self {
+        let queue = QUEUES.with_borrow_mut(|queues| {
+            queues
+                .entry(base_url.to_owned())
+                .or_insert_with(|| {
+                    Self {
+                        state: Rc::new(RefCell::new(State {
+                            max_concurrency,
+                            running: 0,
+                            waiting: VecDeque::new(),
+                            next_start: None,
+                        })),
+                    }
+                })
+                .clone()
+        });
+        queue.set_max_concurrency(max_concurrency);
+        queue
+    }
+
+    pub fn set_max_concurrency(&self, max_concurrency: usize) {
+        let mut state = self.state.borrow_mut();
+        state.max_concurrency = max_concurrency.max(1);
+        state.grant();
+    }
+
+    /// Waits until the request can be sent. The slot is held until the permit
+    /// is dropped.
+    pub async fn acquire(&self) -> Permit {
+        let granted = Rc::new(Cell::new(false));
+        let wake = {
+            let mut state = self.state.borrow_mut();
+            if state.running < state.max_concurrency && state.waiting.is_empty() {
+                state.running += 1;
+                granted.set(true);
+                None
+            }
+            else {
+                let (wake, woken) = oneshot::channel();
+                state.waiting.push_back(Waiter {
+                    granted: granted.clone(),
+                    wake,
+                });
+                Some(woken)
+            }
+        };
+
+        // created before waiting, so that the slot is released even when we're
+        // dropped right after it was granted.
+        let permit = Permit {
+            state: self.state.clone(),
+            granted,
+        };
+
+        if let Some(woken) = wake {
+            woken.await.ok();
+        }
+
+        let delay = {
+            let mut state = self.state.borrow_mut();
+            let now = Utc::now();
+            let start = state
+                .next_start
+                .map_or(now, |next_start| next_start.max(now));
+            state.next_start =
+                Some(start + chrono::Duration::from_std(MIN_SPACING).expect("invalid spacing"));
+            (start - now).to_std().unwrap_or_default()
+        };
+        if !delay.is_zero() {
+            sleep(delay).await;
+        }
+
+        permit
+    }
+}
+
+/// A slot in the [`RequestQueue`].
+#[derive(Debug)]
+pub struct Permit {
+    state: Rc<RefCell<State>>,
+    granted: Rc<Cell<bool>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if self.granted.get() {
+            let mut state = self.state.borrow_mut();
+            state.running -= 1;
+            state.grant();
+        }
+    }
+}
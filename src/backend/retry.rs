@@ -0,0 +1,68 @@
+use std::{
+    future::Future,
+    time::Duration,
+};
+
+use gloo_timers::future::sleep;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::BackendError;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 1000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait before the next attempt, after `attempt` failed.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(u64::from(self.initial_backoff_ms) * u64::from(factor))
+            .min(MAX_BACKOFF)
+    }
+}
+
+/// Runs `f` until it succeeds, fails with an error that isn't retryable, or the
+/// maximum number of attempts is reached. Before each retry `on_retry` is
+/// called with the error, the number of the next attempt and the backoff.
+pub async fn with_retry<T, F, Fut, R>(
+    policy: &RetryPolicy,
+    mut on_retry: R,
+    mut f: F,
+) -> Result<T, BackendError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, BackendError>>,
+    R: FnMut(&BackendError, u32, Duration),
+{
+    let mut attempt = 1;
+
+    loop {
+        match f().await {
+            Err(error) if error.is_retryable() && attempt < policy.max_attempts => {
+                let backoff = policy.backoff(attempt);
+                attempt += 1;
+                log::warn!("request failed, retrying in {backoff:?}: {error}");
+                on_retry(&error, attempt, backoff);
+                sleep(backoff).await;
+            }
+            result => return result,
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Timeouts for requests and streams, so that a stalled connection doesn't
+//! keep a response spinning forever.
+
+use std::{
+    future::Future,
+    pin::pin,
+    time::Duration,
+};
+
+use futures::future::{
+    select,
+    Either,
+};
+use gloo_timers::future::sleep;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::BackendError;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Timeouts {
+    /// How long to wait for a response, or for the stream to start. 0 waits
+    /// forever.
+    pub request_secs: u32,
+    /// How long a stream can go without a token before it's aborted. 0 waits
+    /// forever.
+    pub stall_secs: u32,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            request_secs: 120,
+            stall_secs: 30,
+        }
+    }
+}
+
+impl Timeouts {
+    /// Waits for the response with the request timeout.
+    pub async fn request<T>(
+        &self,
+        future: impl Future<Output = Result<T, BackendError>>,
+    ) -> Result<T, BackendError> {
+        let seconds = self.request_secs;
+        with_timeout(seconds, future, || BackendError::Timeout { seconds }).await
+    }
+
+    /// Waits for the next token with the stall timeout.
+    pub async fn next_token<T>(
+        &self,
+        future: impl Future<Output = Result<T, BackendError>>,
+    ) -> Result<T, BackendError> {
+        let seconds = self.stall_secs;
+        with_timeout(seconds, future, || BackendError::Stalled { seconds }).await
+    }
+}
+
+async fn with_timeout<T>(
+    seconds: u32,
+    future: impl Future<Output = Result<T, BackendError>>,
+    error: impl FnOnce() -> BackendError,
+) -> Result<T, BackendError> {
+    if seconds == 0 {
+        return future.await;
+    }
+
+    let timeout = sleep(Duration::from_secs(seconds.into()));
+    match select(pin!(future), pin!(timeout)).await {
+        Either::Left((result, _)) => result,
+        Either::Right(((), _)) => Err(error()),
+    }
+}
@@ -1,5 +1,18 @@
+//! The app config. A copy of `config.toml` is embedded at build time, and a
+//! config fetched from the deployment at startup can update it, so that the
+//! model list and examples can be changed without rebuilding the app.
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+};
+
 use lazy_static::lazy_static;
-use serde::Deserialize;
+use serde::{
+    de::DeserializeOwned,
+    Deserialize,
+    Serialize,
+};
 
 use crate::state::{
     Model,
@@ -9,15 +22,169 @@ use crate::state::{
 pub const GITHUB_PAGE: &'static str = "https://github.com/jgraef/rusty-chat/";
 pub const GITHUB_ISSUES_PAGE: &'static str = "https://github.com/jgraef/rusty-chat/issues";
 
-#[derive(Debug, Deserialize)]
+/// Where the config is fetched from, if no other URL is set.
+pub const CONFIG_PATH: &'static str = "/config.toml";
+
+#[derive(Clone, Debug, Deserialize)]
 pub struct BuildConfig {
     pub examples: Vec<String>,
     pub default_model: ModelId,
+    pub title_model: ModelId,
     #[serde(rename = "model", default)]
     pub models: Vec<Model>,
 }
 
+impl BuildConfig {
+    /// Replaces the fields that are set in `update`. Models are merged by their
+    /// ID.
+    fn merge(&mut self, update: ConfigUpdate) {
+        if let Some(examples) = update.examples {
+            self.examples = examples;
+        }
+        if let Some(default_model) = update.default_model {
+            self.default_model = default_model;
+        }
+        if let Some(title_model) = update.title_model {
+            self.title_model = title_model;
+        }
+        for model in update.models {
+            if let Some(existing) = self
+                .models
+                .iter_mut()
+                .find(|existing| existing.model_id == model.model_id)
+            {
+                *existing = model;
+            }
+            else {
+                self.models.push(model);
+            }
+        }
+    }
+}
+
+/// A config that is loaded at runtime. Everything is optional.
+#[derive(Debug, Default, Deserialize)]
+struct ConfigUpdate {
+    examples: Option<Vec<String>>,
+    default_model: Option<ModelId>,
+    title_model: Option<ModelId>,
+    #[serde(rename = "model", default)]
+    models: Vec<Model>,
+}
+
+/// Models in a config, so that they can be exported and imported with the same
+/// `[[model]]` tables as in `config.toml`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ModelTables {
+    #[serde(rename = "model", default)]
+    models: Vec<Model>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModelsFormat {
+    Toml,
+    Json,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Could not fetch config")]
+    Request(#[from] reqwest::Error),
+    #[error("Invalid config")]
+    Toml(#[from] toml::de::Error),
+    #[error("Invalid config")]
+    Json(#[from] serde_json::Error),
+    #[error("Could not export models")]
+    Export(#[from] toml::ser::Error),
+}
+
 lazy_static! {
     pub static ref BUILD_CONFIG: BuildConfig =
         toml::from_str(include_str!("../config.toml")).expect("invalid config.toml");
 }
+
+thread_local! {
+    static CONFIG: RefCell<Rc<BuildConfig>> = RefCell::new(Rc::new(BUILD_CONFIG.clone()));
+}
+
+/// The current config. This is the embedded one, until [`load_config`]
+/// succeeded.
+pub fn config() -> Rc<BuildConfig> {
+    CONFIG.with_borrow(Clone::clone)
+}
+
+/// Fetches a config (TOML or JSON) from `url`, or from [`CONFIG_PATH`] on the
+/// deployment's origin.
+async fn fetch_config(url: Option<&str>) -> Result<(String, ConfigUpdate), ConfigError> {
+    let url = match url {
+        Some(url) => url.to_owned(),
+        None => {
+            let origin = gloo_utils::window().location().origin().unwrap_or_default();
+            format!("{origin}{CONFIG_PATH}")
+        }
+    };
+
+    let text = reqwest::get(&url).await?.error_for_status()?.text().await?;
+    let update = parse_config(&text)?;
+
+    Ok((url, update))
+}
+
+/// Parses a config that's either TOML or JSON.
+fn parse_config<T: DeserializeOwned>(text: &str) -> Result<T, ConfigError> {
+    if text.trim_start().starts_with('{') {
+        Ok(serde_json::from_str(text)?)
+    }
+    else {
+        Ok(toml::from_str(text)?)
+    }
+}
+
+/// Fetches the config and merges it into the embedded one.
+pub async fn load_config(url: Option<&str>) -> Result<Rc<BuildConfig>, ConfigError> {
+    let (url, update) = fetch_config(url).await?;
+
+    let mut config = BUILD_CONFIG.clone();
+    config.merge(update);
+    let config = Rc::new(config);
+    CONFIG.set(config.clone());
+
+    log::info!("loaded config from {url}");
+    Ok(config)
+}
+
+/// Fetches the curated list of models from the config, without applying it.
+pub async fn fetch_recommended_models(url: Option<&str>) -> Result<Vec<Model>, ConfigError> {
+    let (_, update) = fetch_config(url).await?;
+    Ok(update.models)
+}
+
+/// Exports models as `[[model]]` tables, which can be pasted into a config.
+/// Credentials are named by each user, so they're left out.
+pub fn export_models<'a>(
+    models: impl IntoIterator<Item = &'a Model>,
+    format: ModelsFormat,
+) -> Result<String, ConfigError> {
+    let tables = ModelTables {
+        models: models
+            .into_iter()
+            .map(|model| {
+                Model {
+                    credential: None,
+                    ..model.clone()
+                }
+            })
+            .collect(),
+    };
+    match format {
+        ModelsFormat::Toml => Ok(toml::to_string_pretty(&tables)?),
+        ModelsFormat::Json => Ok(serde_json::to_string_pretty(&tables)?),
+    }
+}
+
+/// Parses models exported with [`export_models`], or the `[[model]]` tables of
+/// a config.
+pub fn import_models(text: &str) -> Result<Vec<Model>, ConfigError> {
+    let tables: ModelTables = parse_config(text)?;
+    Ok(tables.models)
+}
@@ -0,0 +1,162 @@
+//! Checks the data in local storage against the current types.
+//!
+//! Records are parsed leniently everywhere else, so fields that were renamed or
+//! removed are silently dropped, and new fields silently get defaults. To find
+//! such schema drift, we parse each record, serialize it again, and compare the
+//! result with the stored JSON.
+
+use std::collections::BTreeSet;
+
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+use serde_json::Value;
+
+use crate::state::{
+    read_storage_raw,
+    Artifacts,
+    AuditLog,
+    Bookmarks,
+    Conversation,
+    ConversationId,
+    ConversationSummaries,
+    Conversations,
+    DebugLog,
+    Home,
+    Message,
+    MessageId,
+    Outbox,
+    Settings,
+    StorageKey,
+    Tags,
+    Templates,
+    Trash,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueKind {
+    /// The stored record has a field that the type doesn't know.
+    Unknown,
+    /// The type has a field that is missing in the stored record.
+    Missing,
+    /// The record can't be parsed at all.
+    Invalid(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SchemaIssue {
+    pub key: String,
+    pub path: String,
+    pub kind: IssueKind,
+}
+
+/// Compares objects recursively. Array indices are collapsed to `[]`, so that
+/// an issue in many list items is only reported once.
+fn compare(stored: &Value, parsed: &Value, path: &str, paths: &mut BTreeSet<(String, bool)>) {
+    match (stored, parsed) {
+        (Value::Object(stored), Value::Object(parsed)) => {
+            for (name, value) in stored {
+                let field_path = format!("{path}.{name}");
+                match parsed.get(name) {
+                    Some(parsed) => compare(value, parsed, &field_path, paths),
+                    None => {
+                        paths.insert((field_path, false));
+                    }
+                }
+            }
+            for name in parsed.keys() {
+                if !stored.contains_key(name) {
+                    paths.insert((format!("{path}.{name}"), true));
+                }
+            }
+        }
+        (Value::Array(stored), Value::Array(parsed)) => {
+            let item_path = format!("{path}[]");
+            for (stored, parsed) in stored.iter().zip(parsed) {
+                compare(stored, parsed, &item_path, paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check<T: Serialize + DeserializeOwned>(key: StorageKey, issues: &mut Vec<SchemaIssue>) {
+    let Some(stored) = read_storage_raw(key)
+    else {
+        return;
+    };
+    let key_name = key.as_str().into_owned();
+
+    let parsed = match serde_json::from_value::<T>(stored.clone()) {
+        Ok(parsed) => serde_json::to_value(parsed).expect("failed to serialize"),
+        Err(error) => {
+            issues.push(SchemaIssue {
+                key: key_name,
+                path: "".to_owned(),
+                kind: IssueKind::Invalid(error.to_string()),
+            });
+            return;
+        }
+    };
+
+    let mut paths = BTreeSet::new();
+    compare(&stored, &parsed, "", &mut paths);
+    issues.extend(paths.into_iter().map(|(path, missing)| {
+        SchemaIssue {
+            key: key_name.clone(),
+            path,
+            kind: if missing {
+                IssueKind::Missing
+            }
+            else {
+                IssueKind::Unknown
+            },
+        }
+    }));
+}
+
+fn parse_or_default<T: DeserializeOwned + Default>(key: StorageKey) -> T {
+    read_storage_raw(key)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Checks all records in storage and returns the issues found.
+pub fn check_storage() -> Vec<SchemaIssue> {
+    let mut issues = vec![];
+
+    check::<Home>(StorageKey::Home, &mut issues);
+    check::<Settings>(StorageKey::Settings, &mut issues);
+    check::<Conversations>(StorageKey::Conversations, &mut issues);
+    check::<ConversationSummaries>(StorageKey::ConversationSummaries, &mut issues);
+    check::<Tags>(StorageKey::Tags, &mut issues);
+    check::<Trash>(StorageKey::Trash, &mut issues);
+    check::<Templates>(StorageKey::Templates, &mut issues);
+    check::<Outbox>(StorageKey::Outbox, &mut issues);
+    check::<DebugLog>(StorageKey::DebugLog, &mut issues);
+    check::<Bookmarks>(StorageKey::Bookmarks, &mut issues);
+
+    let mut conversation_ids: Vec<ConversationId> =
+        parse_or_default::<Conversations>(StorageKey::Conversations)
+            .into_iter()
+            .collect();
+    conversation_ids.extend(parse_or_default::<Trash>(StorageKey::Trash).into_keys());
+
+    for id in conversation_ids {
+        check::<Option<Conversation>>(StorageKey::Conversation(id), &mut issues);
+        check::<AuditLog>(StorageKey::AuditLog(id), &mut issues);
+        check::<Artifacts>(StorageKey::Artifacts(id), &mut issues);
+
+        let message_ids = read_storage_raw(StorageKey::Conversation(id))
+            .and_then(|conversation| {
+                serde_json::from_value::<Vec<MessageId>>(conversation["messages"].clone()).ok()
+            })
+            .unwrap_or_default();
+        for message_id in message_ids {
+            check::<Option<Message>>(StorageKey::Message(message_id), &mut issues);
+        }
+    }
+
+    issues
+}
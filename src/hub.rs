@@ -0,0 +1,278 @@
+//! Requests to the Hugging Face Hub API.
+
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::state::{
+    ModelId,
+    ModelMetadata,
+};
+
+pub const HUB_URL: &'static str = "https://huggingface.co";
+pub const HF_TOKENS_PAGE: &'static str = "https://huggingface.co/settings/tokens";
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    #[serde(default)]
+    pub pipeline_tag: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub downloads: Option<u64>,
+    #[serde(default)]
+    pub likes: Option<u64>,
+    #[serde(default)]
+    pub safetensors: Option<Safetensors>,
+    #[serde(default)]
+    pub private: bool,
+    #[serde(default)]
+    pub gated: Gated,
+}
+
+impl ModelInfo {
+    pub fn license(&self) -> Option<&str> {
+        self.tags
+            .iter()
+            .find_map(|tag| tag.strip_prefix("license:"))
+    }
+
+    /// Tags, without the ones that are shown separately (e.g. the license).
+    pub fn display_tags(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .iter()
+            .map(|tag| tag.as_str())
+            .filter(|tag| !tag.contains(':'))
+    }
+
+    pub fn parameter_count(&self) -> Option<u64> {
+        self.safetensors
+            .as_ref()
+            .map(|safetensors| safetensors.total)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Safetensors {
+    pub total: u64,
+}
+
+/// Whether users have to request access to a model. The API returns `false`,
+/// or how requests are approved (`"auto"` or `"manual"`).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(untagged)]
+pub enum Gated {
+    #[default]
+    No,
+    Flag(bool),
+    Approval(String),
+}
+
+impl Gated {
+    pub fn is_gated(&self) -> bool {
+        match self {
+            Self::No => false,
+            Self::Flag(gated) => *gated,
+            Self::Approval(_) => true,
+        }
+    }
+}
+
+/// The parts of a model's `config.json` that tell its context length. Which
+/// one is used depends on the architecture.
+#[derive(Debug, Deserialize)]
+struct ModelConfig {
+    max_position_embeddings: Option<usize>,
+    n_positions: Option<usize>,
+    max_sequence_length: Option<usize>,
+    seq_length: Option<usize>,
+}
+
+pub async fn model_info(model_id: &ModelId) -> Result<ModelInfo, reqwest::Error> {
+    reqwest::get(format!("{HUB_URL}/api/models/{model_id}"))
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+/// Reads the context length from the model's `config.json`. Gated models need
+/// a token for this.
+pub async fn model_context_length(
+    model_id: &ModelId,
+    hf_token: Option<&str>,
+) -> Result<Option<usize>, reqwest::Error> {
+    let mut request =
+        reqwest::Client::new().get(format!("{HUB_URL}/{model_id}/resolve/main/config.json"));
+    if let Some(hf_token) = hf_token {
+        request = request.bearer_auth(hf_token);
+    }
+
+    let config: ModelConfig = request.send().await?.error_for_status()?.json().await?;
+    Ok(config
+        .max_position_embeddings
+        .or(config.n_positions)
+        .or(config.max_sequence_length)
+        .or(config.seq_length))
+}
+
+/// Fetches the metadata that we store with a model. A missing context length
+/// is not an error, since not all models have a config.
+pub async fn fetch_model_metadata(
+    model_id: &ModelId,
+    hf_token: Option<&str>,
+) -> Result<ModelMetadata, reqwest::Error> {
+    let info = model_info(model_id).await?;
+    let context_length = model_context_length(model_id, hf_token)
+        .await
+        .map_err(|error| log::debug!("could not read model config: {error}"))
+        .ok()
+        .flatten();
+
+    Ok(ModelMetadata {
+        license: info.license().map(ToOwned::to_owned),
+        pipeline_tag: info.pipeline_tag,
+        gated: info.gated.is_gated(),
+        private: info.private,
+        context_length,
+        fetched: Utc::now(),
+    })
+}
+
+pub async fn model_readme(model_id: &ModelId) -> Result<String, reqwest::Error> {
+    reqwest::get(format!("{HUB_URL}/{model_id}/raw/main/README.md"))
+        .await?
+        .error_for_status()?
+        .text()
+        .await
+}
+
+/// Removes the YAML metadata block from the start of a model card.
+pub fn strip_front_matter(readme: &str) -> &str {
+    readme
+        .strip_prefix("---")
+        .and_then(|rest| rest.split_once("\n---"))
+        .map(|(_, content)| content)
+        .unwrap_or(readme)
+}
+
+pub fn format_parameter_count(count: u64) -> String {
+    match count {
+        0..=999_999 => format!("{count}"),
+        1_000_000..=999_999_999 => format!("{:.1}M", count as f64 / 1e6),
+        _ => format!("{:.1}B", count as f64 / 1e9),
+    }
+}
+
+/// The fine-grained permission needed to use the serverless Inference API.
+pub const INFERENCE_PERMISSION: &'static str = "inference.serverless.write";
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WhoAmI {
+    pub name: String,
+    #[serde(default)]
+    pub auth: Option<Auth>,
+}
+
+impl WhoAmI {
+    /// Returns the permissions missing from the token to use this app.
+    pub fn missing_permissions(&self) -> Vec<&'static str> {
+        let Some(access_token) = self
+            .auth
+            .as_ref()
+            .and_then(|auth| auth.access_token.as_ref())
+        else {
+            return vec![];
+        };
+
+        match access_token.role {
+            TokenRole::FineGrained => {
+                let has_inference =
+                    access_token
+                        .fine_grained
+                        .as_ref()
+                        .map_or(false, |fine_grained| {
+                            fine_grained
+                                .global
+                                .iter()
+                                .any(|permission| permission == INFERENCE_PERMISSION)
+                        });
+
+                if has_inference {
+                    vec![]
+                }
+                else {
+                    vec![INFERENCE_PERMISSION]
+                }
+            }
+            _ => vec![],
+        }
+    }
+
+    /// What the token is allowed to do: its role, or its global permissions if
+    /// it's fine-grained.
+    pub fn scopes(&self) -> Vec<String> {
+        let Some(access_token) = self
+            .auth
+            .as_ref()
+            .and_then(|auth| auth.access_token.as_ref())
+        else {
+            return vec![];
+        };
+
+        match access_token.role {
+            TokenRole::Read => vec!["read".to_owned()],
+            TokenRole::Write => vec!["write".to_owned()],
+            TokenRole::FineGrained => {
+                access_token
+                    .fine_grained
+                    .as_ref()
+                    .map(|fine_grained| fine_grained.global.clone())
+                    .unwrap_or_default()
+            }
+            TokenRole::Other => vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Auth {
+    #[serde(rename = "accessToken")]
+    pub access_token: Option<AccessToken>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AccessToken {
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+    pub role: TokenRole,
+    #[serde(rename = "fineGrained")]
+    pub fine_grained: Option<FineGrained>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenRole {
+    Read,
+    Write,
+    FineGrained,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FineGrained {
+    #[serde(default)]
+    pub global: Vec<String>,
+}
+
+pub async fn whoami(hf_token: &str) -> Result<WhoAmI, reqwest::Error> {
+    reqwest::Client::new()
+        .get(format!("{HUB_URL}/api/whoami-v2"))
+        .bearer_auth(hf_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
@@ -0,0 +1,227 @@
+//! Importing conversations from the data exports of other chat apps.
+
+use chrono::{
+    DateTime,
+    TimeZone,
+    Utc,
+};
+
+use crate::state::{
+    write_storage_raw,
+    Conversation,
+    ConversationId,
+    Message,
+    MessageId,
+    MessageStatus,
+    ModelId,
+    Role,
+    StorageKey,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("Invalid export file")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("Could not read file: {0}")]
+    ReadFile(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportedConversation {
+    pub conversation: Conversation,
+    pub messages: Vec<Message>,
+}
+
+impl ImportedConversation {
+    /// Writes the conversation and its messages to storage. The caller has to
+    /// add it to the list of conversations.
+    pub fn store(&self) {
+        for message in &self.messages {
+            write_storage_raw(
+                StorageKey::Message(message.id),
+                &serde_json::to_value(message).expect("failed to serialize message"),
+            );
+        }
+        write_storage_raw(
+            StorageKey::Conversation(self.conversation.id),
+            &serde_json::to_value(&self.conversation).expect("failed to serialize conversation"),
+        );
+    }
+}
+
+/// The `conversations.json` of a ChatGPT data export.
+mod chatgpt {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct Conversation {
+        pub title: Option<String>,
+        pub create_time: Option<f64>,
+        pub update_time: Option<f64>,
+        pub mapping: HashMap<String, Node>,
+        pub current_node: Option<String>,
+    }
+
+    /// Messages form a tree, since responses can be regenerated and messages
+    /// edited.
+    #[derive(Debug, Deserialize)]
+    pub struct Node {
+        pub message: Option<Message>,
+        pub parent: Option<String>,
+        #[serde(default)]
+        pub children: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Message {
+        pub author: Author,
+        pub content: Content,
+        pub create_time: Option<f64>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Author {
+        pub role: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Content {
+        pub content_type: String,
+        /// Parts can also be images and other attachments, which we skip.
+        #[serde(default)]
+        pub parts: Vec<serde_json::Value>,
+    }
+}
+
+fn timestamp(seconds: Option<f64>) -> Option<DateTime<Utc>> {
+    Utc.timestamp_millis_opt((seconds? * 1000.0) as i64)
+        .single()
+}
+
+/// The path through the message tree that was shown last, from the root to
+/// `current_node`.
+fn current_branch(conversation: &chatgpt::Conversation) -> Vec<&chatgpt::Node> {
+    let leaf = conversation.current_node.clone().or_else(|| {
+        // fall back to the newest leaf node.
+        conversation
+            .mapping
+            .iter()
+            .filter(|(_, node)| node.children.is_empty())
+            .max_by(|(_, a), (_, b)| {
+                let time = |node: &chatgpt::Node| {
+                    node.message
+                        .as_ref()
+                        .and_then(|message| message.create_time)
+                        .unwrap_or_default()
+                };
+                time(a).total_cmp(&time(b))
+            })
+            .map(|(id, _)| id.clone())
+    });
+
+    let mut branch = vec![];
+    let mut next = leaf;
+    while let Some(node) = next.and_then(|id| conversation.mapping.get(&id)) {
+        branch.push(node);
+        // guard against cycles in broken exports.
+        if branch.len() > conversation.mapping.len() {
+            break;
+        }
+        next = node.parent.clone();
+    }
+    branch.reverse();
+    branch
+}
+
+fn convert_message(message: &chatgpt::Message, fallback_time: DateTime<Utc>) -> Option<Message> {
+    let role = match message.author.role.as_str() {
+        "user" => Role::User,
+        "assistant" => Role::Assitant,
+        // system prompts and tool calls
+        _ => return None,
+    };
+    if message.content.content_type != "text" {
+        return None;
+    }
+
+    let text = message
+        .content
+        .parts
+        .iter()
+        .filter_map(|part| part.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    Some(Message {
+        id: MessageId::new(),
+        role,
+        text,
+        timestamp: timestamp(message.create_time).unwrap_or(fallback_time),
+        generation: None,
+        images: vec![],
+        tool: None,
+        status: MessageStatus::Complete,
+        note: None,
+        rating: None,
+        candidates: vec![],
+    })
+}
+
+/// Parses the `conversations.json` from a ChatGPT data export. The imported
+/// conversations use `model_id` for new messages.
+pub fn parse_chatgpt_export(
+    json: &str,
+    model_id: &ModelId,
+) -> Result<Vec<ImportedConversation>, ImportError> {
+    let conversations: Vec<chatgpt::Conversation> = serde_json::from_str(json)?;
+    let now = Utc::now();
+
+    let imported = conversations
+        .iter()
+        .filter_map(|conversation| {
+            let timestamp_started = timestamp(conversation.create_time).unwrap_or(now);
+            let messages = current_branch(conversation)
+                .into_iter()
+                .filter_map(|node| node.message.as_ref())
+                .filter_map(|message| convert_message(message, timestamp_started))
+                .collect::<Vec<_>>();
+            if messages.is_empty() {
+                return None;
+            }
+
+            let timestamp_last_interaction = timestamp(conversation.update_time)
+                .or_else(|| messages.last().map(|message| message.timestamp))
+                .unwrap_or(timestamp_started);
+
+            let conversation = Conversation {
+                id: ConversationId::new(),
+                model_id: Some(model_id.clone()),
+                title: conversation.title.clone(),
+                auto_title: false,
+                timestamp_started,
+                timestamp_last_interaction,
+                timestamp_modified: timestamp_last_interaction,
+                conversation_parameters: Default::default(),
+                user_message: "".to_owned(),
+                messages: messages.iter().map(|message| message.id).collect(),
+                archived: false,
+                tags: vec![],
+                group: None,
+                pinned: false,
+                summary: None,
+            };
+
+            Some(ImportedConversation {
+                conversation,
+                messages,
+            })
+        })
+        .collect();
+
+    Ok(imported)
+}
@@ -1,6 +1,19 @@
 mod app;
+mod backend;
 mod config;
+mod diagnostics;
+mod hub;
+mod import;
+mod migrate;
+mod parameters;
+mod render;
+mod search;
+mod share;
 mod state;
+mod sync;
+mod tabs;
+mod tokenizer;
+mod tools;
 mod utils;
 
 use wasm_bindgen::JsCast;
@@ -20,4 +33,11 @@ fn main() {
         .unwrap();
 
     leptos::mount_to(root, App);
+
+    utils::record_startup_time();
+
+    // there's no service worker with `trunk serve`.
+    if !cfg!(debug_assertions) {
+        utils::register_service_worker();
+    }
 }
@@ -0,0 +1,199 @@
+//! Migrations of the data in local storage between app versions.
+//!
+//! Migrations work on the raw JSON, since the types in [`crate::state`] only
+//! describe the current format.
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+use semver::Version;
+use serde_json::{
+    json,
+    Map,
+    Value,
+};
+
+use crate::state::{
+    read_storage_raw,
+    write_storage_raw,
+    ConversationId,
+    MessageId,
+    StorageKey,
+};
+
+struct Migration {
+    /// The app version that introduced the new format.
+    version: &'static str,
+    description: &'static str,
+    run: fn(),
+}
+
+const MIGRATIONS: &'static [Migration] = &[
+    Migration {
+        version: "0.2.0",
+        description: "store timestamps in UTC",
+        run: timestamps_to_utc,
+    },
+    Migration {
+        version: "0.3.0",
+        description: "build conversation summaries",
+        run: build_conversation_summaries,
+    },
+    Migration {
+        version: "0.4.0",
+        description: "move API tokens and keys to named credentials",
+        run: named_credentials,
+    },
+];
+
+/// Runs all migrations for versions newer than `from`, in order.
+pub fn migrate(from: &Version) {
+    for migration in MIGRATIONS {
+        let version = Version::parse(migration.version).expect("invalid migration version");
+        if *from < version {
+            log::info!("running migration for {version}: {}", migration.description);
+            (migration.run)();
+        }
+    }
+}
+
+fn conversation_ids() -> Vec<ConversationId> {
+    read_storage_raw(StorageKey::Conversations)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Conversations in the sidebar and in the trash.
+fn all_conversation_ids() -> Vec<ConversationId> {
+    let mut ids = conversation_ids();
+    if let Some(Value::Object(trash)) = read_storage_raw(StorageKey::Trash) {
+        ids.extend(
+            trash
+                .keys()
+                .filter_map(|id| {
+                    serde_json::from_value::<ConversationId>(Value::String(id.clone())).ok()
+                }),
+        );
+    }
+    ids
+}
+
+/// Applies `f` to the value stored at `key`, and writes it back.
+fn update_raw(key: StorageKey, f: impl FnOnce(&mut Value)) {
+    if let Some(mut value) = read_storage_raw(key) {
+        f(&mut value);
+        write_storage_raw(key, &value);
+    }
+}
+
+fn timestamp_to_utc(value: &mut Value) {
+    let Some(timestamp) = value.as_str()
+    else {
+        return;
+    };
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(timestamp) => *value = Value::String(timestamp.with_timezone(&Utc).to_rfc3339()),
+        Err(error) => log::warn!("invalid timestamp '{timestamp}': {error}"),
+    }
+}
+
+fn timestamps_to_utc() {
+    for conversation_id in conversation_ids() {
+        let mut message_ids = vec![];
+
+        update_raw(StorageKey::Conversation(conversation_id), |conversation| {
+            timestamp_to_utc(&mut conversation["timestamp_started"]);
+            timestamp_to_utc(&mut conversation["timestamp_last_interaction"]);
+            message_ids =
+                serde_json::from_value::<Vec<MessageId>>(conversation["messages"].clone())
+                    .unwrap_or_default();
+        });
+
+        for message_id in message_ids {
+            update_raw(StorageKey::Message(message_id), |message| {
+                timestamp_to_utc(&mut message["timestamp"]);
+            });
+        }
+
+        update_raw(StorageKey::AuditLog(conversation_id), |audit_log| {
+            if let Some(entries) = audit_log.as_array_mut() {
+                for entry in entries {
+                    timestamp_to_utc(&mut entry["timestamp"]);
+                }
+            }
+        });
+    }
+}
+
+fn build_conversation_summaries() {
+    let mut summaries = Map::new();
+
+    for conversation_id in all_conversation_ids() {
+        let Some(conversation) = read_storage_raw(StorageKey::Conversation(conversation_id))
+        else {
+            log::warn!("dangling conversation entry: {conversation_id}");
+            continue;
+        };
+
+        let message_count = conversation["messages"]
+            .as_array()
+            .map_or(0, |messages| messages.len());
+        let summary = json!({
+            "id": conversation["id"],
+            "title": conversation["title"],
+            "model_id": conversation["model_id"],
+            "timestamp_started": conversation["timestamp_started"],
+            "timestamp_last_interaction": conversation["timestamp_last_interaction"],
+            "message_count": message_count,
+            "archived": conversation.get("archived").cloned().unwrap_or(Value::Bool(false)),
+            "tags": conversation.get("tags").cloned().unwrap_or_else(|| json!([])),
+        });
+        summaries.insert(conversation_id.to_string(), summary);
+    }
+
+    write_storage_raw(StorageKey::ConversationSummaries, &Value::Object(summaries));
+}
+
+/// The Hugging Face token and the API keys of the Anthropic and OpenRouter
+/// backends become credentials that their backend uses.
+fn named_credentials() {
+    update_raw(StorageKey::Settings, |settings| {
+        let Some(settings) = settings.as_object_mut()
+        else {
+            return;
+        };
+
+        let mut credentials = Map::new();
+        let mut backend_credentials = Map::new();
+        let mut add = |backend: &str, name: &str, secret: Option<Value>| {
+            if let Some(secret) = secret.filter(Value::is_string) {
+                credentials.insert(name.to_owned(), secret);
+                backend_credentials.insert(backend.to_owned(), Value::String(name.to_owned()));
+            }
+        };
+
+        add("HuggingFace", "Hugging Face", settings.remove("hf_token"));
+        add(
+            "Anthropic",
+            "Anthropic",
+            settings
+                .get_mut("anthropic")
+                .and_then(Value::as_object_mut)
+                .and_then(|anthropic| anthropic.remove("api_key")),
+        );
+        add(
+            "OpenRouter",
+            "OpenRouter",
+            settings
+                .remove("openrouter")
+                .and_then(|mut openrouter| openrouter.get_mut("api_key").map(Value::take)),
+        );
+
+        settings.insert("credentials".to_owned(), Value::Object(credentials));
+        settings.insert(
+            "backend_credentials".to_owned(),
+            Value::Object(backend_credentials),
+        );
+    });
+}
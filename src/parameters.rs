@@ -0,0 +1,132 @@
+//! Resolution of the parameters that are used for a single generation request.
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use strum::{
+    EnumMessage,
+    VariantArray,
+};
+
+use crate::state::ConversationParameters;
+
+pub const DEFAULT_TEMPERATURE: f32 = 1.0;
+
+/// Token limit for [`ResponseLength::Unlimited`]. This is still clamped to the
+/// model's context length, if we know it.
+const UNLIMITED_TOKEN_LIMIT: usize = 8192;
+
+/// Presets for the response length, that set the token limit and instruct the
+/// model to keep its response short or long.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, VariantArray, EnumMessage)]
+pub enum ResponseLength {
+    #[strum(message = "Short")]
+    Short,
+    #[strum(message = "Medium")]
+    Medium,
+    #[strum(message = "Long")]
+    Long,
+    #[strum(message = "Unlimited")]
+    Unlimited,
+}
+
+impl ResponseLength {
+    pub fn token_limit(&self) -> usize {
+        match self {
+            Self::Short => 150,
+            Self::Medium => 500,
+            Self::Long => 1500,
+            Self::Unlimited => UNLIMITED_TOKEN_LIMIT,
+        }
+    }
+
+    pub fn instruction(&self) -> Option<&'static str> {
+        match self {
+            Self::Short => Some("Keep your responses short, a few sentences at most."),
+            Self::Medium => Some("Keep your responses reasonably concise."),
+            Self::Long => Some("Give detailed and thorough responses."),
+            Self::Unlimited => None,
+        }
+    }
+}
+
+/// The system prompt with the instruction for the response length appended.
+pub fn system_prompt(conversation_parameters: &ConversationParameters) -> Option<String> {
+    let instruction = conversation_parameters
+        .response_length
+        .and_then(|response_length| response_length.instruction());
+
+    match (&conversation_parameters.system_prompt, instruction) {
+        (Some(system_prompt), Some(instruction)) => {
+            Some(format!("{system_prompt}\n\n{instruction}"))
+        }
+        (Some(system_prompt), None) => Some(system_prompt.clone()),
+        (None, instruction) => instruction.map(ToOwned::to_owned),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenerationParameters {
+    pub max_new_tokens: usize,
+    pub temperature: f32,
+    pub top_k: Option<usize>,
+    pub top_p: Option<f32>,
+    pub repetition_penalty: Option<f32>,
+    pub min_p: Option<f32>,
+    pub typical_p: Option<f32>,
+    pub no_repeat_ngram_size: Option<usize>,
+    pub do_sample: Option<bool>,
+    /// The JSON schema the response is constrained to.
+    pub json_schema: Option<serde_json::Value>,
+}
+
+impl GenerationParameters {
+    /// Resolves the parameters for the response to the `turn`-th user message
+    /// (starting at 1).
+    pub fn resolve(
+        conversation_parameters: &ConversationParameters,
+        turn: usize,
+        stream: bool,
+    ) -> Self {
+        let default_token_limit = stream.then_some(2000).unwrap_or(250);
+
+        let mut temperature = conversation_parameters
+            .temperature
+            .unwrap_or(DEFAULT_TEMPERATURE);
+
+        // switch to the ramp temperature after the first N turns
+        if let (Some(after_turns), Some(ramp_temperature)) = (
+            conversation_parameters.ramp_after_turns,
+            conversation_parameters.ramp_temperature,
+        ) {
+            if turn > after_turns {
+                temperature = ramp_temperature;
+            }
+        }
+
+        // the response length preset overrides the token limit
+        let max_new_tokens = conversation_parameters
+            .response_length
+            .map(|response_length| response_length.token_limit())
+            .or(conversation_parameters.token_limit)
+            .unwrap_or(default_token_limit);
+
+        Self {
+            max_new_tokens,
+            temperature,
+            top_k: conversation_parameters.top_k,
+            top_p: conversation_parameters.top_p,
+            repetition_penalty: conversation_parameters.repetition_penalty,
+            min_p: conversation_parameters.min_p,
+            typical_p: conversation_parameters.typical_p,
+            no_repeat_ngram_size: conversation_parameters.no_repeat_ngram_size,
+            do_sample: conversation_parameters.do_sample,
+            // the input is validated, so an invalid schema is just ignored here.
+            json_schema: conversation_parameters
+                .json_schema
+                .as_deref()
+                .and_then(|json_schema| serde_json::from_str(json_schema).ok()),
+        }
+    }
+}
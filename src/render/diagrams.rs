@@ -0,0 +1,197 @@
+//! Renders ```` ```mermaid ```` code blocks as diagrams.
+//!
+//! `wrap_diagrams` prepares the HTML, and `render_diagrams` asks mermaid.js to
+//! draw them once the HTML is in the page. mermaid.js is only loaded when the
+//! first diagram is drawn. Until then, or if mermaid can't parse the diagram,
+//! the code is shown instead. A button toggles between the diagram and its
+//! code.
+
+use std::cell::{
+    Cell,
+    RefCell,
+};
+
+use uuid::Uuid;
+use wasm_bindgen::{
+    prelude::wasm_bindgen,
+    JsCast,
+    JsValue,
+};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Element,
+    Event,
+};
+
+const MERMAID_BLOCK_START: &'static str = "<pre><code class=\"language-mermaid\">";
+const CODE_BLOCK_END: &'static str = "</code></pre>";
+
+const MERMAID_SRC: &'static str = "https://cdn.jsdelivr.net/npm/mermaid@10.9.0/dist/mermaid.min.js";
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = mermaid, js_name = initialize, catch)]
+    fn mermaid_initialize(config: &JsValue) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(js_namespace = mermaid, js_name = render, catch)]
+    fn mermaid_render(id: &str, code: &str) -> Result<js_sys::Promise, JsValue>;
+}
+
+thread_local! {
+    static MERMAID_LOADED: RefCell<Option<js_sys::Promise>> = RefCell::new(None);
+    static MERMAID_INITIALIZED: Cell<bool> = Cell::new(false);
+}
+
+/// Wraps mermaid code blocks, so that `render_diagrams` can find them.
+pub fn wrap_diagrams(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(MERMAID_BLOCK_START) {
+        let Some(end) = rest[start..].find(CODE_BLOCK_END).map(|end| start + end)
+        else {
+            break;
+        };
+        let end = end + CODE_BLOCK_END.len();
+
+        output.push_str(&rest[..start]);
+        output.push_str(concat!(
+            "<div class=\"diagram\">",
+            "<button type=\"button\" class=\"btn btn-sm btn-link link-secondary p-0 diagram-toggle\" title=\"Toggle between diagram and code\">",
+            "<i class=\"bi bi-code-slash\"></i>",
+            "</button>",
+            "<div class=\"diagram-svg\"></div>",
+        ));
+        output.push_str(&rest[start..end]);
+        output.push_str("</div>");
+
+        rest = &rest[end..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Adds the mermaid script to the page, the first time it's called. The
+/// returned promise resolves once the script is loaded.
+fn load_mermaid() -> Result<js_sys::Promise, JsValue> {
+    MERMAID_LOADED.with_borrow_mut(|loaded| {
+        if let Some(promise) = loaded {
+            return Ok(promise.clone());
+        }
+
+        let document = gloo_utils::document();
+        let script = document.create_element("script")?;
+        script.set_attribute("src", MERMAID_SRC)?;
+        script.set_attribute("crossorigin", "anonymous")?;
+
+        let promise = js_sys::Promise::new(&mut |resolve, reject| {
+            script
+                .add_event_listener_with_callback("load", &resolve)
+                .ok();
+            script
+                .add_event_listener_with_callback("error", &reject)
+                .ok();
+        });
+
+        document
+            .query_selector("head")?
+            .ok_or(JsValue::NULL)?
+            .append_child(&script)?;
+
+        *loaded = Some(promise.clone());
+        Ok(promise)
+    })
+}
+
+/// Sets up mermaid before the first diagram is drawn. The SVG is put into the
+/// page as HTML, so the strict security level is set explicitly, which escapes
+/// HTML in labels and disables click handlers.
+async fn initialize_mermaid() -> Result<(), JsValue> {
+    if MERMAID_INITIALIZED.get() {
+        return Ok(());
+    }
+
+    JsFuture::from(load_mermaid()?).await?;
+    if MERMAID_INITIALIZED.get() {
+        // another diagram was drawn while we were waiting.
+        return Ok(());
+    }
+
+    let config = js_sys::Object::new();
+    js_sys::Reflect::set(&config, &JsValue::from_str("startOnLoad"), &JsValue::FALSE)?;
+    js_sys::Reflect::set(
+        &config,
+        &JsValue::from_str("securityLevel"),
+        &JsValue::from_str("strict"),
+    )?;
+    mermaid_initialize(&config)?;
+
+    MERMAID_INITIALIZED.set(true);
+    Ok(())
+}
+
+async fn render_mermaid(code: &str) -> Result<String, JsValue> {
+    initialize_mermaid().await?;
+    let id = format!("diagram-{}", Uuid::new_v4());
+    let result = JsFuture::from(mermaid_render(&id, code)?).await?;
+    js_sys::Reflect::get(&result, &JsValue::from_str("svg"))?
+        .as_string()
+        .ok_or(JsValue::NULL)
+}
+
+/// Draws the diagrams in `container` that haven't been drawn yet.
+pub async fn render_diagrams(container: &Element) {
+    let Ok(diagrams) = container.query_selector_all(".diagram:not(.rendered)")
+    else {
+        return;
+    };
+
+    for i in 0..diagrams.length() {
+        let Some(diagram) = diagrams
+            .get(i)
+            .and_then(|node| node.dyn_into::<Element>().ok())
+        else {
+            continue;
+        };
+        let Some(code) = diagram
+            .query_selector("code")
+            .ok()
+            .flatten()
+            .and_then(|code| code.text_content())
+        else {
+            continue;
+        };
+        let Ok(Some(target)) = diagram.query_selector(".diagram-svg")
+        else {
+            continue;
+        };
+
+        match render_mermaid(&code).await {
+            Ok(svg) => {
+                target.set_inner_html(&svg);
+                diagram.class_list().add_1("rendered").ok();
+            }
+            // mermaid isn't loaded, or the diagram is invalid. we just keep
+            // showing the code.
+            Err(error) => log::debug!("failed to render diagram: {error:?}"),
+        }
+    }
+}
+
+/// Handles clicks on the toggle buttons of the diagrams in a message.
+pub fn toggle_diagram(event: &Event) {
+    let Some(target) = event
+        .target()
+        .and_then(|target| target.dyn_into::<Element>().ok())
+    else {
+        return;
+    };
+    let Ok(Some(button)) = target.closest(".diagram-toggle")
+    else {
+        return;
+    };
+    if let Ok(Some(diagram)) = button.closest(".diagram") {
+        diagram.class_list().toggle("show-code").ok();
+    }
+}
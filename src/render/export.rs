@@ -0,0 +1,80 @@
+//! Standalone HTML documents of conversations, for exporting and printing.
+
+use chrono::Local;
+
+use super::{
+    escape_html,
+    render_markdown,
+};
+use crate::state::{
+    Conversation,
+    Message,
+    Role,
+};
+
+/// The styles of exported documents. Unlike `app.css`, they don't depend on
+/// Bootstrap, and always use the light theme, since that's what prints well.
+pub const EXPORT_STYLE: &'static str = include_str!("export.css");
+
+/// Renders the title and all messages of a conversation.
+pub fn conversation_html(
+    conversation: &Conversation,
+    model_name: Option<&str>,
+    messages: &[Message],
+) -> String {
+    let mut html = String::new();
+
+    html.push_str("<header><h1>");
+    escape_html(
+        conversation.title.as_deref().unwrap_or("Untitled"),
+        &mut html,
+    );
+    html.push_str("</h1><p>");
+    if let Some(model_name) = model_name {
+        escape_html(model_name, &mut html);
+        html.push_str(" · ");
+    }
+    html.push_str(
+        &conversation
+            .timestamp_started
+            .with_timezone(&Local)
+            .format("%c")
+            .to_string(),
+    );
+    html.push_str("</p></header>");
+
+    for message in messages {
+        let (class, role) = match message.role {
+            Role::User => ("user", "You"),
+            Role::Assitant => ("assistant", model_name.unwrap_or("Assistant")),
+        };
+        html.push_str(&format!(
+            "<div class=\"message {class}\"><div class=\"role\">"
+        ));
+        escape_html(role, &mut html);
+        html.push_str("</div>");
+        html.push_str(&render_markdown(&message.text));
+        html.push_str("</div>");
+    }
+
+    html
+}
+
+/// A complete HTML document with the conversation, that can be opened without
+/// the app.
+pub fn conversation_document(
+    conversation: &Conversation,
+    model_name: Option<&str>,
+    messages: &[Message],
+) -> String {
+    let mut title = String::new();
+    escape_html(
+        conversation.title.as_deref().unwrap_or("Untitled"),
+        &mut title,
+    );
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\" />\n<title>{title}</title>\n<style>\n{EXPORT_STYLE}</style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        conversation_html(conversation, model_name, messages),
+    )
+}
@@ -0,0 +1,432 @@
+//! A lightweight syntax highlighter for fenced code blocks.
+//!
+//! This works on the HTML generated by `markdown::to_html` and wraps tokens in
+//! `<span class="hl-*">` elements, which are styled in `app.css`.
+
+use super::{
+    escape_html,
+    unescape_html,
+};
+
+const CODE_BLOCK_START: &'static str = "<pre><code";
+const CODE_BLOCK_END: &'static str = "</code></pre>";
+const LANGUAGE_CLASS: &'static str = "class=\"language-";
+
+struct Language {
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    string_delimiters: &'static [char],
+    capitalized_types: bool,
+}
+
+const RUST: Language = Language {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while",
+    ],
+    literals: &["true", "false", "None", "Some", "Ok", "Err"],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    string_delimiters: &['"'],
+    capitalized_types: true,
+};
+
+const PYTHON: Language = Language {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+        "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with",
+        "yield",
+    ],
+    literals: &["True", "False", "None", "self"],
+    line_comment: Some("#"),
+    block_comment: None,
+    string_delimiters: &['"', '\''],
+    capitalized_types: true,
+};
+
+const JAVASCRIPT: Language = Language {
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "export",
+        "extends",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "of",
+        "return",
+        "static",
+        "switch",
+        "this",
+        "throw",
+        "try",
+        "type",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "yield",
+    ],
+    literals: &["true", "false", "null", "undefined", "NaN"],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    string_delimiters: &['"', '\'', '`'],
+    capitalized_types: true,
+};
+
+const C: Language = Language {
+    keywords: &[
+        "auto",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "extern",
+        "for",
+        "goto",
+        "if",
+        "inline",
+        "namespace",
+        "new",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "sizeof",
+        "static",
+        "struct",
+        "switch",
+        "template",
+        "this",
+        "throw",
+        "try",
+        "typedef",
+        "union",
+        "using",
+        "virtual",
+        "volatile",
+        "while",
+        "void",
+        "int",
+        "char",
+        "float",
+        "double",
+        "long",
+        "short",
+        "unsigned",
+        "signed",
+        "bool",
+    ],
+    literals: &["true", "false", "NULL", "nullptr"],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    string_delimiters: &['"', '\''],
+    capitalized_types: false,
+};
+
+const JAVA: Language = Language {
+    keywords: &[
+        "abstract",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "do",
+        "else",
+        "enum",
+        "extends",
+        "final",
+        "finally",
+        "for",
+        "fun",
+        "if",
+        "implements",
+        "import",
+        "interface",
+        "new",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "throws",
+        "try",
+        "val",
+        "var",
+        "void",
+        "while",
+        "int",
+        "long",
+        "boolean",
+        "char",
+        "double",
+        "float",
+    ],
+    literals: &["true", "false", "null"],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    string_delimiters: &['"', '\''],
+    capitalized_types: true,
+};
+
+const GO: Language = Language {
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "fallthrough",
+        "for",
+        "func",
+        "go",
+        "goto",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "type",
+        "var",
+    ],
+    literals: &["true", "false", "nil", "iota"],
+    line_comment: Some("//"),
+    block_comment: Some(("/*", "*/")),
+    string_delimiters: &['"', '\'', '`'],
+    capitalized_types: false,
+};
+
+const SHELL: Language = Language {
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "until", "while",
+    ],
+    literals: &["true", "false"],
+    line_comment: Some("#"),
+    block_comment: None,
+    string_delimiters: &['"', '\''],
+    capitalized_types: false,
+};
+
+const TOML: Language = Language {
+    keywords: &[],
+    literals: &["true", "false"],
+    line_comment: Some("#"),
+    block_comment: None,
+    string_delimiters: &['"', '\''],
+    capitalized_types: false,
+};
+
+const JSON: Language = Language {
+    keywords: &[],
+    literals: &["true", "false", "null"],
+    line_comment: None,
+    block_comment: None,
+    string_delimiters: &['"'],
+    capitalized_types: false,
+};
+
+impl Language {
+    fn from_name(name: &str) -> Option<&'static Language> {
+        let language = match name.to_lowercase().as_str() {
+            "rust" | "rs" => &RUST,
+            "python" | "py" => &PYTHON,
+            "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => &JAVASCRIPT,
+            "c" | "h" | "cpp" | "c++" | "cc" | "hpp" | "cs" | "csharp" => &C,
+            "java" | "kotlin" | "kt" => &JAVA,
+            "go" | "golang" => &GO,
+            "sh" | "bash" | "shell" | "zsh" | "console" => &SHELL,
+            "toml" | "ini" => &TOML,
+            "json" => &JSON,
+            _ => return None,
+        };
+        Some(language)
+    }
+}
+
+pub fn highlight_code_blocks(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(CODE_BLOCK_START) {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(tag_end) = rest.find('>')
+        else {
+            break;
+        };
+        let Some(end) = rest.find(CODE_BLOCK_END)
+        else {
+            break;
+        };
+
+        let open_tag = &rest[..=tag_end];
+        let code = &rest[tag_end + 1..end];
+
+        output.push_str(open_tag);
+        if let Some(language) = parse_language(open_tag).and_then(Language::from_name) {
+            highlight(language, &unescape_html(code), &mut output);
+        }
+        else {
+            output.push_str(code);
+        }
+        output.push_str(CODE_BLOCK_END);
+
+        rest = &rest[end + CODE_BLOCK_END.len()..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+fn parse_language(open_tag: &str) -> Option<&str> {
+    let start = open_tag.find(LANGUAGE_CLASS)? + LANGUAGE_CLASS.len();
+    let length = open_tag[start..].find('"')?;
+    Some(&open_tag[start..start + length])
+}
+
+fn highlight(language: &Language, code: &str, output: &mut String) {
+    let mut i = 0;
+
+    while i < code.len() {
+        let rest = &code[i..];
+        let c = rest.chars().next().unwrap();
+
+        let (class, length) = if language
+            .line_comment
+            .map_or(false, |comment| rest.starts_with(comment))
+        {
+            (Some("comment"), rest.find('\n').unwrap_or(rest.len()))
+        }
+        else if let Some((start, end)) = language
+            .block_comment
+            .filter(|(start, _)| rest.starts_with(start))
+        {
+            let length = rest[start.len()..]
+                .find(end)
+                .map(|n| start.len() + n + end.len())
+                .unwrap_or(rest.len());
+            (Some("comment"), length)
+        }
+        else if language.string_delimiters.contains(&c) {
+            (Some("string"), string_length(rest, c))
+        }
+        else if c.is_ascii_digit() {
+            let length = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
+                .unwrap_or(rest.len());
+            (Some("number"), length)
+        }
+        else if c.is_alphabetic() || c == '_' {
+            let length = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..length];
+
+            let class = if language.keywords.contains(&word) {
+                Some("keyword")
+            }
+            else if language.literals.contains(&word) {
+                Some("literal")
+            }
+            else if rest[length..].starts_with('(') {
+                Some("function")
+            }
+            else if language.capitalized_types && c.is_uppercase() {
+                Some("type")
+            }
+            else {
+                None
+            };
+
+            (class, length)
+        }
+        else {
+            (None, c.len_utf8())
+        };
+
+        let token = &rest[..length];
+        if let Some(class) = class {
+            output.push_str("<span class=\"hl-");
+            output.push_str(class);
+            output.push_str("\">");
+            escape_html(token, output);
+            output.push_str("</span>");
+        }
+        else {
+            escape_html(token, output);
+        }
+
+        i += length;
+    }
+}
+
+/// Returns the length of the string literal at the start of `text`, including
+/// the delimiters.
+fn string_length(text: &str, delimiter: char) -> usize {
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        }
+        else if c == '\\' {
+            escaped = true;
+        }
+        else if c == delimiter {
+            return i + c.len_utf8();
+        }
+        else if c == '\n' && delimiter != '`' {
+            return i;
+        }
+    }
+
+    text.len()
+}
@@ -0,0 +1,72 @@
+pub mod diagrams;
+pub mod export;
+mod highlight;
+mod sanitize;
+
+/// Renders the markdown of a message to HTML. The HTML is sanitized, so it's
+/// safe to use with `inner_html`.
+pub fn render_markdown(text: &str) -> String {
+    let html = markdown::to_html(text);
+    sanitize::sanitize_html(&highlight::highlight_code_blocks(&html))
+}
+
+/// Renders the markdown of a message in a shared link. Like `render_markdown`,
+/// but images are removed, so opening the link doesn't load anything from
+/// elsewhere.
+pub fn render_shared_markdown(text: &str) -> String {
+    let html = markdown::to_html(text);
+    sanitize::sanitize_shared_html(&highlight::highlight_code_blocks(&html))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub code: String,
+}
+
+/// Extracts the fenced code blocks from markdown.
+pub fn code_blocks(text: &str) -> Vec<CodeBlock> {
+    let mut blocks = vec![];
+    let mut current: Option<(Option<String>, Vec<&str>)> = None;
+
+    for line in text.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut current, fence) {
+            (None, Some(info)) => {
+                let language = info.split_whitespace().next().map(ToOwned::to_owned);
+                current = Some((language, vec![]));
+            }
+            (Some(_), Some(_)) => {
+                let (language, lines) = current.take().unwrap();
+                blocks.push(CodeBlock {
+                    language,
+                    code: lines.join("\n"),
+                });
+            }
+            (Some((_, lines)), None) => lines.push(line),
+            (None, None) => {}
+        }
+    }
+
+    blocks
+}
+
+pub fn escape_html(text: &str, output: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            _ => output.push(c),
+        }
+    }
+}
+
+pub fn unescape_html(html: &str) -> String {
+    html.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
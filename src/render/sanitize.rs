@@ -0,0 +1,34 @@
+//! Sanitizes the rendered HTML of untrusted text, i.e. responses and model
+//! cards, before it's put into the page with `inner_html`.
+//!
+//! `markdown::to_html` already escapes raw HTML, but we don't want to rely
+//! on that alone, so everything is passed through an allow-list of tags and
+//! attributes.
+
+use ammonia::Builder;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref SANITIZER: Builder<'static> = {
+        let mut builder = Builder::default();
+        // used by the syntax highlighter and for the language of code blocks.
+        builder.add_generic_attributes(["class"]);
+        builder
+    };
+    /// For conversations from a shared link. Whoever made the link chose the
+    /// text, and images would load from their server as soon as it's opened.
+    static ref SHARED_SANITIZER: Builder<'static> = {
+        let mut builder = Builder::default();
+        builder.add_generic_attributes(["class"]);
+        builder.rm_tags(["img"]);
+        builder
+    };
+}
+
+pub fn sanitize_html(html: &str) -> String {
+    SANITIZER.clean(html).to_string()
+}
+
+pub fn sanitize_shared_html(html: &str) -> String {
+    SHARED_SANITIZER.clean(html).to_string()
+}
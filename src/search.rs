@@ -0,0 +1,258 @@
+//! Semantic search over past messages.
+//!
+//! Messages are embedded with the embedding model from the settings, and the
+//! embeddings are kept in an index in storage. The index is brought up to date
+//! before every search, so only new messages need to be embedded. To keep the
+//! index small, embeddings are normalized and stored as bytes.
+
+use std::collections::{
+    BTreeMap,
+    HashMap,
+    HashSet,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    backend::{
+        huggingface::HuggingFace,
+        BackendError,
+    },
+    state::{
+        read_storage_raw,
+        write_storage_raw,
+        Conversation,
+        ConversationId,
+        Message,
+        MessageId,
+        ModelId,
+        StorageKey,
+    },
+};
+
+/// How many messages are embedded with one request.
+const BATCH_SIZE: usize = 16;
+
+/// Embedding models only look at the start of a text anyway.
+const MAX_INPUT_LENGTH: usize = 2000;
+
+const SNIPPET_LENGTH: usize = 160;
+
+const MAX_RESULTS: usize = 10;
+
+/// Results that are less similar than this are left out.
+const MIN_SCORE: f32 = 0.2;
+
+const SCALE: f32 = i8::MAX as f32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SearchError {
+    #[error("No embedding model is set")]
+    NoEmbeddingModel,
+    #[error("Could not compute embeddings")]
+    Backend(#[from] BackendError),
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct SearchIndex {
+    /// The model the embeddings were computed with. The index is rebuilt if
+    /// the model changes.
+    model_id: Option<ModelId>,
+    entries: BTreeMap<MessageId, IndexEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct IndexEntry {
+    conversation_id: ConversationId,
+    embedding: Vec<i8>,
+}
+
+impl SearchIndex {
+    fn read() -> Self {
+        read_storage_raw(StorageKey::SearchIndex)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+
+    fn write(&self) {
+        write_storage_raw(
+            StorageKey::SearchIndex,
+            &serde_json::to_value(self).expect("failed to serialize search index"),
+        );
+    }
+}
+
+/// A conversation that matches a search, and its best matching message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    pub conversation_id: ConversationId,
+    pub message_id: MessageId,
+    pub score: f32,
+    pub snippet: String,
+}
+
+fn read_conversation(id: ConversationId) -> Option<Conversation> {
+    serde_json::from_value(read_storage_raw(StorageKey::Conversation(id))?).ok()
+}
+
+fn read_message(id: MessageId) -> Option<Message> {
+    serde_json::from_value(read_storage_raw(StorageKey::Message(id))?).ok()
+}
+
+fn quantize(embedding: &[f32]) -> Vec<i8> {
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return vec![0; embedding.len()];
+    }
+    embedding
+        .iter()
+        .map(|x| (x / norm * SCALE).round() as i8)
+        .collect()
+}
+
+/// The cosine similarity of two quantized embeddings.
+fn similarity(a: &[i8], b: &[i8]) -> f32 {
+    let dot = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| *a as i32 * *b as i32)
+        .sum::<i32>();
+    dot as f32 / (SCALE * SCALE)
+}
+
+fn snippet(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(SNIPPET_LENGTH) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+/// Embeds the messages of `conversations` that aren't in the index yet, and
+/// removes messages that were deleted. The index is saved after every batch,
+/// so that the work isn't lost if a request fails.
+async fn update_index(
+    backend: &HuggingFace,
+    model_id: &ModelId,
+    conversations: &[ConversationId],
+) -> Result<SearchIndex, SearchError> {
+    let mut index = SearchIndex::read();
+    if index.model_id.as_ref() != Some(model_id) {
+        index = SearchIndex {
+            model_id: Some(model_id.clone()),
+            entries: BTreeMap::new(),
+        };
+    }
+
+    let mut missing = vec![];
+    let mut existing = HashSet::new();
+    for conversation_id in conversations {
+        let Some(conversation) = read_conversation(*conversation_id)
+        else {
+            continue;
+        };
+        for message_id in conversation.messages {
+            existing.insert(message_id);
+            if !index.entries.contains_key(&message_id) {
+                missing.push((*conversation_id, message_id));
+            }
+        }
+    }
+    index
+        .entries
+        .retain(|message_id, _| existing.contains(message_id));
+
+    let missing = missing
+        .into_iter()
+        .filter_map(|(conversation_id, message_id)| {
+            let message = read_message(message_id)?;
+            // tool results are mostly noise.
+            if message.tool.is_some() || message.text.trim().is_empty() {
+                return None;
+            }
+            let text = match message.text.char_indices().nth(MAX_INPUT_LENGTH) {
+                Some((end, _)) => message.text[..end].to_owned(),
+                None => message.text,
+            };
+            Some((conversation_id, message_id, text))
+        })
+        .collect::<Vec<_>>();
+
+    for batch in missing.chunks(BATCH_SIZE) {
+        let inputs = batch
+            .iter()
+            .map(|(_, _, text)| text.clone())
+            .collect::<Vec<_>>();
+        let embeddings = backend.embed(model_id, &inputs).await?;
+        for ((conversation_id, message_id, _), embedding) in batch.iter().zip(embeddings) {
+            index.entries.insert(
+                *message_id,
+                IndexEntry {
+                    conversation_id: *conversation_id,
+                    embedding: quantize(&embedding),
+                },
+            );
+        }
+        index.write();
+    }
+    if missing.is_empty() {
+        index.write();
+    }
+
+    Ok(index)
+}
+
+/// Finds the conversations that are closest in meaning to `query`, best
+/// first.
+pub async fn semantic_search(
+    backend: &HuggingFace,
+    model_id: Option<&ModelId>,
+    conversations: &[ConversationId],
+    query: &str,
+) -> Result<Vec<SearchResult>, SearchError> {
+    let model_id = model_id.ok_or(SearchError::NoEmbeddingModel)?;
+    let index = update_index(backend, model_id, conversations).await?;
+
+    let query = backend
+        .embed(model_id, &[query.to_owned()])
+        .await?
+        .pop()
+        .map(|embedding| quantize(&embedding))
+        .unwrap_or_default();
+
+    // a conversation is as good as its best message.
+    let mut best = HashMap::<ConversationId, (MessageId, f32)>::new();
+    for (message_id, entry) in &index.entries {
+        let score = similarity(&query, &entry.embedding);
+        if score < MIN_SCORE {
+            continue;
+        }
+        let best = best
+            .entry(entry.conversation_id)
+            .or_insert((*message_id, score));
+        if score > best.1 {
+            *best = (*message_id, score);
+        }
+    }
+
+    let mut results = best
+        .into_iter()
+        .map(|(conversation_id, (message_id, score))| {
+            SearchResult {
+                conversation_id,
+                message_id,
+                score,
+                snippet: read_message(message_id)
+                    .map(|message| snippet(&message.text))
+                    .unwrap_or_default(),
+            }
+        })
+        .collect::<Vec<_>>();
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results.truncate(MAX_RESULTS);
+
+    Ok(results)
+}
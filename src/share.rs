@@ -0,0 +1,97 @@
+//! Read-only snapshots of conversations that can be shared as a link.
+//!
+//! The snapshot is serialized, compressed and put into the fragment of the
+//! URL, so it never leaves the browser of whoever opens the link.
+
+use base64::{
+    engine::general_purpose::URL_SAFE_NO_PAD,
+    Engine,
+};
+use chrono::{
+    DateTime,
+    Utc,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::state::{
+    Conversation,
+    Message,
+    ModelId,
+    Role,
+};
+
+/// The route that shows shared conversations.
+pub const SHARED_PATH: &'static str = "/shared";
+
+const COMPRESSION_LEVEL: u8 = 9;
+
+/// Links are short, but can decompress to a lot. Anything larger than this
+/// isn't a conversation we shared.
+const MAX_DECOMPRESSED_SIZE: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ShareError {
+    #[error("The link is not valid")]
+    Base64(#[from] base64::DecodeError),
+    #[error("The link is not valid")]
+    Decompress,
+    #[error("The shared conversation is not valid")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SharedConversation {
+    pub title: Option<String>,
+    pub model_id: Option<ModelId>,
+    pub timestamp: DateTime<Utc>,
+    pub messages: Vec<SharedMessage>,
+}
+
+/// Only the text of a message, to keep links short.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SharedMessage {
+    pub role: Role,
+    pub text: String,
+}
+
+impl SharedConversation {
+    pub fn new(conversation: &Conversation, messages: &[Message]) -> Self {
+        Self {
+            title: conversation.title.clone(),
+            model_id: conversation.model_id.clone(),
+            timestamp: conversation.timestamp_last_interaction,
+            messages: messages
+                .iter()
+                .map(|message| {
+                    SharedMessage {
+                        role: message.role,
+                        text: message.text.clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("failed to serialize shared conversation");
+        let compressed = miniz_oxide::deflate::compress_to_vec(&json, COMPRESSION_LEVEL);
+        URL_SAFE_NO_PAD.encode(compressed)
+    }
+
+    pub fn decode(payload: &str) -> Result<Self, ShareError> {
+        let compressed = URL_SAFE_NO_PAD.decode(payload)?;
+        let json =
+            miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, MAX_DECOMPRESSED_SIZE)
+                .map_err(|_| ShareError::Decompress)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    /// The link to this snapshot, for the app at the current origin.
+    pub fn url(&self) -> String {
+        let origin = gloo_utils::window().location().origin().unwrap_or_default();
+        format!("{origin}{SHARED_PATH}#{}", self.encode())
+    }
+}
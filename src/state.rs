@@ -1,7 +1,11 @@
 use std::{
+    any::Any,
     borrow::Cow,
+    cell::RefCell,
     collections::{
         BTreeMap,
+        BTreeSet,
+        HashMap,
         HashSet,
     },
     fmt::Write,
@@ -9,9 +13,13 @@ use std::{
 
 use chrono::{
     DateTime,
-    Local,
+    Utc,
 };
 use leptos::{
+    create_rw_signal,
+    with_owner,
+    Owner,
+    RwSignal,
     Signal,
     WriteSignal,
 };
@@ -31,7 +39,35 @@ use strum::{
 };
 use uuid::Uuid;
 
-use crate::config::BUILD_CONFIG;
+use crate::{
+    backend::{
+        anthropic::{
+            Anthropic,
+            AnthropicSettings,
+        },
+        connection::Connection,
+        huggingface::{
+            HuggingFace,
+            TgiSettings,
+        },
+        local,
+        openrouter::OpenRouter,
+        queue::DEFAULT_MAX_CONCURRENCY,
+        retry::RetryPolicy,
+        timeout::Timeouts,
+        TextGeneration,
+    },
+    config::config,
+    parameters::{
+        GenerationParameters,
+        ResponseLength,
+    },
+    sync::SyncSettings,
+    tools::{
+        default_tools,
+        Tool,
+    },
+};
 
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
 pub enum StorageKey {
@@ -39,19 +75,39 @@ pub enum StorageKey {
     Home,
     Settings,
     Conversations,
+    ConversationSummaries,
+    Tags,
+    Trash,
+    Templates,
+    Outbox,
+    DebugLog,
+    Bookmarks,
     Conversation(ConversationId),
     Message(MessageId),
+    AuditLog(ConversationId),
+    Artifacts(ConversationId),
+    SearchIndex,
 }
 
 impl StorageKey {
-    fn as_str(&self) -> Cow<'static, str> {
+    pub fn as_str(&self) -> Cow<'static, str> {
         match self {
             Self::Version => "version".into(),
             Self::Home => "home".into(),
             Self::Settings => "settings".into(),
             Self::Conversations => "conversations".into(),
+            Self::ConversationSummaries => "conversation-summaries".into(),
+            Self::Tags => "tags".into(),
+            Self::Trash => "trash".into(),
+            Self::Templates => "templates".into(),
+            Self::Outbox => "outbox".into(),
+            Self::DebugLog => "debug-log".into(),
+            Self::Bookmarks => "bookmarks".into(),
             Self::Conversation(id) => format!("conversation-{id}").into(),
             Self::Message(id) => format!("message-{id}").into(),
+            Self::AuditLog(id) => format!("audit-log-{id}").into(),
+            Self::Artifacts(id) => format!("artifacts-{id}").into(),
+            Self::SearchIndex => "search-index".into(),
         }
     }
 }
@@ -69,9 +125,67 @@ impl<T: 'static> StorageSignals<T> {
     }
 }
 
+thread_local! {
+    /// Values of keys that are only kept in memory, and never written to local
+    /// storage. A key maps to `None` until it's used.
+    static MEMORY_STORAGE: RefCell<HashMap<String, Option<Box<dyn Any>>>> = Default::default();
+
+    /// The owner of the in-memory signals, so that they outlive the component
+    /// that used them first.
+    static MEMORY_OWNER: RefCell<Option<Owner>> = Default::default();
+}
+
+/// Must be called from the root of the app, before [`keep_in_memory`] is used.
+pub fn init_memory_storage() {
+    MEMORY_OWNER.with_borrow_mut(|owner| *owner = Owner::current());
+}
+
+/// Keeps the value of this key only in memory. It's lost when the page is
+/// closed.
+pub fn keep_in_memory(key: StorageKey) {
+    MEMORY_STORAGE.with_borrow_mut(|memory| {
+        memory.entry(key.as_str().into_owned()).or_insert(None);
+    });
+}
+
+pub fn is_in_memory(key: StorageKey) -> bool {
+    MEMORY_STORAGE.with_borrow(|memory| memory.contains_key(key.as_str().as_ref()))
+}
+
+fn use_memory_storage<T: Clone + Default + 'static>(key: StorageKey) -> StorageSignals<T> {
+    let signal = MEMORY_STORAGE.with_borrow_mut(|memory| {
+        let value = memory
+            .get_mut(key.as_str().as_ref())
+            .expect("key is not kept in memory");
+        if let Some(signal) = value
+            .as_ref()
+            .and_then(|value| value.downcast_ref::<RwSignal<T>>())
+        {
+            return *signal;
+        }
+
+        let create = || create_rw_signal(T::default());
+        let signal = match MEMORY_OWNER.with_borrow(|owner| *owner) {
+            Some(owner) => with_owner(owner, create),
+            None => create(),
+        };
+        *value = Some(Box::new(signal));
+        signal
+    });
+
+    StorageSignals {
+        key,
+        read: signal.into(),
+        write: signal.write_only(),
+    }
+}
+
 pub fn use_storage<T: Serialize + for<'de> Deserialize<'de> + Clone + Default + PartialEq>(
     key: StorageKey,
 ) -> StorageSignals<T> {
+    if is_in_memory(key) {
+        return use_memory_storage(key);
+    }
     let (read, write, _) = use_local_storage::<T, JsonCodec>(key.as_str());
     StorageSignals { key, read, write }
 }
@@ -88,7 +202,79 @@ pub fn clear_storage() {
     storage.clear().ok();
 }
 
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+/// Reads a value from storage without deserializing it into a type. This is
+/// used by migrations, which deal with older formats.
+pub fn read_storage_raw(key: StorageKey) -> Option<serde_json::Value> {
+    let json = local_storage()?.get_item(&key.as_str()).ok().flatten()?;
+    serde_json::from_str(&json)
+        .map_err(|error| log::error!("invalid json in storage: {key:?}: {error}"))
+        .ok()
+}
+
+pub fn write_storage_raw(key: StorageKey, value: &serde_json::Value) {
+    let Some(storage) = local_storage()
+    else {
+        return;
+    };
+    if let Err(error) = storage.set_item(&key.as_str(), &value.to_string()) {
+        log::error!("failed to write storage: {key:?}: {error:?}");
+    }
+}
+
+/// All keys in local storage and the size of their values in bytes. This
+/// includes keys that aren't ours.
+pub fn list_storage_raw() -> Vec<(String, usize)> {
+    let Some(storage) = local_storage()
+    else {
+        return vec![];
+    };
+    let length = storage.length().unwrap_or_default();
+    let mut entries = (0..length)
+        .filter_map(|index| storage.key(index).ok().flatten())
+        .map(|key| {
+            let size = storage
+                .get_item(&key)
+                .ok()
+                .flatten()
+                .map_or(0, |value| value.len());
+            (key, size)
+        })
+        .collect::<Vec<_>>();
+    entries.sort();
+    entries
+}
+
+/// Like [`read_storage_raw`], but by name, and without parsing the value, so
+/// that invalid JSON can be fixed.
+pub fn read_storage_by_name(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok().flatten()
+}
+
+pub fn write_storage_by_name(key: &str, value: &str) {
+    let Some(storage) = local_storage()
+    else {
+        return;
+    };
+    if let Err(error) = storage.set_item(key, value) {
+        log::error!("failed to write storage: {key}: {error:?}");
+    }
+}
+
+pub fn delete_storage_by_name(key: &str) {
+    if let Some(storage) = local_storage() {
+        storage.delete(key).ok();
+    }
+}
+
 pub fn delete_storage(key: StorageKey) {
+    if is_in_memory(key) {
+        MEMORY_STORAGE.with_borrow_mut(|memory| memory.remove(key.as_str().as_ref()));
+        return;
+    }
     let Some(window) = web_sys::window()
     else {
         return;
@@ -102,6 +288,194 @@ pub fn delete_storage(key: StorageKey) {
 
 pub type Conversations = HashSet<ConversationId>;
 
+/// Summaries of all conversations, so that lists of conversations don't need
+/// to load every conversation.
+pub type ConversationSummaries = BTreeMap<ConversationId, ConversationSummary>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub id: ConversationId,
+    pub title: Option<String>,
+    pub model_id: Option<ModelId>,
+    pub timestamp_started: DateTime<Utc>,
+    pub timestamp_last_interaction: DateTime<Utc>,
+    pub message_count: usize,
+    pub archived: bool,
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub group: Option<Uuid>,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl From<&Conversation> for ConversationSummary {
+    fn from(conversation: &Conversation) -> Self {
+        Self {
+            id: conversation.id,
+            title: conversation.title.clone(),
+            model_id: conversation.model_id.clone(),
+            timestamp_started: conversation.timestamp_started,
+            timestamp_last_interaction: conversation.timestamp_last_interaction,
+            message_count: conversation.messages.len(),
+            archived: conversation.archived,
+            tags: conversation.tags.clone(),
+            group: conversation.group,
+            pinned: conversation.pinned,
+        }
+    }
+}
+
+/// All tags that can be assigned to conversations.
+pub type Tags = BTreeSet<String>;
+
+/// Saved starter configurations for new conversations.
+pub type Templates = Vec<Template>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    pub id: Uuid,
+    pub name: String,
+    pub model_id: ModelId,
+    pub conversation_parameters: ConversationParameters,
+}
+
+/// An example prompt on the home page that the user added. The model and
+/// parameters that are set replace the selected ones when it's clicked.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExamplePrompt {
+    pub id: Uuid,
+    pub prompt: String,
+    #[serde(default)]
+    pub model_id: Option<ModelId>,
+    #[serde(default)]
+    pub conversation_parameters: Option<ConversationParameters>,
+}
+
+/// Messages that were sent while offline, or while another tab was generating
+/// in the conversation. They're sent once that's over.
+pub type Outbox = Vec<OutboxEntry>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub id: Uuid,
+    pub conversation_id: ConversationId,
+    pub text: String,
+    pub timestamp: DateTime<Utc>,
+    /// See [`crate::app::SendOverrides`].
+    pub model_id: Option<ModelId>,
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub images: Vec<String>,
+    /// The tab that sends this message, if it was queued because another tab
+    /// was generating in the conversation. Messages queued while offline are
+    /// sent by any tab.
+    #[serde(default)]
+    pub tab_id: Option<Uuid>,
+}
+
+/// Messages that were bookmarked, from all conversations.
+pub type Bookmarks = BTreeMap<MessageId, Bookmark>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub conversation_id: ConversationId,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Deleted conversations and when they were deleted. They are kept in storage
+/// until the trash is purged.
+pub type Trash = BTreeMap<ConversationId, DateTime<Utc>>;
+
+/// A passphrase that the app is locked with on load, and after a while
+/// without any input.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AppLock {
+    pub salt: String,
+    /// The PBKDF2-HMAC-SHA256 of the passphrase, hex-encoded.
+    pub passphrase_hash: String,
+    /// The PBKDF2 iterations the hash was derived with.
+    pub iterations: u32,
+    /// The app locks again after this many minutes without any input. It only
+    /// locks on load if this isn't set.
+    pub inactivity_timeout_minutes: Option<u32>,
+}
+
+/// Cleans up conversations that haven't been used for a while. This is applied
+/// on startup.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CleanupPolicy {
+    /// Conversations without any interaction for this many days are cleaned
+    /// up.
+    pub older_than_days: u32,
+    pub action: CleanupAction,
+    #[serde(default = "default_true")]
+    pub keep_pinned: bool,
+}
+
+impl Default for CleanupPolicy {
+    fn default() -> Self {
+        Self {
+            older_than_days: 90,
+            action: CleanupAction::default(),
+            keep_pinned: true,
+        }
+    }
+}
+
+impl CleanupPolicy {
+    pub fn applies_to(&self, summary: &ConversationSummary, now: DateTime<Utc>) -> bool {
+        let old = now - summary.timestamp_last_interaction
+            > chrono::Duration::days(self.older_than_days.into());
+        let kept = self.keep_pinned && summary.pinned;
+        let done = self.action == CleanupAction::Archive && summary.archived;
+        old && !kept && !done
+    }
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    EnumString,
+    AsRefStr,
+    EnumMessage,
+    VariantArray,
+)]
+pub enum CleanupAction {
+    #[strum(message = "Archive them")]
+    Archive,
+    /// Moves them to the trash, from which they are deleted after the trash
+    /// retention.
+    #[strum(message = "Move them to the trash")]
+    Trash,
+}
+
+impl Default for CleanupAction {
+    fn default() -> Self {
+        Self::Archive
+    }
+}
+
+/// Permanently deletes a conversation and everything that belongs to it.
+pub fn purge_conversation(id: ConversationId) {
+    let message_ids = read_storage_raw(StorageKey::Conversation(id))
+        .and_then(|conversation| {
+            serde_json::from_value::<Vec<MessageId>>(conversation["messages"].clone()).ok()
+        })
+        .unwrap_or_default();
+    for message_id in message_ids {
+        delete_storage(StorageKey::Message(message_id));
+    }
+
+    delete_storage(StorageKey::Conversation(id));
+    delete_storage(StorageKey::AuditLog(id));
+    delete_storage(StorageKey::Artifacts(id));
+}
+
 pub fn use_conversation(id: ConversationId) -> StorageSignals<Option<Conversation>> {
     use_storage(StorageKey::Conversation(id))
 }
@@ -110,43 +484,504 @@ pub fn use_message(id: MessageId) -> StorageSignals<Option<Message>> {
     use_storage(StorageKey::Message(id))
 }
 
+pub fn use_debug_log() -> StorageSignals<DebugLog> {
+    use_storage(StorageKey::DebugLog)
+}
+
+pub fn use_artifacts(id: ConversationId) -> StorageSignals<Artifacts> {
+    use_storage(StorageKey::Artifacts(id))
+}
+
+pub fn use_audit_log(id: ConversationId) -> StorageSignals<AuditLog> {
+    use_storage(StorageKey::AuditLog(id))
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Settings {
     pub models: BTreeMap<ModelId, Model>,
     pub debug_mode: bool,
-    pub hf_token: Option<String>,
+    /// API tokens and keys by their name.
+    #[serde(default)]
+    pub credentials: BTreeMap<String, String>,
+    /// The name of the credential that each backend uses. Models can use
+    /// another one.
+    #[serde(default)]
+    pub backend_credentials: HashMap<ModelBackend, String>,
+    #[serde(default)]
+    pub audit_log: bool,
+    #[serde(default = "default_title_model")]
+    pub title_model: ModelId,
+    #[serde(default = "default_true")]
+    pub generate_titles: bool,
+    /// The model that summarizes conversations. If this isn't set, the
+    /// conversation's own model is used.
+    #[serde(default)]
+    pub summary_model: Option<ModelId>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Conversations in the trash are deleted after this many days.
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: Option<u32>,
+    /// Old conversations are kept as they are if this isn't set.
+    #[serde(default)]
+    pub cleanup: Option<CleanupPolicy>,
+    #[serde(default)]
+    pub theme: Theme,
+    #[serde(default)]
+    pub appearance: Appearance,
+    /// Whether clicking an example sends it right away, instead of putting it
+    /// into the input. Holding Shift does the opposite.
+    #[serde(default = "default_true")]
+    pub send_examples: bool,
+    /// Shown on the home page after the examples from the config.
+    #[serde(default)]
+    pub examples: Vec<ExamplePrompt>,
+    /// Whether to periodically check for a newer deployed version.
+    #[serde(default = "default_true")]
+    pub check_for_updates: bool,
+    /// Notify when a response finishes while the app is in the background.
+    #[serde(default)]
+    pub notifications: bool,
+    /// Where the app config is loaded from, instead of the deployment.
+    #[serde(default)]
+    pub config_url: Option<String>,
+    /// How many requests are sent to a backend at the same time.
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// Where conversations are synced to. Syncing is off if this isn't set.
+    #[serde(default)]
+    pub sync: Option<SyncSettings>,
+    /// The speech recognition model used for voice input. Voice input is off
+    /// if this isn't set.
+    #[serde(default)]
+    pub speech_model: Option<ModelId>,
+    /// The tools that models with tool calling can use.
+    #[serde(default = "default_tools")]
+    pub tools: Vec<Tool>,
+    /// The SearxNG instance for the web search tool.
+    #[serde(default)]
+    pub search_url: Option<String>,
+    /// The embedding model for semantic search over past messages. Messages
+    /// are only indexed if this is set.
+    #[serde(default)]
+    pub embedding_model: Option<ModelId>,
+    #[serde(default)]
+    pub anthropic: AnthropicSettings,
+    #[serde(default)]
+    pub tgi: TgiSettings,
+    /// Backends that aren't listed use the default timeouts.
+    #[serde(default)]
+    pub timeouts: HashMap<ModelBackend, Timeouts>,
+    /// Extra headers and base URLs of the backends.
+    #[serde(default)]
+    pub connections: HashMap<ModelBackend, Connection>,
+    /// The app isn't locked if this isn't set.
+    #[serde(default)]
+    pub lock: Option<AppLock>,
 }
 
 impl Settings {
     pub fn reset_models(&mut self) {
-        self.models = BUILD_CONFIG
+        self.models = config()
             .models
             .iter()
             .map(|model| (model.model_id.clone(), model.clone()))
             .collect();
     }
 
+    /// The secret of the credential that requests to `backend` are sent with.
+    /// The model's own credential is used if it has one and runs there.
+    pub fn credential(&self, backend: ModelBackend, model_id: Option<&ModelId>) -> Option<String> {
+        let name = model_id
+            .and_then(|model_id| self.models.get(model_id))
+            .filter(|model| model.backend == backend)
+            .and_then(|model| model.credential.as_ref())
+            .or_else(|| self.backend_credentials.get(&backend))?;
+        let secret = self.credentials.get(name);
+        if secret.is_none() {
+            log::warn!("credential not found: {name}");
+        }
+        secret.cloned()
+    }
+
+    /// The Hugging Face token of the Inference API backend.
+    pub fn hf_token(&self) -> Option<String> {
+        self.credential(ModelBackend::HuggingFace, None)
+    }
+
+    /// Sets the secret of the Inference API backend's credential, which is
+    /// added if the backend doesn't have one. Without a token, the backend
+    /// doesn't use a credential.
+    pub fn set_hf_token(&mut self, hf_token: Option<String>) {
+        let Some(hf_token) = hf_token
+        else {
+            self.backend_credentials.remove(&ModelBackend::HuggingFace);
+            return;
+        };
+        let name = self
+            .backend_credentials
+            .entry(ModelBackend::HuggingFace)
+            .or_insert_with(|| DEFAULT_HF_CREDENTIAL.to_owned());
+        self.credentials.insert(name.clone(), hf_token);
+    }
+
+    /// Renames a credential, and updates the backends and models that use it.
+    pub fn rename_credential(&mut self, name: &str, new_name: String) {
+        let Some(secret) = self.credentials.remove(name)
+        else {
+            return;
+        };
+        self.credentials.insert(new_name.clone(), secret);
+        for used in self
+            .backend_credentials
+            .values_mut()
+            .chain(self.models.values_mut().filter_map(|model| model.credential.as_mut()))
+        {
+            if *used == name {
+                *used = new_name.clone();
+            }
+        }
+    }
+
+    /// Removes a credential. Backends and models that used it don't use any
+    /// anymore.
+    pub fn remove_credential(&mut self, name: &str) {
+        self.credentials.remove(name);
+        self.backend_credentials.retain(|_, used| *used != name);
+        for model in self.models.values_mut() {
+            if model.credential.as_deref() == Some(name) {
+                model.credential = None;
+            }
+        }
+    }
+
+    /// The client that models are checked with when they're added. It only
+    /// takes the token, not the backend's connection.
     pub fn api(&self) -> hf_textgen::Api {
         let mut builder = hf_textgen::Api::builder();
-        if let Some(hf_token) = &self.hf_token {
-            builder = builder.with_hf_token(hf_token.clone());
+        if let Some(hf_token) = self.hf_token() {
+            builder = builder.with_hf_token(hf_token);
         }
         builder.build()
     }
+
+    pub fn backend(&self) -> HuggingFace {
+        HuggingFace::new(
+            self.hf_token(),
+            &self.connection(ModelBackend::HuggingFace),
+            self.max_concurrent_requests,
+        )
+    }
+
+    pub fn timeouts(&self, backend: ModelBackend) -> Timeouts {
+        self.timeouts.get(&backend).copied().unwrap_or_default()
+    }
+
+    pub fn connection(&self, backend: ModelBackend) -> Connection {
+        self.connections.get(&backend).cloned().unwrap_or_default()
+    }
+
+    /// Generates with a model on the backend it's set up for.
+    pub fn text_generation(
+        &self,
+        model_id: &ModelId,
+        parameters: GenerationParameters,
+    ) -> TextGeneration {
+        let backend = self
+            .models
+            .get(model_id)
+            .map(|model| model.backend)
+            .unwrap_or_default();
+        self.text_generation_on(backend, model_id, parameters)
+    }
+
+    /// The backends to generate with, in the order they are tried: the model's
+    /// own backend, and then its fallbacks.
+    pub fn failover_chain(
+        &self,
+        model_id: &ModelId,
+        parameters: GenerationParameters,
+    ) -> Vec<TextGeneration> {
+        let Some(model) = self.models.get(model_id)
+        else {
+            return vec![self.text_generation(model_id, parameters)];
+        };
+
+        let primary = (model.backend, model_id);
+        let fallbacks = model.fallbacks.iter().map(|fallback| {
+            (
+                fallback.backend,
+                fallback.model_id.as_ref().unwrap_or(model_id),
+            )
+        });
+        std::iter::once(primary)
+            .chain(fallbacks)
+            .map(|(backend, model_id)| {
+                self.text_generation_on(backend, model_id, parameters.clone())
+            })
+            .collect()
+    }
+
+    pub fn text_generation_on(
+        &self,
+        backend: ModelBackend,
+        model_id: &ModelId,
+        parameters: GenerationParameters,
+    ) -> TextGeneration {
+        match backend {
+            ModelBackend::HuggingFace => {
+                TextGeneration::HuggingFace(
+                    HuggingFace::new(
+                        self.credential(backend, Some(model_id)),
+                        &self.connection(backend),
+                        self.max_concurrent_requests,
+                    )
+                    .text_generation(model_id, parameters),
+                )
+            }
+            ModelBackend::Local => {
+                TextGeneration::Local(local::TextGeneration::new(model_id, parameters))
+            }
+            ModelBackend::Anthropic => {
+                TextGeneration::Anthropic(
+                    Anthropic::new(
+                        &self.anthropic,
+                        self.credential(backend, Some(model_id)),
+                        &self.connection(backend),
+                        self.max_concurrent_requests,
+                    )
+                    .text_generation(model_id, parameters),
+                )
+            }
+            ModelBackend::OpenRouter => {
+                TextGeneration::OpenRouter(
+                    OpenRouter::new(
+                        self.credential(backend, Some(model_id)),
+                        &self.connection(backend),
+                        self.max_concurrent_requests,
+                    )
+                    .text_generation(model_id, parameters),
+                )
+            }
+            ModelBackend::Tgi => {
+                TextGeneration::Tgi(
+                    HuggingFace::endpoint(
+                        &self.tgi.url,
+                        self.tgi.authorization.clone().or_else(|| {
+                            self.credential(backend, Some(model_id))
+                                .map(|secret| format!("Bearer {secret}"))
+                        }),
+                        &self.connection(backend),
+                        self.max_concurrent_requests,
+                    )
+                    .text_generation(model_id, parameters),
+                )
+            }
+        }
+    }
+
+    /// The reasoning delimiters of the model that generated `message`.
+    /// `model_id` is used if that isn't known yet, e.g. while the response is
+    /// generated.
+    pub fn reasoning_delimiters(
+        &self,
+        message: &Message,
+        model_id: Option<&ModelId>,
+    ) -> Option<&ReasoningDelimiters> {
+        if !matches!(message.role, Role::Assitant) {
+            return None;
+        }
+        let model_id = message
+            .generation
+            .as_ref()
+            .map(|generation| &generation.model_id)
+            .or(model_id)?;
+        self.models.get(model_id)?.reasoning.as_ref()
+    }
+
+    /// The models in the order they're listed in: favorites first, then by
+    /// position and name.
+    pub fn sorted_models(&self) -> Vec<&Model> {
+        let mut models = self.models.values().collect::<Vec<_>>();
+        models.sort_by_cached_key(|model| model.list_order());
+        models
+    }
 }
 
+/// The name of the credential that's added when a Hugging Face token is
+/// entered.
+pub const DEFAULT_HF_CREDENTIAL: &'static str = "Hugging Face";
+
 impl Default for Settings {
     fn default() -> Self {
         let mut this = Self {
             models: BTreeMap::new(),
             debug_mode: false,
-            hf_token: None,
+            credentials: BTreeMap::new(),
+            backend_credentials: HashMap::new(),
+            audit_log: false,
+            title_model: default_title_model(),
+            generate_titles: true,
+            summary_model: None,
+            retry: Default::default(),
+            send_examples: true,
+            examples: vec![],
+            check_for_updates: true,
+            notifications: false,
+            max_concurrent_requests: default_max_concurrent_requests(),
+            config_url: None,
+            sync: None,
+            speech_model: None,
+            tools: default_tools(),
+            search_url: None,
+            embedding_model: None,
+            anthropic: Default::default(),
+            tgi: Default::default(),
+            timeouts: Default::default(),
+            connections: Default::default(),
+            lock: None,
+            trash_retention_days: default_trash_retention_days(),
+            cleanup: None,
+            theme: Default::default(),
+            appearance: Default::default(),
         };
         this.reset_models();
         this
     }
 }
 
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    EnumString,
+    AsRefStr,
+    EnumMessage,
+    VariantArray,
+)]
+pub enum Theme {
+    /// Follows the preference of the operating system.
+    #[strum(message = "Same as the system")]
+    Auto,
+    #[strum(message = "Light")]
+    Light,
+    #[strum(message = "Dark")]
+    Dark,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+impl Theme {
+    /// The theme after this one, when cycling through them with the theme
+    /// button.
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Auto => Self::Light,
+            Self::Light => Self::Dark,
+            Self::Dark => Self::Auto,
+        }
+    }
+}
+
+/// How the UI looks, on top of the theme. The colors are CSS colors like
+/// `#6f42c1`, and the theme's colors are used for those that aren't set.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Appearance {
+    #[serde(default)]
+    pub accent_color: Option<String>,
+    #[serde(default)]
+    pub user_bubble_color: Option<String>,
+    #[serde(default)]
+    pub assistant_bubble_color: Option<String>,
+    /// In percent of the browser's font size.
+    #[serde(default = "default_font_size")]
+    pub font_size: u32,
+    #[serde(default)]
+    pub density: Density,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            accent_color: None,
+            user_bubble_color: None,
+            assistant_bubble_color: None,
+            font_size: default_font_size(),
+            density: Density::default(),
+        }
+    }
+}
+
+impl Appearance {
+    /// The CSS variables for the `style` of the `html` element.
+    pub fn style(&self) -> String {
+        let mut style = format!("font-size: {}%;", self.font_size);
+        if let Some(accent_color) = &self.accent_color {
+            style.push_str(&format!(
+                "--rc-accent: {accent_color}; --bs-primary: {accent_color}; --bs-link-color: {accent_color}; --bs-link-hover-color: {accent_color};"
+            ));
+            if let Some((r, g, b)) = hex_rgb(accent_color) {
+                style.push_str(&format!(
+                    "--bs-primary-rgb: {r}, {g}, {b}; --bs-link-color-rgb: {r}, {g}, {b}; --bs-link-hover-color-rgb: {r}, {g}, {b};"
+                ));
+            }
+        }
+        if let Some(color) = &self.user_bubble_color {
+            style.push_str(&format!("--rc-user-bubble: {color};"));
+        }
+        if let Some(color) = &self.assistant_bubble_color {
+            style.push_str(&format!("--rc-assistant-bubble: {color};"));
+        }
+        style
+    }
+}
+
+/// Parses a color like `#6f42c1`, which is what color inputs give.
+fn hex_rgb(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn default_font_size() -> u32 {
+    100
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    EnumString,
+    AsRefStr,
+    EnumMessage,
+    VariantArray,
+)]
+pub enum Density {
+    #[strum(message = "Comfortable")]
+    Comfortable,
+    /// Less padding around messages and in the sidebar.
+    #[strum(message = "Compact")]
+    Compact,
+}
+
+impl Default for Density {
+    fn default() -> Self {
+        Self::Comfortable
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Home {
     pub selected_model: ModelId,
@@ -154,6 +989,10 @@ pub struct Home {
     pub conversation_parameters: ConversationParameters,
     #[serde(default)]
     pub user_message: String,
+    /// Further models that a new conversation is started with. Each gets its
+    /// own conversation with the same first message.
+    #[serde(default)]
+    pub broadcast_models: BTreeSet<ModelId>,
 }
 
 impl Default for Home {
@@ -162,12 +1001,29 @@ impl Default for Home {
             selected_model: default_model(),
             conversation_parameters: Default::default(),
             user_message: Default::default(),
+            broadcast_models: Default::default(),
         }
     }
 }
 
 fn default_model() -> ModelId {
-    BUILD_CONFIG.default_model.clone()
+    config().default_model.clone()
+}
+
+fn default_title_model() -> ModelId {
+    config().title_model.clone()
+}
+
+fn default_trash_retention_days() -> Option<u32> {
+    Some(30)
+}
+
+fn default_max_concurrent_requests() -> usize {
+    DEFAULT_MAX_CONCURRENCY
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
@@ -180,6 +1036,20 @@ pub struct ConversationParameters {
     pub top_k: Option<usize>,
     pub top_p: Option<f32>,
     pub repetition_penalty: Option<f32>,
+    pub min_p: Option<f32>,
+    pub typical_p: Option<f32>,
+    pub no_repeat_ngram_size: Option<usize>,
+    /// Whether to sample, or always pick the most likely token. Unset uses the
+    /// API's default.
+    pub do_sample: Option<bool>,
+    pub ramp_after_turns: Option<usize>,
+    pub ramp_temperature: Option<f32>,
+    pub response_length: Option<ResponseLength>,
+    /// A JSON schema that responses have to follow, as it was entered.
+    pub json_schema: Option<String>,
+    /// How many responses are generated for each message, to choose from.
+    /// They're generated one after another.
+    pub n: Option<usize>,
 }
 
 #[derive(
@@ -210,11 +1080,45 @@ pub struct Conversation {
     pub id: ConversationId,
     pub model_id: Option<ModelId>,
     pub title: Option<String>,
-    pub timestamp_started: DateTime<Local>,
-    pub timestamp_last_interaction: DateTime<Local>,
+    /// Whether a generated title may be set for this conversation.
+    #[serde(default = "default_true")]
+    pub auto_title: bool,
+    pub timestamp_started: DateTime<Utc>,
+    pub timestamp_last_interaction: DateTime<Utc>,
+    /// When the conversation was last changed without a new message, e.g. when
+    /// it was restored from the trash. Sync compares the later of this and the
+    /// last interaction.
+    #[serde(default)]
+    pub timestamp_modified: DateTime<Utc>,
     pub conversation_parameters: ConversationParameters,
     pub user_message: String,
     pub messages: Vec<MessageId>,
+    /// Archived conversations are hidden from the sidebar.
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Conversations that were started together, by sending the same message
+    /// to several models.
+    #[serde(default)]
+    pub group: Option<Uuid>,
+    /// Pinned conversations are listed first in the sidebar.
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub summary: Option<TranscriptSummary>,
+}
+
+/// A summary of a conversation that the user asked for.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptSummary {
+    pub text: String,
+    /// The last message that the summary covers.
+    pub up_to: MessageId,
+    pub timestamp: DateTime<Utc>,
+    /// Whether the summary is sent instead of the messages it covers, to keep
+    /// long conversations within the context length.
+    pub use_as_context: bool,
 }
 
 #[derive(
@@ -253,12 +1157,164 @@ pub struct Model {
     pub chat_template: ChatTemplate,
     #[serde(default)]
     pub stream: bool,
+    #[serde(default)]
+    pub context_length: Option<usize>,
+    /// Parameters that new conversations with this model start with, instead
+    /// of the ones set on the home page.
+    #[serde(default)]
+    pub default_parameters: ConversationParameters,
+    /// Fetched from the Hub when the model is added.
+    #[serde(default)]
+    pub metadata: Option<ModelMetadata>,
+    #[serde(default)]
+    pub favorite: bool,
+    /// Position in the model lists. Models without one come after the others.
+    #[serde(default)]
+    pub position: Option<u32>,
+    /// Whether the model takes images as input.
+    #[serde(default)]
+    pub vision: bool,
+    /// How the model marks its reasoning, if it does.
+    #[serde(default)]
+    pub reasoning: Option<ReasoningDelimiters>,
+    /// Whether the model can call tools.
+    #[serde(default)]
+    pub tools: bool,
+    /// Where the model runs.
+    #[serde(default)]
+    pub backend: ModelBackend,
+    /// Tried in order if the backend is rate-limited or unavailable.
+    #[serde(default)]
+    pub fallbacks: Vec<Fallback>,
+    /// The name of the credential used for this model, instead of the one of
+    /// its backend.
+    #[serde(default)]
+    pub credential: Option<String>,
+}
+
+/// Another backend that a model can be generated with.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fallback {
+    pub backend: ModelBackend,
+    /// The model's ID on that backend, if it's a different one.
+    #[serde(default)]
+    pub model_id: Option<ModelId>,
+}
+
+/// Marks the reasoning at the start of a response, e.g. `<think>` and
+/// `</think>`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReasoningDelimiters {
+    pub start: String,
+    pub end: String,
+}
+
+impl ReasoningDelimiters {
+    /// Splits a response into its reasoning and the answer. The reasoning is
+    /// only recognized at the start of the response. While the end delimiter
+    /// hasn't been generated yet, all of it is reasoning.
+    pub fn split<'a>(&self, text: &'a str) -> (Option<&'a str>, &'a str) {
+        let Some(rest) = text.trim_start().strip_prefix(self.start.as_str())
+        else {
+            return (None, text);
+        };
+        match rest.find(self.end.as_str()) {
+            Some(end) => {
+                (
+                    Some(rest[..end].trim()),
+                    rest[end + self.end.len()..].trim_start(),
+                )
+            }
+            None => (Some(rest.trim()), ""),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModelMetadata {
+    pub pipeline_tag: Option<String>,
+    pub license: Option<String>,
+    pub gated: bool,
+    pub private: bool,
+    /// The context length from the model's config. [`Model::context_length`]
+    /// is what's actually used, since it can be changed.
+    pub context_length: Option<usize>,
+    pub fetched: DateTime<Utc>,
 }
 
 impl Model {
     pub fn display_name(&self) -> &str {
         self.name.as_ref().unwrap_or_else(|| &self.model_id.0)
     }
+
+    pub fn has_default_parameters(&self) -> bool {
+        self.default_parameters != ConversationParameters::default()
+    }
+
+    fn list_order(&self) -> (bool, u32, String) {
+        (
+            !self.favorite,
+            self.position.unwrap_or(u32::MAX),
+            self.display_name().to_lowercase(),
+        )
+    }
+
+    /// What the model can do, from its settings and the fetched metadata.
+    pub fn capabilities(&self) -> Vec<Capability> {
+        let context_length = self.context_length.or_else(|| {
+            self.metadata
+                .as_ref()
+                .and_then(|metadata| metadata.context_length)
+        });
+
+        let mut capabilities = vec![];
+        if self.stream {
+            capabilities.push(Capability::Streaming);
+        }
+        if self.backend.is_chat() || self.chat_template.supports_system_prompt() {
+            capabilities.push(Capability::SystemPrompt);
+        }
+        if self.vision {
+            capabilities.push(Capability::Vision);
+        }
+        if context_length.map_or(false, |context_length| context_length >= LONG_CONTEXT_LENGTH) {
+            capabilities.push(Capability::LongContext);
+        }
+        if self.backend == ModelBackend::Local {
+            capabilities.push(Capability::Local);
+        }
+        capabilities
+    }
+}
+
+/// Models with at least this many tokens of context have
+/// [`Capability::LongContext`].
+pub const LONG_CONTEXT_LENGTH: usize = 32768;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, EnumMessage)]
+pub enum Capability {
+    #[strum(message = "Streams its responses")]
+    Streaming,
+    #[strum(message = "Follows a system prompt")]
+    SystemPrompt,
+    #[strum(message = "Takes images")]
+    Vision,
+    #[strum(message = "Long context (32k tokens or more)")]
+    LongContext,
+    #[strum(message = "Runs in your browser")]
+    Local,
+}
+
+impl Capability {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Streaming => "lightning-charge",
+            Self::SystemPrompt => "gear-wide-connected",
+            Self::Vision => "eye",
+            Self::LongContext => "text-paragraph",
+            Self::Local => "pc-display",
+        }
+    }
 }
 
 #[derive(
@@ -290,6 +1346,51 @@ impl Default for ChatTemplate {
     }
 }
 
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    VariantArray,
+    EnumString,
+    AsRefStr,
+    EnumMessage,
+)]
+pub enum ModelBackend {
+    #[strum(message = "Hugging Face Inference API")]
+    HuggingFace,
+    /// Experimental. See [`crate::backend::local`].
+    #[strum(message = "In the browser (WebGPU, experimental)")]
+    Local,
+    #[strum(message = "Anthropic")]
+    Anthropic,
+    #[strum(message = "OpenRouter")]
+    OpenRouter,
+    #[strum(message = "Self-hosted text-generation-inference")]
+    Tgi,
+}
+
+impl ModelBackend {
+    /// Whether the backend takes a list of messages, instead of a prompt that
+    /// is formatted with the chat template.
+    pub fn is_chat(&self) -> bool {
+        match self {
+            Self::Anthropic | Self::OpenRouter => true,
+            Self::HuggingFace | Self::Local | Self::Tgi => false,
+        }
+    }
+}
+
+impl Default for ModelBackend {
+    fn default() -> Self {
+        Self::HuggingFace
+    }
+}
+
 impl ChatTemplate {
     pub fn supports_system_prompt(&self) -> bool {
         match self {
@@ -379,7 +1480,136 @@ pub struct Message {
     pub id: MessageId,
     pub role: Role,
     pub text: String,
-    pub timestamp: DateTime<Local>,
+    pub timestamp: DateTime<Utc>,
+    /// How an assistant message was generated.
+    pub generation: Option<GenerationMetadata>,
+    /// Images attached to a user message, as data URLs.
+    #[serde(default)]
+    pub images: Vec<String>,
+    /// The tool whose result this message is. These are user messages, so
+    /// they work with every chat template.
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Whether the response is done.
+    #[serde(default)]
+    pub status: MessageStatus,
+    /// A note by the user. This is never sent to the model.
+    #[serde(default)]
+    pub note: Option<String>,
+    /// How the user rated a response.
+    #[serde(default)]
+    pub rating: Option<Rating>,
+    /// The responses that were generated to choose from, if more than one
+    /// was requested. The text and generation of the chosen one are copied to
+    /// the message, so that later prompts continue with it.
+    #[serde(default)]
+    pub candidates: Vec<Candidate>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub text: String,
+    pub generation: Option<GenerationMetadata>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rating {
+    Good,
+    Bad,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MessageStatus {
+    /// The response was requested, but nothing arrived yet.
+    Pending,
+    Streaming,
+    Complete,
+    /// Generating the response failed. It can be retried.
+    Failed {
+        reason: String,
+    },
+}
+
+impl Default for MessageStatus {
+    fn default() -> Self {
+        Self::Complete
+    }
+}
+
+impl MessageStatus {
+    pub fn is_done(&self) -> bool {
+        matches!(self, Self::Complete | Self::Failed { .. })
+    }
+
+    pub fn failure_reason(&self) -> Option<&str> {
+        match self {
+            Self::Failed { reason } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+impl Message {
+    /// Keeps the current response as a candidate.
+    pub fn push_candidate(&mut self) {
+        self.candidates.push(Candidate {
+            text: self.text.clone(),
+            generation: self.generation.clone(),
+        });
+    }
+
+    /// Which candidate the message shows, if there are candidates.
+    pub fn selected_candidate(&self) -> Option<usize> {
+        self.candidates
+            .iter()
+            .position(|candidate| candidate.text == self.text)
+    }
+
+    pub fn select_candidate(&mut self, index: usize) {
+        if let Some(candidate) = self.candidates.get(index) {
+            self.text = candidate.text.clone();
+            self.generation = candidate.generation.clone();
+        }
+    }
+
+    /// The text with the images in front of it, as markdown images. This is
+    /// how TGI takes image inputs for vision models.
+    pub fn with_inline_images(&self) -> Self {
+        let mut text = String::new();
+        for image in &self.images {
+            write!(&mut text, "![]({image})").unwrap();
+        }
+        text.push_str(&self.text);
+        Self {
+            text,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenerationMetadata {
+    pub model_id: ModelId,
+    pub conversation_parameters: ConversationParameters,
+    /// Number of generated tokens. This is an estimate for responses that
+    /// weren't streamed.
+    pub tokens: usize,
+    pub duration_ms: u64,
+    /// Only known for streamed responses.
+    pub time_to_first_token_ms: Option<u64>,
+    /// Whether the response stopped because it reached the token limit.
+    #[serde(default)]
+    pub truncated: bool,
+    /// The backend that generated the response. This isn't the model's own
+    /// one, if it failed over.
+    #[serde(default)]
+    pub backend: Option<ModelBackend>,
+}
+
+impl GenerationMetadata {
+    pub fn tokens_per_second(&self) -> Option<f32> {
+        (self.duration_ms > 0).then(|| self.tokens as f32 * 1000.0 / self.duration_ms as f32)
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -387,3 +1617,61 @@ pub enum Role {
     Assitant,
     User,
 }
+
+/// The exact prompts and parameters that were sent to generate the assistant
+/// messages of a conversation.
+pub type AuditLog = Vec<AuditLogEntry>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub message_id: MessageId,
+    pub timestamp: DateTime<Utc>,
+    pub model_id: ModelId,
+    pub prompt: String,
+    pub parameters: GenerationParameters,
+}
+
+/// The newest generation requests and their outcome, recorded in debug mode.
+pub type DebugLog = Vec<DebugLogEntry>;
+
+/// How many requests the [`DebugLog`] keeps.
+pub const DEBUG_LOG_SIZE: usize = 100;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DebugLogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub model_id: ModelId,
+    pub parameters: GenerationParameters,
+    pub stream: bool,
+    /// In characters.
+    pub prompt_length: usize,
+    pub duration_ms: i64,
+    pub outcome: RequestOutcome,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RequestOutcome {
+    Success { tokens: usize },
+    Failed { status: Option<u16>, error: String },
+}
+
+pub type Artifacts = Vec<Artifact>;
+
+/// A document or code file that was promoted from a message, so that it can be
+/// viewed separately. Later messages can add new versions.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Artifact {
+    pub id: Uuid,
+    pub title: String,
+    /// The language of the code block, or `None` if this is a markdown
+    /// document.
+    pub language: Option<String>,
+    pub versions: Vec<ArtifactVersion>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactVersion {
+    pub message_id: MessageId,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
@@ -0,0 +1,332 @@
+//! Syncing conversations between devices through a WebDAV server.
+//!
+//! All conversations are stored in a single snapshot file on the server. When
+//! syncing, we download it, merge it with the local conversations, and upload
+//! the result. If a conversation was changed on both sides, the one with the
+//! newer change wins. Conversations that were moved to the trash are
+//! kept as tombstones, so that other devices delete them too.
+//!
+//! The upload only replaces the snapshot that was downloaded. If another
+//! device uploaded in between, the server refuses it, and we merge again.
+
+use std::collections::BTreeMap;
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::state::{
+    is_in_memory,
+    read_storage_raw,
+    Conversation,
+    ConversationId,
+    Conversations,
+    Message,
+    StorageKey,
+    Trash,
+};
+
+/// The name of the snapshot file in the sync folder.
+pub const SNAPSHOT_FILE: &'static str = "rusty-chat.json";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncSettings {
+    /// The folder on the WebDAV server.
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl SyncSettings {
+    fn snapshot_url(&self) -> String {
+        format!("{}/{SNAPSHOT_FILE}", self.url.trim_end_matches('/'))
+    }
+
+    fn request(&self, method: reqwest::Method) -> reqwest::RequestBuilder {
+        let request = reqwest::Client::new().request(method, self.snapshot_url());
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_ref()),
+            None => request,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    #[error("Sync request failed")]
+    Request(#[from] reqwest::Error),
+    #[error("Sync server returned status {0}")]
+    Status(u16),
+    #[error("The snapshot on the sync server was changed by another device")]
+    Conflict,
+    #[error("Invalid snapshot on the sync server")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub conversations: BTreeMap<ConversationId, SyncedConversation>,
+    /// Conversations that were deleted, and when.
+    #[serde(default)]
+    pub deleted: BTreeMap<ConversationId, DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyncedConversation {
+    pub conversation: Conversation,
+    pub messages: Vec<Message>,
+}
+
+impl SyncedConversation {
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.conversation
+            .timestamp_last_interaction
+            .max(self.conversation.timestamp_modified)
+    }
+
+    /// Reads a conversation and its messages from storage.
+    fn read(id: ConversationId) -> Option<Self> {
+        let conversation: Conversation =
+            serde_json::from_value(read_storage_raw(StorageKey::Conversation(id))?).ok()?;
+        let messages = conversation
+            .messages
+            .iter()
+            .filter_map(|message_id| {
+                serde_json::from_value(read_storage_raw(StorageKey::Message(*message_id))?).ok()
+            })
+            .collect();
+        Some(Self {
+            conversation,
+            messages,
+        })
+    }
+}
+
+/// The snapshot of the conversations on this device. Private conversations
+/// are never synced.
+pub fn local_snapshot(conversations: &Conversations, trash: &Trash) -> Snapshot {
+    let conversations = conversations
+        .iter()
+        .filter(|id| !is_in_memory(StorageKey::Conversation(**id)))
+        .filter_map(|id| Some((*id, SyncedConversation::read(*id)?)))
+        .collect();
+    Snapshot {
+        conversations,
+        deleted: trash.clone(),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Merged {
+    /// What is uploaded to the server.
+    pub snapshot: Snapshot,
+    /// Conversations that are new or newer on the server.
+    pub pulled: Vec<SyncedConversation>,
+    /// Local conversations that were deleted on another device.
+    pub deleted: Vec<(ConversationId, DateTime<Utc>)>,
+}
+
+pub fn merge(local: Snapshot, remote: Snapshot) -> Merged {
+    let mut merged = Merged::default();
+
+    let mut deleted = remote.deleted;
+    for (id, timestamp) in local.deleted {
+        let entry = deleted.entry(id).or_insert(timestamp);
+        *entry = timestamp.max(*entry);
+    }
+    // a conversation that was continued after it was deleted is kept.
+    let is_deleted = |synced: &SyncedConversation| {
+        deleted
+            .get(&synced.conversation.id)
+            .map_or(false, |deleted| *deleted >= synced.timestamp())
+    };
+
+    let mut local_conversations = local.conversations;
+    for (id, remote) in remote.conversations {
+        match local_conversations.get(&id) {
+            Some(local) if local.timestamp() >= remote.timestamp() => {}
+            _ => {
+                if !is_deleted(&remote) {
+                    local_conversations.remove(&id);
+                    merged.pulled.push(remote.clone());
+                }
+                merged.snapshot.conversations.insert(id, remote);
+            }
+        }
+    }
+
+    for (id, local) in local_conversations {
+        if is_deleted(&local) {
+            merged.deleted.push((id, deleted[&id]));
+        }
+        else {
+            merged.snapshot.conversations.insert(id, local);
+        }
+    }
+
+    merged
+        .snapshot
+        .conversations
+        .retain(|_, synced| !is_deleted(synced));
+    merged.snapshot.deleted = deleted;
+
+    merged
+}
+
+/// Which snapshot an upload may replace.
+#[derive(Clone, Debug)]
+pub enum Precondition {
+    /// There was no snapshot on the server.
+    Missing,
+    /// The snapshot with this ETag.
+    Unchanged(String),
+    /// The server didn't send an ETag (or doesn't expose it to us), so we
+    /// can't tell.
+    Unknown,
+}
+
+/// Downloads the snapshot from the server. If there is none yet, an empty
+/// snapshot is returned.
+pub async fn download(settings: &SyncSettings) -> Result<(Snapshot, Precondition), SyncError> {
+    let response = settings.request(reqwest::Method::GET).send().await?;
+    match response.status().as_u16() {
+        404 => Ok((Snapshot::default(), Precondition::Missing)),
+        status if !response.status().is_success() => Err(SyncError::Status(status)),
+        _ => {
+            let precondition = response
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|etag| etag.to_str().ok())
+                .map_or(Precondition::Unknown, |etag| {
+                    Precondition::Unchanged(etag.to_owned())
+                });
+            Ok((
+                serde_json::from_str(&response.text().await?)?,
+                precondition,
+            ))
+        }
+    }
+}
+
+/// Uploads the snapshot, unless the one on the server doesn't match
+/// `precondition` anymore. Then [`SyncError::Conflict`] is returned.
+pub async fn upload(
+    settings: &SyncSettings,
+    snapshot: &Snapshot,
+    precondition: &Precondition,
+) -> Result<(), SyncError> {
+    let request = settings
+        .request(reqwest::Method::PUT)
+        .header("Content-Type", "application/json");
+    let request = match precondition {
+        Precondition::Missing => request.header(reqwest::header::IF_NONE_MATCH, "*"),
+        Precondition::Unchanged(etag) => request.header(reqwest::header::IF_MATCH, etag),
+        Precondition::Unknown => request,
+    };
+    let response = request.body(serde_json::to_string(snapshot)?).send().await?;
+    match response.status().as_u16() {
+        412 => Err(SyncError::Conflict),
+        status if !response.status().is_success() => Err(SyncError::Status(status)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    fn synced(last_interaction: DateTime<Utc>) -> SyncedConversation {
+        SyncedConversation {
+            conversation: Conversation {
+                id: ConversationId::new(),
+                model_id: None,
+                title: None,
+                auto_title: true,
+                timestamp_started: last_interaction,
+                timestamp_last_interaction: last_interaction,
+                timestamp_modified: last_interaction,
+                conversation_parameters: Default::default(),
+                user_message: "".to_owned(),
+                messages: vec![],
+                archived: false,
+                tags: vec![],
+                group: None,
+                pinned: false,
+                summary: None,
+            },
+            messages: vec![],
+        }
+    }
+
+    fn snapshot(
+        conversations: impl IntoIterator<Item = SyncedConversation>,
+        deleted: impl IntoIterator<Item = (ConversationId, DateTime<Utc>)>,
+    ) -> Snapshot {
+        Snapshot {
+            conversations: conversations
+                .into_iter()
+                .map(|synced| (synced.conversation.id, synced))
+                .collect(),
+            deleted: deleted.into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn it_deletes_conversations_deleted_on_another_device() {
+        let conversation = synced(at(1));
+        let id = conversation.conversation.id;
+
+        let merged = merge(snapshot([conversation], []), snapshot([], [(id, at(2))]));
+
+        assert_eq!(merged.deleted, vec![(id, at(2))]);
+        assert!(!merged.snapshot.conversations.contains_key(&id));
+        assert_eq!(merged.snapshot.deleted.get(&id), Some(&at(2)));
+    }
+
+    #[test]
+    fn it_keeps_conversations_continued_after_they_were_deleted() {
+        let conversation = synced(at(3));
+        let id = conversation.conversation.id;
+
+        let merged = merge(snapshot([conversation], []), snapshot([], [(id, at(2))]));
+
+        assert!(merged.deleted.is_empty());
+        assert!(merged.snapshot.conversations.contains_key(&id));
+    }
+
+    #[test]
+    fn it_keeps_restored_conversations() {
+        let mut conversation = synced(at(1));
+        conversation.conversation.timestamp_modified = at(3);
+        let id = conversation.conversation.id;
+
+        let merged = merge(snapshot([conversation], []), snapshot([], [(id, at(2))]));
+
+        assert!(merged.deleted.is_empty());
+        assert!(merged.snapshot.conversations.contains_key(&id));
+    }
+
+    #[test]
+    fn it_pulls_conversations_restored_on_another_device() {
+        let mut conversation = synced(at(1));
+        conversation.conversation.timestamp_modified = at(3);
+        let id = conversation.conversation.id;
+
+        let merged = merge(snapshot([], [(id, at(2))]), snapshot([conversation], []));
+
+        assert_eq!(merged.pulled.len(), 1);
+        assert_eq!(merged.pulled[0].conversation.id, id);
+        assert!(merged.snapshot.conversations.contains_key(&id));
+    }
+}
@@ -0,0 +1,258 @@
+//! Coordination between multiple tabs of the app.
+//!
+//! Changes to the local storage are already propagated between tabs by the
+//! `storage` event. On top of that, tabs hold a lock on a conversation while
+//! generating into it, and announce it over a `BroadcastChannel`. This way two
+//! tabs can't generate into the same conversation at the same time. A tab can
+//! take over the lock, which stops the generation in the tab that held it.
+//! Messages that are sent while another tab holds the lock wait in the outbox
+//! until it's released.
+
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use leptos::{
+    create_rw_signal,
+    ev,
+    store_value,
+    window_event_listener,
+    RwSignal,
+    SignalUpdate,
+    SignalWith,
+    SignalWithUntracked,
+    StoredValue,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use uuid::Uuid;
+use wasm_bindgen::{
+    closure::Closure,
+    JsCast,
+    JsValue,
+};
+use web_sys::{
+    BroadcastChannel,
+    MessageEvent,
+};
+
+use crate::state::ConversationId;
+
+const CHANNEL_NAME: &'static str = "rusty-chat";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum TabMessage {
+    /// Sent by a tab when it starts up, so that other tabs announce the locks
+    /// they hold.
+    Hello { tab_id: Uuid },
+    Locked {
+        tab_id: Uuid,
+        conversation_id: ConversationId,
+    },
+    Unlocked {
+        tab_id: Uuid,
+        conversation_id: ConversationId,
+    },
+    /// Asks the tab holding the lock to stop generating and release it.
+    TakeOver {
+        tab_id: Uuid,
+        conversation_id: ConversationId,
+    },
+}
+
+#[derive(Copy, Clone)]
+pub struct Tabs {
+    tab_id: Uuid,
+    channel: StoredValue<Option<BroadcastChannel>>,
+    local_locks: RwSignal<HashSet<ConversationId>>,
+    /// Locks held by other tabs, and which tab holds them.
+    remote_locks: RwSignal<HashMap<ConversationId, Uuid>>,
+}
+
+impl Tabs {
+    pub fn new() -> Self {
+        let this = Self {
+            tab_id: Uuid::new_v4(),
+            channel: store_value(None),
+            local_locks: create_rw_signal(HashSet::new()),
+            remote_locks: create_rw_signal(HashMap::new()),
+        };
+
+        let channel = match BroadcastChannel::new(CHANNEL_NAME) {
+            Ok(channel) => channel,
+            Err(error) => {
+                log::warn!("BroadcastChannel not available: {error:?}");
+                return this;
+            }
+        };
+
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            let Some(data) = event.data().as_string()
+            else {
+                return;
+            };
+            match serde_json::from_str(&data) {
+                Ok(message) => this.handle_message(message),
+                Err(error) => log::warn!("invalid tab message: {error}"),
+            }
+        });
+        channel.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        on_message.forget();
+
+        this.channel.set_value(Some(channel));
+        this.send(&TabMessage::Hello {
+            tab_id: this.tab_id,
+        });
+
+        // don't leave other tabs waiting for locks that will never be released.
+        window_event_listener(ev::pagehide, move |_| {
+            for conversation_id in this.local_lock_ids() {
+                this.unlock(conversation_id);
+            }
+        });
+
+        this
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.tab_id
+    }
+
+    fn handle_message(&self, message: TabMessage) {
+        log::debug!("tab message: {message:?}");
+
+        match message {
+            TabMessage::Hello { .. } => {
+                for conversation_id in self.local_lock_ids() {
+                    self.send(&TabMessage::Locked {
+                        tab_id: self.tab_id,
+                        conversation_id,
+                    });
+                }
+            }
+            TabMessage::Locked {
+                tab_id,
+                conversation_id,
+            } => {
+                if self.holds_lock(conversation_id) {
+                    // both tabs locked the conversation at the same time. the tab with the
+                    // lower id wins.
+                    if self.tab_id < tab_id {
+                        self.send(&TabMessage::Locked {
+                            tab_id: self.tab_id,
+                            conversation_id,
+                        });
+                        return;
+                    }
+                    log::warn!("lost lock race for conversation {conversation_id}");
+                    self.release(conversation_id);
+                }
+
+                self.remote_locks.update(|locks| {
+                    locks.insert(conversation_id, tab_id);
+                });
+            }
+            TabMessage::Unlocked {
+                tab_id,
+                conversation_id,
+            } => {
+                self.remote_locks.update(|locks| {
+                    if locks.get(&conversation_id) == Some(&tab_id) {
+                        locks.remove(&conversation_id);
+                    }
+                });
+            }
+            TabMessage::TakeOver {
+                conversation_id, ..
+            } => {
+                if self.holds_lock(conversation_id) {
+                    log::info!("another tab took over conversation {conversation_id}");
+                    self.unlock(conversation_id);
+                }
+            }
+        }
+    }
+
+    fn local_lock_ids(&self) -> Vec<ConversationId> {
+        self.local_locks
+            .with_untracked(|locks| locks.iter().copied().collect())
+    }
+
+    fn send(&self, message: &TabMessage) {
+        let message = serde_json::to_string(message).expect("failed to serialize tab message");
+        self.channel.with_value(|channel| {
+            if let Some(channel) = channel {
+                if let Err(error) = channel.post_message(&JsValue::from_str(&message)) {
+                    log::warn!("failed to send tab message: {error:?}");
+                }
+            }
+        });
+    }
+
+    /// Removes the local lock. Returns whether we held it.
+    fn release(&self, conversation_id: ConversationId) -> bool {
+        self.local_locks
+            .try_update(|locks| locks.remove(&conversation_id))
+            .unwrap_or_default()
+    }
+
+    /// Tries to lock the conversation for generation. Returns `false` if
+    /// another tab holds the lock.
+    pub fn lock(&self, conversation_id: ConversationId) -> bool {
+        let locked_elsewhere = self
+            .remote_locks
+            .with_untracked(|locks| locks.contains_key(&conversation_id));
+        if locked_elsewhere {
+            return false;
+        }
+
+        self.local_locks.update(|locks| {
+            locks.insert(conversation_id);
+        });
+        self.send(&TabMessage::Locked {
+            tab_id: self.tab_id,
+            conversation_id,
+        });
+
+        true
+    }
+
+    pub fn unlock(&self, conversation_id: ConversationId) {
+        if self.release(conversation_id) {
+            self.send(&TabMessage::Unlocked {
+                tab_id: self.tab_id,
+                conversation_id,
+            });
+        }
+    }
+
+    /// Whether this tab still holds the lock. Generations should stop once
+    /// this returns `false`, because another tab took over.
+    pub fn holds_lock(&self, conversation_id: ConversationId) -> bool {
+        self.local_locks
+            .with_untracked(|locks| locks.contains(&conversation_id))
+    }
+
+    /// Stops the generation in the tab that holds the lock, so that this tab
+    /// can lock the conversation.
+    pub fn take_over(&self, conversation_id: ConversationId) {
+        self.send(&TabMessage::TakeOver {
+            tab_id: self.tab_id,
+            conversation_id,
+        });
+        self.remote_locks.update(|locks| {
+            locks.remove(&conversation_id);
+        });
+    }
+
+    /// Whether another tab currently holds the lock for this conversation.
+    /// This is reactive.
+    pub fn is_locked_elsewhere(&self, conversation_id: ConversationId) -> bool {
+        self.remote_locks
+            .with(|locks| locks.contains_key(&conversation_id))
+    }
+}
@@ -0,0 +1,33 @@
+//! Approximate token counting.
+//!
+//! We don't ship the vocabularies of the models, so this estimates the number
+//! of tokens a BPE tokenizer would produce: words are split into chunks of
+//! about 4 characters, and every symbol and non-latin character counts as a
+//! token of its own.
+
+const CHARS_PER_TOKEN: usize = 4;
+
+pub fn count_tokens(text: &str) -> usize {
+    let mut count = 0;
+    let mut word_length = 0;
+
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            word_length += 1;
+        }
+        else {
+            count += word_tokens(word_length);
+            word_length = 0;
+
+            if !c.is_whitespace() {
+                count += 1;
+            }
+        }
+    }
+
+    count + word_tokens(word_length)
+}
+
+fn word_tokens(length: usize) -> usize {
+    (length + CHARS_PER_TOKEN - 1) / CHARS_PER_TOKEN
+}
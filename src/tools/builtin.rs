@@ -0,0 +1,225 @@
+//! The implementations of the built-in tools.
+
+use chrono::{
+    Local,
+    Utc,
+};
+use serde_json::Value;
+
+use super::ToolError;
+use crate::render::unescape_html;
+
+/// Fetched pages are cut off after this many characters, so they fit into the
+/// context.
+const MAX_FETCH_LENGTH: usize = 20000;
+
+pub(super) fn string_argument<'a>(
+    arguments: &'a Value,
+    name: &'static str,
+) -> Result<&'a str, ToolError> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or(ToolError::MissingArgument(name))
+}
+
+pub fn current_time() -> String {
+    let now = Utc::now();
+    format!(
+        "UTC: {}\nLocal: {}",
+        now.to_rfc3339(),
+        now.with_timezone(&Local)
+            .format("%A, %Y-%m-%d %H:%M:%S %:z")
+    )
+}
+
+pub async fn fetch_url(arguments: &Value) -> Result<String, ToolError> {
+    let url = string_argument(arguments, "url")?;
+    let response = reqwest::get(url).await?.error_for_status()?;
+    let is_html = response
+        .headers()
+        .get("content-type")
+        .and_then(|content_type| content_type.to_str().ok())
+        .map_or(false, |content_type| content_type.contains("html"));
+    let mut text = response.text().await?;
+
+    if is_html {
+        // keeps only the text. scripts and styles are removed with their content.
+        text = unescape_html(&ammonia::Builder::empty().clean(&text).to_string());
+    }
+
+    if let Some((cut, _)) = text.char_indices().nth(MAX_FETCH_LENGTH) {
+        text.truncate(cut);
+        text.push_str("\n[cut off]");
+    }
+    Ok(text)
+}
+
+pub fn calculator(arguments: &Value) -> Result<String, ToolError> {
+    let expression = string_argument(arguments, "expression")?;
+    let mut parser = Parser {
+        input: expression,
+        position: 0,
+    };
+    let value = parser.expression()?;
+    parser.skip_whitespace();
+    if parser.position < parser.input.len() {
+        return Err(parser.error("unexpected input"));
+    }
+    Ok(value.to_string())
+}
+
+/// A recursive descent parser for math expressions, that evaluates them while
+/// parsing.
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> ToolError {
+        ToolError::Expression(format!("{message} at position {}", self.position))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.input[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    /// `term (('+' | '-') term)*`
+    fn expression(&mut self) -> Result<f64, ToolError> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            }
+            else if self.eat('-') {
+                value -= self.term()?;
+            }
+            else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// `power (('*' | '/' | '%') power)*`
+    fn term(&mut self) -> Result<f64, ToolError> {
+        let mut value = self.power()?;
+        loop {
+            if self.eat('*') {
+                value *= self.power()?;
+            }
+            else if self.eat('/') {
+                value /= self.power()?;
+            }
+            else if self.eat('%') {
+                value %= self.power()?;
+            }
+            else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// `unary ('^' power)?`, which is right-associative.
+    fn power(&mut self) -> Result<f64, ToolError> {
+        let base = self.unary()?;
+        if self.eat('^') {
+            Ok(base.powf(self.power()?))
+        }
+        else {
+            Ok(base)
+        }
+    }
+
+    fn unary(&mut self) -> Result<f64, ToolError> {
+        if self.eat('-') {
+            Ok(-self.unary()?)
+        }
+        else if self.eat('+') {
+            self.unary()
+        }
+        else {
+            self.atom()
+        }
+    }
+
+    fn atom(&mut self) -> Result<f64, ToolError> {
+        if self.eat('(') {
+            let value = self.expression()?;
+            if !self.eat(')') {
+                return Err(self.error("expected ')'"));
+            }
+            return Ok(value);
+        }
+
+        let rest = &self.input[self.position..];
+        match rest.chars().next() {
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let length = rest
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                    .unwrap_or(rest.len());
+                let value = rest[..length]
+                    .parse()
+                    .map_err(|_| self.error("invalid number"))?;
+                self.position += length;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let length = rest
+                    .find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(rest.len());
+                let name = &rest[..length];
+                self.position += length;
+                self.function_or_constant(name)
+            }
+            _ => Err(self.error("expected a number")),
+        }
+    }
+
+    fn function_or_constant(&mut self, name: &str) -> Result<f64, ToolError> {
+        match name {
+            "pi" => return Ok(std::f64::consts::PI),
+            "e" => return Ok(std::f64::consts::E),
+            _ => {}
+        }
+
+        let function: fn(f64) -> f64 = match name {
+            "sqrt" => f64::sqrt,
+            "abs" => f64::abs,
+            "sin" => f64::sin,
+            "cos" => f64::cos,
+            "tan" => f64::tan,
+            "ln" => f64::ln,
+            "log" => f64::log10,
+            "exp" => f64::exp,
+            "floor" => f64::floor,
+            "ceil" => f64::ceil,
+            "round" => f64::round,
+            _ => return Err(self.error(&format!("unknown function '{name}'"))),
+        };
+        if !self.eat('(') {
+            return Err(self.error("expected '('"));
+        }
+        let argument = self.expression()?;
+        if !self.eat(')') {
+            return Err(self.error("expected ')'"));
+        }
+        Ok(function(argument))
+    }
+}
@@ -0,0 +1,214 @@
+//! Tools that models can call.
+//!
+//! The tools are described in the system prompt for models that support tool
+//! calling. A model calls a tool by responding with a `<tool_call>` block with
+//! the tool's name and arguments as JSON. The tool is then run in the browser,
+//! and its result is sent back in a `<tool_response>` block, so that the model
+//! can answer with it.
+
+mod builtin;
+pub mod search;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    json,
+    Value,
+};
+use strum::{
+    EnumMessage,
+    VariantArray,
+};
+use uuid::Uuid;
+
+const TOOL_CALL_START: &'static str = "<tool_call>";
+const TOOL_CALL_END: &'static str = "</tool_call>";
+
+/// How often a model may call tools for one response, so that it can't loop
+/// forever.
+pub const MAX_TOOL_ROUNDS: usize = 5;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ToolError {
+    #[error("Unknown tool: {0}")]
+    UnknownTool(String),
+    #[error("Missing argument: {0}")]
+    MissingArgument(&'static str),
+    #[error("Invalid expression: {0}")]
+    Expression(String),
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("No search server is set up")]
+    SearchNotConfigured,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tool {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    /// The JSON schema of the arguments.
+    pub parameters: Value,
+    pub implementation: Implementation,
+}
+
+/// What a tool does when it's called.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, VariantArray, EnumMessage)]
+pub enum Implementation {
+    #[strum(message = "Web search")]
+    WebSearch,
+    #[strum(message = "Fetch URL")]
+    FetchUrl,
+    #[strum(message = "Calculator")]
+    Calculator,
+    #[strum(message = "Current time")]
+    CurrentTime,
+}
+
+impl Implementation {
+    /// A tool with this implementation, and a name, description and schema
+    /// that fit it.
+    pub fn default_tool(&self) -> Tool {
+        let (name, description, parameters) = match self {
+            Self::WebSearch => {
+                (
+                    "web_search",
+                    "Searches the web and returns the top results with a short snippet each.",
+                    json!({
+                        "type": "object",
+                        "properties": {
+                            "query": { "type": "string", "description": "What to search for" }
+                        },
+                        "required": ["query"]
+                    }),
+                )
+            }
+            Self::FetchUrl => {
+                (
+                    "fetch_url",
+                    "Fetches a web page and returns its text.",
+                    json!({
+                        "type": "object",
+                        "properties": {
+                            "url": { "type": "string", "description": "The URL of the page" }
+                        },
+                        "required": ["url"]
+                    }),
+                )
+            }
+            Self::Calculator => {
+                (
+                    "calculator",
+                    "Evaluates a math expression, e.g. `2 * (3 + 4) ^ 2` or `sqrt(2) / pi`.",
+                    json!({
+                        "type": "object",
+                        "properties": {
+                            "expression": { "type": "string", "description": "The expression to evaluate" }
+                        },
+                        "required": ["expression"]
+                    }),
+                )
+            }
+            Self::CurrentTime => {
+                (
+                    "current_time",
+                    "Returns the current date and time.",
+                    json!({ "type": "object", "properties": {} }),
+                )
+            }
+        };
+        Tool {
+            id: Uuid::new_v4(),
+            name: name.to_owned(),
+            description: description.to_owned(),
+            parameters,
+            implementation: *self,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        self.get_message().unwrap()
+    }
+}
+
+pub fn default_tools() -> Vec<Tool> {
+    Implementation::VARIANTS
+        .iter()
+        .map(Implementation::default_tool)
+        .collect()
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// Describes the tools and how to call them, for the system prompt.
+pub fn instructions(tools: &[Tool]) -> Option<String> {
+    if tools.is_empty() {
+        return None;
+    }
+
+    let mut instructions = "You can call these tools:\n<tools>\n".to_owned();
+    for tool in tools {
+        let description = json!({
+            "name": tool.name,
+            "description": tool.description,
+            "parameters": tool.parameters,
+        });
+        instructions.push_str(&description.to_string());
+        instructions.push('\n');
+    }
+    instructions.push_str(concat!(
+        "</tools>\n",
+        "To call a tool, respond with only the tool call, like this:\n",
+        "<tool_call>\n",
+        "{\"name\": \"<tool name>\", \"arguments\": {<arguments>}}\n",
+        "</tool_call>\n",
+        "The result is sent back to you in a <tool_response> block.",
+    ));
+    Some(instructions)
+}
+
+/// Finds a tool call in a response. Returns the call, and where the response
+/// should be cut off, since models sometimes keep going after the call.
+pub fn parse_tool_call(text: &str) -> Option<(ToolCall, usize)> {
+    let start = text.find(TOOL_CALL_START)? + TOOL_CALL_START.len();
+    // the end tag might be a stop token, and then it's missing.
+    let (end, cut_off) = text[start..]
+        .find(TOOL_CALL_END)
+        .map(|end| (start + end, start + end + TOOL_CALL_END.len()))
+        .unwrap_or((text.len(), text.len()));
+    let call = serde_json::from_str(text[start..end].trim()).ok()?;
+    Some((call, cut_off))
+}
+
+/// The message with the result of a tool call, that is sent back to the model.
+pub fn tool_response(result: Result<String, ToolError>) -> String {
+    let result = result.unwrap_or_else(|error| format!("Error: {error}"));
+    format!("<tool_response>\n{result}\n</tool_response>")
+}
+
+/// Runs a tool call. `search_url` is the SearxNG instance for web searches.
+pub async fn run(
+    tools: &[Tool],
+    search_url: Option<&str>,
+    call: &ToolCall,
+) -> Result<String, ToolError> {
+    let tool = tools
+        .iter()
+        .find(|tool| tool.name == call.name)
+        .ok_or_else(|| ToolError::UnknownTool(call.name.clone()))?;
+    log::info!("calling tool {}: {}", tool.name, call.arguments);
+
+    match tool.implementation {
+        Implementation::WebSearch => search::web_search(search_url, &call.arguments).await,
+        Implementation::FetchUrl => builtin::fetch_url(&call.arguments).await,
+        Implementation::Calculator => builtin::calculator(&call.arguments),
+        Implementation::CurrentTime => Ok(builtin::current_time()),
+    }
+}
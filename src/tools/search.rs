@@ -0,0 +1,147 @@
+//! The web search tool, which uses a SearxNG instance, and citing its results
+//! in responses.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{
+    builtin::string_argument,
+    ToolError,
+};
+use crate::render::escape_html;
+
+/// How many results are given to the model.
+const MAX_RESULTS: usize = 5;
+
+const URL_PREFIX: &'static str = "URL: ";
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    results: Vec<SearchResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: String,
+}
+
+/// A search result that a response can cite.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Source {
+    pub number: usize,
+    pub title: String,
+    pub url: String,
+}
+
+/// Searches with the JSON API of a SearxNG instance. The instance has to allow
+/// the JSON format, and requests from this origin.
+pub async fn web_search(search_url: Option<&str>, arguments: &Value) -> Result<String, ToolError> {
+    let search_url = search_url.ok_or(ToolError::SearchNotConfigured)?;
+    let query = string_argument(arguments, "query")?;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/search", search_url.trim_end_matches('/')))
+        .query(&[("q", query), ("format", "json")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<SearchResponse>()
+        .await?;
+    if response.results.is_empty() {
+        return Ok(format!("No results for \"{query}\"."));
+    }
+
+    let mut output = format!("Search results for \"{query}\":\n");
+    for (i, result) in response.results.iter().take(MAX_RESULTS).enumerate() {
+        output.push_str(&format!(
+            "\n[{}] {}\n{URL_PREFIX}{}\n{}\n",
+            i + 1,
+            result.title.trim(),
+            result.url,
+            result.content.trim()
+        ));
+    }
+    output.push_str("\nCite the results you use with their number, like [1].");
+    Ok(output)
+}
+
+/// Finds the sources in the result of a web search. This is empty for the
+/// results of other tools.
+pub fn search_sources(text: &str) -> Vec<Source> {
+    let mut sources = vec![];
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let Some((number, title)) = line
+            .strip_prefix('[')
+            .and_then(|line| line.split_once("] "))
+        else {
+            continue;
+        };
+        let Ok(number) = number.parse()
+        else {
+            continue;
+        };
+        let Some(url) = lines.peek().and_then(|line| line.strip_prefix(URL_PREFIX))
+        else {
+            continue;
+        };
+        sources.push(Source {
+            number,
+            title: title.to_owned(),
+            url: url.to_owned(),
+        });
+    }
+
+    sources
+}
+
+/// Turns citations like `[1]` in the rendered HTML of a response into links
+/// to the sources, and lists the cited sources below it.
+pub fn link_citations(html: &str, sources: &[Source]) -> String {
+    let mut html = html.to_owned();
+    let mut cited = vec![];
+
+    for source in sources {
+        // only link to web pages, and not e.g. `javascript:` URLs.
+        if !(source.url.starts_with("https://") || source.url.starts_with("http://")) {
+            continue;
+        }
+        let citation = format!("[{}]", source.number);
+        if !html.contains(&citation) {
+            continue;
+        }
+
+        let mut link = "<sup><a target=\"_blank\" rel=\"noopener noreferrer\" href=\"".to_owned();
+        escape_html(&source.url, &mut link);
+        link.push_str("\" title=\"");
+        escape_html(&source.title, &mut link);
+        link.push_str("\">");
+        link.push_str(&citation);
+        link.push_str("</a></sup>");
+
+        html = html.replace(&citation, &link);
+        cited.push(source);
+    }
+
+    if !cited.is_empty() {
+        html.push_str("<ol class=\"sources small\">");
+        for source in cited {
+            html.push_str(&format!(
+                "<li value=\"{}\"><a target=\"_blank\" rel=\"noopener noreferrer\" href=\"",
+                source.number
+            ));
+            escape_html(&source.url, &mut html);
+            html.push_str("\">");
+            escape_html(&source.title, &mut html);
+            html.push_str("</a></li>");
+        }
+        html.push_str("</ol>");
+    }
+
+    html
+}
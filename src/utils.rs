@@ -1,3 +1,27 @@
+use std::sync::OnceLock;
+
+use base64::{
+    engine::general_purpose::STANDARD,
+    Engine,
+};
+use wasm_bindgen::{
+    closure::Closure,
+    JsCast,
+    JsValue,
+};
+use web_sys::{
+    Blob,
+    BlobPropertyBag,
+    HtmlAnchorElement,
+    HtmlTextAreaElement,
+    KeyboardEvent,
+    Notification,
+    NotificationOptions,
+    NotificationPermission,
+    PerformanceResourceTiming,
+    Url,
+};
+
 pub trait IsEmpty {
     fn is_empty(&self) -> bool;
 }
@@ -16,3 +40,196 @@ pub fn non_empty<T: IsEmpty>(x: T) -> Option<T> {
         Some(x)
     }
 }
+
+/// Resizes a textarea to fit its content.
+pub fn auto_grow(textarea: &HtmlTextAreaElement) {
+    let style = textarea.style();
+    style.set_property("height", "auto").ok();
+    style
+        .set_property("height", &format!("{}px", textarea.scroll_height()))
+        .ok();
+}
+
+/// Keydown handler for message inputs: Enter submits the form, Shift+Enter
+/// inserts a newline.
+pub fn submit_on_enter(event: KeyboardEvent) {
+    if event.key() != "Enter" || event.shift_key() || event.is_composing() {
+        return;
+    }
+
+    event.prevent_default();
+
+    let Some(textarea) = event
+        .target()
+        .and_then(|target| target.dyn_into::<HtmlTextAreaElement>().ok())
+    else {
+        return;
+    };
+
+    if let Some(form) = textarea.form() {
+        form.request_submit().ok();
+    }
+}
+
+/// Lets the user download `content` as a file.
+pub fn download_file(file_name: &str, mime_type: &str, content: &str) {
+    let result = (|| {
+        let parts = js_sys::Array::of1(&JsValue::from_str(content));
+        let mut options = BlobPropertyBag::new();
+        options.type_(mime_type);
+        let blob = Blob::new_with_str_sequence_and_options(&parts, &options)?;
+        let url = Url::create_object_url_with_blob(&blob)?;
+
+        let anchor: HtmlAnchorElement = gloo_utils::document().create_element("a")?.dyn_into()?;
+        anchor.set_href(&url);
+        anchor.set_download(file_name);
+        anchor.click();
+
+        Url::revoke_object_url(&url)?;
+
+        Ok::<(), JsValue>(())
+    })();
+
+    if let Err(error) = result {
+        log::error!("download failed: {error:?}");
+    }
+}
+
+/// Reads a file into a `data:` URL.
+pub async fn read_data_url(file: &web_sys::File) -> Result<String, JsValue> {
+    let buffer = wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await?;
+    let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+    Ok(format!(
+        "data:{};base64,{}",
+        file.type_(),
+        STANDARD.encode(bytes)
+    ))
+}
+
+/// Copies text to the clipboard. The Clipboard API is still unstable in
+/// `web-sys`, so we call it through `js_sys`.
+pub fn copy_to_clipboard(text: &str) {
+    let result = (|| {
+        let navigator = web_sys::window().ok_or(JsValue::NULL)?.navigator();
+        let clipboard = js_sys::Reflect::get(&navigator, &JsValue::from_str("clipboard"))?;
+        let write_text = js_sys::Reflect::get(&clipboard, &JsValue::from_str("writeText"))?
+            .dyn_into::<js_sys::Function>()?;
+        write_text.call1(&clipboard, &JsValue::from_str(text))?;
+        Ok::<_, JsValue>(())
+    })();
+
+    if let Err(error) = result {
+        log::error!("copy to clipboard failed: {error:?}");
+    }
+}
+
+static STARTUP_TIME_MS: OnceLock<f64> = OnceLock::new();
+
+/// Records the time since navigation start at which the app was mounted.
+pub fn record_startup_time() {
+    if let Some(performance) = web_sys::window().and_then(|window| window.performance()) {
+        STARTUP_TIME_MS.get_or_init(|| performance.now());
+    }
+}
+
+pub fn startup_time_ms() -> Option<f64> {
+    STARTUP_TIME_MS.get().copied()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadedResource {
+    pub name: String,
+    /// Bytes transferred over the network. This is 0 for cached resources.
+    pub transfer_size: f64,
+    pub decoded_size: f64,
+    pub duration_ms: f64,
+}
+
+/// Lists the scripts, styles and wasm that were loaded by the page.
+pub fn loaded_resources() -> Vec<LoadedResource> {
+    let Some(performance) = web_sys::window().and_then(|window| window.performance())
+    else {
+        return vec![];
+    };
+
+    performance
+        .get_entries_by_type("resource")
+        .iter()
+        .filter_map(|entry| entry.dyn_into::<PerformanceResourceTiming>().ok())
+        .filter(|entry| {
+            let name = entry.name();
+            let path = name.split(['?', '#']).next().unwrap_or_default();
+            path.ends_with(".wasm") || path.ends_with(".js") || path.ends_with(".css")
+        })
+        .map(|entry| {
+            LoadedResource {
+                name: entry.name(),
+                transfer_size: entry.transfer_size(),
+                decoded_size: entry.decoded_body_size(),
+                duration_ms: entry.duration(),
+            }
+        })
+        .collect()
+}
+
+/// Whether the page is in a background tab or minimized.
+pub fn is_page_hidden() -> bool {
+    gloo_utils::document().hidden()
+}
+
+/// Asks the user for permission to show notifications. Returns whether it was
+/// granted.
+pub async fn request_notification_permission() -> bool {
+    let Ok(promise) = Notification::request_permission()
+    else {
+        return false;
+    };
+    match wasm_bindgen_futures::JsFuture::from(promise).await {
+        Ok(permission) => permission.as_string().as_deref() == Some("granted"),
+        Err(error) => {
+            log::error!("notification permission request failed: {error:?}");
+            false
+        }
+    }
+}
+
+/// Shows a notification, if the user gave permission. Clicking it focuses the
+/// app.
+pub fn notify(title: &str, body: &str) {
+    if Notification::permission() != NotificationPermission::Granted {
+        return;
+    }
+
+    let mut options = NotificationOptions::new();
+    options.body(body);
+    match Notification::new_with_options(title, &options) {
+        Ok(notification) => {
+            let on_click = Closure::once_into_js(|| {
+                if let Some(window) = web_sys::window() {
+                    window.focus().ok();
+                }
+            });
+            notification.set_onclick(Some(on_click.unchecked_ref()));
+        }
+        Err(error) => log::error!("failed to show notification: {error:?}"),
+    }
+}
+
+/// Registers the service worker that caches the app for offline use.
+pub fn register_service_worker() {
+    let Some(window) = web_sys::window()
+    else {
+        return;
+    };
+    let container = window.navigator().service_worker();
+    let promise = container.register("/sw.js");
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(error) = wasm_bindgen_futures::JsFuture::from(promise).await {
+            log::warn!("service worker registration failed: {error:?}");
+        }
+    });
+}
+
+pub fn is_online() -> bool {
+    web_sys::window().map_or(true, |window| window.navigator().on_line())
+}
@@ -0,0 +1,65 @@
+// Service worker for RustyChat. It caches the app shell, so that the app
+// loads offline. Requests go to the network first, so that a reload always
+// gets the latest deployment, and fall back to the cache when offline.
+//
+// The Hugging Face API is never cached, and neither is `version.json`, which
+// the app uses to check for updates.
+
+const CACHE = "rusty-chat-v1";
+
+self.addEventListener("install", (event) => {
+    event.waitUntil(
+        caches.open(CACHE).then((cache) => cache.addAll(["/", "/manifest.webmanifest"]))
+    );
+    self.skipWaiting();
+});
+
+self.addEventListener("activate", (event) => {
+    event.waitUntil(
+        caches
+            .keys()
+            .then((keys) => Promise.all(keys.filter((key) => key !== CACHE).map((key) => caches.delete(key))))
+            .then(() => self.clients.claim())
+    );
+});
+
+function isCacheable(request) {
+    if (request.method !== "GET") {
+        return false;
+    }
+    const url = new URL(request.url);
+    if (url.origin === self.location.origin) {
+        return url.pathname !== "/version.json";
+    }
+    // stylesheets, scripts and fonts from the CDN
+    return url.hostname === "cdn.jsdelivr.net";
+}
+
+self.addEventListener("fetch", (event) => {
+    const request = event.request;
+    if (!isCacheable(request)) {
+        return;
+    }
+
+    event.respondWith(
+        fetch(request)
+            .then((response) => {
+                if (response.ok) {
+                    const copy = response.clone();
+                    caches.open(CACHE).then((cache) => cache.put(request, copy));
+                }
+                return response;
+            })
+            .catch(async () => {
+                const cached = await caches.match(request);
+                if (cached) {
+                    return cached;
+                }
+                // all routes are served by the app shell
+                if (request.mode === "navigate") {
+                    return caches.match("/");
+                }
+                return Response.error();
+            })
+    );
+});